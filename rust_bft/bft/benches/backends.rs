@@ -0,0 +1,53 @@
+//! Compare the interpreter backends on loop heavy brainfuck programs
+//!
+//! Run with `cargo bench`, each workload is executed several times
+//! per backend and the average wall-clock time is reported.
+
+use bft_interp::{BFBackend, BFVirtualMachine};
+use bft_types::BFProgram;
+use std::io::{self, Cursor};
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 5;
+
+const BACKENDS: &[BFBackend] = &[
+    BFBackend::Loop,
+    BFBackend::Threaded,
+    #[cfg(feature = "jit")]
+    BFBackend::Jit,
+];
+
+const WORKLOADS: [(&str, &str); 2] = [
+    ("nested loops", "-[>-[>-[-]<-]<-]"),
+    (
+        "hello world",
+        "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..
+        +++.>>.<-.<.+++.------.--------.>>+.>++.",
+    ),
+];
+
+fn bench_backend(source: &str, backend: BFBackend) -> Duration {
+    let bf_info = BFProgram::new("", source).validate().unwrap();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut bf_vm = BFVirtualMachine::<u8>::new(0, false, &bf_info);
+        bf_vm
+            .interpret_with(backend, &mut Cursor::new(Vec::new()), &mut io::sink())
+            .unwrap();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    for (name, source) in WORKLOADS {
+        for &backend in BACKENDS {
+            let elapsed = bench_backend(source, backend);
+            println!(
+                "{:<15}{:<12}{:>12.3?}",
+                name,
+                format!("{:?}", backend),
+                elapsed
+            );
+        }
+    }
+}
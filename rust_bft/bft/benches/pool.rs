@@ -0,0 +1,69 @@
+//! Allocation overhead of short runs, with and without a `BFVmPool`
+//!
+//! Run with `cargo bench --bench pool`.
+
+use bft_interp::pool::BFVmPool;
+use bft_interp::{BFEofBehavior, BFVirtualMachine};
+use bft_types::{BFProgram, BFValidProgram};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// runs of every measure
+const RUNS: u32 = 20_000;
+
+/// short programs a service would run one after another
+const PROGRAMS: [&str; 3] = ["+++++[>+++++<-]>.", ",[.,]", "++>+++[<+>-]<."];
+
+/// run `bf_vm` to the end, discarding its output
+fn run_once(bf_vm: &mut BFVirtualMachine<u8>) {
+    let mut output = Vec::new();
+    bf_vm.set_final_newline(false);
+    bf_vm.set_prompt(false);
+    bf_vm.set_eof(BFEofBehavior::Zero);
+    bf_vm.interpret(&mut &b"ab"[..], &mut output).unwrap();
+    black_box(output);
+}
+
+/// mean time of a run, allocating a tape for every virtual machine
+fn fresh(programs: &[BFValidProgram], len: usize) -> Duration {
+    let start = Instant::now();
+    for run in 0..RUNS {
+        let bf_info = &programs[run as usize % programs.len()];
+        let mut bf_vm = BFVirtualMachine::<u8>::new(len, false, bf_info);
+        run_once(&mut bf_vm);
+    }
+    start.elapsed() / RUNS
+}
+
+/// mean time of a run, reusing the tapes of a pool
+fn pooled(programs: &[BFValidProgram], len: usize) -> Duration {
+    let mut pool = BFVmPool::<u8>::new(len, false);
+    let start = Instant::now();
+    for run in 0..RUNS {
+        let bf_info = &programs[run as usize % programs.len()];
+        let mut bf_vm = pool.acquire(bf_info);
+        run_once(&mut bf_vm);
+        pool.release(bf_vm);
+    }
+    let mean = start.elapsed() / RUNS;
+    assert_eq!(pool.allocations(), 1);
+    mean
+}
+
+fn main() {
+    let programs: Vec<_> = PROGRAMS
+        .iter()
+        .map(|source| BFProgram::new("", source).validate().unwrap())
+        .collect();
+    for len in [64, 30000, 1 << 20] {
+        let fresh = fresh(&programs, len);
+        let pooled = pooled(&programs, len);
+        println!(
+            "{:>8} cells  fresh {:>10.3?}  pooled {:>10.3?}  {:>6.2}x",
+            len,
+            fresh,
+            pooled,
+            fresh.as_secs_f64() / pooled.as_secs_f64()
+        );
+    }
+}
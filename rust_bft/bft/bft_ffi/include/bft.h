@@ -0,0 +1,70 @@
+/* C interface of the bft brainfuck interpreter, see bft_ffi/src/lib.rs */
+
+#ifndef BFT_H
+#define BFT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status code returned by the functions below */
+typedef enum bft_status {
+    BFT_OK = 0,          /* success, for bft_step the program goes on */
+    BFT_HALTED = 1,      /* bft_step ran the last instruction */
+    BFT_ERR_NULL = -1,   /* a required pointer argument is NULL */
+    BFT_ERR_UTF8 = -2,   /* the source isn't valid UTF-8 */
+    BFT_ERR_SYNTAX = -3, /* the brackets of the source don't match */
+    BFT_ERR_HEAD = -4,   /* the head fell off either edge of the tape */
+    BFT_ERR_IO = -5,     /* a callback failed, or input ended with EOF set to error */
+    BFT_ERR_FUEL = -6,   /* the fuel limit ran out before the program halted */
+    BFT_ERR_RANGE = -7,  /* a cell index or setting is out of range */
+    BFT_ERR_LIMIT = -8,  /* a sandbox limit of the virtual machine was hit */
+} bft_status;
+
+/* Validated brainfuck program */
+typedef struct bft_program bft_program;
+
+/* Virtual machine with byte cells running a program */
+typedef struct bft_vm bft_vm;
+
+/* Returns the next input byte, -1 at the end of input or < -1 on error */
+typedef int (*bft_read_fn)(void *user);
+
+/* Writes one output byte, returns 0 on success or non-zero on error */
+typedef int (*bft_write_fn)(void *user, uint8_t byte);
+
+/* Message of the last error on this thread, NULL if none */
+const char *bft_last_error(void);
+
+/* Parse and validate len bytes of UTF-8 source into *out */
+bft_status bft_parse(const uint8_t *source, size_t len, bft_program **out);
+void bft_program_free(bft_program *program);
+
+/* The virtual machine shares the program, cells is 30000 if 0 */
+bft_vm *bft_vm_new(const bft_program *program, size_t cells, bool extensible);
+void bft_vm_free(bft_vm *vm);
+
+/* eof: 0 zero, 1 minus one, 2 unchanged, 3 error */
+bft_status bft_vm_set_eof(bft_vm *vm, int eof);
+bft_status bft_vm_set_fuel(bft_vm *vm, uint64_t fuel, bool limited);
+bft_status bft_vm_set_final_newline(bft_vm *vm, bool enable);
+
+/* NULL callbacks read nothing and discard the output */
+bft_status bft_run(bft_vm *vm, bft_read_fn read, bft_write_fn write, void *user);
+bft_status bft_step(bft_vm *vm, bft_read_fn read, bft_write_fn write, void *user);
+
+size_t bft_vm_head(const bft_vm *vm);
+size_t bft_vm_cells_len(const bft_vm *vm);
+const uint8_t *bft_vm_cells(const bft_vm *vm);
+bft_status bft_vm_cell(const bft_vm *vm, size_t index, uint8_t *out);
+uint64_t bft_vm_steps(const bft_vm *vm);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BFT_H */
@@ -0,0 +1,504 @@
+//! C interface of the brainfuck interpreter
+//!
+//! Builds as a `cdylib` and `staticlib` exporting the functions declared in
+//! `include/bft.h`. Programs and virtual machines are opaque handles owned
+//! by the caller, every function reports a `BFFfiStatus` and the message of
+//! the last error on the calling thread is kept for `bft_last_error`.
+//!
+//! Input and output go through C callbacks called once per byte with the
+//! caller's `user` pointer.
+//!
+//! With the `python` feature the library is a Python extension module too.
+
+use bft_interp::{
+    BFEofBehavior, BFFnReader, BFFnWriter, BFIoError, BFOwnedVirtualMachine, BFVirtualMachine,
+    BFVmErr,
+};
+use bft_types::{BFProgram, BFValidProgram};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::sync::Arc;
+use std::{ptr, slice, str};
+
+#[cfg(feature = "python")]
+mod python;
+
+/// Status code returned by the exported functions
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BFFfiStatus {
+    /// success, for `bft_step` the program goes on
+    Ok = 0,
+
+    /// `bft_step` ran the last instruction or the program already halted
+    Halted = 1,
+
+    /// a required pointer argument is NULL
+    NullErr = -1,
+
+    /// the source isn't valid UTF-8
+    Utf8Err = -2,
+
+    /// the brackets of the source don't match
+    SyntaxErr = -3,
+
+    /// the head fell off either edge of the tape
+    HeadErr = -4,
+
+    /// a callback reported an error, or input ended with EOF set to error
+    IoErr = -5,
+
+    /// the fuel limit ran out before the program halted
+    FuelErr = -6,
+
+    /// a cell index or setting is out of range
+    RangeErr = -7,
+
+    /// a sandbox limit of the virtual machine was hit
+    LimitErr = -8,
+}
+
+/// Read callback, returns the next byte, -1 at the end of input
+/// or any other negative value on error
+pub type BFFfiReadFn = Option<extern "C" fn(user: *mut c_void) -> c_int>;
+
+/// Write callback, returns 0 on success or non-zero on error
+pub type BFFfiWriteFn = Option<extern "C" fn(user: *mut c_void, byte: u8) -> c_int>;
+
+/// Virtual machine with byte cells behind a `bft_vm` handle,
+/// sharing the program so either may be released first
+pub struct BFFfiVm {
+    vm: BFOwnedVirtualMachine<u8>,
+}
+
+thread_local! {
+    /// message of the last error on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember `message` as the last error and return `status`
+fn fail(status: BFFfiStatus, message: impl ToString) -> BFFfiStatus {
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+    status
+}
+
+/// Map an error of the virtual machine to its status
+fn vm_fail(err: BFVmErr) -> BFFfiStatus {
+    let status = match err {
+        BFVmErr::HeadInvalidPositionErr(_) => BFFfiStatus::HeadErr,
+        BFVmErr::FuelExhaustedErr(_) => BFFfiStatus::FuelErr,
+        BFVmErr::IOErr(_) | BFVmErr::NewlineErr(_) => BFFfiStatus::IoErr,
+        BFVmErr::LimitErr(..) => BFFfiStatus::LimitErr,
+    };
+    fail(status, err)
+}
+
+/// Byte reader calling `read` with `user`, no input if `read` is NULL
+fn reader(
+    read: BFFfiReadFn,
+    user: *mut c_void,
+) -> BFFnReader<impl FnMut() -> Result<Option<u8>, BFIoError>> {
+    BFFnReader::new(move || match read {
+        None => Ok(None),
+        Some(read) => match read(user) {
+            byte @ 0..=255 => Ok(Some(byte as u8)),
+            -1 => Ok(None),
+            code => Err(BFIoError::other(format!(
+                "Read callback failed with {}",
+                code
+            ))),
+        },
+    })
+}
+
+/// Byte writer calling `write` with `user`, output discarded if `write` is NULL
+fn writer(
+    write: BFFfiWriteFn,
+    user: *mut c_void,
+) -> BFFnWriter<impl FnMut(u8) -> Result<(), BFIoError>> {
+    BFFnWriter::new(move |byte| match write {
+        None => Ok(()),
+        Some(write) => match write(user, byte) {
+            0 => Ok(()),
+            code => Err(BFIoError::other(format!(
+                "Write callback failed with {}",
+                code
+            ))),
+        },
+    })
+}
+
+/// Message of the last error on this thread, NULL if none,
+/// valid until the next failing call on the same thread
+#[no_mangle]
+pub extern "C" fn bft_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Parse and validate `len` bytes of UTF-8 brainfuck `source`, storing the
+/// program in `*out`, to be released with `bft_program_free`
+///
+/// # Safety
+///
+/// `source` must point to `len` readable bytes and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn bft_parse(
+    source: *const u8,
+    len: usize,
+    out: *mut *mut BFValidProgram,
+) -> BFFfiStatus {
+    if source.is_null() || out.is_null() {
+        return fail(BFFfiStatus::NullErr, "NULL source or output");
+    }
+    let source = match str::from_utf8(slice::from_raw_parts(source, len)) {
+        Ok(source) => source,
+        Err(e) => return fail(BFFfiStatus::Utf8Err, e),
+    };
+    match BFProgram::new("ffi", source).validate() {
+        Ok(bf_info) => {
+            *out = Arc::into_raw(Arc::new(bf_info)).cast_mut();
+            BFFfiStatus::Ok
+        }
+        Err(e) => fail(BFFfiStatus::SyntaxErr, e),
+    }
+}
+
+/// Release a program from `bft_parse`, NULL is ignored
+///
+/// # Safety
+///
+/// `program` must be NULL or from `bft_parse`, virtual machines running
+/// it keep their own share.
+#[no_mangle]
+pub unsafe extern "C" fn bft_program_free(program: *mut BFValidProgram) {
+    if !program.is_null() {
+        drop(Arc::from_raw(program));
+    }
+}
+
+/// Create a virtual machine of `cells` byte cells, 30000 if zero, running
+/// `program`, NULL if `program` is NULL, reads print no prompt so the
+/// output only goes to the callbacks
+///
+/// # Safety
+///
+/// `program` must be NULL or from `bft_parse` and not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_new(
+    program: *const BFValidProgram,
+    cells: usize,
+    extensible: bool,
+) -> *mut BFFfiVm {
+    if program.is_null() {
+        fail(BFFfiStatus::NullErr, "NULL program");
+        return ptr::null_mut();
+    }
+    Arc::increment_strong_count(program);
+    let bf_info = Arc::from_raw(program);
+    let mut vm = BFVirtualMachine::new_shared(cells, extensible, bf_info);
+    vm.set_prompt(false);
+    Box::into_raw(Box::new(BFFfiVm { vm }))
+}
+
+/// Release a virtual machine from `bft_vm_new`, NULL is ignored
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_free(vm: *mut BFFfiVm) {
+    if !vm.is_null() {
+        drop(Box::from_raw(vm));
+    }
+}
+
+/// Choose what reading does once input is exhausted, 0 zero, 1 minus one,
+/// 2 unchanged or 3 error
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_set_eof(vm: *mut BFFfiVm, eof: c_int) -> BFFfiStatus {
+    let Some(vm) = vm.as_mut() else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine");
+    };
+    let eof = match eof {
+        0 => BFEofBehavior::Zero,
+        1 => BFEofBehavior::MinusOne,
+        2 => BFEofBehavior::Unchanged,
+        3 => BFEofBehavior::Error,
+        _ => {
+            return fail(
+                BFFfiStatus::RangeErr,
+                format!("Unknown EOF behavior {}", eof),
+            )
+        }
+    };
+    vm.vm.set_eof(eof);
+    BFFfiStatus::Ok
+}
+
+/// Limit how many instructions may still execute, unlimited if `limited`
+/// is false
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_set_fuel(
+    vm: *mut BFFfiVm,
+    fuel: u64,
+    limited: bool,
+) -> BFFfiStatus {
+    let Some(vm) = vm.as_mut() else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine");
+    };
+    vm.vm.set_fuel(limited.then_some(fuel));
+    BFFfiStatus::Ok
+}
+
+/// Choose whether a newline is written after the output if it doesn't
+/// end with one, on by default
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_set_final_newline(vm: *mut BFFfiVm, enable: bool) -> BFFfiStatus {
+    let Some(vm) = vm.as_mut() else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine");
+    };
+    vm.vm.set_final_newline(enable);
+    BFFfiStatus::Ok
+}
+
+/// Run the program until it halts, NULL callbacks read nothing and
+/// discard the output
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`, the callbacks must be safe to
+/// call with `user`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_run(
+    vm: *mut BFFfiVm,
+    read: BFFfiReadFn,
+    write: BFFfiWriteFn,
+    user: *mut c_void,
+) -> BFFfiStatus {
+    let Some(vm) = vm.as_mut() else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine");
+    };
+    match vm
+        .vm
+        .interpret(&mut reader(read, user), &mut writer(write, user))
+    {
+        Ok(()) => BFFfiStatus::Ok,
+        Err(e) => vm_fail(e),
+    }
+}
+
+/// Execute the next instruction, `BFT_OK` if the program goes on and
+/// `BFT_HALTED` once it halted
+///
+/// # Safety
+///
+/// Same as `bft_run`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_step(
+    vm: *mut BFFfiVm,
+    read: BFFfiReadFn,
+    write: BFFfiWriteFn,
+    user: *mut c_void,
+) -> BFFfiStatus {
+    let Some(vm) = vm.as_mut() else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine");
+    };
+    match vm
+        .vm
+        .step(&mut reader(read, user), &mut writer(write, user))
+    {
+        Ok(true) => BFFfiStatus::Ok,
+        Ok(false) => BFFfiStatus::Halted,
+        Err(e) => vm_fail(e),
+    }
+}
+
+/// Position of the head cell, 0 if `vm` is NULL
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_head(vm: *const BFFfiVm) -> usize {
+    vm.as_ref().map_or(0, |vm| vm.vm.head())
+}
+
+/// Number of cells on the tape, 0 if `vm` is NULL
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_cells_len(vm: *const BFFfiVm) -> usize {
+    vm.as_ref().map_or(0, |vm| vm.vm.cells().len())
+}
+
+/// Tape of `bft_vm_cells_len` cells, valid until the virtual machine runs
+/// again or is released, NULL if `vm` is NULL
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_cells(vm: *const BFFfiVm) -> *const u8 {
+    vm.as_ref().map_or(ptr::null(), |vm| vm.vm.cells().as_ptr())
+}
+
+/// Store the value of cell `index` in `*out`
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new` and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_cell(
+    vm: *const BFFfiVm,
+    index: usize,
+    out: *mut u8,
+) -> BFFfiStatus {
+    let (Some(vm), false) = (vm.as_ref(), out.is_null()) else {
+        return fail(BFFfiStatus::NullErr, "NULL virtual machine or output");
+    };
+    match vm.vm.cells().get(index) {
+        Some(&value) => {
+            *out = value;
+            BFFfiStatus::Ok
+        }
+        None => fail(BFFfiStatus::RangeErr, format!("No cell at {}", index)),
+    }
+}
+
+/// Number of instructions executed so far, 0 if `vm` is NULL
+///
+/// # Safety
+///
+/// `vm` must be NULL or from `bft_vm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bft_vm_steps(vm: *const BFFfiVm) -> u64 {
+    vm.as_ref().map_or(0, |vm| vm.vm.stats().steps())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// input bytes and collected output of the test callbacks
+    struct Buffers {
+        input: Vec<u8>,
+        output: Vec<u8>,
+    }
+
+    extern "C" fn read(user: *mut c_void) -> c_int {
+        let buffers = unsafe { &mut *(user as *mut Buffers) };
+        if buffers.input.is_empty() {
+            -1
+        } else {
+            buffers.input.remove(0).into()
+        }
+    }
+
+    extern "C" fn write(user: *mut c_void, byte: u8) -> c_int {
+        let buffers = unsafe { &mut *(user as *mut Buffers) };
+        buffers.output.push(byte);
+        0
+    }
+
+    extern "C" fn broken_write(_user: *mut c_void, _byte: u8) -> c_int {
+        1
+    }
+
+    fn parse(source: &str) -> *mut BFValidProgram {
+        let mut program = ptr::null_mut();
+        let status = unsafe { bft_parse(source.as_ptr(), source.len(), &mut program) };
+        assert_eq!(status, BFFfiStatus::Ok);
+        program
+    }
+
+    #[test]
+    fn test_run() {
+        let program = parse(",[.,]");
+        let mut buffers = Buffers {
+            input: b"abc".to_vec(),
+            output: Vec::new(),
+        };
+        let user = &mut buffers as *mut Buffers as *mut c_void;
+        unsafe {
+            let vm = bft_vm_new(program, 4, false);
+            bft_program_free(program);
+            assert!(!(*vm).vm.prompt());
+            assert_eq!(bft_vm_set_eof(vm, 0), BFFfiStatus::Ok);
+            assert_eq!(bft_run(vm, Some(read), Some(write), user), BFFfiStatus::Ok);
+            assert_eq!(bft_vm_steps(vm), 11);
+            bft_vm_free(vm);
+        }
+        assert_eq!(buffers.output, b"abc\n");
+    }
+
+    #[test]
+    fn test_step_and_cells() {
+        let program = parse("++>+");
+        unsafe {
+            let vm = bft_vm_new(program, 2, false);
+            let step = || bft_step(vm, None, None, ptr::null_mut());
+            assert_eq!(step(), BFFfiStatus::Ok);
+            assert_eq!(step(), BFFfiStatus::Ok);
+            assert_eq!(step(), BFFfiStatus::Halted);
+            assert_eq!(step(), BFFfiStatus::Halted);
+
+            let mut value = 0;
+            assert_eq!(bft_vm_cell(vm, 0, &mut value), BFFfiStatus::Ok);
+            assert_eq!(value, 2);
+            assert_eq!(bft_vm_cell(vm, 2, &mut value), BFFfiStatus::RangeErr);
+            assert_eq!(bft_vm_head(vm), 1);
+            let cells = slice::from_raw_parts(bft_vm_cells(vm), bft_vm_cells_len(vm));
+            assert_eq!(cells, &[2, 1]);
+            bft_vm_free(vm);
+            bft_program_free(program);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut program = ptr::null_mut();
+        let status = unsafe { bft_parse(b"[".as_ptr(), 1, &mut program) };
+        assert_eq!(status, BFFfiStatus::SyntaxErr);
+        let message = unsafe { CStr::from_ptr(bft_last_error()) };
+        assert!(message.to_str().unwrap().contains("line 1 column 1"));
+
+        let status = unsafe { bft_parse([0xff].as_ptr(), 1, &mut program) };
+        assert_eq!(status, BFFfiStatus::Utf8Err);
+        assert!(unsafe { bft_vm_new(ptr::null(), 0, false) }.is_null());
+
+        let program = parse("<");
+        unsafe {
+            let vm = bft_vm_new(program, 1, false);
+            let status = bft_run(vm, None, None, ptr::null_mut());
+            assert_eq!(status, BFFfiStatus::HeadErr);
+            bft_vm_free(vm);
+            bft_program_free(program);
+        }
+
+        let program = parse(".");
+        unsafe {
+            let vm = bft_vm_new(program, 1, false);
+            let status = bft_run(vm, None, Some(broken_write), ptr::null_mut());
+            assert_eq!(status, BFFfiStatus::IoErr);
+            assert_eq!(bft_vm_set_eof(vm, 9), BFFfiStatus::RangeErr);
+            bft_vm_free(vm);
+            bft_program_free(program);
+        }
+    }
+}
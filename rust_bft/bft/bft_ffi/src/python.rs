@@ -0,0 +1,221 @@
+//! PyO3 bindings, enabled with the `python` feature
+//!
+//! The shared library doubles as the `bft_ffi` Python extension module
+//! exposing `BFProgram` and `BFVirtualMachine`. Input and output go either
+//! through byte strings or through Python callables, `read()` returns the
+//! next byte or None at the end of input and `write(byte)` receives every
+//! output byte. An exception raised by a callable stops the program and is
+//! raised again by the call running it.
+
+use bft_interp::{
+    BFEofBehavior, BFFnReader, BFFnWriter, BFIoError, BFOwnedVirtualMachine, BFVirtualMachine,
+    BFVmErr,
+};
+use bft_types::{BFProgram, BFValidProgram};
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// Validated brainfuck program
+#[pyclass(frozen, name = "BFProgram")]
+pub struct PyBFProgram {
+    program: Arc<BFValidProgram>,
+}
+
+#[pymethods]
+impl PyBFProgram {
+    /// parse and validate brainfuck `source`, ValueError if the brackets
+    /// don't match
+    #[new]
+    #[pyo3(signature = (source, filename = "python"))]
+    fn new(source: &str, filename: &str) -> PyResult<Self> {
+        let program = BFProgram::new(filename, source)
+            .validate()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self {
+            program: Arc::new(program),
+        })
+    }
+
+    /// number of brainfuck instructions
+    fn __len__(&self) -> usize {
+        self.program.instructions().len()
+    }
+}
+
+/// Virtual machine with byte cells running a `BFProgram`
+#[pyclass(name = "BFVirtualMachine")]
+pub struct PyBFVirtualMachine {
+    vm: BFOwnedVirtualMachine<u8>,
+    program: Arc<BFValidProgram>,
+}
+
+impl PyBFVirtualMachine {
+    /// Execute `f` with handlers calling `read` and `write` in Python,
+    /// input ends right away without `read` and output goes to `output`
+    /// without `write`
+    fn with_io<T>(
+        &mut self,
+        read: Option<&Bound<'_, PyAny>>,
+        write: Option<&Bound<'_, PyAny>>,
+        output: &mut Vec<u8>,
+        f: impl FnOnce(
+            &mut BFOwnedVirtualMachine<u8>,
+            &mut BFFnReader<&mut dyn FnMut() -> Result<Option<u8>, BFIoError>>,
+            &mut BFFnWriter<&mut dyn FnMut(u8) -> Result<(), BFIoError>>,
+        ) -> Result<T, BFVmErr>,
+    ) -> PyResult<T> {
+        let slot = RefCell::new(None);
+        let fail = |err: PyErr| {
+            *slot.borrow_mut() = Some(err);
+            BFIoError::other("Python callable raised an exception")
+        };
+        let mut read_fn = || match read {
+            None => Ok(None),
+            Some(read) => read
+                .call0()
+                .and_then(|value| value.extract::<Option<u8>>())
+                .map_err(fail),
+        };
+        let mut write_fn = |byte| match write {
+            None => {
+                output.push(byte);
+                Ok(())
+            }
+            Some(write) => write.call1((byte,)).map(|_| ()).map_err(fail),
+        };
+        let result = f(
+            &mut self.vm,
+            &mut BFFnReader::new(&mut read_fn as &mut dyn FnMut() -> _),
+            &mut BFFnWriter::new(&mut write_fn as &mut dyn FnMut(u8) -> _),
+        );
+        // an exception raised by a callable wins over the IO error it caused
+        result.map_err(|e| {
+            slot.take()
+                .unwrap_or_else(|| PyRuntimeError::new_err(e.to_string()))
+        })
+    }
+}
+
+#[pymethods]
+impl PyBFVirtualMachine {
+    /// create a virtual machine of `cells` cells, 30000 if zero, `eof` is
+    /// one of zero, minus-one, unchanged or error, reads print no prompt
+    #[new]
+    #[pyo3(signature = (program, cells = 30000, extensible = false, eof = "error", fuel = None, final_newline = true))]
+    fn new(
+        program: &PyBFProgram,
+        cells: usize,
+        extensible: bool,
+        eof: &str,
+        fuel: Option<u64>,
+        final_newline: bool,
+    ) -> PyResult<Self> {
+        let eof = match eof {
+            "zero" => BFEofBehavior::Zero,
+            "minus-one" => BFEofBehavior::MinusOne,
+            "unchanged" => BFEofBehavior::Unchanged,
+            "error" => BFEofBehavior::Error,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown EOF behavior {}",
+                    eof
+                )))
+            }
+        };
+        let program = Arc::clone(&program.program);
+        let mut vm = BFVirtualMachine::new_shared(cells, extensible, Arc::clone(&program));
+        vm.set_eof(eof);
+        vm.set_fuel(fuel);
+        vm.set_final_newline(final_newline);
+        vm.set_prompt(false);
+        Ok(Self { vm, program })
+    }
+
+    /// run the program until it halts on `input`, returning the output,
+    /// `read` and `write` callables replace `input` and the output
+    #[pyo3(signature = (input = None, read = None, write = None))]
+    fn run<'py>(
+        &mut self,
+        py: Python<'py>,
+        input: Option<&[u8]>,
+        read: Option<&Bound<'py, PyAny>>,
+        write: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut output = Vec::new();
+        match (input, read) {
+            (Some(mut input), None) => self.with_io(None, write, &mut output, |vm, _, w| {
+                vm.interpret(&mut input, w)
+            })?,
+            (None, read) => {
+                self.with_io(read, write, &mut output, |vm, r, w| vm.interpret(r, w))?
+            }
+            (Some(_), Some(_)) => {
+                return Err(PyValueError::new_err("Pass either input or read, not both"))
+            }
+        }
+        Ok(PyBytes::new(py, &output))
+    }
+
+    /// execute the next instruction, returning whether the program goes on,
+    /// output is written to `write` and dropped without it
+    #[pyo3(signature = (read = None, write = None))]
+    fn step(
+        &mut self,
+        read: Option<&Bound<'_, PyAny>>,
+        write: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        self.with_io(read, write, &mut Vec::new(), |vm, r, w| vm.step(r, w))
+    }
+
+    /// the program this virtual machine runs
+    #[getter]
+    fn program(&self) -> PyBFProgram {
+        PyBFProgram {
+            program: Arc::clone(&self.program),
+        }
+    }
+
+    /// copy of the tape
+    #[getter]
+    fn tape<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.vm.cells())
+    }
+
+    /// position of the head cell
+    #[getter]
+    fn head(&self) -> usize {
+        self.vm.head()
+    }
+
+    /// number of instructions executed so far
+    #[getter]
+    fn steps(&self) -> u64 {
+        self.vm.stats().steps()
+    }
+
+    /// whether the program ran past its last instruction
+    #[getter]
+    fn halted(&self) -> bool {
+        self.vm.halted()
+    }
+
+    /// value of cell `index`, IndexError past the tape
+    fn __getitem__(&self, index: usize) -> PyResult<u8> {
+        self.vm
+            .cells()
+            .get(index)
+            .copied()
+            .ok_or_else(|| PyIndexError::new_err(format!("No cell at {}", index)))
+    }
+}
+
+/// Python module of the brainfuck interpreter
+#[pymodule]
+fn bft_ffi(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBFProgram>()?;
+    m.add_class::<PyBFVirtualMachine>()?;
+    Ok(())
+}
@@ -0,0 +1,383 @@
+//! Static analysis of brainfuck programs
+//!
+//! The head excursion of a program is bounded without running it as long
+//! as every loop is balanced, ie. its body moves the head back to where the
+//! iteration started: every iteration then covers the cells of the first
+//! one. A loop ending elsewhere, eg. the scan `[>]`, moves the head by an
+//! amount depending on the data and leaves the excursion unbounded.
+//!
+//! `BFProgramMetrics` counts the instructions, loops and head moves of a
+//! program along with its tape usage, `BFLoopTree` nests its loops.
+
+use alloc::vec::Vec;
+use bft_types::{BFCharCmdName, BFCharInfo, BFValidProgram};
+
+/// Bounds of the cells the head may visit, relative to the first cell
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFTapeUsage {
+    left: usize,
+    right: usize,
+    unbounded: Option<BFCharInfo>,
+}
+
+impl BFTapeUsage {
+    /// Bound the head excursion of a validated program
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::analysis::BFTapeUsage;
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+[->>+<<]>>>.").validate().unwrap();
+    /// let usage = BFTapeUsage::analyze(&bf_info);
+    /// assert_eq!(usage.cells(), Some(4));
+    ///
+    /// let bf_info = BFProgram::new("", "+[>+]").validate().unwrap();
+    /// let usage = BFTapeUsage::analyze(&bf_info);
+    /// assert_eq!(usage.cells(), None);
+    /// assert_eq!(usage.unbounded_loop().unwrap().column(), 2);
+    /// ```
+    pub fn analyze(bf_info: &BFValidProgram) -> Self {
+        let instructions = bf_info.instructions();
+        let (mut offset, mut min, mut max) = (0i64, 0i64, 0i64);
+        // offset of the head when each enclosing loop was entered
+        let mut entries = Vec::new();
+        let mut unbounded = None;
+        for (index, info) in instructions.iter().enumerate() {
+            match info.get_raw() {
+                BFCharCmdName::PointerIncrement => offset += 1,
+                BFCharCmdName::PointerDecrement => offset -= 1,
+                BFCharCmdName::LoopStart(_) => entries.push(offset),
+                BFCharCmdName::LoopTerminate(_) => {
+                    let entry = entries.pop();
+                    if entry != Some(offset) {
+                        let start = bf_info.jump_target(index);
+                        unbounded = Some(instructions.at(start));
+                        break;
+                    }
+                }
+                _ => (),
+            }
+            min = min.min(offset);
+            max = max.max(offset);
+        }
+        Self {
+            left: min.unsigned_abs() as usize,
+            right: max as usize,
+            unbounded,
+        }
+    }
+
+    /// Cells the head may move left of the first cell, up to the first
+    /// unbalanced loop
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    /// Cells the head may move right of the first cell, up to the first
+    /// unbalanced loop
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    /// Smallest tape the program never runs off on the right, None if the
+    /// excursion is unbounded
+    pub fn cells(&self) -> Option<usize> {
+        match self.unbounded {
+            Some(_) => None,
+            None => Some(self.right + 1),
+        }
+    }
+
+    /// First loop leaving the excursion unbounded
+    pub fn unbounded_loop(&self) -> Option<BFCharInfo> {
+        self.unbounded
+    }
+}
+
+/// The eight core commands, in the order of `BFProgramMetrics::histogram`
+const COMMANDS: [char; 8] = ['+', '-', '<', '>', ',', '.', '[', ']'];
+
+/// Counts of the instructions of a program without running it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFProgramMetrics {
+    histogram: [usize; 8],
+    extensions: usize,
+    loops: usize,
+    max_depth: usize,
+    drift: i64,
+    tape: BFTapeUsage,
+}
+
+impl BFProgramMetrics {
+    /// Measure a validated program
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::analysis::BFProgramMetrics;
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+[->+[-]<]>>").validate().unwrap();
+    /// let metrics = BFProgramMetrics::measure(&bf_info);
+    /// assert_eq!(metrics.count('-'), 2);
+    /// assert_eq!((metrics.loops(), metrics.max_depth()), (2, 2));
+    /// assert_eq!(metrics.drift(), 2);
+    /// assert_eq!(metrics.tape().cells(), Some(3));
+    /// ```
+    pub fn measure(bf_info: &BFValidProgram) -> Self {
+        let mut metrics = Self {
+            histogram: [0; 8],
+            extensions: 0,
+            loops: 0,
+            max_depth: 0,
+            drift: 0,
+            tape: BFTapeUsage::analyze(bf_info),
+        };
+        let mut depth = 0;
+        for info in bf_info.instructions() {
+            let index = match info.get_raw() {
+                BFCharCmdName::DataIncrement => 0,
+                BFCharCmdName::DataDecrement => 1,
+                BFCharCmdName::PointerDecrement => 2,
+                BFCharCmdName::PointerIncrement => 3,
+                BFCharCmdName::DataInput => 4,
+                BFCharCmdName::DataOutput => 5,
+                BFCharCmdName::LoopStart(_) => 6,
+                BFCharCmdName::LoopTerminate(_) => 7,
+                BFCharCmdName::Extension(_) => {
+                    metrics.extensions += 1;
+                    continue;
+                }
+            };
+            metrics.histogram[index] += 1;
+            match index {
+                2 => metrics.drift -= 1,
+                3 => metrics.drift += 1,
+                6 => {
+                    metrics.loops += 1;
+                    depth += 1;
+                    metrics.max_depth = metrics.max_depth.max(depth);
+                }
+                7 => depth -= 1,
+                _ => (),
+            }
+        }
+        metrics
+    }
+
+    /// Number of instructions, extension commands included
+    pub fn instructions(&self) -> usize {
+        self.histogram.iter().sum::<usize>() + self.extensions
+    }
+
+    /// Number of instructions of each core command, in the order
+    /// `+-<>,.[]`
+    pub fn histogram(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        COMMANDS.iter().copied().zip(self.histogram.iter().copied())
+    }
+
+    /// Number of instructions of the core command `command`, zero for any
+    /// other character
+    pub fn count(&self, command: char) -> usize {
+        self.histogram()
+            .find(|(c, _)| *c == command)
+            .map_or(0, |(_, count)| count)
+    }
+
+    /// Number of extension commands
+    pub fn extensions(&self) -> usize {
+        self.extensions
+    }
+
+    /// Number of loops
+    pub fn loops(&self) -> usize {
+        self.loops
+    }
+
+    /// Deepest nesting of loops
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Number of `>` less the number of `<`, where the head ends if no
+    /// loop moves it
+    pub fn drift(&self) -> i64 {
+        self.drift
+    }
+
+    /// Bounds of the cells the head may visit
+    pub fn tape(&self) -> BFTapeUsage {
+        self.tape
+    }
+}
+
+/// A loop of a program with the loops nested in its body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFLoop {
+    start: BFCharInfo,
+    end: BFCharInfo,
+    instructions: usize,
+    loops: Vec<BFLoop>,
+}
+
+impl BFLoop {
+    /// The `[` opening the loop
+    pub fn start(&self) -> BFCharInfo {
+        self.start
+    }
+
+    /// The `]` closing the loop
+    pub fn end(&self) -> BFCharInfo {
+        self.end
+    }
+
+    /// Number of instructions of the body, nested loops included
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    /// Loops directly nested in the body, in source order
+    pub fn loops(&self) -> &[BFLoop] {
+        &self.loops
+    }
+}
+
+/// Structure of the loops of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFLoopTree {
+    instructions: usize,
+    loops: Vec<BFLoop>,
+}
+
+impl BFLoopTree {
+    /// Nest the loops of a validated program
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::analysis::BFLoopTree;
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+[->[-]<][.]").validate().unwrap();
+    /// let tree = BFLoopTree::build(&bf_info);
+    /// assert_eq!(tree.loops().len(), 2);
+    /// assert_eq!(tree.loops()[0].instructions(), 6);
+    /// assert_eq!(tree.loops()[0].loops()[0].start().column(), 5);
+    /// ```
+    pub fn build(bf_info: &BFValidProgram) -> Self {
+        // the loops being closed with the index of their `[`, and their
+        // nested loops so far
+        let mut open: Vec<(usize, Vec<BFLoop>)> = Vec::new();
+        let mut loops = Vec::new();
+        let instructions = bf_info.instructions();
+        for (index, info) in instructions.iter().enumerate() {
+            match info.get_raw() {
+                BFCharCmdName::LoopStart(_) => open.push((index, Vec::new())),
+                BFCharCmdName::LoopTerminate(_) => {
+                    let Some((start, nested)) = open.pop() else {
+                        continue;
+                    };
+                    let closed = BFLoop {
+                        start: instructions.at(start),
+                        end: info,
+                        instructions: index - start - 1,
+                        loops: nested,
+                    };
+                    match open.last_mut() {
+                        Some((_, parent)) => parent.push(closed),
+                        None => loops.push(closed),
+                    }
+                }
+                _ => (),
+            }
+        }
+        Self {
+            instructions: instructions.len(),
+            loops,
+        }
+    }
+
+    /// Number of instructions of the program
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    /// Loops outside of any loop, in source order
+    pub fn loops(&self) -> &[BFLoop] {
+        &self.loops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    fn usage(source: &str) -> BFTapeUsage {
+        BFTapeUsage::analyze(&BFProgram::new("", source).validate().unwrap())
+    }
+
+    #[test]
+    fn test_tape_usage() {
+        assert_eq!(usage("").cells(), Some(1));
+        assert_eq!(usage(">>><<<<").left(), 1);
+        assert_eq!(usage(">>><<<<").cells(), Some(4));
+
+        // nested balanced loops only cover their first iteration
+        let nested = usage("++[>++[>>+<<-]<-]>");
+        assert_eq!((nested.left(), nested.right()), (0, 3));
+
+        // bounds stop at the first unbalanced loop
+        let scan = usage(">>[-]<[<]>>>>>>");
+        assert_eq!(scan.cells(), None);
+        assert_eq!(scan.right(), 2);
+        assert_eq!(scan.unbounded_loop().unwrap().column(), 7);
+        assert_eq!(usage("+[[>]+]").unbounded_loop().unwrap().column(), 3);
+    }
+
+    #[test]
+    fn test_program_metrics() {
+        let bf_info = BFProgram::with_extensions("", "?,[.<<]#", &['?'])
+            .validate()
+            .unwrap();
+        let metrics = BFProgramMetrics::measure(&bf_info);
+        assert_eq!(
+            metrics.histogram().collect::<Vec<_>>(),
+            [
+                ('+', 0),
+                ('-', 0),
+                ('<', 2),
+                ('>', 0),
+                (',', 1),
+                ('.', 1),
+                ('[', 1),
+                (']', 1)
+            ]
+        );
+        // comments aren't instructions, extensions are
+        assert_eq!((metrics.instructions(), metrics.extensions()), (7, 1));
+        assert_eq!((metrics.drift(), metrics.count('#')), (-2, 0));
+        assert_eq!(metrics.tape().cells(), None);
+    }
+
+    #[test]
+    fn test_loop_tree() {
+        let bf_info = BFProgram::new("", "+[>[-]\n<[-]]>[.]").validate().unwrap();
+        let tree = BFLoopTree::build(&bf_info);
+        assert_eq!(tree.instructions(), 15);
+        let outer = &tree.loops()[0];
+        assert_eq!((outer.start().line(), outer.start().column()), (1, 2));
+        assert_eq!((outer.end().line(), outer.end().column()), (2, 5));
+        assert_eq!(outer.instructions(), 8);
+        let nested: Vec<_> = outer.loops().iter().map(BFLoop::instructions).collect();
+        assert_eq!(nested, [1, 1]);
+        assert_eq!(tree.loops()[1].loops(), []);
+        assert!(
+            BFLoopTree::build(&BFProgram::new("", "+-").validate().unwrap())
+                .loops()
+                .is_empty()
+        );
+    }
+}
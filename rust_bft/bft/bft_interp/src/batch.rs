@@ -0,0 +1,253 @@
+//! Running many programs concurrently
+//!
+//! Every job carries its own source, input and optionally the output it
+//! is expected to write. The jobs are shared out to a pool of worker
+//! threads, each one runs a job at a time with `run` and the results come
+//! back in the order of the jobs.
+
+use crate::{run, BFRunOptions, BFRunStats};
+use bft_types::BFProgram;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A program to run with its input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFBatchJob {
+    /// name reported in the results, also used as the program filename
+    pub name: String,
+
+    /// brainfuck source of the program
+    pub source: String,
+
+    /// bytes the program reads
+    pub input: Vec<u8>,
+
+    /// output the program must write to pass, None to only run it
+    pub expected: Option<Vec<u8>>,
+}
+
+/// How a job ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BFBatchOutcome {
+    /// the output is the expected one
+    Passed,
+
+    /// the output differs from the expected one
+    Failed,
+
+    /// the program halted and there was no expected output
+    Finished,
+
+    /// the program is invalid or stopped with an error
+    Errored(String),
+}
+
+impl BFBatchOutcome {
+    /// short lowercase name of the outcome
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Passed => "pass",
+            Self::Failed => "fail",
+            Self::Finished => "done",
+            Self::Errored(_) => "error",
+        }
+    }
+}
+
+/// Result of one job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFBatchResult {
+    name: String,
+    outcome: BFBatchOutcome,
+    elapsed: Duration,
+    stats: Option<BFRunStats>,
+    output: Vec<u8>,
+}
+
+impl BFBatchResult {
+    /// get the name of the job
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// get how the job ended
+    pub fn outcome(&self) -> &BFBatchOutcome {
+        &self.outcome
+    }
+
+    /// get the wall-clock time of parsing and running the program
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// get the statistics of the run, None if the program didn't halt
+    pub fn stats(&self) -> Option<BFRunStats> {
+        self.stats
+    }
+
+    /// get the bytes written by the program
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+}
+
+/// Counts of the outcomes of a batch
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BFBatchSummary {
+    /// jobs whose output matched
+    pub passed: usize,
+
+    /// jobs whose output didn't match
+    pub failed: usize,
+
+    /// jobs which ran without an expected output
+    pub finished: usize,
+
+    /// jobs which couldn't run to the end
+    pub errored: usize,
+}
+
+impl BFBatchSummary {
+    /// count the outcomes of `results`
+    pub fn new(results: &[BFBatchResult]) -> Self {
+        let mut summary = Self::default();
+        for result in results {
+            match result.outcome {
+                BFBatchOutcome::Passed => summary.passed += 1,
+                BFBatchOutcome::Failed => summary.failed += 1,
+                BFBatchOutcome::Finished => summary.finished += 1,
+                BFBatchOutcome::Errored(_) => summary.errored += 1,
+            }
+        }
+        summary
+    }
+
+    /// whether no job failed or errored
+    pub fn success(&self) -> bool {
+        self.failed == 0 && self.errored == 0
+    }
+}
+
+/// run a single job
+fn run_job(job: &BFBatchJob, options: &BFRunOptions) -> BFBatchResult {
+    let start = Instant::now();
+    let result = BFProgram::new(&job.name, &job.source)
+        .validate()
+        .map_err(|e| e.to_string())
+        .and_then(|bf_info| run(&bf_info, &job.input, options).map_err(|e| e.to_string()));
+
+    let (outcome, stats, output) = match result {
+        Ok(output) => {
+            let outcome = match &job.expected {
+                Some(expected) if expected == output.output() => BFBatchOutcome::Passed,
+                Some(_) => BFBatchOutcome::Failed,
+                None => BFBatchOutcome::Finished,
+            };
+            (outcome, Some(output.stats()), output.into_output())
+        }
+        Err(e) => (BFBatchOutcome::Errored(e), None, Vec::new()),
+    };
+    BFBatchResult {
+        name: job.name.clone(),
+        outcome,
+        elapsed: start.elapsed(),
+        stats,
+        output,
+    }
+}
+
+/// Run `jobs` on `threads` worker threads, at least one, returning the
+/// results in the order of the jobs
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::batch::{run_batch, BFBatchJob, BFBatchOutcome};
+/// use bft_interp::BFRunOptions;
+///
+/// let job = BFBatchJob {
+///     name: "inc.bf".to_string(),
+///     source: ",+.".to_string(),
+///     input: b"a".to_vec(),
+///     expected: Some(b"b\n".to_vec()),
+/// };
+/// let results = run_batch(&[job], &BFRunOptions::default(), 2);
+/// assert_eq!(results[0].outcome(), &BFBatchOutcome::Passed);
+/// ```
+pub fn run_batch(
+    jobs: &[BFBatchJob],
+    options: &BFRunOptions,
+    threads: usize,
+) -> Vec<BFBatchResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; jobs.len()]);
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+                let result = run_job(job, options);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("bft: every job has been run"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BFEofBehavior;
+
+    fn job(name: &str, source: &str, expected: Option<&[u8]>) -> BFBatchJob {
+        BFBatchJob {
+            name: name.to_string(),
+            source: source.to_string(),
+            input: b"abc".to_vec(),
+            expected: expected.map(<[u8]>::to_vec),
+        }
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let jobs = [
+            job("echo", ",[.,]", Some(b"abc\n")),
+            job("wrong", ",[.,]", Some(b"abd\n")),
+            job("plain", "+.", None),
+            job("open", "[", None),
+            job("edge", "<", Some(b"")),
+        ];
+        let options = BFRunOptions {
+            eof: BFEofBehavior::Zero,
+            ..BFRunOptions::default()
+        };
+        let results = run_batch(&jobs, &options, 3);
+        let outcomes: Vec<_> = results.iter().map(|r| r.outcome().label()).collect();
+        assert_eq!(outcomes, ["pass", "fail", "done", "error", "error"]);
+        assert_eq!(results[0].name(), "echo");
+        assert_eq!(results[2].output(), b"\x01\n");
+        assert!(results[3].stats().is_none());
+
+        let summary = BFBatchSummary::new(&results);
+        assert_eq!(
+            summary,
+            BFBatchSummary {
+                passed: 1,
+                failed: 1,
+                finished: 1,
+                errored: 2,
+            }
+        );
+        assert!(!summary.success());
+        assert!(run_batch(&[], &options, 4).is_empty());
+    }
+}
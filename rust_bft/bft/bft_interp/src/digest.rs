@@ -0,0 +1,227 @@
+//! Execution digests
+//!
+//! A digest is a 64-bit FNV-1a hash of everything observable about a run:
+//! every byte read or written, in order, with the source instruction doing
+//! it, then the head and the final tape. Two runs with the same digest
+//! read and wrote the same bytes at the same instructions, so comparing
+//! digests catches semantic regressions without storing whole traces.
+//!
+//! Instructions are identified by their source index, IO instructions are
+//! never merged, so the digest doesn't depend on the optimization level.
+//! Trailing zero cells are left out of the tape, so neither does it depend
+//! on how far an extensible tape grew. Reading past the end of input is
+//! hashed as an event of its own, the final newline as a write of the last
+//! instruction.
+
+use crate::{BFIoError, BFRead, BFVirtualMachine, BFVmErr, BFWrite, CellKind};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Digest of a run
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BFDigest(u64);
+
+impl BFDigest {
+    /// hash value of the digest
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for BFDigest {
+    /// print the digest as 16 hexadecimal digits
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// FNV-1a hasher, stable across platforms and releases unlike `Hasher`s
+/// of the standard library
+struct BFFnv(u64);
+
+impl BFFnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+}
+
+/// how an event of the run touched a byte
+#[derive(Copy, Clone)]
+enum BFEvent {
+    Read(Option<u8>),
+    Write(u8),
+}
+
+/// reader recording what the current instruction read
+struct BFDigestReader<'a, R> {
+    inner: &'a mut R,
+    events: &'a mut Vec<BFEvent>,
+}
+
+impl<R: BFRead> BFRead for BFDigestReader<'_, R> {
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        let byte = self.inner.read_byte()?;
+        self.events.push(BFEvent::Read(byte));
+        Ok(byte)
+    }
+}
+
+/// writer recording what the current instruction wrote
+struct BFDigestWriter<'a, W> {
+    inner: &'a mut W,
+    events: &'a mut Vec<BFEvent>,
+}
+
+impl<W: BFWrite> BFWrite for BFDigestWriter<'_, W> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        self.inner.write_byte(byte)?;
+        self.events.push(BFEvent::Write(byte));
+        Ok(())
+    }
+}
+
+/// hash the `events` of the instruction at source index `index`
+fn hash_events(hasher: &mut BFFnv, index: u64, events: Vec<BFEvent>) {
+    for event in events {
+        hasher.u64(index);
+        match event {
+            BFEvent::Read(Some(byte)) => hasher.bytes(&[b'i', byte]),
+            BFEvent::Read(None) => hasher.bytes(b"e"),
+            BFEvent::Write(byte) => hasher.bytes(&[b'o', byte]),
+        }
+    }
+}
+
+/// run the program to its end, hashing the events of every instruction
+fn digest_steps<T: Default + CellKind>(
+    bf_vm: &mut BFVirtualMachine<T>,
+    reader: &mut impl BFRead,
+    writer: &mut impl BFWrite,
+    hasher: &mut BFFnv,
+) -> Result<(), BFVmErr> {
+    let mut running = !bf_vm.halted();
+    while running {
+        let index = bf_vm.code.source_index(bf_vm.program_cnt);
+        let (mut events, mut writes) = (Vec::new(), Vec::new());
+        running = bf_vm.step(
+            &mut BFDigestReader {
+                inner: reader,
+                events: &mut events,
+            },
+            &mut BFDigestWriter {
+                inner: writer,
+                events: &mut writes,
+            },
+        )?;
+        // extensions may both read and write, their reads come first
+        events.extend(writes);
+        hash_events(hasher, index as u64, events);
+    }
+    Ok(())
+}
+
+/// run the program step by step like `interpret`, returning the digest of
+/// the run
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::digest::interpret_digested;
+/// use bft_interp::{BFOptLevel, BFVirtualMachine};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",[->+<]>.").validate().unwrap();
+/// let mut digests = Vec::new();
+/// for level in [BFOptLevel::O0, BFOptLevel::O3] {
+///     let mut bf_vm = BFVirtualMachine::<u8>::new(2, false, &bf_info);
+///     bf_vm.set_opt_level(level);
+///     let digest = interpret_digested(&mut bf_vm, &mut &b"a"[..], &mut Vec::new()).unwrap();
+///     digests.push(digest);
+/// }
+/// assert_eq!(digests[0], digests[1]);
+/// ```
+pub fn interpret_digested<T>(
+    bf_vm: &mut BFVirtualMachine<T>,
+    reader: &mut impl BFRead,
+    writer: &mut impl BFWrite,
+) -> Result<BFDigest, BFVmErr>
+where
+    T: Default + CellKind + Copy + Into<u64>,
+{
+    let mut hasher = BFFnv::new();
+    // the final newline is written once the loop is over, as the event of
+    // no instruction since the last one depends on the optimization level
+    let final_newline = bf_vm.final_newline;
+    bf_vm.final_newline = false;
+    let result = digest_steps(bf_vm, reader, writer, &mut hasher);
+    bf_vm.final_newline = final_newline;
+    result?;
+    let mut writes = Vec::new();
+    bf_vm.finish_output(&mut BFDigestWriter {
+        inner: writer,
+        events: &mut writes,
+    })?;
+    hash_events(&mut hasher, u64::MAX, writes);
+
+    hasher.u64(bf_vm.head as u64);
+    let cells = bf_vm.cells();
+    let len = cells
+        .iter()
+        .rposition(|&cell| cell.into() != 0)
+        .map_or(0, |last| last + 1);
+    hasher.u64(len as u64);
+    for &cell in &cells[..len] {
+        hasher.u64(cell.into());
+    }
+    Ok(BFDigest(hasher.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BFEofBehavior, BFOptLevel};
+    use bft_types::BFProgram;
+
+    fn digest(source: &str, input: &[u8], level: BFOptLevel, cells: usize) -> BFDigest {
+        let bf_info = BFProgram::new("", source).validate().unwrap();
+        let mut bf_vm = BFVirtualMachine::<u16>::new(cells, true, &bf_info);
+        bf_vm.set_opt_level(level);
+        bf_vm.set_eof(BFEofBehavior::Zero);
+        bf_vm.set_prompt(false);
+        interpret_digested(&mut bf_vm, &mut &input[..], &mut Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn test_digest() {
+        let echo = ",[.,]>>+++[-]";
+        let reference = digest(echo, b"hi", BFOptLevel::O0, 1);
+        // optimizations and tape growth don't change the digest
+        assert_eq!(digest(echo, b"hi", BFOptLevel::O3, 1), reference);
+        assert_eq!(digest(echo, b"hi", BFOptLevel::O1, 100), reference);
+        // the bytes, the instructions printing them and the tape do
+        assert_ne!(digest(echo, b"ho", BFOptLevel::O0, 1), reference);
+        assert_ne!(
+            digest(",[.,]>>+++[-]+", b"hi", BFOptLevel::O0, 1),
+            reference
+        );
+        assert_ne!(
+            digest(",[.,]>>+++[-]<", b"hi", BFOptLevel::O0, 1),
+            reference
+        );
+        assert_ne!(
+            digest(",[ .,]>>+++[-]", b"hi", BFOptLevel::O0, 1),
+            digest(",[+-.,]>>+++[-]", b"hi", BFOptLevel::O0, 1)
+        );
+        assert_eq!(reference.to_string().len(), 16);
+    }
+}
@@ -0,0 +1,228 @@
+//! C code emitter, the output is plain C99 building with any `cc -O2`
+
+use super::{ext, BFEmitOptions};
+use crate::{BFCellWidth, BFEofBehavior, BFOpCode, BFPackedProgram};
+use alloc::string::{String, ToString};
+use bft_types::BFValidProgram;
+use core::fmt::Write;
+
+/// Runtime support of the emitted program, `@` markers are substituted
+const PRELUDE: &str = r#"/* Generated by bft from `@FILE`, do not edit */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef @CELL cell_t;
+
+#define CELLS ((size_t)@CELLS)
+#define EXTENSIBLE @EXTENSIBLE
+
+#if defined(__GNUC__)
+#define BF_UNUSED __attribute__((unused))
+#else
+#define BF_UNUSED
+#endif
+
+static cell_t *tape;
+static size_t tape_len;
+static size_t head;
+static unsigned char tail;
+
+static void fail(const char *msg, const char *pos)
+{
+    fflush(stdout);
+    fprintf(stderr, "%s by %s\n", msg, pos);
+    exit(1);
+}
+
+static BF_UNUSED void right(size_t n, const char *pos)
+{
+    size_t target = head + n;
+    if (target >= tape_len) {
+        if (!EXTENSIBLE) {
+            fail("Head falling off edge", pos);
+        }
+        cell_t *grown = realloc(tape, (target + 1) * sizeof(cell_t));
+        if (grown == NULL) {
+            fail("out of memory", pos);
+        }
+        memset(grown + tape_len, 0, (target + 1 - tape_len) * sizeof(cell_t));
+        tape = grown;
+        tape_len = target + 1;
+    }
+    head = target;
+}
+
+static BF_UNUSED void left(size_t n, const char *pos)
+{
+    if (head < n) {
+        fail("Head falling off edge", pos);
+    }
+    head -= n;
+}
+
+static BF_UNUSED void output(const char *pos)
+{
+    unsigned char value = (unsigned char)tape[head];
+    if (putchar(value) == EOF) {
+        fail("failed to write whole buffer", pos);
+    }
+    tail = value;
+}
+
+static BF_UNUSED void input(const char *pos)
+{
+    int c;
+    fflush(stdout);
+    c = getchar();
+    if (c != EOF) {
+        tape[head] = (cell_t)c;
+        return;
+    }
+    if (ferror(stdin)) {
+        fail("failed to read input", pos);
+    }
+@EOF}
+
+int main(void)
+{
+    tape_len = CELLS;
+    tape = calloc(tape_len, sizeof(cell_t));
+    if (tape == NULL) {
+        fail("out of memory", "startup");
+    }
+
+"#;
+
+/// End of `main`
+const EPILOGUE: &str = r#"
+    if (tail != '\n') {
+        putchar('\n');
+    }
+    free(tape);
+    return 0;
+}
+"#;
+
+/// Escape `s` into a C string literal
+fn c_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_ascii() && !c.is_ascii_control() => out.push(c),
+            c => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    let _ = write!(out, "\\{:03o}", b);
+                }
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Transpile a validated brainfuck program into a standalone C program
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::emit::{emit_c, BFEmitOptions};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+/// let source = emit_c(&bf_info, &BFEmitOptions::default());
+/// assert!(source.contains("int main(void)"));
+/// ```
+pub fn emit_c(bf_info: &BFValidProgram, options: &BFEmitOptions) -> String {
+    let code = BFPackedProgram::new(bf_info).fold_runs();
+
+    let cell = match options.cell_width {
+        BFCellWidth::U8 => "uint8_t",
+        BFCellWidth::U16 => "uint16_t",
+        BFCellWidth::U32 => "uint32_t",
+    };
+    let eof_stmt = match options.eof {
+        BFEofBehavior::Zero => "    tape[head] = 0;\n",
+        BFEofBehavior::MinusOne => "    tape[head] = (cell_t)-1;\n",
+        BFEofBehavior::Unchanged => "",
+        BFEofBehavior::Error => "    fail(\"failed to fill whole buffer\", pos);\n",
+    };
+
+    let mut out = PRELUDE
+        .replace("@FILE", &bf_info.program().display_filename().to_string())
+        .replace("@CELLS", &options.cells.max(1).to_string())
+        .replace("@EXTENSIBLE", if options.extensible { "1" } else { "0" })
+        .replace("@CELL", cell)
+        .replace("@EOF", eof_stmt);
+
+    let mut depth = 1;
+    for (pc, op) in code.ops().iter().enumerate() {
+        let n = op.operand();
+        let pos = c_string(&code.source_info(bf_info, pc).to_string());
+        if op.opcode() == BFOpCode::LoopTerminate {
+            depth -= 1;
+        }
+        let indent = "    ".repeat(depth);
+        let _ = match op.opcode() {
+            BFOpCode::PointerIncrement => writeln!(out, "{indent}right({n}u, {pos});"),
+            BFOpCode::PointerDecrement => writeln!(out, "{indent}left({n}u, {pos});"),
+            BFOpCode::DataIncrement => writeln!(out, "{indent}tape[head] += (cell_t){n}u;"),
+            BFOpCode::DataDecrement => writeln!(out, "{indent}tape[head] -= (cell_t){n}u;"),
+            BFOpCode::DataOutput => writeln!(out, "{indent}output({pos});"),
+            BFOpCode::DataInput => writeln!(out, "{indent}input({pos});"),
+            BFOpCode::LoopStart => writeln!(out, "{indent}while (tape[head]) {{"),
+            BFOpCode::LoopTerminate => writeln!(out, "{indent}}}"),
+            BFOpCode::Extension => writeln!(out, "{indent}/* extension {:?} skipped */", ext(n)),
+            BFOpCode::Clear
+            | BFOpCode::Set
+            | BFOpCode::MulAdd
+            | BFOpCode::ScanRight
+            | BFOpCode::ScanLeft
+            | BFOpCode::ClearRange => {
+                unreachable!("bft: folded runs hold no optimized instructions")
+            }
+        };
+        if op.opcode() == BFOpCode::LoopStart {
+            depth += 1;
+        }
+    }
+
+    out.push_str(EPILOGUE);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_emit_c_options() {
+        let bf_info = BFProgram::new("", "+++[>,.<-]").validate().unwrap();
+        let options = BFEmitOptions {
+            cells: 16,
+            extensible: true,
+            cell_width: BFCellWidth::U32,
+            eof: BFEofBehavior::Unchanged,
+        };
+        let source = emit_c(&bf_info, &options);
+
+        assert!(source.contains("typedef uint32_t cell_t;"));
+        assert!(source.contains("#define CELLS ((size_t)16)"));
+        assert!(source.contains("#define EXTENSIBLE 1"));
+        assert!(source.contains("    tape[head] += (cell_t)3u;\n"));
+        assert!(source.contains("    while (tape[head]) {\n        right(1u, "));
+        assert!(!source.contains("failed to fill whole buffer"));
+    }
+
+    #[test]
+    fn test_c_string() {
+        assert_eq!(c_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(c_string("é"), "\"\\303\\251\"");
+    }
+}
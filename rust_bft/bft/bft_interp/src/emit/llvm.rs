@@ -0,0 +1,318 @@
+//! LLVM IR emitter, the output runs with `lli` or builds with `llc`
+//!
+//! The module is textual IR with opaque pointers linking against libc,
+//! `size_t` is assumed to be 64 bits wide.
+
+use super::{ext, BFEmitOptions};
+use crate::{BFCellWidth, BFEofBehavior, BFOpCode, BFPackedProgram};
+use bft_types::BFValidProgram;
+use core::fmt::Write;
+
+/// Runtime support of the emitted module, `@` markers are substituted
+const PRELUDE: &str = r#"; Generated by bft from `@FILE`, do not edit
+
+@tape = internal global ptr null
+@len = internal global i64 0
+@head = internal global i64 0
+@tail = internal global i8 0
+
+@fmt = private unnamed_addr constant [10 x i8] c"%s by %s\0A\00"
+@msg.edge = private unnamed_addr constant [22 x i8] c"Head falling off edge\00"
+@msg.oom = private unnamed_addr constant [14 x i8] c"out of memory\00"
+@msg.write = private unnamed_addr constant [29 x i8] c"failed to write whole buffer\00"
+@msg.read = private unnamed_addr constant [28 x i8] c"failed to fill whole buffer\00"
+@msg.startup = private unnamed_addr constant [8 x i8] c"startup\00"
+
+declare i32 @getchar()
+declare i32 @putchar(i32)
+declare i32 @dprintf(i32, ptr, ...)
+declare i32 @fflush(ptr)
+declare ptr @calloc(i64, i64)
+declare ptr @realloc(ptr, i64)
+declare void @free(ptr)
+declare void @exit(i32) noreturn
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define internal void @fail(ptr %msg, ptr %pos) noreturn {
+  %flush = call i32 @fflush(ptr null)
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @fmt, ptr %msg, ptr %pos)
+  call void @exit(i32 1)
+  unreachable
+}
+
+define internal ptr @cell() {
+  %tape = load ptr, ptr @tape
+  %head = load i64, ptr @head
+  %p = getelementptr @CELL, ptr %tape, i64 %head
+  ret ptr %p
+}
+
+define internal void @right(i64 %n, ptr %pos) {
+entry:
+  %head = load i64, ptr @head
+  %len = load i64, ptr @len
+  %target = add i64 %head, %n
+  %fits = icmp ult i64 %target, %len
+  br i1 %fits, label %done, label %grow
+grow:
+@GROW
+done:
+  store i64 %target, ptr @head
+  ret void
+}
+
+define internal void @left(i64 %n, ptr %pos) {
+entry:
+  %head = load i64, ptr @head
+  %under = icmp ult i64 %head, %n
+  br i1 %under, label %edge, label %done
+edge:
+  call void @fail(ptr @msg.edge, ptr %pos)
+  unreachable
+done:
+  %target = sub i64 %head, %n
+  store i64 %target, ptr @head
+  ret void
+}
+
+define internal void @output(ptr %pos) {
+entry:
+  %p = call ptr @cell()
+  %v = load @CELL, ptr %p
+  %wide = @WIDEN @CELL %v to i32
+  %byte = and i32 %wide, 255
+  %r = call i32 @putchar(i32 %byte)
+  %err = icmp eq i32 %r, -1
+  br i1 %err, label %fail, label %done
+fail:
+  call void @fail(ptr @msg.write, ptr %pos)
+  unreachable
+done:
+  %t = trunc i32 %byte to i8
+  store i8 %t, ptr @tail
+  ret void
+}
+
+define internal void @input(ptr %pos) {
+entry:
+  %flush = call i32 @fflush(ptr null)
+  %c = call i32 @getchar()
+  %p = call ptr @cell()
+  %eof = icmp eq i32 %c, -1
+  br i1 %eof, label %exhausted, label %store
+store:
+  %v = @NARROW i32 %c to @CELL
+  store @CELL %v, ptr %p
+  ret void
+exhausted:
+@EOF}
+
+define i32 @main() {
+entry:
+  %tape = call ptr @calloc(i64 @CELLS, i64 @SIZE)
+  %null = icmp eq ptr %tape, null
+  br i1 %null, label %oom, label %start
+oom:
+  call void @fail(ptr @msg.oom, ptr @msg.startup)
+  unreachable
+start:
+  store ptr %tape, ptr @tape
+  store i64 @CELLS, ptr @len
+"#;
+
+/// Growing the tape when the head moves past its end
+const GROW: &str = r#"  %new_len = add i64 %target, 1
+  %bytes = mul i64 %new_len, @SIZE
+  %tape = load ptr, ptr @tape
+  %grown = call ptr @realloc(ptr %tape, i64 %bytes)
+  %null = icmp eq ptr %grown, null
+  br i1 %null, label %oom, label %clear
+oom:
+  call void @fail(ptr @msg.oom, ptr %pos)
+  unreachable
+clear:
+  %old = mul i64 %len, @SIZE
+  %start = getelementptr i8, ptr %grown, i64 %old
+  %fill = sub i64 %bytes, %old
+  call void @llvm.memset.p0.i64(ptr %start, i8 0, i64 %fill, i1 false)
+  store ptr %grown, ptr @tape
+  store i64 %new_len, ptr @len
+  br label %done"#;
+
+/// End of `main`
+const EPILOGUE: &str = r#"  %last = load i8, ptr @tail
+  %newline = icmp eq i8 %last, 10
+  br i1 %newline, label %exit, label %append
+append:
+  %appended = call i32 @putchar(i32 10)
+  br label %exit
+exit:
+  %final = load ptr, ptr @tape
+  call void @free(ptr %final)
+  ret i32 0
+}
+"#;
+
+/// Escape `s` into the content of an LLVM string constant with its size
+fn llvm_string(s: &str) -> (usize, String) {
+    let mut out = String::new();
+    for b in s.bytes() {
+        match b {
+            b'"' | b'\\' => {
+                let _ = write!(out, "\\{:02X}", b);
+            }
+            b' '..=b'~' => out.push(b as char),
+            _ => {
+                let _ = write!(out, "\\{:02X}", b);
+            }
+        }
+    }
+    out.push_str("\\00");
+    (s.len() + 1, out)
+}
+
+/// Transpile a validated brainfuck program into a textual LLVM IR module
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::emit::{emit_llvm, BFEmitOptions};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+/// let source = emit_llvm(&bf_info, &BFEmitOptions::default());
+/// assert!(source.contains("define i32 @main()"));
+/// ```
+pub fn emit_llvm(bf_info: &BFValidProgram, options: &BFEmitOptions) -> String {
+    let code = BFPackedProgram::new(bf_info).fold_runs();
+
+    let (cell, widen, narrow) = match options.cell_width {
+        BFCellWidth::U8 => ("i8", "zext", "trunc"),
+        BFCellWidth::U16 => ("i16", "zext", "trunc"),
+        BFCellWidth::U32 => ("i32", "bitcast", "bitcast"),
+    };
+    let mask = u64::MAX >> (64 - options.cell_width.bits());
+    let eof_stmt = match options.eof {
+        BFEofBehavior::Zero => "  store @CELL 0, ptr %p\n  ret void\n",
+        BFEofBehavior::MinusOne => "  store @CELL -1, ptr %p\n  ret void\n",
+        BFEofBehavior::Unchanged => "  ret void\n",
+        BFEofBehavior::Error => "  call void @fail(ptr @msg.read, ptr %pos)\n  unreachable\n",
+    };
+    let grow = if options.extensible {
+        GROW
+    } else {
+        "  call void @fail(ptr @msg.edge, ptr %pos)\n  unreachable"
+    };
+
+    let mut out = PRELUDE
+        .replace("@FILE", &bf_info.program().display_filename().to_string())
+        .replace("@GROW", grow)
+        .replace("@EOF", eof_stmt)
+        .replace("@CELLS", &options.cells.max(1).to_string())
+        .replace("@CELL", cell)
+        .replace("@SIZE", &(options.cell_width.bits() / 8).to_string())
+        .replace("@WIDEN", widen)
+        .replace("@NARROW", narrow);
+
+    let mut positions = String::new();
+    for (pc, op) in code.ops().iter().enumerate() {
+        let n = op.operand();
+        if matches!(
+            op.opcode(),
+            BFOpCode::PointerIncrement
+                | BFOpCode::PointerDecrement
+                | BFOpCode::DataOutput
+                | BFOpCode::DataInput
+        ) {
+            let (len, pos) = llvm_string(&code.source_info(bf_info, pc).to_string());
+            let _ = writeln!(
+                positions,
+                "@pos.{pc} = private unnamed_addr constant [{len} x i8] c\"{pos}\""
+            );
+        }
+        let _ = match op.opcode() {
+            BFOpCode::PointerIncrement => {
+                writeln!(out, "  call void @right(i64 {n}, ptr @pos.{pc})")
+            }
+            BFOpCode::PointerDecrement => {
+                writeln!(out, "  call void @left(i64 {n}, ptr @pos.{pc})")
+            }
+            BFOpCode::DataIncrement | BFOpCode::DataDecrement => {
+                let inst = if op.opcode() == BFOpCode::DataIncrement {
+                    "add"
+                } else {
+                    "sub"
+                };
+                let n = u64::from(n) & mask;
+                writeln!(
+                    out,
+                    "  %p{pc} = call ptr @cell()\n  \
+                     %v{pc} = load {cell}, ptr %p{pc}\n  \
+                     %w{pc} = {inst} {cell} %v{pc}, {n}\n  \
+                     store {cell} %w{pc}, ptr %p{pc}"
+                )
+            }
+            BFOpCode::DataOutput => writeln!(out, "  call void @output(ptr @pos.{pc})"),
+            BFOpCode::DataInput => writeln!(out, "  call void @input(ptr @pos.{pc})"),
+            BFOpCode::LoopStart => writeln!(
+                out,
+                "  br label %loop{pc}\n\
+                 loop{pc}:\n  \
+                 %p{pc} = call ptr @cell()\n  \
+                 %v{pc} = load {cell}, ptr %p{pc}\n  \
+                 %nz{pc} = icmp ne {cell} %v{pc}, 0\n  \
+                 br i1 %nz{pc}, label %body{pc}, label %end{pc}\n\
+                 body{pc}:"
+            ),
+            BFOpCode::LoopTerminate => writeln!(out, "  br label %loop{n}\nend{n}:"),
+            BFOpCode::Extension => writeln!(out, "  ; extension {:?} skipped", ext(n)),
+            BFOpCode::Clear
+            | BFOpCode::Set
+            | BFOpCode::MulAdd
+            | BFOpCode::ScanRight
+            | BFOpCode::ScanLeft
+            | BFOpCode::ClearRange => {
+                unreachable!("bft: folded runs hold no optimized instructions")
+            }
+        };
+    }
+
+    out.push_str(EPILOGUE);
+    if !positions.is_empty() {
+        out.push('\n');
+        out.push_str(&positions);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_emit_llvm_options() {
+        let bf_info = BFProgram::new("", "+++[>,.<-]").validate().unwrap();
+        let options = BFEmitOptions {
+            cells: 16,
+            extensible: true,
+            cell_width: BFCellWidth::U16,
+            eof: BFEofBehavior::MinusOne,
+        };
+        let source = emit_llvm(&bf_info, &options);
+
+        assert!(source.contains("%tape = call ptr @calloc(i64 16, i64 2)"));
+        assert!(source.contains("@realloc"));
+        assert!(source.contains("store i16 -1, ptr %p"));
+        assert!(source.contains("  %w0 = add i16 %v0, 3\n"));
+        assert!(source.contains("br i1 %nz1, label %body1, label %end1"));
+        assert!(source.contains("  br label %loop1\nend1:\n"));
+        assert!(source.contains("@pos.2 = private unnamed_addr constant [39 x i8]"));
+    }
+
+    #[test]
+    fn test_llvm_string() {
+        assert_eq!(llvm_string("a\"b\\"), (5, "a\\22b\\5C\\00".to_string()));
+        assert_eq!(llvm_string("é").0, 3);
+    }
+}
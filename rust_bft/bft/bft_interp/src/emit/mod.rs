@@ -0,0 +1,82 @@
+//! Emit brainfuck programs as source code of other languages
+//!
+//! The folded packed program is translated into a standalone program
+//! with the same cell width, EOF behavior and tape semantics as running
+//! it on the brainfuck virtual machine.
+
+use crate::{BFCellWidth, BFEofBehavior};
+use alloc::string::String;
+use bft_types::BFValidProgram;
+
+mod c;
+#[cfg(feature = "llvm")]
+mod llvm;
+mod rust;
+mod wat;
+pub use c::emit_c;
+#[cfg(feature = "llvm")]
+pub use llvm::emit_llvm;
+pub use rust::emit_rust;
+pub use wat::emit_wat;
+
+/// Character of an extension instruction with operand `n`, emitted
+/// programs have no handlers so extensions are skipped like comments
+fn ext(n: u32) -> char {
+    char::from_u32(n).expect("bft: extension holds a character")
+}
+
+/// Languages brainfuck programs can be emitted as
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFEmitTarget {
+    /// standalone Rust source file, see `emit_rust`
+    #[default]
+    Rust,
+
+    /// portable C source file, see `emit_c`
+    C,
+
+    /// WebAssembly text module, see `emit_wat`
+    Wat,
+
+    /// textual LLVM IR module, see `emit_llvm`
+    #[cfg(feature = "llvm")]
+    Llvm,
+}
+
+/// Emit a validated brainfuck program as source code of `target`
+pub fn emit(bf_info: &BFValidProgram, target: BFEmitTarget, options: &BFEmitOptions) -> String {
+    match target {
+        BFEmitTarget::Rust => emit_rust(bf_info, options),
+        BFEmitTarget::C => emit_c(bf_info, options),
+        BFEmitTarget::Wat => emit_wat(bf_info, options),
+        #[cfg(feature = "llvm")]
+        BFEmitTarget::Llvm => emit_llvm(bf_info, options),
+    }
+}
+
+/// Runtime semantics baked into the emitted program
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFEmitOptions {
+    /// number of cells allocated for the tape
+    pub cells: usize,
+
+    /// whether the tape extends when the head moves past its end
+    pub extensible: bool,
+
+    /// width of each cell
+    pub cell_width: BFCellWidth,
+
+    /// what reading does once input is exhausted
+    pub eof: BFEofBehavior,
+}
+
+impl Default for BFEmitOptions {
+    fn default() -> Self {
+        Self {
+            cells: 30000,
+            extensible: false,
+            cell_width: BFCellWidth::default(),
+            eof: BFEofBehavior::default(),
+        }
+    }
+}
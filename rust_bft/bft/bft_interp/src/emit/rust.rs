@@ -0,0 +1,208 @@
+//! Rust code emitter, the output builds with a plain `rustc -O`
+
+use super::{ext, BFEmitOptions};
+use crate::{BFCellWidth, BFEofBehavior, BFOpCode, BFPackedProgram};
+use alloc::format;
+use alloc::string::{String, ToString};
+use bft_types::BFValidProgram;
+use core::fmt::Write;
+
+/// Runtime support of the emitted program, `@` markers are substituted
+const PRELUDE: &str = r#"//! Generated by bft from `@FILE`, do not edit
+
+#![allow(dead_code, unused_mut, unused_variables)]
+
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+type Cell = @CELL;
+
+const CELLS: usize = @CELLS;
+const EXTENSIBLE: bool = @EXTENSIBLE;
+
+fn right(tape: &mut Vec<Cell>, head: &mut usize, n: usize, pos: &str) -> Result<(), String> {
+    let target = *head + n;
+    if target >= tape.len() {
+        if !EXTENSIBLE {
+            return Err(format!("Head falling off edge by {}", pos));
+        }
+        tape.resize(target + 1, 0);
+    }
+    *head = target;
+    Ok(())
+}
+
+fn left(head: &mut usize, n: usize, pos: &str) -> Result<(), String> {
+    if *head < n {
+        return Err(format!("Head falling off edge by {}", pos));
+    }
+    *head -= n;
+    Ok(())
+}
+
+fn output(writer: &mut impl Write, cell: Cell, tail: &mut u8, pos: &str) -> Result<(), String> {
+    let value = cell as u8;
+    writer
+        .write_all(&[value])
+        .map_err(|e| format!("{} by {}", e, pos))?;
+    *tail = value;
+    Ok(())
+}
+
+fn input(reader: &mut impl Read, cell: &mut Cell, pos: &str) -> Result<(), String> {
+    let mut buf = [0u8; 1];
+    match reader.read_exact(&mut buf) {
+        Ok(()) => *cell = buf[0].into(),
+@EOF        Err(e) => return Err(format!("{} by {}", e, pos)),
+    }
+    Ok(())
+}
+
+fn run(reader: &mut impl Read, writer: &mut impl Write) -> Result<(), String> {
+    let mut tape: Vec<Cell> = vec![0; CELLS];
+    let mut head: usize = 0;
+    let mut tail: u8 = 0;
+
+"#;
+
+/// End of `run` and program entry
+const EPILOGUE: &str = r#"
+    if tail != b'\n' {
+        writer.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let mut reader = io::stdin().lock();
+    let mut writer = io::stdout().lock();
+    match run(&mut reader, &mut writer) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(1)
+        }
+    }
+}
+"#;
+
+/// Transpile a validated brainfuck program into a standalone Rust program
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::emit::{emit_rust, BFEmitOptions};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+/// let source = emit_rust(&bf_info, &BFEmitOptions::default());
+/// assert!(source.contains("fn main()"));
+/// ```
+pub fn emit_rust(bf_info: &BFValidProgram, options: &BFEmitOptions) -> String {
+    let code = BFPackedProgram::new(bf_info).fold_runs();
+
+    let cell = match options.cell_width {
+        BFCellWidth::U8 => "u8",
+        BFCellWidth::U16 => "u16",
+        BFCellWidth::U32 => "u32",
+    };
+    let eof_arm = match options.eof {
+        BFEofBehavior::Zero => "*cell = 0",
+        BFEofBehavior::MinusOne => "*cell = Cell::MAX",
+        BFEofBehavior::Unchanged => "()",
+        BFEofBehavior::Error => "",
+    };
+    let eof_arm = if eof_arm.is_empty() {
+        String::new()
+    } else {
+        format!("        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {eof_arm},\n")
+    };
+
+    let mut out = PRELUDE
+        .replace("@FILE", &bf_info.program().display_filename().to_string())
+        .replace("@CELLS", &options.cells.max(1).to_string())
+        .replace("@EXTENSIBLE", &options.extensible.to_string())
+        .replace("@CELL", cell)
+        .replace("@EOF", &eof_arm);
+
+    let mut depth = 1;
+    for (pc, op) in code.ops().iter().enumerate() {
+        let n = op.operand();
+        let pos = code.source_info(bf_info, pc).to_string();
+        if op.opcode() == BFOpCode::LoopTerminate {
+            depth -= 1;
+        }
+        let indent = "    ".repeat(depth);
+        let _ = match op.opcode() {
+            BFOpCode::PointerIncrement => {
+                writeln!(out, "{indent}right(&mut tape, &mut head, {n}, {pos:?})?;")
+            }
+            BFOpCode::PointerDecrement => writeln!(out, "{indent}left(&mut head, {n}, {pos:?})?;"),
+            BFOpCode::DataIncrement => writeln!(
+                out,
+                "{indent}tape[head] = tape[head].wrapping_add({n}u32 as Cell);"
+            ),
+            BFOpCode::DataDecrement => writeln!(
+                out,
+                "{indent}tape[head] = tape[head].wrapping_sub({n}u32 as Cell);"
+            ),
+            BFOpCode::DataOutput => writeln!(
+                out,
+                "{indent}output(writer, tape[head], &mut tail, {pos:?})?;"
+            ),
+            BFOpCode::DataInput => {
+                writeln!(out, "{indent}input(reader, &mut tape[head], {pos:?})?;")
+            }
+            BFOpCode::LoopStart => writeln!(out, "{indent}while tape[head] != 0 {{"),
+            BFOpCode::LoopTerminate => writeln!(out, "{indent}}}"),
+            BFOpCode::Extension => writeln!(out, "{indent}// extension {:?} skipped", ext(n)),
+            BFOpCode::Clear
+            | BFOpCode::Set
+            | BFOpCode::MulAdd
+            | BFOpCode::ScanRight
+            | BFOpCode::ScanLeft
+            | BFOpCode::ClearRange => {
+                unreachable!("bft: folded runs hold no optimized instructions")
+            }
+        };
+        if op.opcode() == BFOpCode::LoopStart {
+            depth += 1;
+        }
+    }
+
+    out.push_str(EPILOGUE);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_emit_rust_options() {
+        let bf_info = BFProgram::new("", "+++[>,.<-]").validate().unwrap();
+        let options = BFEmitOptions {
+            cells: 16,
+            extensible: true,
+            cell_width: BFCellWidth::U16,
+            eof: BFEofBehavior::MinusOne,
+        };
+        let source = emit_rust(&bf_info, &options);
+
+        assert!(source.contains("type Cell = u16;"));
+        assert!(source.contains("const CELLS: usize = 16;"));
+        assert!(source.contains("const EXTENSIBLE: bool = true;"));
+        assert!(source.contains("UnexpectedEof => *cell = Cell::MAX,"));
+        assert!(source.contains("    tape[head] = tape[head].wrapping_add(3u32 as Cell);\n"));
+        assert!(source.contains("    while tape[head] != 0 {\n        right("));
+    }
+
+    #[test]
+    fn test_emit_rust_eof_error() {
+        let bf_info = BFProgram::new("", ",").validate().unwrap();
+        let source = emit_rust(&bf_info, &BFEmitOptions::default());
+        assert!(!source.contains("UnexpectedEof"));
+        assert!(source.contains("type Cell = u8;"));
+    }
+}
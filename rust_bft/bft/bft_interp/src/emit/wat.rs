@@ -0,0 +1,239 @@
+//! WebAssembly text emitter, the output assembles with any WAT toolchain
+//!
+//! The module imports `env.read_byte` returning the next input byte or a
+//! negative value once input is exhausted, and `env.write_byte` taking the
+//! byte to output. It exports the tape as `memory` and a `run` function
+//! returning 0 on success, 1 when the head falls off the tape and 2 when
+//! reading fails, the source position of a fault is left in the exported
+//! `fault_line` and `fault_column` globals.
+
+use super::{ext, BFEmitOptions};
+use crate::{BFCellWidth, BFEofBehavior, BFOpCode, BFPackedProgram};
+use alloc::format;
+use alloc::string::{String, ToString};
+use bft_types::BFValidProgram;
+use core::fmt::Write;
+
+/// Size of a WebAssembly memory page in bytes
+const PAGE_SIZE: usize = 65536;
+
+/// Runtime support of the emitted module, `@` markers are substituted
+const PRELUDE: &str = r#";; Generated by bft from `@FILE`, do not edit
+(module
+  (import "env" "read_byte" (func $read_byte (result i32)))
+  (import "env" "write_byte" (func $write_byte (param i32)))
+
+  (memory (export "memory") @PAGES)
+
+  (global $len (mut i32) (i32.const @CELLS))
+  (global $head (mut i32) (i32.const 0))
+  (global $fault_line (export "fault_line") (mut i32) (i32.const 0))
+  (global $fault_column (export "fault_column") (mut i32) (i32.const 0))
+
+  (func $fault (param $status i32) (param $line i32) (param $column i32) (result i32)
+    (global.set $fault_line (local.get $line))
+    (global.set $fault_column (local.get $column))
+    (local.get $status))
+
+  (func $get (result i32)
+    (@LOAD (i32.shl (global.get $head) (i32.const @SHIFT))))
+
+  (func $set (param $value i32)
+    (@STORE (i32.shl (global.get $head) (i32.const @SHIFT)) (local.get $value)))
+
+  (func $right (param $n i32) (result i32)
+    (local $target i32)
+    (local $pages i32)
+    (local.set $target (i32.add (global.get $head) (local.get $n)))
+    (if (i32.lt_u (local.get $target) (global.get $head))
+      (then (return (i32.const 1))))
+    (if (i32.ge_u (local.get $target) (global.get $len))
+      (then
+@GROW))
+    (global.set $head (local.get $target))
+    (i32.const 0))
+
+  (func $left (param $n i32) (result i32)
+    (if (i32.lt_u (global.get $head) (local.get $n))
+      (then (return (i32.const 1))))
+    (global.set $head (i32.sub (global.get $head) (local.get $n)))
+    (i32.const 0))
+
+  (func $input (result i32)
+    (local $c i32)
+    (local.set $c (call $read_byte))
+    (if (i32.ge_s (local.get $c) (i32.const 0))
+      (then
+        (call $set (i32.and (local.get $c) (i32.const 255)))
+        (return (i32.const 0))))
+@EOF    (i32.const 0))
+
+  (func (export "run") (result i32)
+    (global.set $head (i32.const 0))
+"#;
+
+/// Growing the memory when the head moves past the end of the tape
+const GROW: &str = r#"        (local.set $pages
+          (i32.shr_u
+            (i32.add
+              (i32.shl (i32.add (local.get $target) (i32.const 1)) (i32.const @SHIFT))
+              (i32.const 65535))
+            (i32.const 16)))
+        (if (i32.gt_u (local.get $pages) (memory.size))
+          (then
+            (if (i32.eq (memory.grow (i32.sub (local.get $pages) (memory.size))) (i32.const -1))
+              (then (return (i32.const 1))))))
+        (global.set $len (i32.add (local.get $target) (i32.const 1)))"#;
+
+/// End of `run` and the module
+const EPILOGUE: &str = r#"    (i32.const 0))
+)
+"#;
+
+/// Transpile a validated brainfuck program into a WebAssembly text module
+///
+/// Unlike the other emitters no newline is appended after the output,
+/// the embedder owns the whole output stream.
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::emit::{emit_wat, BFEmitOptions};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+/// let source = emit_wat(&bf_info, &BFEmitOptions::default());
+/// assert!(source.contains("(func (export \"run\") (result i32)"));
+/// ```
+pub fn emit_wat(bf_info: &BFValidProgram, options: &BFEmitOptions) -> String {
+    let code = BFPackedProgram::new(bf_info).fold_runs();
+
+    let (load, store, shift) = match options.cell_width {
+        BFCellWidth::U8 => ("i32.load8_u", "i32.store8", 0),
+        BFCellWidth::U16 => ("i32.load16_u", "i32.store16", 1),
+        BFCellWidth::U32 => ("i32.load", "i32.store", 2),
+    };
+    let eof_stmt = match options.eof {
+        BFEofBehavior::Zero => "    (call $set (i32.const 0))\n",
+        BFEofBehavior::MinusOne => "    (call $set (i32.const -1))\n",
+        BFEofBehavior::Unchanged => "",
+        BFEofBehavior::Error => "    (return (i32.const 2))\n",
+    };
+    let grow = if options.extensible {
+        GROW
+    } else {
+        "        (return (i32.const 1))"
+    };
+    let cells = options.cells.max(1);
+    let pages = (cells << shift).div_ceil(PAGE_SIZE);
+
+    let mut out = PRELUDE
+        .replace("@FILE", &bf_info.program().display_filename().to_string())
+        .replace("@GROW", grow)
+        .replace("@PAGES", &pages.to_string())
+        .replace("@CELLS", &cells.to_string())
+        .replace("@LOAD", load)
+        .replace("@STORE", store)
+        .replace("@SHIFT", &shift.to_string())
+        .replace("@EOF", eof_stmt);
+
+    let mut depth = 2;
+    for (pc, op) in code.ops().iter().enumerate() {
+        let n = op.operand();
+        let pos = code.source_info(bf_info, pc);
+        let (line, column) = (pos.line(), pos.column());
+        if op.opcode() == BFOpCode::LoopTerminate {
+            depth -= 3;
+        }
+        let indent = "  ".repeat(depth);
+        let fault = |status| {
+            format!("(then (return (call $fault (i32.const {status}) (i32.const {line}) (i32.const {column}))))")
+        };
+        let _ = match op.opcode() {
+            BFOpCode::PointerIncrement => {
+                writeln!(
+                    out,
+                    "{indent}(if (call $right (i32.const {n})) {})",
+                    fault(1)
+                )
+            }
+            BFOpCode::PointerDecrement => {
+                writeln!(
+                    out,
+                    "{indent}(if (call $left (i32.const {n})) {})",
+                    fault(1)
+                )
+            }
+            BFOpCode::DataIncrement => writeln!(
+                out,
+                "{indent}(call $set (i32.add (call $get) (i32.const {n})))"
+            ),
+            BFOpCode::DataDecrement => writeln!(
+                out,
+                "{indent}(call $set (i32.sub (call $get) (i32.const {n})))"
+            ),
+            BFOpCode::DataOutput => writeln!(
+                out,
+                "{indent}(call $write_byte (i32.and (call $get) (i32.const 255)))"
+            ),
+            BFOpCode::DataInput => writeln!(out, "{indent}(if (call $input) {})", fault(2)),
+            BFOpCode::LoopStart => writeln!(
+                out,
+                "{indent}(if (call $get)\n{indent}  (then\n{indent}    (loop $loop{pc}"
+            ),
+            BFOpCode::LoopTerminate => {
+                writeln!(out, "{indent}      (br_if $loop{n} (call $get)))))")
+            }
+            BFOpCode::Extension => writeln!(out, "{indent};; extension {:?} skipped", ext(n)),
+            BFOpCode::Clear
+            | BFOpCode::Set
+            | BFOpCode::MulAdd
+            | BFOpCode::ScanRight
+            | BFOpCode::ScanLeft
+            | BFOpCode::ClearRange => {
+                unreachable!("bft: folded runs hold no optimized instructions")
+            }
+        };
+        if op.opcode() == BFOpCode::LoopStart {
+            depth += 3;
+        }
+    }
+
+    out.push_str(EPILOGUE);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_emit_wat_options() {
+        let bf_info = BFProgram::new("", "+++[>,.<-]").validate().unwrap();
+        let options = BFEmitOptions {
+            cells: 40000,
+            extensible: true,
+            cell_width: BFCellWidth::U16,
+            eof: BFEofBehavior::MinusOne,
+        };
+        let source = emit_wat(&bf_info, &options);
+
+        assert!(source.contains("(memory (export \"memory\") 2)"));
+        assert!(source.contains("(global $len (mut i32) (i32.const 40000))"));
+        assert!(source.contains("(i32.load16_u (i32.shl (global.get $head) (i32.const 1)))"));
+        assert!(source.contains("memory.grow"));
+        assert!(source.contains("(call $set (i32.const -1))"));
+        assert!(source.contains("(call $set (i32.add (call $get) (i32.const 3)))"));
+        assert!(source.contains("(loop $loop1"));
+        assert!(source.contains("(br_if $loop1 (call $get))"));
+    }
+
+    #[test]
+    fn test_emit_wat_fixed_tape() {
+        let bf_info = BFProgram::new("", "<").validate().unwrap();
+        let source = emit_wat(&bf_info, &BFEmitOptions::default());
+        assert!(!source.contains("memory.grow"));
+        assert!(source.contains("(call $fault (i32.const 1) (i32.const 1) (i32.const 1))"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Extra command characters
+//!
+//! The eight core commands are fixed, any other character can be bound to
+//! a handler of the host for a run: the program is parsed with
+//! `BFProgram::with_extensions` keeping the characters of
+//! `BFExtensions::commands`, and the registry is handed to the virtual
+//! machine with `set_extensions`. An extension character without a handler
+//! is a comment, like every other character.
+//!
+//! `BFRandom` is the handler of the common `?` dialect storing a random
+//! byte into the head cell.
+
+use crate::{BFIoError, BFRead, BFWrite, CellKind};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// State of the virtual machine an extension handler works on
+pub struct BFExtensionCtx<'c, T> {
+    pub(crate) cells: &'c mut [T],
+    pub(crate) head: usize,
+    pub(crate) reader: &'c mut dyn BFRead,
+    pub(crate) writer: &'c mut dyn BFWrite,
+}
+
+impl<T: CellKind> BFExtensionCtx<'_, T> {
+    /// Position of the head cell
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// A reference of the tape
+    pub fn cells(&self) -> &[T] {
+        self.cells
+    }
+
+    /// A mutable reference of the tape
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        self.cells
+    }
+
+    /// Low byte of the head cell
+    pub fn get(&mut self) -> u8 {
+        self.cells[self.head].get_value()
+    }
+
+    /// Store `value` into the head cell
+    pub fn set(&mut self, value: u8) {
+        self.cells[self.head].set_value(value);
+    }
+
+    /// Read a byte of the input of the program, None at the end of input
+    pub fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        self.reader.read_byte()
+    }
+
+    /// Write a byte to the output of the program
+    pub fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        self.writer.write_byte(byte)
+    }
+}
+
+/// Handler of an extension command, handlers are `Send` and `Sync` so a
+/// virtual machine holding them still moves to other threads
+pub trait BFExtension<T>: Send + Sync {
+    /// execute the extension command `cmd`, an error stops the program as
+    /// an IO error of the instruction
+    fn execute(&mut self, cmd: char, ctx: &mut BFExtensionCtx<'_, T>) -> Result<(), BFIoError>;
+}
+
+/// closures are handlers
+impl<T, F> BFExtension<T> for F
+where
+    F: FnMut(char, &mut BFExtensionCtx<'_, T>) -> Result<(), BFIoError> + Send + Sync,
+{
+    fn execute(&mut self, cmd: char, ctx: &mut BFExtensionCtx<'_, T>) -> Result<(), BFIoError> {
+        self(cmd, ctx)
+    }
+}
+
+/// Handler storing a pseudo-random byte into the head cell, the bytes only
+/// depend on the seed so runs are reproducible
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::extension::{BFExtensions, BFRandom};
+/// use bft_interp::BFVirtualMachine;
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::with_extensions("", "?.>?.", &['?']).validate().unwrap();
+/// let mut outputs = Vec::new();
+/// for _ in 0..2 {
+///     let mut extensions = BFExtensions::new();
+///     extensions.register('?', BFRandom::new(42));
+///     let mut bf_vm = BFVirtualMachine::<u8>::new(2, false, &bf_info);
+///     bf_vm.set_final_newline(false);
+///     bf_vm.set_extensions(extensions);
+///     let mut output = Vec::new();
+///     bf_vm.interpret(&mut &b""[..], &mut output).unwrap();
+///     outputs.push(output);
+/// }
+/// assert_eq!(outputs[0], outputs[1]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BFRandom {
+    state: u64,
+}
+
+impl BFRandom {
+    /// Create a generator out of `seed`
+    pub fn new(seed: u64) -> Self {
+        // one splitmix64 step spreads close seeds, xorshift never leaves
+        // a zero state
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 0x9e37_79b9_7f4a_7c15 } else { z },
+        }
+    }
+
+    /// Next byte of the xorshift64* generator
+    pub fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 56) as u8
+    }
+}
+
+impl<T: CellKind> BFExtension<T> for BFRandom {
+    fn execute(&mut self, _: char, ctx: &mut BFExtensionCtx<'_, T>) -> Result<(), BFIoError> {
+        let byte = self.next_byte();
+        ctx.set(byte);
+        Ok(())
+    }
+}
+
+/// Registry of extension handlers by command character
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::extension::{BFExtensionCtx, BFExtensions};
+/// use bft_interp::BFVirtualMachine;
+/// use bft_types::BFProgram;
+///
+/// let mut extensions = BFExtensions::new();
+/// // `$` writes the head position
+/// extensions.register('$', |_, ctx: &mut BFExtensionCtx<'_, u8>| {
+///     let head = ctx.head() as u8;
+///     ctx.write_byte(b'0' + head)
+/// });
+/// let bf_info = BFProgram::with_extensions("", ">>$", &extensions.commands())
+///     .validate()
+///     .unwrap();
+/// let mut bf_vm = BFVirtualMachine::<u8>::new(0, false, &bf_info);
+/// bf_vm.set_extensions(extensions);
+/// let mut output = Vec::new();
+/// bf_vm.interpret(&mut &b""[..], &mut output).unwrap();
+/// assert_eq!(output, b"2\n");
+/// ```
+pub struct BFExtensions<'r, T> {
+    handlers: Vec<(char, Box<dyn BFExtension<T> + 'r>)>,
+}
+
+impl<'r, T> BFExtensions<'r, T> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Bind `cmd` to `handler`, replacing any handler bound to it
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cmd` is one of the eight core commands
+    pub fn register(&mut self, cmd: char, handler: impl BFExtension<T> + 'r) -> &mut Self {
+        assert!(
+            !"<>+-.,[]".contains(cmd),
+            "bft: core command {:?} can't be an extension",
+            cmd
+        );
+        self.handlers.retain(|(bound, _)| *bound != cmd);
+        self.handlers.push((cmd, Box::new(handler)));
+        self
+    }
+
+    /// Characters bound to a handler, to be kept by the parser
+    pub fn commands(&self) -> Vec<char> {
+        self.handlers.iter().map(|(cmd, _)| *cmd).collect()
+    }
+
+    /// Whether no character is bound
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Handler bound to `cmd`
+    pub(crate) fn handler(&mut self, cmd: char) -> Option<&mut (dyn BFExtension<T> + 'r)> {
+        self.handlers
+            .iter_mut()
+            .find(|(bound, _)| *bound == cmd)
+            .map(|(_, handler)| handler.as_mut())
+    }
+}
+
+impl<T> Default for BFExtensions<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for BFExtensions<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.commands()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BFOptLevel, BFVirtualMachine};
+    use bft_types::BFProgram;
+
+    /// xorshift generator standing in for a random byte source
+    struct BFXorShift(u32);
+
+    impl BFExtension<u8> for BFXorShift {
+        fn execute(&mut self, _: char, ctx: &mut BFExtensionCtx<'_, u8>) -> Result<(), BFIoError> {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            ctx.set(self.0 as u8);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_extensions() {
+        let source = "%>%>++[-<+>]$ ?";
+        for level in [BFOptLevel::O0, BFOptLevel::O3] {
+            let mut dumps = Vec::new();
+            let mut extensions = BFExtensions::new();
+            extensions
+                .register('%', BFXorShift(1))
+                .register('$', |_, ctx: &mut BFExtensionCtx<'_, u8>| {
+                    dumps.push(ctx.cells()[..3].to_vec());
+                    Ok(())
+                })
+                .register('?', |_, _: &mut BFExtensionCtx<'_, u8>| {
+                    Err(BFIoError::other("unsupported"))
+                });
+            let bf_info = BFProgram::with_extensions("", source, &extensions.commands())
+                .validate()
+                .unwrap();
+            let mut bf_vm = BFVirtualMachine::<u8>::new(3, false, &bf_info);
+            bf_vm.set_opt_level(level);
+            bf_vm.set_extensions(extensions);
+            let result = bf_vm.interpret(&mut &b""[..], &mut Vec::new());
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("Run extension '?'"));
+            drop(bf_vm);
+            assert_eq!(dumps, [vec![33, 3, 0]]);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let bytes = |seed: u64| -> Vec<u8> {
+            let mut random = BFRandom::new(seed);
+            (0..64).map(|_| random.next_byte()).collect()
+        };
+        assert_eq!(bytes(7), bytes(7));
+        assert_ne!(bytes(7), bytes(8));
+        let zero = bytes(0);
+        assert!(zero.iter().any(|&byte| byte != zero[0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "core command")]
+    fn test_core_command_extension() {
+        BFExtensions::<u8>::new().register('+', |_, _: &mut BFExtensionCtx<'_, u8>| Ok(()));
+    }
+}
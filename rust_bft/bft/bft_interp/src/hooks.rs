@@ -0,0 +1,167 @@
+//! Memory-mapped cells
+//!
+//! A cell index can be bound to a hook of the host, turning the cell into
+//! a port: every instruction using the value of the cell first reads it
+//! from the hook and every instruction changing it writes the new value
+//! to the hook, so a program drives LEDs, sockets or any other resource of
+//! its host through ordinary cells. Values are the low byte of the cell,
+//! as for input and output.
+//!
+//! Runs of instructions are folded, eg. `+++` writes the hook once, and
+//! the program is packed at most with `O1` so every access goes through
+//! the head cell.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+/// Host side of a memory-mapped cell
+pub trait BFCellHook: Send + Sync {
+    /// value the program reads from the cell at `index`
+    fn read(&mut self, index: usize) -> u8;
+
+    /// the program changed the cell at `index` to `value`
+    fn write(&mut self, index: usize, value: u8);
+}
+
+/// Hook calling a closure on every read and another one on every write
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::hooks::{BFCellHooks, BFFnCellHook};
+/// use bft_interp::BFVirtualMachine;
+/// use bft_types::BFProgram;
+/// use std::sync::Mutex;
+///
+/// // cell 1 is a dimmer reading back as off, `+++` is folded into one write
+/// let levels = Mutex::new(Vec::new());
+/// let mut hooks = BFCellHooks::new();
+/// hooks.bind(1, BFFnCellHook::new(|_| 0, |_, value| levels.lock().unwrap().push(value)));
+///
+/// let bf_info = BFProgram::new("", ">+++<>+").validate().unwrap();
+/// let mut bf_vm = BFVirtualMachine::<u8>::new(0, false, &bf_info);
+/// bf_vm.set_cell_hooks(hooks);
+/// bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+/// drop(bf_vm);
+/// assert_eq!(levels.into_inner().unwrap(), [3, 1]);
+/// ```
+pub struct BFFnCellHook<R, W> {
+    read: R,
+    write: W,
+}
+
+impl<R, W> BFFnCellHook<R, W>
+where
+    R: FnMut(usize) -> u8 + Send + Sync,
+    W: FnMut(usize, u8) + Send + Sync,
+{
+    /// create a hook out of `read` and `write`
+    pub fn new(read: R, write: W) -> Self {
+        Self { read, write }
+    }
+}
+
+impl<R, W> BFCellHook for BFFnCellHook<R, W>
+where
+    R: FnMut(usize) -> u8 + Send + Sync,
+    W: FnMut(usize, u8) + Send + Sync,
+{
+    fn read(&mut self, index: usize) -> u8 {
+        (self.read)(index)
+    }
+
+    fn write(&mut self, index: usize, value: u8) {
+        (self.write)(index, value)
+    }
+}
+
+impl<R, W> fmt::Debug for BFFnCellHook<R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BFFnCellHook")
+    }
+}
+
+/// Hooks of the memory-mapped cells by cell index
+#[derive(Default)]
+pub struct BFCellHooks<'r> {
+    hooks: BTreeMap<usize, Box<dyn BFCellHook + 'r>>,
+}
+
+impl<'r> BFCellHooks<'r> {
+    /// Create a set without any bound cell
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind the cell at `index` to `hook`, replacing any hook bound to it
+    pub fn bind(&mut self, index: usize, hook: impl BFCellHook + 'r) -> &mut Self {
+        self.hooks.insert(index, Box::new(hook));
+        self
+    }
+
+    /// Whether no cell is bound
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Value of the cell at `index` read from its hook, None if unbound
+    pub(crate) fn read(&mut self, index: usize) -> Option<u8> {
+        self.hooks.get_mut(&index).map(|hook| hook.read(index))
+    }
+
+    /// Hand the new value of the cell at `index` to its hook if bound
+    pub(crate) fn write(&mut self, index: usize, value: u8) {
+        if let Some(hook) = self.hooks.get_mut(&index) {
+            hook.write(index, value);
+        }
+    }
+}
+
+impl fmt::Debug for BFCellHooks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.hooks.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BFBackend, BFOptLevel, BFVirtualMachine};
+    use bft_types::BFProgram;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_cell_hooks() {
+        let bf_info = BFProgram::new("", "+++[->++<]>>.").validate().unwrap();
+        for backend in [BFBackend::Loop, BFBackend::Threaded] {
+            let register = Mutex::new(0);
+            let writes = Mutex::new(Vec::new());
+            let mut hooks = BFCellHooks::new();
+            hooks
+                .bind(
+                    1,
+                    BFFnCellHook::new(
+                        |_| *register.lock().unwrap(),
+                        |_, value| {
+                            *register.lock().unwrap() = value;
+                            writes.lock().unwrap().push(value);
+                        },
+                    ),
+                )
+                .bind(2, BFFnCellHook::new(|_| 7, |_, _| ()));
+
+            let mut bf_vm = BFVirtualMachine::<u8>::new(3, false, &bf_info);
+            bf_vm.set_opt_level(BFOptLevel::O3);
+            bf_vm.set_cell_hooks(hooks);
+            assert_eq!(bf_vm.stats().opt_level(), BFOptLevel::O1);
+            let mut output = Vec::new();
+            bf_vm
+                .interpret_with(backend, &mut &b""[..], &mut output)
+                .unwrap();
+            assert_eq!(output, b"\x07\n");
+            drop(bf_vm);
+            assert_eq!(writes.into_inner().unwrap(), [2, 4, 6]);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Byte level IO of the virtual machine
+//!
+//! The virtual machine only ever reads or writes a single byte, so it talks
+//! to `BFRead` and `BFWrite` instead of `std::io`. With the `std` feature
+//! every `std::io::Read` and `std::io::Write` is one of them, without it
+//! the closure based handlers connect the virtual machine to whatever the
+//! target offers, eg. a UART on an embedded board.
+
+use core::fmt;
+
+/// Error of a byte reader or writer
+#[cfg(feature = "std")]
+pub type BFIoError = std::io::Error;
+
+/// Error of a byte reader or writer
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFIoError {
+    message: &'static str,
+}
+
+#[cfg(not(feature = "std"))]
+impl BFIoError {
+    /// create an error described by `message`
+    pub fn new(message: &'static str) -> Self {
+        Self { message }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for BFIoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for BFIoError {}
+
+/// Error of reading once the input is exhausted
+pub(crate) fn eof_error() -> BFIoError {
+    #[cfg(feature = "std")]
+    return std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "failed to fill whole buffer",
+    );
+    #[cfg(not(feature = "std"))]
+    return BFIoError::new("failed to fill whole buffer");
+}
+
+/// Source of the bytes read by the program
+pub trait BFRead {
+    /// read the next byte, None once the input is exhausted
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError>;
+}
+
+/// Sink of the bytes written by the program
+pub trait BFWrite {
+    /// write a single byte
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError>;
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> BFRead for R {
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        let mut buf = [0u8; 1];
+        match self.read_exact(&mut buf) {
+            Ok(()) => Ok(Some(buf[0])),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> BFWrite for W {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        self.write_all(&[byte])
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl BFRead for &[u8] {
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        match self.split_first() {
+            Some((&byte, rest)) => {
+                *self = rest;
+                Ok(Some(byte))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl BFWrite for alloc::vec::Vec<u8> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        self.push(byte);
+        Ok(())
+    }
+}
+
+/// Translation of the line endings read by the program
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFNewline {
+    /// bytes are read as they come
+    #[default]
+    Unchanged,
+
+    /// `\r\n` is read as `\n`, as typed on a Unix terminal
+    Lf,
+
+    /// a `\n` not already preceded by `\r` is read as `\r\n`, as typed on
+    /// a Windows terminal
+    CrLf,
+}
+
+/// State of the line ending translation between two reads
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BFNewlineState {
+    mode: BFNewline,
+
+    /// byte read ahead of the program, returned by the next read
+    pending: Option<u8>,
+
+    /// whether the last byte read from the input was `\r`
+    after_cr: bool,
+}
+
+impl BFNewlineState {
+    pub(crate) fn new(mode: BFNewline) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// read the next byte of `reader` with its line endings translated
+    pub(crate) fn read_byte<R>(&mut self, reader: &mut R) -> Result<Option<u8>, BFIoError>
+    where
+        R: BFRead + ?Sized,
+    {
+        if let Some(byte) = self.pending.take() {
+            return Ok(Some(byte));
+        }
+        let byte = reader.read_byte()?;
+        match (self.mode, byte) {
+            (BFNewline::Lf, Some(b'\r')) => match reader.read_byte()? {
+                Some(b'\n') => Ok(Some(b'\n')),
+                next => {
+                    // a lone `\r` is kept, the byte after it comes next
+                    self.pending = next;
+                    Ok(byte)
+                }
+            },
+            (BFNewline::CrLf, Some(b'\n')) if !self.after_cr => {
+                self.pending = byte;
+                Ok(Some(b'\r'))
+            }
+            _ => {
+                self.after_cr = byte == Some(b'\r');
+                Ok(byte)
+            }
+        }
+    }
+}
+
+/// Reader translating the line endings of another one
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::{BFNewline, BFNewlineReader, BFRead};
+///
+/// let mut reader = BFNewlineReader::new(&b"a\r\nb"[..], BFNewline::Lf);
+/// let mut bytes = Vec::new();
+/// while let Some(byte) = reader.read_byte().unwrap() {
+///     bytes.push(byte);
+/// }
+/// assert_eq!(bytes, b"a\nb");
+/// ```
+#[derive(Debug)]
+pub struct BFNewlineReader<R> {
+    inner: R,
+    state: BFNewlineState,
+}
+
+impl<R: BFRead> BFNewlineReader<R> {
+    /// create a reader translating the line endings of `inner` with `mode`
+    pub fn new(inner: R, mode: BFNewline) -> Self {
+        Self {
+            inner,
+            state: BFNewlineState::new(mode),
+        }
+    }
+
+    /// the reader being translated
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: BFRead> BFRead for BFNewlineReader<R> {
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        self.state.read_byte(&mut self.inner)
+    }
+}
+
+/// Reader calling a closure for every byte
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::{BFFnReader, BFRead};
+///
+/// let mut bytes = b"ab".iter().copied();
+/// let mut reader = BFFnReader::new(|| Ok(bytes.next()));
+/// assert_eq!(reader.read_byte().unwrap(), Some(b'a'));
+/// ```
+pub struct BFFnReader<F> {
+    read: F,
+}
+
+impl<F> BFFnReader<F>
+where
+    F: FnMut() -> Result<Option<u8>, BFIoError>,
+{
+    /// create a reader out of `read`, which returns None at the end of input
+    pub fn new(read: F) -> Self {
+        Self { read }
+    }
+}
+
+impl<F> BFRead for BFFnReader<F>
+where
+    F: FnMut() -> Result<Option<u8>, BFIoError>,
+{
+    fn read_byte(&mut self) -> Result<Option<u8>, BFIoError> {
+        (self.read)()
+    }
+}
+
+impl<F> fmt::Debug for BFFnReader<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BFFnReader")
+    }
+}
+
+/// Writer calling a closure for every byte
+pub struct BFFnWriter<F> {
+    write: F,
+}
+
+impl<F> BFFnWriter<F>
+where
+    F: FnMut(u8) -> Result<(), BFIoError>,
+{
+    /// create a writer out of `write`
+    pub fn new(write: F) -> Self {
+        Self { write }
+    }
+}
+
+impl<F> BFWrite for BFFnWriter<F>
+where
+    F: FnMut(u8) -> Result<(), BFIoError>,
+{
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        (self.write)(byte)
+    }
+}
+
+impl<F> fmt::Debug for BFFnWriter<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BFFnWriter")
+    }
+}
+
+/// Writer copying every byte to two others, eg. to keep a transcript of
+/// the output while it is shown
+///
+/// With the `std` feature it's a `std::io::Write` of two of them, without
+/// it a `BFWrite` of two of them. Every byte goes to `first` then to
+/// `second`, the first error stops the writing.
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::{BFTee, BFWrite};
+///
+/// let mut tee = BFTee::new(Vec::new(), Vec::new());
+/// tee.write_byte(b'a').unwrap();
+/// assert_eq!(tee.into_inner(), (b"a".to_vec(), b"a".to_vec()));
+/// ```
+#[derive(Debug)]
+pub struct BFTee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> BFTee<A, B> {
+    /// create a writer copying every byte to `first` and `second`
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// the two writers being copied to
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: std::io::Write, B: std::io::Write> std::io::Write for BFTee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.first.write_all(buf)?;
+        self.second.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<A: BFWrite, B: BFWrite> BFWrite for BFTee<A, B> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BFIoError> {
+        self.first.write_byte(byte)?;
+        self.second.write_byte(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BFEofBehavior, BFVirtualMachine};
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_fn_handlers() {
+        let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+        let mut bf_vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        bf_vm.set_eof(BFEofBehavior::Zero);
+        let mut input = b"abc".iter().copied();
+        let mut output = Vec::new();
+        let result = bf_vm.interpret(
+            &mut BFFnReader::new(|| Ok(input.next())),
+            &mut BFFnWriter::new(|byte| {
+                output.push(byte);
+                Ok(())
+            }),
+        );
+        assert!(result.is_ok());
+        assert_eq!(output, b"abc\n");
+    }
+
+    #[test]
+    fn test_newline() {
+        let translated = |input: &[u8], mode: BFNewline| {
+            let mut reader = BFNewlineReader::new(input, mode);
+            let mut bytes = Vec::new();
+            while let Some(byte) = reader.read_byte().unwrap() {
+                bytes.push(byte);
+            }
+            bytes
+        };
+        let input = b"a\r\nb\nc\rd\r";
+        assert_eq!(translated(input, BFNewline::Unchanged), input);
+        assert_eq!(translated(input, BFNewline::Lf), b"a\nb\nc\rd\r");
+        assert_eq!(translated(input, BFNewline::CrLf), b"a\r\nb\r\nc\rd\r");
+
+        // the translation carries over between the reads of a run
+        let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+        let mut bf_vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        bf_vm.set_eof(BFEofBehavior::Zero);
+        bf_vm.set_prompt(false);
+        bf_vm.set_newline(BFNewline::CrLf);
+        let mut output = Vec::new();
+        bf_vm.interpret(&mut &b"x\ny\n"[..], &mut output).unwrap();
+        assert_eq!(output, b"x\r\ny\r\n");
+    }
+
+    #[test]
+    fn test_tee() {
+        let bf_info = BFProgram::new("", "+++[>++++++++++<-]>+++.+.")
+            .validate()
+            .unwrap();
+        let mut bf_vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        let mut tee = BFTee::new(Vec::new(), Vec::new());
+        bf_vm.interpret(&mut &b""[..], &mut tee).unwrap();
+        let (first, second) = tee.into_inner();
+        assert_eq!(first, b"!\"\n");
+        assert_eq!(first, second);
+
+        // the first writer already has the byte the second one fails on
+        let mut full = [0u8; 1];
+        let mut tee = BFTee::new(Vec::new(), &mut full[..]);
+        tee.write_byte(b'a').unwrap();
+        assert!(tee.write_byte(b'b').is_err());
+        assert_eq!(tee.into_inner().0, b"ab");
+    }
+}
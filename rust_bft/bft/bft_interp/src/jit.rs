@@ -0,0 +1,652 @@
+//! Cranelift JIT backend, enabled with the `jit` feature
+//!
+//! The folded packed program is compiled into one native function at
+//! startup. Cells are read and written directly on the tape memory, while
+//! IO, tape extension, optimized instructions and errors call back into the
+//! virtual machine so the observable behavior stays the same as the other
+//! backends.
+
+use crate::{BFHeadMode, BFOpCode, BFRead, BFVirtualMachine, BFVmErr, BFWrite, CellKind};
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, BlockArg, InstBuilder, MemFlagsData, UserFuncName};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use std::mem::offset_of;
+
+/// compiled function returned normally
+const JIT_DONE: i64 = 0;
+
+/// a host callback failed and stored the error
+const JIT_HOST_ERR: i64 = 1;
+
+/// head falling off the left edge of the tape
+const JIT_HEAD_ERR: i64 = 2;
+
+/// fuel ran out
+const JIT_FUEL_ERR: i64 = 3;
+
+/// Machine state shared between compiled code and host callbacks
+#[repr(C)]
+struct BFJitState {
+    tape: *mut u8,
+    len: usize,
+    head: usize,
+    fuel: u64,
+    pc: usize,
+}
+
+/// Host side of the compiled code, reachable from callbacks
+struct BFJitHost<'h, 'a, T> {
+    vm: &'h mut BFVirtualMachine<'a, T>,
+    reader: &'h mut dyn BFRead,
+    writer: &'h mut dyn BFWrite,
+    err: Option<BFVmErr>,
+}
+
+/// Signature of the compiled program
+type BFJitFn = unsafe extern "C" fn(*mut BFJitState, *mut u8) -> u32;
+
+/// Callback writing the head cell out, returns non-zero on error
+extern "C" fn jit_output<T: Default + CellKind>(host: *mut u8, pc: usize, head: usize) -> u32 {
+    let host = unsafe { &mut *(host as *mut BFJitHost<'_, '_, T>) };
+    host.vm.program_cnt = pc;
+    host.vm.head = head;
+    match host.vm.write_value(&mut *host.writer) {
+        Ok(()) => 0,
+        Err(e) => {
+            host.err = Some(e);
+            1
+        }
+    }
+}
+
+/// Callback reading into the head cell, returns non-zero on error
+extern "C" fn jit_input<T: Default + CellKind>(host: *mut u8, pc: usize, head: usize) -> u32 {
+    let host = unsafe { &mut *(host as *mut BFJitHost<'_, '_, T>) };
+    host.vm.program_cnt = pc;
+    host.vm.head = head;
+    match host.vm.read_value(&mut *host.reader) {
+        Ok(()) => 0,
+        Err(e) => {
+            host.err = Some(e);
+            1
+        }
+    }
+}
+
+/// Callback moving the head past the end of the tape, extending the tape
+/// if allowed and refreshing tape and head in `state`, returns non-zero on error
+extern "C" fn jit_extend<T: Default + CellKind>(
+    host: *mut u8,
+    state: *mut BFJitState,
+    pc: usize,
+    head: usize,
+    n: usize,
+) -> u32 {
+    let host = unsafe { &mut *(host as *mut BFJitHost<'_, '_, T>) };
+    let state = unsafe { &mut *state };
+    host.vm.program_cnt = pc;
+    host.vm.head = head;
+    match host.vm.move_head_right_by(n) {
+        Ok(()) => {
+            state.head = host.vm.head;
+            let tape = T::as_bytes_mut(&mut host.vm.cells).expect("bft: jit needs byte cells");
+            state.tape = tape.as_mut_ptr();
+            state.len = tape.len();
+            0
+        }
+        Err(e) => {
+            host.err = Some(e);
+            1
+        }
+    }
+}
+
+/// Callback executing an instruction working on cells only, refreshing
+/// tape and head in `state`, returns non-zero on error
+extern "C" fn jit_exec<T: Default + CellKind>(
+    host: *mut u8,
+    state: *mut BFJitState,
+    pc: usize,
+    head: usize,
+) -> u32 {
+    let host = unsafe { &mut *(host as *mut BFJitHost<'_, '_, T>) };
+    let state = unsafe { &mut *state };
+    host.vm.program_cnt = pc;
+    host.vm.head = head;
+    let result = host.vm.execute_cell_op(host.vm.code.ops()[pc]);
+
+    state.head = host.vm.head;
+    let tape = T::as_bytes_mut(&mut host.vm.cells).expect("bft: jit needs byte cells");
+    state.tape = tape.as_mut_ptr();
+    state.len = tape.len();
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            host.err = Some(e);
+            1
+        }
+    }
+}
+
+impl<'a, T> BFVirtualMachine<'a, T>
+where
+    T: Default + CellKind,
+{
+    /// Compile the folded program into native code owned by the module, the
+    /// code entering at instruction `start`
+    fn compile_jit(&self, module: &mut JITModule, ptr_ty: types::Type, start: usize) -> BFJitFn {
+        let fuel_enabled = self.fuel.is_some();
+        let clamp = self.head_mode == BFHeadMode::Clamp;
+        let frontend_config = module.target_config();
+        let mut ctx = module.make_context();
+        let mut func_ctx = FunctionBuilderContext::new();
+
+        let mut sig = module.make_signature();
+        sig.params.push(AbiParam::new(ptr_ty));
+        sig.params.push(AbiParam::new(ptr_ty));
+        sig.returns.push(AbiParam::new(types::I32));
+
+        let mut io_sig = module.make_signature();
+        io_sig.params.extend([AbiParam::new(ptr_ty); 3]);
+        io_sig.returns.push(AbiParam::new(types::I32));
+
+        let mut extend_sig = module.make_signature();
+        extend_sig.params.extend([AbiParam::new(ptr_ty); 5]);
+        extend_sig.returns.push(AbiParam::new(types::I32));
+
+        let mut exec_sig = module.make_signature();
+        exec_sig.params.extend([AbiParam::new(ptr_ty); 4]);
+        exec_sig.returns.push(AbiParam::new(types::I32));
+
+        let func_id = module
+            .declare_function("bf_program", Linkage::Local, &sig)
+            .expect("bft: failed to declare jit function");
+        ctx.func.signature = sig;
+        ctx.func.name = UserFuncName::user(0, func_id.as_u32());
+
+        {
+            let mut bcx = FunctionBuilder::new(&mut ctx.func, &mut func_ctx);
+            let io_sig = bcx.import_signature(io_sig);
+            let extend_sig = bcx.import_signature(extend_sig);
+            let exec_sig = bcx.import_signature(exec_sig);
+            let flags = MemFlagsData::trusted();
+
+            let entry = bcx.create_block();
+            let exit = bcx.create_block();
+            bcx.append_block_param(exit, types::I32);
+            bcx.append_block_param(exit, ptr_ty);
+
+            bcx.append_block_params_for_function_params(entry);
+            bcx.switch_to_block(entry);
+            let state = bcx.block_params(entry)[0];
+            let host = bcx.block_params(entry)[1];
+
+            let head = bcx.declare_var(ptr_ty);
+            let tape = bcx.declare_var(ptr_ty);
+            let len = bcx.declare_var(ptr_ty);
+            let fuel = bcx.declare_var(types::I64);
+            let v = bcx
+                .ins()
+                .load(ptr_ty, flags, state, offset_of!(BFJitState, head) as i32);
+            bcx.def_var(head, v);
+            let v = bcx
+                .ins()
+                .load(ptr_ty, flags, state, offset_of!(BFJitState, tape) as i32);
+            bcx.def_var(tape, v);
+            let v = bcx
+                .ins()
+                .load(ptr_ty, flags, state, offset_of!(BFJitState, len) as i32);
+            bcx.def_var(len, v);
+            let v = bcx.ins().load(
+                types::I64,
+                flags,
+                state,
+                offset_of!(BFJitState, fuel) as i32,
+            );
+            bcx.def_var(fuel, v);
+
+            // a resumed program jumps straight to its instruction, the code
+            // before it only reachable through loops jumping back
+            let resume = bcx.create_block();
+            if start > 0 {
+                bcx.ins().jump(resume, &[]);
+                let skipped = bcx.create_block();
+                bcx.switch_to_block(skipped);
+            }
+
+            let mut loops = Vec::new();
+            for (pc, op) in self.code.ops().iter().enumerate() {
+                if pc == start {
+                    bcx.ins().jump(resume, &[]);
+                    bcx.switch_to_block(resume);
+                }
+                let pc_val = bcx.ins().iconst(ptr_ty, pc as i64);
+                let n = op.operand() as i64;
+
+                let f = bcx.use_var(fuel);
+                if fuel_enabled {
+                    let next = bcx.create_block();
+                    let code = bcx.ins().iconst(types::I32, JIT_FUEL_ERR);
+                    bcx.ins().brif(
+                        f,
+                        next,
+                        &[],
+                        exit,
+                        &[BlockArg::from(code), BlockArg::from(pc_val)],
+                    );
+                    bcx.switch_to_block(next);
+                }
+                let f = bcx.ins().iadd_imm_s(f, -1);
+                bcx.def_var(fuel, f);
+
+                match op.opcode() {
+                    BFOpCode::Extension => {
+                        unreachable!("bft: programs with extensions fall back to interpret")
+                    }
+                    BFOpCode::PointerIncrement => {
+                        let next = bcx.create_block();
+                        let slow = bcx.create_block();
+                        let h = bcx.use_var(head);
+                        let target = bcx.ins().iadd_imm_s(h, n);
+                        let l = bcx.use_var(len);
+                        let oob = bcx.ins().icmp(IntCC::UnsignedGreaterThanOrEqual, target, l);
+                        bcx.def_var(head, target);
+                        bcx.ins().brif(oob, slow, &[], next, &[]);
+
+                        bcx.switch_to_block(slow);
+                        let callee = bcx
+                            .ins()
+                            .iconst(ptr_ty, jit_extend::<T> as *const () as usize as i64);
+                        let n_val = bcx.ins().iconst(ptr_ty, n);
+                        let call = bcx.ins().call_indirect(
+                            extend_sig,
+                            callee,
+                            &[host, state, pc_val, h, n_val],
+                        );
+                        let ret = bcx.inst_results(call)[0];
+                        let ok = bcx.create_block();
+                        let code = bcx.ins().iconst(types::I32, JIT_HOST_ERR);
+                        bcx.ins().brif(
+                            ret,
+                            exit,
+                            &[BlockArg::from(code), BlockArg::from(pc_val)],
+                            ok,
+                            &[],
+                        );
+                        bcx.switch_to_block(ok);
+                        let v = bcx.ins().load(
+                            ptr_ty,
+                            flags,
+                            state,
+                            offset_of!(BFJitState, tape) as i32,
+                        );
+                        bcx.def_var(tape, v);
+                        let v = bcx.ins().load(
+                            ptr_ty,
+                            flags,
+                            state,
+                            offset_of!(BFJitState, len) as i32,
+                        );
+                        bcx.def_var(len, v);
+                        let v = bcx.ins().load(
+                            ptr_ty,
+                            flags,
+                            state,
+                            offset_of!(BFJitState, head) as i32,
+                        );
+                        bcx.def_var(head, v);
+                        bcx.ins().jump(next, &[]);
+
+                        bcx.switch_to_block(next);
+                    }
+                    BFOpCode::PointerDecrement if clamp => {
+                        let h = bcx.use_var(head);
+                        let oob = bcx.ins().icmp_imm_u(IntCC::UnsignedLessThan, h, n);
+                        let zero = bcx.ins().iconst(ptr_ty, 0);
+                        let moved = bcx.ins().iadd_imm_s(h, -n);
+                        let target = bcx.ins().select(oob, zero, moved);
+                        bcx.def_var(head, target);
+                    }
+                    BFOpCode::PointerDecrement => {
+                        let next = bcx.create_block();
+                        let h = bcx.use_var(head);
+                        let oob = bcx.ins().icmp_imm_u(IntCC::UnsignedLessThan, h, n);
+                        let code = bcx.ins().iconst(types::I32, JIT_HEAD_ERR);
+                        bcx.ins().brif(
+                            oob,
+                            exit,
+                            &[BlockArg::from(code), BlockArg::from(pc_val)],
+                            next,
+                            &[],
+                        );
+                        bcx.switch_to_block(next);
+                        let target = bcx.ins().iadd_imm_s(h, -n);
+                        bcx.def_var(head, target);
+                    }
+                    BFOpCode::DataIncrement | BFOpCode::DataDecrement if op.offset() == 0 => {
+                        let h = bcx.use_var(head);
+                        let t = bcx.use_var(tape);
+                        let addr = bcx.ins().iadd(t, h);
+                        let cell = bcx.ins().load(types::I8, flags, addr, 0);
+                        let delta = if op.opcode() == BFOpCode::DataIncrement {
+                            n
+                        } else {
+                            -n
+                        };
+                        let cell = bcx.ins().iadd_imm_s(cell, delta);
+                        bcx.ins().store(flags, cell, addr, 0);
+                    }
+                    BFOpCode::DataOutput | BFOpCode::DataInput => {
+                        let callee = if op.opcode() == BFOpCode::DataOutput {
+                            jit_output::<T> as *const () as usize
+                        } else {
+                            jit_input::<T> as *const () as usize
+                        };
+                        let callee = bcx.ins().iconst(ptr_ty, callee as i64);
+                        let h = bcx.use_var(head);
+                        let call = bcx.ins().call_indirect(io_sig, callee, &[host, pc_val, h]);
+                        let ret = bcx.inst_results(call)[0];
+                        let next = bcx.create_block();
+                        let code = bcx.ins().iconst(types::I32, JIT_HOST_ERR);
+                        bcx.ins().brif(
+                            ret,
+                            exit,
+                            &[BlockArg::from(code), BlockArg::from(pc_val)],
+                            next,
+                            &[],
+                        );
+                        bcx.switch_to_block(next);
+                    }
+                    BFOpCode::LoopStart => {
+                        let body = bcx.create_block();
+                        let after = bcx.create_block();
+                        let h = bcx.use_var(head);
+                        let t = bcx.use_var(tape);
+                        let addr = bcx.ins().iadd(t, h);
+                        let cell = bcx.ins().load(types::I8, flags, addr, 0);
+                        bcx.ins().brif(cell, body, &[], after, &[]);
+                        bcx.switch_to_block(body);
+                        loops.push((body, after));
+                    }
+                    BFOpCode::LoopTerminate => {
+                        let (body, after) = loops
+                            .pop()
+                            .expect("bft: validated program has balanced loops");
+                        let h = bcx.use_var(head);
+                        let t = bcx.use_var(tape);
+                        let addr = bcx.ins().iadd(t, h);
+                        let cell = bcx.ins().load(types::I8, flags, addr, 0);
+                        bcx.ins().brif(cell, body, &[], after, &[]);
+                        bcx.switch_to_block(after);
+                    }
+                    BFOpCode::DataIncrement
+                    | BFOpCode::DataDecrement
+                    | BFOpCode::Clear
+                    | BFOpCode::Set
+                    | BFOpCode::MulAdd
+                    | BFOpCode::ScanRight
+                    | BFOpCode::ScanLeft
+                    | BFOpCode::ClearRange => {
+                        let callee = bcx
+                            .ins()
+                            .iconst(ptr_ty, jit_exec::<T> as *const () as usize as i64);
+                        let h = bcx.use_var(head);
+                        let call =
+                            bcx.ins()
+                                .call_indirect(exec_sig, callee, &[host, state, pc_val, h]);
+                        let ret = bcx.inst_results(call)[0];
+                        for (var, field) in [
+                            (head, offset_of!(BFJitState, head)),
+                            (tape, offset_of!(BFJitState, tape)),
+                            (len, offset_of!(BFJitState, len)),
+                        ] {
+                            let v = bcx.ins().load(ptr_ty, flags, state, field as i32);
+                            bcx.def_var(var, v);
+                        }
+                        let next = bcx.create_block();
+                        let code = bcx.ins().iconst(types::I32, JIT_HOST_ERR);
+                        bcx.ins().brif(
+                            ret,
+                            exit,
+                            &[BlockArg::from(code), BlockArg::from(pc_val)],
+                            next,
+                            &[],
+                        );
+                        bcx.switch_to_block(next);
+                    }
+                }
+            }
+
+            if start >= self.code.ops().len() {
+                bcx.ins().jump(resume, &[]);
+                bcx.switch_to_block(resume);
+            }
+            let code = bcx.ins().iconst(types::I32, JIT_DONE);
+            let pc_val = bcx.ins().iconst(ptr_ty, self.code.ops().len() as i64);
+            bcx.ins()
+                .jump(exit, &[BlockArg::from(code), BlockArg::from(pc_val)]);
+
+            bcx.switch_to_block(exit);
+            let code = bcx.block_params(exit)[0];
+            let pc_val = bcx.block_params(exit)[1];
+            let h = bcx.use_var(head);
+            bcx.ins()
+                .store(flags, h, state, offset_of!(BFJitState, head) as i32);
+            let f = bcx.use_var(fuel);
+            bcx.ins()
+                .store(flags, f, state, offset_of!(BFJitState, fuel) as i32);
+            bcx.ins()
+                .store(flags, pc_val, state, offset_of!(BFJitState, pc) as i32);
+            bcx.ins().return_(&[code]);
+
+            bcx.seal_all_blocks();
+            bcx.finalize(frontend_config);
+        }
+
+        module
+            .define_function(func_id, &mut ctx)
+            .expect("bft: failed to compile jit function");
+        module.clear_context(&mut ctx);
+        module
+            .finalize_definitions()
+            .expect("bft: failed to finalize jit function");
+
+        let code = module.get_finalized_function(func_id);
+        unsafe { std::mem::transmute::<*const u8, BFJitFn>(code) }
+    }
+
+    /// run brainfuck program compiled into native code
+    ///
+    /// Only byte cells are supported by the compiled code, any other cell
+    /// type, a virtual machine with sandbox limits, a program with extension
+    /// commands or a host cranelift does not support falls back to
+    /// `interpret`. The compiled code resumes at the current instruction,
+    /// head bounds, tape extension, fuel and IO errors behave exactly like
+    /// the interpreter backends.
+    pub fn interpret_jit<R, W>(&mut self, reader: &mut R, writer: &mut W) -> Result<(), BFVmErr>
+    where
+        R: BFRead,
+        W: BFWrite,
+    {
+        let extended = self
+            .code
+            .ops()
+            .iter()
+            .any(|op| op.opcode() == BFOpCode::Extension);
+        if self.limited() || extended {
+            return self.interpret(reader, writer);
+        }
+        let (tape, len) = match T::as_bytes_mut(&mut self.cells) {
+            Some(tape) => (tape.as_mut_ptr(), tape.len()),
+            None => return self.interpret(reader, writer),
+        };
+
+        let mut flag_builder = settings::builder();
+        flag_builder
+            .set("opt_level", "speed")
+            .expect("bft: invalid jit setting");
+        let isa = match cranelift_native::builder() {
+            Ok(isa_builder) => isa_builder.finish(settings::Flags::new(flag_builder)),
+            Err(_) => return self.interpret(reader, writer),
+        };
+        let isa = match isa {
+            Ok(isa) => isa,
+            Err(_) => return self.interpret(reader, writer),
+        };
+        let ptr_ty = isa.pointer_type();
+        let mut module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+        let compiled = self.compile_jit(&mut module, ptr_ty, self.program_cnt);
+
+        let start_fuel = self.fuel.unwrap_or(u64::MAX);
+        let mut state = BFJitState {
+            tape,
+            len,
+            head: self.head,
+            fuel: start_fuel,
+            pc: self.program_cnt,
+        };
+        let fuel_enabled = self.fuel.is_some();
+        let mut host = BFJitHost {
+            vm: self,
+            reader: &mut *reader,
+            writer: &mut *writer,
+            err: None,
+        };
+        let status = unsafe {
+            compiled(
+                &mut state,
+                &mut host as *mut BFJitHost<'_, 'a, T> as *mut u8,
+            )
+        };
+        let err = host.err.take();
+        unsafe { module.free_memory() };
+
+        self.head = state.head;
+        self.program_cnt = state.pc;
+        self.steps += start_fuel - state.fuel;
+        if fuel_enabled {
+            self.fuel = Some(state.fuel);
+        }
+        match status as i64 {
+            JIT_DONE => self.finish_output(writer),
+            JIT_HEAD_ERR => Err(BFVmErr::HeadInvalidPositionErr(self.current_cmd())),
+            JIT_FUEL_ERR => Err(BFVmErr::FuelExhaustedErr(self.current_cmd())),
+            _ => Err(err.expect("bft: jit host error not recorded")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BFBackend, BFHeadMode, BFOptLevel, BFVirtualMachine, BFVmErr};
+    use bft_types::BFProgram;
+    use std::io::Cursor;
+
+    fn run(
+        source: &str,
+        backend: BFBackend,
+        len: usize,
+        extend: bool,
+        fuel: Option<u64>,
+    ) -> (Result<(), BFVmErr>, Vec<u8>, Option<u64>) {
+        let bf_info = BFProgram::new("", source).validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(len, extend, &bf_info);
+        vm.set_fuel(fuel);
+        let mut r_buf = Cursor::new(vec![3, 200]);
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        let result = vm.interpret_with(backend, &mut r_buf, &mut w_buf);
+        (result, w_buf.into_inner(), vm.fuel())
+    }
+
+    #[test]
+    fn test_jit_matches_loop() {
+        let source = ",[->+>+<<]>[-<+>]>.<<.,---.>>>>>>+++[<+++>-]<.";
+        let (jit_result, jit_out, _) = run(source, BFBackend::Jit, 4, true, None);
+        let (_, loop_out, _) = run(source, BFBackend::Loop, 4, true, None);
+        assert!(jit_result.is_ok());
+        assert_eq!(jit_out, loop_out);
+        assert_eq!(jit_out, vec![3, 3, 197, 9, 10]);
+    }
+
+    #[test]
+    fn test_jit_optimized() {
+        let bf_info = BFProgram::new("", ",[->+>++<<]>[-<+>]<[>]>.>>>>[-]+<<<<<<.")
+            .validate()
+            .unwrap();
+        let mut outputs = Vec::new();
+
+        for backend in [BFBackend::Loop, BFBackend::Jit] {
+            let mut vm = BFVirtualMachine::<u8>::new(4, true, &bf_info);
+            vm.set_opt_level(BFOptLevel::O3);
+            let mut r_buf = Cursor::new(vec![3]);
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            vm.interpret_with(backend, &mut r_buf, &mut w_buf).unwrap();
+            outputs.push((w_buf.into_inner(), vm.cells().to_vec(), vm.stats()));
+        }
+        assert_eq!(outputs[0].0, vec![6, 3, 10]);
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_jit_clamp_head() {
+        let bf_info = BFProgram::new("", "<<+>>>>>+<<<<[->>+<<]>>.")
+            .validate()
+            .unwrap();
+        let mut outputs = Vec::new();
+
+        for backend in [BFBackend::Loop, BFBackend::Jit] {
+            let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+            vm.set_head_mode(BFHeadMode::Clamp);
+            let mut r_buf = Cursor::new(Vec::<u8>::new());
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            vm.interpret_with(backend, &mut r_buf, &mut w_buf).unwrap();
+            outputs.push((w_buf.into_inner(), vm.cells().to_vec(), vm.head()));
+        }
+        assert_eq!(outputs[0], (vec![1, 10], vec![0, 0, 1, 1], 2));
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_jit_errors() {
+        let (result, _, _) = run("+<", BFBackend::Jit, 4, false, None);
+        assert!(matches!(result, Err(BFVmErr::HeadInvalidPositionErr(_))));
+
+        let (result, _, _) = run(">>>>", BFBackend::Jit, 4, false, None);
+        assert!(matches!(result, Err(BFVmErr::HeadInvalidPositionErr(_))));
+
+        let (result, _, _) = run(",,,", BFBackend::Jit, 4, false, None);
+        assert!(matches!(result, Err(BFVmErr::IOErr(_))));
+    }
+
+    #[test]
+    fn test_jit_fuel() {
+        let (result, _, fuel) = run("+[]", BFBackend::Jit, 4, false, Some(100));
+        assert!(matches!(result, Err(BFVmErr::FuelExhaustedErr(_))));
+        assert_eq!(fuel, Some(0));
+
+        let (jit_result, _, jit_fuel) = run("++[-]", BFBackend::Jit, 4, false, Some(100));
+        let (_, _, loop_fuel) = run("++[-]", BFBackend::Loop, 4, false, Some(100));
+        assert!(jit_result.is_ok());
+        assert_eq!(jit_fuel, loop_fuel);
+    }
+
+    #[test]
+    fn test_jit_resume() {
+        let bf_info = BFProgram::new("", "+++[->++<]>.").validate().unwrap();
+        for stepped in 0..6 {
+            let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+            let mut r_buf = Cursor::new(Vec::<u8>::new());
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            for _ in 0..stepped {
+                vm.step(&mut r_buf, &mut w_buf).unwrap();
+            }
+            vm.interpret_with(BFBackend::Jit, &mut r_buf, &mut w_buf)
+                .unwrap();
+            assert_eq!(w_buf.into_inner(), vec![6, 10]);
+            assert!(vm.halted());
+        }
+    }
+}
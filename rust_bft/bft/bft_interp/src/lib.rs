@@ -2,21 +2,212 @@
 //! typical with 30000 cells and doesn't allow to extend
 //!
 //! Running brainfuck program on the virtual machine
+//!
+//! Without the default `std` feature the crate is `no_std` and only needs
+//! `alloc`, IO then goes through the closure based handlers of `BFFnReader`
+//! and `BFFnWriter`.
+//!
+//! The `tracing` feature emits `tracing` events as the virtual machine
+//! packs its program and extends its tape at debug level, and for every
+//! byte read or written at trace level.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bft_types::{BFCharInfo, BFValidProgram};
+use core::default::Default;
+use core::error::Error;
+use core::fmt;
+
+mod io;
+use io::BFNewlineState;
+pub use io::{
+    BFFnReader, BFFnWriter, BFIoError, BFNewline, BFNewlineReader, BFRead, BFTee, BFWrite,
+};
+
+mod packed;
+pub use packed::{BFOpCode, BFPackedOp, BFPackedProgram, BFSourceSpan};
+
+mod optimize;
+pub use optimize::BFOptLevel;
+
+mod run;
+pub use run::{run, BFRunOptions, BFRunOutput};
+
+mod threaded;
+
+pub mod emit;
+
+pub mod analysis;
+
+pub mod digest;
+
+pub mod lint;
+
+pub mod extension;
+use extension::{BFExtensionCtx, BFExtensions};
+
+pub mod hooks;
+use hooks::BFCellHooks;
+
+pub mod pool;
+
+#[cfg(feature = "jit")]
+mod jit;
+
+#[cfg(feature = "std")]
+pub mod verify;
+
+#[cfg(feature = "std")]
+pub mod batch;
+
+#[cfg(feature = "std")]
+pub mod sandbox;
+
+#[cfg(feature = "std")]
+pub mod metrics;
+
+#[cfg(feature = "std")]
+pub mod trace;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Interpreter backends able to run a brainfuck program
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFBackend {
+    /// match on every packed instruction in a single dispatch loop
+    #[default]
+    Loop,
+
+    /// pre-compile into an array of handlers with operands, see `interpret_threaded`
+    Threaded,
+
+    /// compile into native code with cranelift, see `interpret_jit`
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+/// Statistics of the virtual machine, see `stats`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFRunStats {
+    opt_level: BFOptLevel,
+    ops: usize,
+    steps: u64,
+}
+
+impl BFRunStats {
+    /// get the optimization level the program was packed with
+    pub fn opt_level(&self) -> BFOptLevel {
+        self.opt_level
+    }
+
+    /// get the number of packed instructions
+    pub fn ops(&self) -> usize {
+        self.ops
+    }
+
+    /// get the number of packed instructions executed so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+/// Tape and head of the virtual machine, see `snapshot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFSnapshot<T> {
+    cells: Vec<T>,
+    head: usize,
+}
+
+impl<T> BFSnapshot<T> {
+    /// get the cells of the tape
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// get the position of the head cell
+    pub fn head(&self) -> usize {
+        self.head
+    }
+}
+
+/// Direction of a byte moved by the program
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BFIoKind {
+    /// read by `,`
+    Input,
+
+    /// written by `.`
+    Output,
+}
+
+/// A byte read or written by the program, see `record_io`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFIoEvent {
+    kind: BFIoKind,
+    value: Option<u8>,
+    cmd: BFCharInfo,
+}
+
+impl BFIoEvent {
+    /// create a new IO event
+    pub fn new(kind: BFIoKind, value: Option<u8>, cmd: BFCharInfo) -> Self {
+        Self { kind, value, cmd }
+    }
+
+    /// get the direction
+    pub fn kind(&self) -> BFIoKind {
+        self.kind
+    }
+
+    /// get the byte moved, None when reading hit the end of input
+    pub fn value(&self) -> Option<u8> {
+        self.value
+    }
+
+    /// get the instruction moving the byte
+    pub fn cmd(&self) -> BFCharInfo {
+        self.cmd
+    }
+}
 
-use bft_types::{BFCharCmdName, BFCharInfo, BFProgram};
-use std::default::Default;
-use std::error::Error;
-use std::fmt;
-use std::io::{self, Read, Write};
-use std::ops::Drop;
+impl fmt::Display for BFIoEvent {
+    /// print IO event in human readable format
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            BFIoKind::Input => "input",
+            BFIoKind::Output => "output",
+        };
+        match self.value {
+            Some(value) => write!(f, "{} {:#04x} by {}", kind, value, self.cmd),
+            None => write!(f, "{} EOF by {}", kind, self.cmd),
+        }
+    }
+}
 
 /// Brainfuck IO error with command causes that error
 #[derive(Debug)]
 pub struct BFVirtualMachineIOErr {
-    err: io::Error,
+    err: BFIoError,
     cmd: BFCharInfo,
 }
 
+impl BFVirtualMachineIOErr {
+    /// IO error of the reader or writer
+    pub fn err(&self) -> &BFIoError {
+        &self.err
+    }
+
+    /// instruction reading or writing
+    pub fn cmd(&self) -> BFCharInfo {
+        self.cmd
+    }
+}
+
 /// Definition of brainfuck virtual machine errors
 #[derive(Debug)]
 pub enum BFVmErr {
@@ -28,8 +219,16 @@ pub enum BFVmErr {
     /// IO error when reading/writing brainfuck virtual machine's cell
     IOErr(BFVirtualMachineIOErr),
 
-    /// brainfuck program square bracket unmatch error
-    BracketPairErr(BFCharInfo),
+    /// fuel limit ran out before the program halted,
+    /// constructed with the instruction about to be executed
+    FuelExhaustedErr(BFCharInfo),
+
+    /// IO error when writing the newline after the program halted
+    NewlineErr(BFIoError),
+
+    /// a limit of the sandbox was hit,
+    /// constructed with the instruction which hit it
+    LimitErr(BFLimit, BFCharInfo),
 }
 
 impl fmt::Display for BFVmErr {
@@ -42,50 +241,208 @@ impl fmt::Display for BFVmErr {
             Self::IOErr(e) => {
                 write!(f, "{} by {}", e.err, e.cmd)
             }
-            Self::BracketPairErr(e) => {
-                write!(f, "Unmatched square bracket by {}", e)
+            Self::FuelExhaustedErr(e) => {
+                write!(f, "Fuel exhausted by {}", e)
+            }
+            Self::NewlineErr(e) => {
+                write!(f, "{} by final newline", e)
+            }
+            Self::LimitErr(limit, e) => {
+                write!(f, "{} by {}", limit, e)
             }
         }
     }
 }
 
-/// convert std::io::Error into BFVmErr
+impl BFVmErr {
+    /// short lowercase name of the kind of error, eg. for metrics labels
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::HeadInvalidPositionErr(_) => "head",
+            Self::IOErr(_) | Self::NewlineErr(_) => "io",
+            Self::FuelExhaustedErr(_) => "fuel",
+            Self::LimitErr(..) => "limit",
+        }
+    }
+
+    /// stable numeric code of the error, eg. for exit statuses or
+    /// protocols, never reused for another error
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::BFVirtualMachine;
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+\n<").validate().unwrap();
+    /// let mut bf_vm = BFVirtualMachine::<u8>::new(1, false, &bf_info);
+    /// let err = bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap_err();
+    /// assert_eq!((err.code(), err.code_name()), (1, "head-out-of-bounds"));
+    /// assert_eq!(err.cmd().map(|cmd| cmd.line()), Some(2));
+    /// ```
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::HeadInvalidPositionErr(_) => 1,
+            Self::IOErr(_) => 2,
+            Self::FuelExhaustedErr(_) => 3,
+            Self::NewlineErr(_) => 4,
+            Self::LimitErr(BFLimit::Timeout, _) => 5,
+            Self::LimitErr(BFLimit::Tape, _) => 6,
+            Self::LimitErr(BFLimit::Output, _) => 7,
+            Self::LimitErr(BFLimit::Input, _) => 8,
+        }
+    }
+
+    /// stable string code of the error, in the order of `code`
+    pub fn code_name(&self) -> &'static str {
+        match self {
+            Self::HeadInvalidPositionErr(_) => "head-out-of-bounds",
+            Self::IOErr(_) => "io",
+            Self::FuelExhaustedErr(_) => "fuel-exhausted",
+            Self::NewlineErr(_) => "final-newline",
+            Self::LimitErr(BFLimit::Timeout, _) => "timeout",
+            Self::LimitErr(BFLimit::Tape, _) => "tape-limit",
+            Self::LimitErr(BFLimit::Output, _) => "output-limit",
+            Self::LimitErr(BFLimit::Input, _) => "input-denied",
+        }
+    }
+
+    /// instruction causing the error, None for the final newline
+    pub fn cmd(&self) -> Option<BFCharInfo> {
+        match self {
+            Self::HeadInvalidPositionErr(cmd)
+            | Self::FuelExhaustedErr(cmd)
+            | Self::LimitErr(_, cmd) => Some(*cmd),
+            Self::IOErr(e) => Some(e.cmd),
+            Self::NewlineErr(_) => None,
+        }
+    }
+
+    /// IO error of the reader or writer, None for the other errors
+    pub fn io_err(&self) -> Option<&BFIoError> {
+        match self {
+            Self::IOErr(e) => Some(&e.err),
+            Self::NewlineErr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// convert BFVirtualMachineIOErr into BFVmErr
 impl From<BFVirtualMachineIOErr> for BFVmErr {
     fn from(error: BFVirtualMachineIOErr) -> Self {
         BFVmErr::IOErr(error)
     }
 }
 
-/// convert BFVmErr to std:error::Error
-impl Error for BFVmErr {}
+/// convert BFVmErr to core::error::Error, the IO errors are the source
+impl Error for BFVmErr {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.io_err().map(|err| err as &(dyn Error + 'static))
+    }
+}
 
 /// Detect and print a newline if non at the end of Brainfuck output
 pub struct BFPrintNewLine<'a> {
     tail: u8,
-    writer: &'a mut dyn Write,
+    writer: &'a mut dyn BFWrite,
 }
 
 impl<'a> BFPrintNewLine<'a> {
     /// create a new print newline descriptor
-    pub fn new(tail: u8, writer: &'a mut dyn Write) -> Self {
+    pub fn new(tail: u8, writer: &'a mut dyn BFWrite) -> Self {
         Self { tail, writer }
     }
 
     /// print out newline if necessary
-    pub fn print_newline(&mut self) {
+    pub fn print_newline(&mut self) -> Result<(), BFIoError> {
         if self.tail != 0xA {
-            if let Some(e) = self.writer.write_all(&[0xA]).err() {
-                println!("{}", e);
-            }
+            self.writer.write_byte(0xA)?;
         }
+        Ok(())
     }
 }
 
-impl<'a> Drop for BFPrintNewLine<'a> {
-    /// destructor for BFPrintNewLine, print newline automatically at the
-    /// end of lifecycle of the BFPrintNewLine
-    fn drop(&mut self) {
-        self.print_newline();
+/// Limit on a running program, besides fuel, see `sandbox`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BFLimit {
+    /// the wall-clock deadline passed
+    Timeout,
+
+    /// the tape would grow past its maximum number of cells
+    Tape,
+
+    /// the program wrote its maximum number of bytes
+    Output,
+
+    /// the program tried reading while input is denied
+    Input,
+}
+
+impl fmt::Display for BFLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::Timeout => "Time limit exceeded",
+            Self::Tape => "Tape limit exceeded",
+            Self::Output => "Output limit exceeded",
+            Self::Input => "Input denied",
+        };
+        f.write_str(message)
+    }
+}
+
+/// Behavior of reading input once it is exhausted
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFEofBehavior {
+    /// store zero into the head cell
+    Zero,
+
+    /// store minus one, ie. all bits set, into the head cell
+    MinusOne,
+
+    /// leave the head cell unchanged
+    Unchanged,
+
+    /// report an IO error
+    #[default]
+    Error,
+}
+
+/// Behavior of moving the head off either edge of the tape
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFHeadMode {
+    /// report a head position error
+    #[default]
+    Strict,
+
+    /// stop the head at the edge, ie. every single step off the tape is
+    /// ignored, the program is optimized at most with `O1` as the other
+    /// optimizations assume the head never stops
+    Clamp,
+}
+
+/// Width of brainfuck virtual machine cells
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFCellWidth {
+    /// 8-bit cells, `u8`
+    #[default]
+    U8,
+
+    /// 16-bit cells, `u16`
+    U16,
+
+    /// 32-bit cells, `u32`
+    U32,
+}
+
+impl BFCellWidth {
+    /// number of bits in a cell
+    pub fn bits(&self) -> u32 {
+        match self {
+            Self::U8 => 8,
+            Self::U16 => 16,
+            Self::U32 => 32,
+        }
     }
 }
 
@@ -102,8 +459,65 @@ pub trait CellKind {
 
     /// write value into current cell
     fn set_value(&mut self, value: u8);
+
+    /// read the whole value of current cell
+    fn get_u32(&mut self) -> u32 {
+        self.get_value().into()
+    }
+
+    /// whether current cell holds zero
+    fn is_zero(&mut self) -> bool {
+        self.get_value() == 0
+    }
+
+    /// increment value in current cell by `n`
+    fn increment_by(&mut self, n: u32) {
+        for _ in 0..n {
+            self.increment();
+        }
+    }
+
+    /// decrement value in current cell by `n`
+    fn decrement_by(&mut self, n: u32) {
+        for _ in 0..n {
+            self.decrement();
+        }
+    }
+
+    /// view a tape of cells as raw bytes, only possible for byte cells
+    fn as_bytes_mut(_cells: &mut [Self]) -> Option<&mut [u8]>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/// Program run by a virtual machine, borrowed or shared
+#[derive(Debug, Clone)]
+enum BFProgramRef<'a> {
+    /// borrowed from the caller for the lifetime of the virtual machine
+    Borrowed(&'a BFValidProgram),
+
+    /// shared ownership, see `new_shared`
+    Shared(Arc<BFValidProgram>),
 }
 
+impl core::ops::Deref for BFProgramRef<'_> {
+    type Target = BFValidProgram;
+
+    fn deref(&self) -> &BFValidProgram {
+        match self {
+            Self::Borrowed(program) => program,
+            Self::Shared(program) => program,
+        }
+    }
+}
+
+/// Virtual machine owning a share of its program, free of any borrow so
+/// it can be stored in structs or moved to worker threads
+pub type BFOwnedVirtualMachine<T> = BFVirtualMachine<'static, T>;
+
 /// Brainfuck virtual machine
 ///
 /// Run simple virtual machine which consists of a tape of cells
@@ -116,7 +530,7 @@ pub trait CellKind {
 /// use bft_types::BFProgram;
 /// use std::io;
 ///
-/// let bf_info = BFProgram::new("", "abcd+++123--><,>");
+/// let bf_info = BFProgram::new("", "abcd+++123--><,>").validate().unwrap();
 /// let mut bf_vm = BFVirtualMachine::<u8>::new(0, false, &bf_info);
 /// bf_vm.move_head_left();
 /// let result = bf_vm.interpret(&mut io::stdin(), &mut io::stdout());
@@ -132,162 +546,886 @@ pub struct BFVirtualMachine<'a, T> {
     /// extendable flag for the tape
     allow_extend: bool,
 
-    /// brainfuck program to be executed on the virtual machine
-    program: &'a BFProgram,
+    /// validated brainfuck program to be executed on the virtual machine
+    program: BFProgramRef<'a>,
+
+    /// packed execution form of the program
+    code: BFPackedProgram,
 
     /// current program counter
     program_cnt: usize,
+
+    /// last byte written out by the program
+    tail: u8,
+
+    /// remaining instructions allowed to execute, unlimited if None
+    fuel: Option<u64>,
+
+    /// what reading does once input is exhausted
+    eof: BFEofBehavior,
+
+    /// IO events in execution order, not recorded if None
+    io_log: Option<Vec<BFIoEvent>>,
+
+    /// optimization level of the packed program
+    opt_level: BFOptLevel,
+
+    /// packed instructions executed so far
+    steps: u64,
+
+    /// whether the tape was only changed by running, so it is all zero
+    /// with the head on the first cell until the first step
+    fresh_tape: bool,
+
+    /// print a newline after the output if it doesn't end with one
+    final_newline: bool,
+
+    /// print a prompt before reading a value
+    prompt: bool,
+
+    /// translation of the line endings read by the program
+    newline: BFNewlineState,
+
+    /// what moving the head off the tape does
+    head_mode: BFHeadMode,
+
+    /// number of cells the tape may extend to, unlimited if None
+    max_cells: Option<usize>,
+
+    /// bytes the program may write, unlimited if None
+    max_output: Option<u64>,
+
+    /// bytes written so far
+    written: u64,
+
+    /// whether reading is refused
+    input_denied: bool,
+
+    /// wall-clock time the program must halt by, none if None
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>,
+
+    /// how long packing the program took the last time
+    #[cfg(feature = "std")]
+    pack_time: std::time::Duration,
+
+    /// handlers of the extension commands of the program
+    extensions: BFExtensions<'a, T>,
+
+    /// hooks of the memory-mapped cells
+    hooks: BFCellHooks<'a>,
 }
 
+/// zeroed tape of `len` cells, the 30000 cells of the original
+/// implementation if `len` is zero
+fn tape_with_len<T: Default>(len: usize) -> Vec<T> {
+    let mut cells = Vec::new();
+    cells.resize_with(if len > 0 { len } else { 30000 }, T::default);
+    cells
+}
+
+/// number of instructions between two checks of the deadline
+#[cfg(feature = "std")]
+const DEADLINE_INTERVAL: u64 = 1 << 12;
+
 impl<'a, T> BFVirtualMachine<'a, T>
 where
     T: Default + CellKind,
 {
     /// Create a new brainfuck virtual machine
-    pub fn new(len: usize, extendable: bool, bf_info: &'a BFProgram) -> Self {
+    pub fn new(len: usize, extendable: bool, bf_info: &'a BFValidProgram) -> Self {
+        Self::with_program(len, extendable, BFProgramRef::Borrowed(bf_info))
+    }
+
+    /// Create a new brainfuck virtual machine sharing ownership of the
+    /// program, see `BFOwnedVirtualMachine`
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::{BFOwnedVirtualMachine, BFVirtualMachine};
+    /// use bft_types::BFProgram;
+    /// use std::sync::Arc;
+    ///
+    /// let bf_info = Arc::new(BFProgram::new("", "+.").validate().unwrap());
+    /// let mut bf_vm: BFOwnedVirtualMachine<u8> = BFVirtualMachine::new_shared(0, false, bf_info);
+    /// let worker = std::thread::spawn(move || {
+    ///     let mut output = Vec::new();
+    ///     bf_vm.interpret(&mut &b""[..], &mut output).map(|_| output)
+    /// });
+    /// assert_eq!(worker.join().unwrap().unwrap(), b"\x01\n");
+    /// ```
+    pub fn new_shared(len: usize, extendable: bool, bf_info: Arc<BFValidProgram>) -> Self {
+        Self::with_program(len, extendable, BFProgramRef::Shared(bf_info))
+    }
+
+    /// Create a new brainfuck virtual machine running `program`
+    fn with_program(len: usize, extendable: bool, program: BFProgramRef<'a>) -> Self {
+        Self::with_tape(tape_with_len(len), extendable, program)
+    }
+
+    /// Create a new brainfuck virtual machine running `program` on `cells`
+    fn with_tape(cells: Vec<T>, extendable: bool, program: BFProgramRef<'a>) -> Self {
+        #[cfg(feature = "std")]
+        let packing = std::time::Instant::now();
+        let code = BFPackedProgram::optimize(&program, BFOptLevel::default());
         Self {
-            cells: {
-                let mut cell_len = 30000;
-                if len > 0 {
-                    cell_len = len;
-                }
-                let mut __cells = Vec::<T>::new();
-                __cells.resize_with(cell_len, || T::default());
-                __cells
-            },
+            cells,
             head: 0,
             allow_extend: extendable,
-            program: bf_info,
+            program,
+            code,
             program_cnt: 0,
+            tail: 0,
+            fuel: None,
+            eof: BFEofBehavior::default(),
+            io_log: None,
+            opt_level: BFOptLevel::default(),
+            steps: 0,
+            fresh_tape: true,
+            final_newline: true,
+            prompt: true,
+            newline: BFNewlineState::default(),
+            head_mode: BFHeadMode::default(),
+            max_cells: None,
+            max_output: None,
+            written: 0,
+            input_denied: false,
+            #[cfg(feature = "std")]
+            deadline: None,
+            #[cfg(feature = "std")]
+            pack_time: packing.elapsed(),
+            extensions: BFExtensions::new(),
+            hooks: BFCellHooks::new(),
+        }
+    }
+
+    /// Choose what moving the head off the tape does, the program is
+    /// repacked so this must be chosen before the program starts running
+    pub fn set_head_mode(&mut self, mode: BFHeadMode) {
+        self.head_mode = mode;
+        self.repack();
+    }
+
+    /// Choose whether a newline is printed after the output if it doesn't
+    /// end with one, on by default
+    pub fn set_final_newline(&mut self, enable: bool) {
+        self.final_newline = enable;
+    }
+
+    /// Choose whether a prompt is printed to stdout before reading a value,
+    /// on by default
+    pub fn set_prompt(&mut self, enable: bool) {
+        self.prompt = enable;
+    }
+
+    /// Choose how the line endings read by the program are translated,
+    /// unchanged by default
+    pub fn set_newline(&mut self, mode: BFNewline) {
+        self.newline = BFNewlineState::new(mode);
+    }
+
+    /// Repack the program at optimization level `level`,
+    /// must be chosen before the program starts running
+    pub fn set_opt_level(&mut self, level: BFOptLevel) {
+        self.opt_level = level;
+        self.repack();
+    }
+
+    /// Optimization level the program is packed with
+    fn packed_opt_level(&self) -> BFOptLevel {
+        match self.head_mode {
+            BFHeadMode::Strict if self.hooks.is_empty() => self.opt_level,
+            _ => self.opt_level.min(BFOptLevel::O1),
+        }
+    }
+
+    /// Pack the program again after the options changed
+    fn repack(&mut self) {
+        #[cfg(feature = "std")]
+        let packing = std::time::Instant::now();
+        let fresh = self.fresh_tape && self.steps == 0;
+        self.code = BFPackedProgram::optimize_on(&self.program, self.packed_opt_level(), fresh);
+        #[cfg(feature = "std")]
+        {
+            self.pack_time = packing.elapsed();
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            level = %self.packed_opt_level(),
+            instructions = self.program.instructions().len(),
+            ops = self.code.ops().len(),
+            "packed the program"
+        );
+    }
+
+    /// Packed program being executed
+    pub fn code(&self) -> &BFPackedProgram {
+        &self.code
+    }
+
+    /// Statistics of the program run so far
+    pub fn stats(&self) -> BFRunStats {
+        BFRunStats {
+            opt_level: self.packed_opt_level(),
+            ops: self.code.ops().len(),
+            steps: self.steps,
+        }
+    }
+
+    /// How long packing the program took the last time it was packed
+    #[cfg(feature = "std")]
+    pub fn pack_time(&self) -> std::time::Duration {
+        self.pack_time
+    }
+
+    /// Choose what reading does once input is exhausted
+    pub fn set_eof(&mut self, eof: BFEofBehavior) {
+        self.eof = eof;
+    }
+
+    /// Limit how many packed instructions may execute, None for unlimited
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Remaining fuel, None if unlimited
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Whether a prompt is printed to stdout before reading a value
+    pub fn prompt(&self) -> bool {
+        self.prompt
+    }
+
+    /// Limit how many cells an extensible tape may grow to, None for
+    /// unlimited, moving past it is a tape limit error
+    pub fn set_max_cells(&mut self, max_cells: Option<usize>) {
+        self.max_cells = max_cells;
+    }
+
+    /// Limit how many bytes the program may write, None for unlimited,
+    /// writing past it is an output limit error
+    pub fn set_max_output(&mut self, max_output: Option<u64>) {
+        self.max_output = max_output;
+    }
+
+    /// Refuse or allow reading, a refused read is an input limit error
+    /// whatever the reader holds
+    pub fn deny_input(&mut self, deny: bool) {
+        self.input_denied = deny;
+    }
+
+    /// Stop the program with a timeout error once `deadline` passed, None
+    /// for no deadline, checked every few thousand instructions
+    #[cfg(feature = "std")]
+    pub fn set_deadline(&mut self, deadline: Option<std::time::Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Whether any limit besides fuel is set
+    #[cfg(feature = "jit")]
+    fn limited(&self) -> bool {
+        #[cfg(feature = "std")]
+        if self.deadline.is_some() {
+            return true;
+        }
+        self.max_cells.is_some() || self.max_output.is_some() || self.input_denied
+    }
+
+    /// Execute extension commands of the program with the handlers of
+    /// `extensions`
+    pub fn set_extensions(&mut self, extensions: BFExtensions<'a, T>) {
+        self.extensions = extensions;
+    }
+
+    /// Bind memory-mapped cells to the hooks of `hooks`, the program is
+    /// repacked at most with `O1` so this must be chosen before the program
+    /// starts running, see `hooks`
+    pub fn set_cell_hooks(&mut self, hooks: BFCellHooks<'a>) {
+        self.hooks = hooks;
+        self.repack();
+    }
+
+    /// Turn recording of IO events on or off, clearing recorded events
+    pub fn record_io(&mut self, enable: bool) {
+        self.io_log = enable.then(Vec::new);
+    }
+
+    /// IO events recorded so far in execution order
+    pub fn io_log(&self) -> &[BFIoEvent] {
+        self.io_log.as_deref().unwrap_or_default()
+    }
+
+    /// A reference of the tape
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Position of the head cell
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// Copy of the tape and head, to `restore` them later
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::BFVirtualMachine;
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+>++").validate().unwrap();
+    /// let mut bf_vm = BFVirtualMachine::<u8>::new(2, false, &bf_info);
+    /// let snapshot = bf_vm.snapshot();
+    /// bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!((bf_vm.cells(), bf_vm.head()), (&[1, 2][..], 1));
+    /// bf_vm.restore(snapshot);
+    /// assert_eq!((bf_vm.cells(), bf_vm.head()), (&[0, 0][..], 0));
+    /// ```
+    pub fn snapshot(&self) -> BFSnapshot<T>
+    where
+        T: Clone,
+    {
+        BFSnapshot {
+            cells: self.cells.clone(),
+            head: self.head,
+        }
+    }
+
+    /// Put back the tape and head of `snapshot`, the program, its counter
+    /// and the options are kept
+    pub fn restore(&mut self, snapshot: BFSnapshot<T>) {
+        self.cells = snapshot.cells;
+        self.head = snapshot.head;
+        self.fresh_tape = false;
+    }
+
+    /// Index of the next packed instruction in `code`
+    pub fn program_counter(&self) -> usize {
+        self.program_cnt
+    }
+
+    /// Record an IO event of the current instruction if recording, trace
+    /// it with the `tracing` feature
+    fn log_io(&mut self, kind: BFIoKind, value: Option<u8>) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(?kind, ?value, step = self.steps, "io");
+        if self.io_log.is_some() {
+            let event = BFIoEvent::new(kind, value, self.current_cmd());
+            if let Some(log) = self.io_log.as_mut() {
+                log.push(event);
+            }
+        }
+    }
+
+    /// Consume one unit of fuel, error if none left, the step only counted
+    /// once the instruction is allowed to run
+    fn burn_fuel(&mut self) -> Result<(), BFVmErr> {
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(DEADLINE_INTERVAL) && std::time::Instant::now() >= deadline
+            {
+                return Err(BFVmErr::LimitErr(BFLimit::Timeout, self.current_cmd()));
+            }
+        }
+        match self.fuel {
+            Some(0) => return Err(BFVmErr::FuelExhaustedErr(self.current_cmd())),
+            Some(ref mut fuel) => *fuel -= 1,
+            None => {}
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    /// Finish the output once the program halted,
+    /// printing the final newline if turned on
+    fn finish_output(&mut self, writer: &mut impl BFWrite) -> Result<(), BFVmErr> {
+        if self.final_newline {
+            BFPrintNewLine::new(self.tail, writer)
+                .print_newline()
+                .map_err(BFVmErr::NewlineErr)?;
         }
+        Ok(())
+    }
+
+    /// Source instruction currently being executed
+    fn current_cmd(&self) -> BFCharInfo {
+        self.code.source_info(&self.program, self.program_cnt)
     }
 
     /// Move the head to the left cell, error if falling off low edge
     pub fn move_head_left(&mut self) -> Result<(), BFVmErr> {
-        if self.head > 0 {
-            self.head -= 1;
+        self.move_head_left_by(1)
+    }
+
+    /// Move the head `n` cells to the left, error if falling off low edge
+    /// unless the head is clamped
+    pub fn move_head_left_by(&mut self, n: usize) -> Result<(), BFVmErr> {
+        if self.head >= n {
+            self.head -= n;
+            Ok(())
+        } else if self.head_mode == BFHeadMode::Clamp {
+            self.head = 0;
             Ok(())
         } else {
-            Err(BFVmErr::HeadInvalidPositionErr(
-                self.program.instructions()[self.program_cnt],
-            ))
+            Err(BFVmErr::HeadInvalidPositionErr(self.current_cmd()))
         }
     }
 
     /// Move the head to the right cell, error if falling off high edge
     pub fn move_head_right(&mut self) -> Result<(), BFVmErr> {
-        if self.head >= self.cells.len() - 1 {
+        self.move_head_right_by(1)
+    }
+
+    /// Move the head `n` cells to the right, error if falling off high edge
+    /// unless the tape is extensible or the head is clamped
+    pub fn move_head_right_by(&mut self, n: usize) -> Result<(), BFVmErr> {
+        let mut target = self.head + n;
+        if target >= self.cells.len() {
             if self.allow_extend {
-                self.cells.push(T::default());
+                self.extend_tape(target)?;
+            } else if self.head_mode == BFHeadMode::Clamp {
+                target = self.cells.len() - 1;
             } else {
-                return Err(BFVmErr::HeadInvalidPositionErr(
-                    self.program.instructions()[self.program_cnt],
-                ));
+                return Err(BFVmErr::HeadInvalidPositionErr(self.current_cmd()));
             }
         }
-        self.head += 1;
+        self.head = target;
         Ok(())
     }
 
+    /// The head cell, the head always points into the tape as every move
+    /// checks the new position against the tape length
+    #[inline(always)]
+    fn head_cell(&mut self) -> &mut T {
+        debug_assert!(self.head < self.cells.len());
+        // SAFETY: `head` is only changed by moves keeping it within `cells`
+        unsafe { self.cells.get_unchecked_mut(self.head) }
+    }
+
     /// add value at head of tape by 1
     pub fn add_head_by_one(&mut self) {
-        self.cells[self.head].increment();
+        self.head_cell().increment();
     }
 
     /// minus value at head of tape by 1
     pub fn minus_head_by_one(&mut self) {
-        self.cells[self.head].decrement();
+        self.head_cell().decrement();
+    }
+
+    /// add value at head of tape by `n`
+    pub fn add_head_by(&mut self, n: u32) {
+        self.head_cell().increment_by(n);
+    }
+
+    /// minus value at head of tape by `n`
+    pub fn minus_head_by(&mut self, n: u32) {
+        self.head_cell().decrement_by(n);
+    }
+
+    /// Index of the cell `offset` cells away from the head, extending the
+    /// tape if allowed, error if falling off either edge
+    fn cell_index(&mut self, offset: i16) -> Result<usize, BFVmErr> {
+        match self.head.checked_add_signed(offset.into()) {
+            Some(index) => self.ensure_cell(index),
+            None => Err(BFVmErr::HeadInvalidPositionErr(self.current_cmd())),
+        }
+    }
+
+    /// Make sure the cell at `index` exists, extending the tape if allowed,
+    /// error if falling off the high edge
+    fn ensure_cell(&mut self, index: usize) -> Result<usize, BFVmErr> {
+        if index >= self.cells.len() {
+            if !self.allow_extend {
+                return Err(BFVmErr::HeadInvalidPositionErr(self.current_cmd()));
+            }
+            self.extend_tape(index)?;
+        }
+        Ok(index)
+    }
+
+    /// Extend the tape up to the cell at `index`, error if it would grow
+    /// past its maximum number of cells
+    fn extend_tape(&mut self, index: usize) -> Result<(), BFVmErr> {
+        if self.max_cells.is_some_and(|max_cells| index >= max_cells) {
+            return Err(BFVmErr::LimitErr(BFLimit::Tape, self.current_cmd()));
+        }
+        self.cells.resize_with(index + 1, T::default);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(cells = self.cells.len(), "extended the tape");
+        Ok(())
+    }
+
+    /// Jump the head to the first zero cell at or right of it, or to the
+    /// last cell if there is none, only done for byte cells
+    fn skip_bytes_right(&mut self) {
+        if let Some(tape) = T::as_bytes_mut(&mut self.cells) {
+            self.head = match memchr::memchr(0, &tape[self.head..]) {
+                Some(pos) => self.head + pos,
+                None => tape.len() - 1,
+            };
+        }
+    }
+
+    /// Jump the head to the first zero cell at or left of it, or to the
+    /// first cell if there is none, only done for byte cells
+    fn skip_bytes_left(&mut self) {
+        if let Some(tape) = T::as_bytes_mut(&mut self.cells) {
+            self.head = memchr::memrchr(0, &tape[..=self.head]).unwrap_or(0);
+        }
+    }
+
+    /// Execute a packed instruction working on cells only, ie. a data
+    /// instruction or one of the optimized instructions
+    pub(crate) fn execute_cell_op(&mut self, op: BFPackedOp) -> Result<(), BFVmErr> {
+        let n = op.operand();
+        match op.opcode() {
+            BFOpCode::DataIncrement => {
+                let index = self.cell_index(op.offset())?;
+                self.cells[index].increment_by(n);
+            }
+            BFOpCode::DataDecrement => {
+                let index = self.cell_index(op.offset())?;
+                self.cells[index].decrement_by(n);
+            }
+            BFOpCode::Clear | BFOpCode::Set => {
+                let index = self.cell_index(op.offset())?;
+                self.cells[index].set_value(0);
+                self.cells[index].increment_by(n);
+            }
+            BFOpCode::MulAdd => {
+                let value = self.head_cell().get_u32();
+                if value != 0 {
+                    let index = self.cell_index(op.offset())?;
+                    self.cells[index].increment_by(value.wrapping_mul(n));
+                }
+            }
+            BFOpCode::ScanRight => {
+                if n == 1 {
+                    self.skip_bytes_right();
+                }
+                while !self.head_cell().is_zero() {
+                    self.move_head_right_by(n as usize)?;
+                }
+            }
+            BFOpCode::ScanLeft => {
+                if n == 1 {
+                    self.skip_bytes_left();
+                }
+                while !self.head_cell().is_zero() {
+                    self.move_head_left_by(n as usize)?;
+                }
+            }
+            BFOpCode::ClearRange => {
+                let start = self.cell_index(op.offset())?;
+                let end = self.ensure_cell(start + n as usize - 1)?;
+                let range = &mut self.cells[start..=end];
+                if let Some(bytes) = T::as_bytes_mut(range) {
+                    bytes.fill(0);
+                } else {
+                    range.iter_mut().for_each(|cell| cell.set_value(0));
+                }
+            }
+            _ => unreachable!("bft: {:?} does not work on cells only", op.opcode()),
+        }
+        Ok(())
     }
 
     /// read value from reader to head of tape
     pub fn read_value<R>(&mut self, reader: &mut R) -> Result<(), BFVmErr>
     where
-        R: Read,
+        R: BFRead + ?Sized,
+    {
+        if self.input_denied {
+            return Err(BFVmErr::LimitErr(BFLimit::Input, self.current_cmd()));
+        }
+        #[cfg(feature = "std")]
+        if self.prompt {
+            println!("Input a value: ");
+        }
+        match self.newline.read_byte(reader) {
+            Ok(Some(value)) => {
+                self.head_cell().set_value(value);
+                self.log_io(BFIoKind::Input, Some(value));
+            }
+            Ok(None) if self.eof != BFEofBehavior::Error => {
+                self.log_io(BFIoKind::Input, None);
+                let cell = &mut self.cells[self.head];
+                match self.eof {
+                    BFEofBehavior::Zero => cell.set_value(0),
+                    BFEofBehavior::MinusOne => {
+                        cell.set_value(0);
+                        cell.decrement();
+                    }
+                    BFEofBehavior::Unchanged | BFEofBehavior::Error => (),
+                }
+            }
+            Ok(None) => {
+                return Err(BFVmErr::from(BFVirtualMachineIOErr {
+                    err: io::eof_error(),
+                    cmd: self.current_cmd(),
+                }))
+            }
+            Err(err) => {
+                return Err(BFVmErr::from(BFVirtualMachineIOErr {
+                    err,
+                    cmd: self.current_cmd(),
+                }))
+            }
+        }
+        Ok(())
+    }
+
+    /// write value from head of tape to writer
+    pub fn write_value<W>(&mut self, writer: &mut W) -> Result<(), BFVmErr>
+    where
+        W: BFWrite + ?Sized,
     {
-        let mut buf = vec![0u8; 1];
-        println!("Input a value: ");
-        reader.read_exact(&mut buf).map_err(|err| {
+        if self
+            .max_output
+            .is_some_and(|max_output| self.written >= max_output)
+        {
+            return Err(BFVmErr::LimitErr(BFLimit::Output, self.current_cmd()));
+        }
+        let value = self.head_cell().get_value();
+        writer.write_byte(value).map_err(|err| {
             BFVmErr::from(BFVirtualMachineIOErr {
                 err,
-                cmd: self.program.instructions()[self.program_cnt],
+                cmd: self.current_cmd(),
             })
         })?;
-        self.cells[self.head].set_value(buf[0]);
+        self.log_io(BFIoKind::Output, Some(value));
+        self.tail = value;
+        self.written += 1;
         Ok(())
     }
 
-    /// write value from head of tape to writer
-    pub fn write_value<W>(&mut self, writer: &mut W) -> Result<(), BFVmErr>
+    /// run the handler of the extension command of `op`, nothing if the
+    /// command has no handler
+    pub(crate) fn execute_extension<R, W>(
+        &mut self,
+        op: BFPackedOp,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<(), BFVmErr>
     where
-        W: Write,
+        R: BFRead,
+        W: BFWrite,
     {
-        writer
-            .write_all(&[self.cells[self.head].get_value()])
-            .map_err(|err| {
-                BFVmErr::from(BFVirtualMachineIOErr {
-                    err,
-                    cmd: self.program.instructions()[self.program_cnt],
-                })
+        let cmd = char::from_u32(op.operand()).expect("bft: extension holds a character");
+        let Some(handler) = self.extensions.handler(cmd) else {
+            return Ok(());
+        };
+        let mut ctx = BFExtensionCtx {
+            cells: &mut self.cells,
+            head: self.head,
+            reader,
+            writer,
+        };
+        handler.execute(cmd, &mut ctx).map_err(|err| {
+            BFVmErr::from(BFVirtualMachineIOErr {
+                err,
+                cmd: self.code.source_info(&self.program, self.program_cnt),
             })
+        })
     }
 
-    /// enter into loop mode in brainfuck program
-    pub fn start_loop(&mut self, idx: Option<usize>) -> Result<(), BFVmErr> {
-        if self.cells[self.head].get_value() == 0 {
-            match idx {
-                Some(r) => self.program_cnt = r,
-                None => {
-                    return Err(BFVmErr::BracketPairErr(
-                        self.program.instructions()[self.program_cnt],
-                    ))
+    /// enter into loop mode in brainfuck program,
+    /// jump to the matching close bracket `target` if head cell is zero
+    pub fn start_loop(&mut self, target: usize) {
+        if self.head_cell().is_zero() {
+            self.program_cnt = target;
+        }
+    }
+
+    /// exit loop mode in brainfuck program,
+    /// jump back to the matching open bracket `target` if head cell is non-zero
+    pub fn stop_loop(&mut self, target: usize) {
+        if !self.head_cell().is_zero() {
+            self.program_cnt = target;
+        }
+    }
+
+    /// Execute one packed instruction, the program counter is left on it
+    #[inline(always)]
+    fn execute_op(
+        &mut self,
+        op: BFPackedOp,
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
+    ) -> Result<(), BFVmErr> {
+        self.burn_fuel()?;
+        let n = op.operand();
+        match op.opcode() {
+            BFOpCode::PointerIncrement => self.move_head_right_by(n as usize)?,
+            BFOpCode::PointerDecrement => self.move_head_left_by(n as usize)?,
+            BFOpCode::DataIncrement if op.offset() == 0 => self.head_cell().increment_by(n),
+            BFOpCode::DataDecrement if op.offset() == 0 => self.head_cell().decrement_by(n),
+            BFOpCode::DataOutput => self.write_value(writer)?,
+            BFOpCode::DataInput => self.read_value(reader)?,
+            BFOpCode::Extension => self.execute_extension(op, reader, writer)?,
+            BFOpCode::LoopStart => {
+                if self.head_cell().is_zero() {
+                    self.program_cnt = n as usize;
                 }
             }
+            BFOpCode::LoopTerminate => {
+                if !self.head_cell().is_zero() {
+                    self.program_cnt = n as usize;
+                }
+            }
+            BFOpCode::DataIncrement
+            | BFOpCode::DataDecrement
+            | BFOpCode::Clear
+            | BFOpCode::Set
+            | BFOpCode::MulAdd
+            | BFOpCode::ScanRight
+            | BFOpCode::ScanLeft
+            | BFOpCode::ClearRange => self.execute_cell_op(op)?,
         }
         Ok(())
     }
 
-    /// exit loop mode in brainfuck program
-    pub fn stop_loop(&mut self, idx: Option<usize>) -> Result<(), BFVmErr> {
-        if self.cells[self.head].get_value() > 0 {
-            match idx {
-                Some(r) => self.program_cnt = r,
-                None => {
-                    return Err(BFVmErr::BracketPairErr(
-                        self.program.instructions()[self.program_cnt],
-                    ))
-                }
+    /// Execute one packed instruction going through the hook of the head
+    /// cell, the instruction reads the cell from the hook first and writes
+    /// the changed cell back to it
+    fn execute_hooked_op(
+        &mut self,
+        op: BFPackedOp,
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
+    ) -> Result<(), BFVmErr> {
+        let index = self.head;
+        let (uses, changes) = match op.opcode() {
+            BFOpCode::DataIncrement | BFOpCode::DataDecrement => (true, true),
+            BFOpCode::DataInput => (false, true),
+            BFOpCode::DataOutput | BFOpCode::LoopStart | BFOpCode::LoopTerminate => (true, false),
+            _ => (false, false),
+        };
+        if uses {
+            if let Some(value) = self.hooks.read(index) {
+                self.cells[index].set_value(value);
             }
         }
+        self.execute_op(op, reader, writer)?;
+        if changes {
+            let value = self.cells[index].get_value();
+            self.hooks.write(index, value);
+        }
         Ok(())
     }
 
     /// run brainfuck program on the virtual machine
+    ///
+    /// The instructions are taken out of the packed program for the run so
+    /// the loop borrows nothing from the virtual machine and restored once
+    /// it stops, data and loop instructions on the head cell, the hottest
+    /// ones, are executed inline.
     pub fn interpret(
         &mut self,
-        reader: &mut impl Read,
-        writer: &mut impl Write,
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
     ) -> Result<(), BFVmErr> {
-        let mut tail: u8 = 0;
-        let cmd_len = self.program.instructions().len();
-        while self.program_cnt < cmd_len {
-            match self.program.instructions()[self.program_cnt].get_raw() {
-                BFCharCmdName::PointerIncrement => self.move_head_right()?,
-                BFCharCmdName::PointerDecrement => self.move_head_left()?,
-                BFCharCmdName::DataIncrement => self.add_head_by_one(),
-                BFCharCmdName::DataDecrement => self.minus_head_by_one(),
-                BFCharCmdName::DataOutput => {
-                    tail = self.cells[self.head].get_value();
-                    self.write_value(writer)?;
-                }
-                BFCharCmdName::DataInput => self.read_value(reader)?,
-                BFCharCmdName::LoopStart(r) => self.start_loop(r)?,
-                BFCharCmdName::LoopTerminate(r) => self.stop_loop(r)?,
+        let ops = self.code.take_ops();
+        let result = self.execute_ops(&ops, reader, writer);
+        self.code.restore_ops(ops);
+        result?;
+        self.finish_output(writer)
+    }
+
+    /// Execute `ops` from the program counter to the end
+    fn execute_ops(
+        &mut self,
+        ops: &[BFPackedOp],
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
+    ) -> Result<(), BFVmErr> {
+        if self.hooks.is_empty() {
+            while let Some(&op) = ops.get(self.program_cnt) {
+                self.execute_op(op, reader, writer)?;
+                self.program_cnt += 1;
+            }
+        } else {
+            while let Some(&op) = ops.get(self.program_cnt) {
+                self.execute_hooked_op(op, reader, writer)?;
+                self.program_cnt += 1;
             }
-            self.program_cnt += 1;
         }
-        BFPrintNewLine::new(tail, writer);
         Ok(())
     }
+
+    /// Execute the next packed instruction, returning whether the program
+    /// goes on, the output is finished after the last instruction and
+    /// stepping a halted program does nothing
+    pub fn step(
+        &mut self,
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
+    ) -> Result<bool, BFVmErr> {
+        let Some(&op) = self.code.ops().get(self.program_cnt) else {
+            return Ok(false);
+        };
+        if self.hooks.is_empty() {
+            self.execute_op(op, reader, writer)?;
+        } else {
+            self.execute_hooked_op(op, reader, writer)?;
+        }
+        self.program_cnt += 1;
+        if self.program_cnt < self.code.ops().len() {
+            return Ok(true);
+        }
+        self.finish_output(writer)?;
+        Ok(false)
+    }
+
+    /// Run `bf_info` next from its first instruction, keeping the tape, the
+    /// head and the options, eg. for the lines typed in a REPL
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::BFVirtualMachine;
+    /// use bft_types::BFProgram;
+    /// use std::sync::Arc;
+    ///
+    /// let first = Arc::new(BFProgram::new("", "+++>").validate().unwrap());
+    /// let mut bf_vm = BFVirtualMachine::<u8>::new_shared(2, false, first);
+    /// bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+    /// bf_vm.load_shared(Arc::new(BFProgram::new("", "<+").validate().unwrap()));
+    /// bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(bf_vm.cells(), [4, 0]);
+    /// ```
+    pub fn load_shared(&mut self, bf_info: Arc<BFValidProgram>) {
+        self.program = BFProgramRef::Shared(bf_info);
+        self.program_cnt = 0;
+        self.repack();
+    }
+
+    /// Whether the program ran past its last instruction
+    pub fn halted(&self) -> bool {
+        self.program_cnt >= self.code.ops().len()
+    }
+
+    /// run brainfuck program on the virtual machine with the chosen backend,
+    /// memory-mapped cells are only run by `interpret`
+    pub fn interpret_with(
+        &mut self,
+        backend: BFBackend,
+        reader: &mut impl BFRead,
+        writer: &mut impl BFWrite,
+    ) -> Result<(), BFVmErr> {
+        if !self.hooks.is_empty() {
+            return self.interpret(reader, writer);
+        }
+        match backend {
+            BFBackend::Loop => self.interpret(reader, writer),
+            BFBackend::Threaded => self.interpret_threaded(reader, writer),
+            #[cfg(feature = "jit")]
+            BFBackend::Jit => self.interpret_jit(reader, writer),
+        }
+    }
 }
 
 impl CellKind for u8 {
@@ -310,15 +1448,75 @@ impl CellKind for u8 {
     fn set_value(&mut self, value: u8) {
         *self = value;
     }
+
+    /// increment value in current cell by `n`
+    fn increment_by(&mut self, n: u32) {
+        *self = self.wrapping_add(n as u8);
+    }
+
+    /// decrement value in current cell by `n`
+    fn decrement_by(&mut self, n: u32) {
+        *self = self.wrapping_sub(n as u8);
+    }
+
+    /// byte cells are already raw bytes
+    fn as_bytes_mut(cells: &mut [Self]) -> Option<&mut [u8]> {
+        Some(cells)
+    }
 }
 
+/// implement CellKind for cells wider than a byte,
+/// input sets the whole cell while output writes the low byte only
+macro_rules! impl_wide_cell_kind {
+    ($($t:ty),*) => {
+        $(
+            impl CellKind for $t {
+                fn increment(&mut self) {
+                    *self = self.wrapping_add(1);
+                }
+
+                fn decrement(&mut self) {
+                    *self = self.wrapping_sub(1);
+                }
+
+                fn get_value(&mut self) -> u8 {
+                    *self as u8
+                }
+
+                fn set_value(&mut self, value: u8) {
+                    *self = value.into();
+                }
+
+                fn get_u32(&mut self) -> u32 {
+                    *self as u32
+                }
+
+                fn is_zero(&mut self) -> bool {
+                    *self == 0
+                }
+
+                fn increment_by(&mut self, n: u32) {
+                    *self = self.wrapping_add(n as $t);
+                }
+
+                fn decrement_by(&mut self, n: u32) {
+                    *self = self.wrapping_sub(n as $t);
+                }
+            }
+        )*
+    };
+}
+
+impl_wide_cell_kind!(u16, u32);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bft_types::BFProgram;
 
     #[test]
     fn test_move_head() {
-        let bf_info = BFProgram::new("", "hello++--,><>");
+        let bf_info = BFProgram::new("", "hello++--,><>").validate().unwrap();
         let mut vm = BFVirtualMachine::<u8>::new(2, false, &bf_info);
         assert!(vm.move_head_left().is_err());
         assert!(vm.move_head_right().is_ok());
@@ -327,7 +1525,7 @@ mod tests {
 
     #[test]
     fn test_move_head_extend() {
-        let bf_info = BFProgram::new("", "hello++--,><>");
+        let bf_info = BFProgram::new("", "hello++--,><>").validate().unwrap();
         let mut vm = BFVirtualMachine::<u8>::new(2, true, &bf_info);
         assert!(vm.move_head_left().is_err());
         assert!(vm.move_head_right().is_ok());
@@ -339,7 +1537,7 @@ mod tests {
         use std::io::Cursor;
         let mut r_buf = Cursor::new(vec![1, 2, 3, 4, 5]);
         let mut w_buf = Cursor::new(vec![0, 0, 0, 0, 0]);
-        let bf_info = BFProgram::new("", "hello++--,><>");
+        let bf_info = BFProgram::new("", "hello++--,><>").validate().unwrap();
         let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
 
         assert_ne!(r_buf.get_ref(), w_buf.get_ref());
@@ -355,7 +1553,7 @@ mod tests {
         use std::io::Cursor;
         let mut r_buf = Cursor::new(vec![1, 2, 3, 4, 5]);
         let mut w_buf = Cursor::new(vec![0, 0, 0, 0, 0]);
-        let bf_info = BFProgram::new("", "hello++--,><>");
+        let bf_info = BFProgram::new("", "hello++--,><>").validate().unwrap();
         let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
 
         for __i in 0..5 {
@@ -374,4 +1572,294 @@ mod tests {
         }
         assert_eq!(w_buf.get_ref(), &Vec::<u8>::from([0, 1, 2, 3, 4]));
     }
+
+    #[test]
+    fn test_loop_jump() {
+        let bf_info = BFProgram::new("", "[+]>+[-<+>]").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+
+        vm.start_loop(bf_info.jump_target(0));
+        assert_eq!(vm.program_cnt, 2);
+
+        vm.program_cnt = 10;
+        vm.add_head_by_one();
+        vm.stop_loop(bf_info.jump_target(10));
+        assert_eq!(vm.program_cnt, 5);
+    }
+
+    #[test]
+    fn test_fuel_exhausted() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "+[]").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+        vm.set_fuel(Some(1000));
+
+        let mut r_buf = Cursor::new(Vec::<u8>::new());
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        let result = vm.interpret(&mut r_buf, &mut w_buf);
+        assert!(matches!(result, Err(BFVmErr::FuelExhaustedErr(_))));
+        assert_eq!(vm.fuel(), Some(0));
+        assert_eq!(vm.stats().steps(), 1000);
+    }
+
+    #[test]
+    fn test_load_shared_optimized() {
+        let first = Arc::new(BFProgram::new("", "+++").validate().unwrap());
+        let mut vm = BFVirtualMachine::<u8>::new_shared(2, false, first);
+        vm.set_opt_level(BFOptLevel::O3);
+        vm.set_final_newline(false);
+        let mut output = Vec::new();
+        vm.interpret(&mut &b""[..], &mut output).unwrap();
+        let snapshot = vm.snapshot();
+
+        // the tape isn't fresh anymore, so the loop can't be dropped
+        let countdown = Arc::new(BFProgram::new("", "[.-]").validate().unwrap());
+        vm.load_shared(Arc::clone(&countdown));
+        vm.interpret(&mut &b""[..], &mut output).unwrap();
+        assert_eq!(output, [3, 2, 1]);
+
+        let mut vm = BFVirtualMachine::<u8>::new_shared(2, false, countdown);
+        vm.set_final_newline(false);
+        vm.restore(snapshot);
+        vm.set_opt_level(BFOptLevel::O3);
+        output.clear();
+        vm.interpret(&mut &b""[..], &mut output).unwrap();
+        assert_eq!(output, [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_owned_vm() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let bf_info = Arc::new(BFProgram::new("", ",+.").validate().unwrap());
+        let mut vm = BFOwnedVirtualMachine::<u16>::new_shared(1, false, Arc::clone(&bf_info));
+        assert_send_sync(&vm);
+        drop(bf_info);
+
+        let mut output = Vec::new();
+        vm.interpret(&mut &b"a"[..], &mut output).unwrap();
+        assert_eq!(output, b"b\n");
+    }
+
+    #[test]
+    fn test_step() {
+        let bf_info = BFProgram::new("", "++[->+<]>.").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+        let mut output = Vec::new();
+        let mut steps = 0;
+        while vm.step(&mut &b""[..], &mut output).unwrap() {
+            steps += 1;
+        }
+        assert!(vm.halted());
+        assert_eq!(steps + 1, vm.stats().steps());
+        assert_eq!(output, &[2, b'\n']);
+        assert!(!vm.step(&mut &b""[..], &mut output).unwrap());
+        assert_eq!(output, &[2, b'\n']);
+    }
+
+    #[test]
+    fn test_interpret_error_keeps_code() {
+        let bf_info = BFProgram::new("", "+<+").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+        assert!(vm.interpret(&mut &b""[..], &mut Vec::new()).is_err());
+        assert_eq!(vm.code().ops().len(), 3);
+        assert!(!vm.halted());
+    }
+
+    #[test]
+    fn test_eof_behavior() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "+,").validate().unwrap();
+        for (eof, value) in [
+            (BFEofBehavior::Zero, Some(0)),
+            (BFEofBehavior::MinusOne, Some(255)),
+            (BFEofBehavior::Unchanged, Some(1)),
+            (BFEofBehavior::Error, None),
+        ] {
+            let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+            vm.set_eof(eof);
+            let mut r_buf = Cursor::new(Vec::<u8>::new());
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            let result = vm.interpret(&mut r_buf, &mut w_buf);
+            assert_eq!(result.ok().map(|_| vm.cells[0]), value);
+        }
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let bf_info = BFProgram::new("", "+\n ,").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(1, false, &bf_info);
+        vm.set_prompt(false);
+        let err = vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap_err();
+        assert_eq!((err.code(), err.code_name(), err.kind()), (2, "io", "io"));
+        let cmd = err.cmd().unwrap();
+        assert_eq!((cmd.line(), cmd.column()), (2, 2));
+        let source = err.source().unwrap().downcast_ref::<BFIoError>().unwrap();
+        assert_eq!(source.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let err = BFVmErr::LimitErr(BFLimit::Output, cmd);
+        assert_eq!((err.code(), err.code_name()), (7, "output-limit"));
+        assert!(err.source().is_none());
+        let err = BFVmErr::NewlineErr(BFIoError::other("closed"));
+        assert_eq!((err.code(), err.cmd().map(|_| ())), (4, None));
+        assert_eq!(err.source().unwrap().to_string(), "closed");
+    }
+
+    #[test]
+    fn test_clamp_head() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "<<+>>>>>+<<<<[->>+<<]>>.")
+            .validate()
+            .unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        vm.set_opt_level(BFOptLevel::O3);
+        vm.set_head_mode(BFHeadMode::Clamp);
+        assert_eq!(vm.stats().opt_level(), BFOptLevel::O1);
+
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        vm.interpret(&mut Cursor::new(Vec::<u8>::new()), &mut w_buf)
+            .unwrap();
+        assert_eq!(vm.cells(), &[0, 0, 1, 1]);
+        assert_eq!(w_buf.into_inner(), vec![1, 10]);
+    }
+
+    #[test]
+    fn test_final_newline() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "+.").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+        vm.set_final_newline(false);
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        vm.interpret(&mut Cursor::new(Vec::<u8>::new()), &mut w_buf)
+            .unwrap();
+        assert_eq!(w_buf.into_inner(), vec![1]);
+
+        let mut vm = BFVirtualMachine::<u8>::new(5, false, &bf_info);
+        let mut w_buf = [0u8; 1];
+        let result = vm.interpret(&mut Cursor::new(Vec::<u8>::new()), &mut &mut w_buf[..]);
+        assert!(matches!(result, Err(BFVmErr::NewlineErr(_))));
+        assert_eq!(w_buf, [1]);
+    }
+
+    #[test]
+    fn test_wide_cells() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "-[>+<-----]>.").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u16>::new(5, false, &bf_info);
+        let mut r_buf = Cursor::new(Vec::<u8>::new());
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        vm.interpret(&mut r_buf, &mut w_buf).unwrap();
+        assert_eq!(vm.cells[1], 13107);
+        assert_eq!(w_buf.into_inner(), vec![0x33, 10]);
+    }
+
+    #[test]
+    fn test_move_head_by() {
+        let bf_info = BFProgram::new("", ">>>").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        assert!(vm.move_head_right_by(3).is_ok());
+        assert!(vm.move_head_right_by(1).is_err());
+        assert!(vm.move_head_left_by(4).is_err());
+        assert!(vm.move_head_left_by(3).is_ok());
+
+        let mut vm = BFVirtualMachine::<u8>::new(4, true, &bf_info);
+        assert!(vm.move_head_right_by(10).is_ok());
+        assert_eq!(vm.cells.len(), 11);
+    }
+
+    #[test]
+    fn test_opt_levels_agree() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", ",[->+>++<<]>[-<+>]>>+++[<+++>-]<[>][.]<.<<.")
+            .validate()
+            .unwrap();
+        let mut outputs = Vec::new();
+
+        for level in [
+            BFOptLevel::O0,
+            BFOptLevel::O1,
+            BFOptLevel::O2,
+            BFOptLevel::O3,
+        ] {
+            let mut vm = BFVirtualMachine::<u16>::new(8, false, &bf_info);
+            vm.set_opt_level(level);
+            let mut r_buf = Cursor::new(vec![7]);
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            vm.interpret(&mut r_buf, &mut w_buf).unwrap();
+            assert_eq!(vm.stats().opt_level(), level);
+            assert!(vm.stats().steps() > 0);
+            outputs.push((w_buf.into_inner(), vm.cells().to_vec(), vm.stats()));
+        }
+        assert_eq!(outputs[0].0, vec![23, 7, 10]);
+        for (output, cells, _) in &outputs[1..] {
+            assert_eq!((output, cells), (&outputs[0].0, &outputs[0].1));
+        }
+        assert!(outputs[3].2.ops() < outputs[1].2.ops());
+        assert!(outputs[3].2.steps() < outputs[1].2.steps());
+    }
+
+    #[test]
+    fn test_clear_range() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "+>+>+>+<<[-]>[-]>[-]")
+            .validate()
+            .unwrap();
+
+        let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        vm.set_opt_level(BFOptLevel::O3);
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        vm.interpret(&mut Cursor::new(Vec::<u8>::new()), &mut w_buf)
+            .unwrap();
+        assert_eq!(vm.cells(), &[1, 0, 0, 0]);
+
+        let mut vm = BFVirtualMachine::<u16>::new(3, true, &bf_info);
+        vm.set_opt_level(BFOptLevel::O3);
+        vm.interpret(&mut Cursor::new(Vec::<u8>::new()), &mut w_buf)
+            .unwrap();
+        assert_eq!(vm.cells(), &[1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scan_bytes() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", "[>]<[<]").validate().unwrap();
+        let run = |extend: bool| {
+            let mut vm = BFVirtualMachine::<u8>::new(6, extend, &bf_info);
+            vm.set_opt_level(BFOptLevel::O2);
+            vm.cells = vec![1, 1, 0, 1, 1, 1];
+            vm.head = 3;
+            let mut r_buf = Cursor::new(Vec::<u8>::new());
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            let result = vm.interpret(&mut r_buf, &mut w_buf);
+            (result.is_ok(), vm.head(), vm.cells().len())
+        };
+        assert_eq!(run(false), (false, 5, 6));
+        assert_eq!(run(true), (true, 2, 7));
+    }
+
+    #[test]
+    fn test_offset_out_of_tape() {
+        use std::io::Cursor;
+        let bf_info = BFProgram::new("", ">>+<<<+").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(4, false, &bf_info);
+        vm.set_opt_level(BFOptLevel::O3);
+        let mut r_buf = Cursor::new(Vec::<u8>::new());
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        let result = vm.interpret(&mut r_buf, &mut w_buf);
+        assert!(matches!(result, Err(BFVmErr::HeadInvalidPositionErr(_))));
+        assert_eq!(vm.cells()[2], 1);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let bf_info = BFProgram::new("", "+>>+").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(1, true, &bf_info);
+        let snapshot = vm.snapshot();
+        vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(vm.cells(), [1, 0, 1]);
+        // the extended tape goes back to its length
+        vm.restore(snapshot.clone());
+        assert_eq!((vm.cells(), vm.head()), (snapshot.cells(), snapshot.head()));
+        assert_eq!(vm.cells().len(), 1);
+    }
 }
@@ -0,0 +1,245 @@
+//! Diagnostics of brainfuck programs without running them
+//!
+//! `check` reports every unmatched bracket as an error, where validating
+//! the program stops at the first one, and warns about instructions which
+//! are almost always mistakes: pairs cancelling each other, loops which
+//! never run or never end once entered, and heads moving left of the first
+//! cell.
+
+use alloc::vec::Vec;
+use bft_types::{BFCharCmdName, BFCharInfo, BFInstructions, BFProgram};
+use core::fmt;
+
+/// How bad a diagnostic is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BFSeverity {
+    /// the program can't run
+    Error,
+
+    /// the program runs but likely not as intended
+    Warning,
+}
+
+impl fmt::Display for BFSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        })
+    }
+}
+
+/// Kinds of diagnostics
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BFLintKind {
+    /// `[` without a matching `]`
+    UnmatchedOpen,
+
+    /// `]` without a matching `[`
+    UnmatchedClose,
+
+    /// `[]` never ends once entered
+    EmptyLoop,
+
+    /// loop at the start of the program or right after another loop, where
+    /// the cell is always zero
+    DeadLoop,
+
+    /// `+-`, `-+`, `<>` or `><` doing nothing
+    CancellingPair,
+
+    /// the head moves left of the first cell, off a strict tape
+    HeadLeft,
+}
+
+impl BFLintKind {
+    /// severity of the diagnostics of this kind
+    pub fn severity(self) -> BFSeverity {
+        match self {
+            Self::UnmatchedOpen | Self::UnmatchedClose => BFSeverity::Error,
+            _ => BFSeverity::Warning,
+        }
+    }
+
+    /// stable string code of the kind
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::UnmatchedOpen => "unmatched-open",
+            Self::UnmatchedClose => "unmatched-close",
+            Self::EmptyLoop => "empty-loop",
+            Self::DeadLoop => "dead-loop",
+            Self::CancellingPair => "cancelling-pair",
+            Self::HeadLeft => "head-left",
+        }
+    }
+
+    /// human readable description of the kind
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::UnmatchedOpen => "no close bracket matches this open bracket",
+            Self::UnmatchedClose => "no open bracket matches this close bracket",
+            Self::EmptyLoop => "empty loop never ends once entered",
+            Self::DeadLoop => "loop never runs, the cell is always zero here",
+            Self::CancellingPair => "these two instructions cancel each other",
+            Self::HeadLeft => "the head moves left of the first cell",
+        }
+    }
+}
+
+/// Diagnostic of an instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFDiagnostic {
+    kind: BFLintKind,
+    cmd: BFCharInfo,
+}
+
+impl BFDiagnostic {
+    /// Kind of the diagnostic
+    pub fn kind(&self) -> BFLintKind {
+        self.kind
+    }
+
+    /// Instruction the diagnostic is about
+    pub fn cmd(&self) -> BFCharInfo {
+        self.cmd
+    }
+
+    /// Severity of the diagnostic
+    pub fn severity(&self) -> BFSeverity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for BFDiagnostic {
+    /// print the diagnostic as `LINE:COLUMN: SEVERITY: MESSAGE`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.cmd.line(),
+            self.cmd.column(),
+            self.severity(),
+            self.kind.message()
+        )
+    }
+}
+
+/// whether `first` then `second` do nothing
+fn cancels(first: BFCharCmdName, second: BFCharCmdName) -> bool {
+    use BFCharCmdName::*;
+    matches!(
+        (first, second),
+        (DataIncrement, DataDecrement)
+            | (DataDecrement, DataIncrement)
+            | (PointerIncrement, PointerDecrement)
+            | (PointerDecrement, PointerIncrement)
+    )
+}
+
+/// first instruction moving the head left of the first cell, up to the
+/// first loop leaving the excursion unbounded
+fn head_left(instructions: &BFInstructions) -> Option<BFCharInfo> {
+    let mut offset = 0i64;
+    let mut entries = Vec::new();
+    for info in instructions {
+        match info.get_raw() {
+            BFCharCmdName::PointerIncrement => offset += 1,
+            BFCharCmdName::PointerDecrement if offset == 0 => return Some(info),
+            BFCharCmdName::PointerDecrement => offset -= 1,
+            BFCharCmdName::LoopStart(_) => entries.push(offset),
+            // the entry is popped whether the guard holds or not
+            BFCharCmdName::LoopTerminate(_) if entries.pop() != Some(offset) => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Diagnostics of `bf_info` in source order, errors first at a position
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::lint::{check, BFLintKind};
+/// use bft_types::BFProgram;
+///
+/// let diagnostics = check(&BFProgram::new("", "+-[]\n]"));
+/// let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind()).collect();
+/// assert_eq!(
+///     kinds,
+///     [BFLintKind::CancellingPair, BFLintKind::EmptyLoop, BFLintKind::UnmatchedClose]
+/// );
+/// assert_eq!(diagnostics[2].to_string(), "2:1: error: no open bracket matches this close bracket");
+/// ```
+pub fn check(bf_info: &BFProgram) -> Vec<BFDiagnostic> {
+    let instructions = bf_info.instructions();
+    let mut diagnostics = Vec::new();
+    let mut push = |kind, cmd| diagnostics.push(BFDiagnostic { kind, cmd });
+
+    let mut previous = None;
+    // whether the previous instruction ends a cancelling pair, `+-+` is a
+    // single pair
+    let mut paired = false;
+    for (index, info) in instructions.iter().enumerate() {
+        let raw = info.get_raw();
+        match raw {
+            BFCharCmdName::LoopStart(None) => push(BFLintKind::UnmatchedOpen, info),
+            BFCharCmdName::LoopTerminate(None) => push(BFLintKind::UnmatchedClose, info),
+            BFCharCmdName::LoopStart(Some(end)) => {
+                if matches!(previous, None | Some(BFCharCmdName::LoopTerminate(Some(_)))) {
+                    push(BFLintKind::DeadLoop, info);
+                } else if end == index + 1 {
+                    push(BFLintKind::EmptyLoop, info);
+                }
+            }
+            _ => (),
+        }
+        paired = !paired && previous.is_some_and(|first| cancels(first, raw));
+        if paired {
+            push(BFLintKind::CancellingPair, instructions.at(index - 1));
+        }
+        previous = Some(raw);
+    }
+    if let Some(info) = head_left(instructions) {
+        push(BFLintKind::HeadLeft, info);
+    }
+
+    diagnostics.sort_by_key(|d| (d.cmd.line(), d.cmd.column(), d.severity()));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(BFLintKind, usize)> {
+        check(&BFProgram::new("", source))
+            .iter()
+            .map(|d| (d.kind(), d.cmd().column()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(kinds("+[->+<]>."), []);
+        assert_eq!(
+            kinds("[comment].+[-][-]"),
+            [(BFLintKind::DeadLoop, 1), (BFLintKind::DeadLoop, 15)]
+        );
+        assert_eq!(kinds("+-+>"), [(BFLintKind::CancellingPair, 1)]);
+        assert_eq!(
+            kinds("+[]<"),
+            [(BFLintKind::EmptyLoop, 2), (BFLintKind::HeadLeft, 4)]
+        );
+        // every unmatched bracket is an error, not only the first one
+        assert_eq!(
+            kinds("]+[[-]"),
+            [
+                (BFLintKind::UnmatchedClose, 1),
+                (BFLintKind::UnmatchedOpen, 3)
+            ]
+        );
+        // the head isn't followed past a loop moving it off its start
+        assert_eq!(kinds("+[>]<<"), []);
+    }
+}
@@ -0,0 +1,170 @@
+//! Monitoring runs
+//!
+//! Whoever runs programs, eg. an execution service, reports every run to a
+//! `BFMetricsSink`. `BFPrometheusMetrics` is a sink keeping counters and a
+//! histogram of the run durations, rendered in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds in seconds of the run duration histogram buckets
+const DURATION_BUCKETS: [f64; 6] = [0.001, 0.005, 0.025, 0.1, 0.5, 2.5];
+
+/// How a program ran, reported to a metrics sink
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFRunReport<'a> {
+    /// instructions executed
+    pub steps: u64,
+
+    /// wall-clock time of the run
+    pub elapsed: Duration,
+
+    /// kind of the error which stopped the program, None if it halted,
+    /// see `BFVmErr::kind`
+    pub error: Option<&'a str>,
+}
+
+/// Receiver of run reports
+pub trait BFMetricsSink: Send + Sync {
+    /// record a program which ran or failed to
+    fn record(&self, report: &BFRunReport);
+}
+
+/// Sink keeping Prometheus metrics of the runs
+#[derive(Debug, Default)]
+pub struct BFPrometheusMetrics {
+    runs: AtomicU64,
+    steps: AtomicU64,
+    fuel_exhaustions: AtomicU64,
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    duration_micros: AtomicU64,
+    errors: Mutex<BTreeMap<String, u64>>,
+}
+
+impl BFPrometheusMetrics {
+    /// Create metrics with every counter at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::metrics::{BFMetricsSink, BFPrometheusMetrics, BFRunReport};
+    /// use std::time::Duration;
+    ///
+    /// let metrics = BFPrometheusMetrics::new();
+    /// metrics.record(&BFRunReport {
+    ///     steps: 10,
+    ///     elapsed: Duration::from_millis(3),
+    ///     error: Some("fuel"),
+    /// });
+    /// let text = metrics.render();
+    /// assert!(text.contains("bft_instructions_executed_total 10\n"));
+    /// assert!(text.contains("bft_errors_total{kind=\"fuel\"} 1\n"));
+    /// ```
+    pub fn render(&self) -> String {
+        // the buckets are loaded before the total they never exceed, as
+        // runs are counted before their bucket
+        let buckets: Vec<u64> = self
+            .duration_buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        let runs = self.runs.load(Ordering::Relaxed);
+        let mut text = String::new();
+        let mut counter = |name: &str, help: &str, value: u64| {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} counter", name);
+            let _ = writeln!(text, "{} {}", name, value);
+        };
+        counter("bft_programs_run_total", "Programs run.", runs);
+        counter(
+            "bft_instructions_executed_total",
+            "Instructions executed by all programs.",
+            self.steps.load(Ordering::Relaxed),
+        );
+        counter(
+            "bft_fuel_exhaustions_total",
+            "Programs stopped by running out of fuel.",
+            self.fuel_exhaustions.load(Ordering::Relaxed),
+        );
+
+        let name = "bft_run_duration_seconds";
+        let _ = writeln!(text, "# HELP {} Wall-clock time of the runs.", name);
+        let _ = writeln!(text, "# TYPE {} histogram", name);
+        let mut count = 0;
+        for (bound, bucket) in DURATION_BUCKETS.iter().zip(buckets) {
+            count += bucket;
+            let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(text, "{}_bucket{{le=\"+Inf\"}} {}", name, runs);
+        let micros = self.duration_micros.load(Ordering::Relaxed);
+        let _ = writeln!(text, "{}_sum {}", name, micros as f64 / 1e6);
+        let _ = writeln!(text, "{}_count {}", name, runs);
+
+        let name = "bft_errors_total";
+        let _ = writeln!(text, "# HELP {} Programs stopped by an error.", name);
+        let _ = writeln!(text, "# TYPE {} counter", name);
+        for (kind, value) in self.errors.lock().unwrap().iter() {
+            let _ = writeln!(text, "{}{{kind=\"{}\"}} {}", name, kind, value);
+        }
+        text
+    }
+}
+
+impl BFMetricsSink for BFPrometheusMetrics {
+    fn record(&self, report: &BFRunReport) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        let seconds = report.elapsed.as_secs_f64();
+        if let Some(index) = DURATION_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.duration_buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+        let micros = report.elapsed.as_micros().try_into().unwrap_or(u64::MAX);
+        self.duration_micros.fetch_add(micros, Ordering::Relaxed);
+        self.steps.fetch_add(report.steps, Ordering::Relaxed);
+        if let Some(kind) = report.error {
+            if kind == "fuel" {
+                self.fuel_exhaustions.fetch_add(1, Ordering::Relaxed);
+            }
+            *self
+                .errors
+                .lock()
+                .unwrap()
+                .entry(kind.to_string())
+                .or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_metrics() {
+        let metrics = BFPrometheusMetrics::new();
+        for (steps, millis, error) in [(5, 0, None), (7, 30, Some("head")), (1, 10_000, None)] {
+            metrics.record(&BFRunReport {
+                steps,
+                elapsed: Duration::from_millis(millis),
+                error,
+            });
+        }
+        let text = metrics.render();
+        assert!(text.contains("bft_programs_run_total 3\n"));
+        assert!(text.contains("bft_instructions_executed_total 13\n"));
+        assert!(text.contains("bft_fuel_exhaustions_total 0\n"));
+        assert!(text.contains("bft_run_duration_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(text.contains("bft_run_duration_seconds_bucket{le=\"0.1\"} 2\n"));
+        assert!(text.contains("bft_run_duration_seconds_bucket{le=\"2.5\"} 2\n"));
+        assert!(text.contains("bft_run_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("bft_run_duration_seconds_sum 10.03\n"));
+        assert!(text.contains("bft_errors_total{kind=\"head\"} 1\n"));
+    }
+}
@@ -0,0 +1,718 @@
+//! Optimizer of packed brainfuck programs
+//!
+//! Every level builds on the one below it:
+//!     `O0` runs the raw instruction stream
+//!     `O1` folds runs of pointer and data instructions
+//!     `O2` turns clear, multiply-copy and scan loops into single instructions
+//!     `O3` fuses pointer movement into cell offsets, folds constants,
+//!          clears contiguous cells at once and runs the IO-free prefix of
+//!          the program at compile time
+//!
+//! From `O2` on a head falling off the tape is reported by the optimized
+//! instruction, and `O3` only checks cells that are actually touched.
+//! Dropping loops on known zero cells and running the prefix assume the
+//! program starts on a fresh tape, `BFPackedProgram::optimize_on` packs a
+//! program which goes on with the tape of an earlier one without them.
+
+use crate::{BFOpCode, BFPackedOp, BFPackedProgram, BFSourceSpan};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use bft_types::BFValidProgram;
+use core::fmt;
+
+/// Optimization level of the packed program
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BFOptLevel {
+    /// raw instruction stream
+    O0,
+
+    /// fold runs of pointer and data instructions
+    #[default]
+    O1,
+
+    /// in addition replace clear, multiply-copy and scan loops
+    O2,
+
+    /// in addition fuse offsets and fold constants
+    O3,
+}
+
+impl BFOptLevel {
+    /// numeric level as given to `-O`
+    pub fn level(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl fmt::Display for BFOptLevel {
+    /// print optimization level as the `-O` flag
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "-O{}", self.level())
+    }
+}
+
+/// Packed instructions paired with the source span they come from
+type BFOps = Vec<(BFPackedOp, BFSourceSpan)>;
+
+/// signed amount an add or subtract instruction changes its cell by
+fn cell_delta(op: &BFPackedOp) -> u32 {
+    match op.opcode() {
+        BFOpCode::DataIncrement => op.operand(),
+        BFOpCode::DataDecrement => op.operand().wrapping_neg(),
+        _ => 0,
+    }
+}
+
+/// add or subtract instruction changing the cell at `offset` by `delta`
+fn delta_op(offset: i16, delta: u32) -> BFPackedOp {
+    if delta > i32::MAX as u32 {
+        BFPackedOp::with_offset(BFOpCode::DataDecrement, offset, delta.wrapping_neg())
+    } else {
+        BFPackedOp::with_offset(BFOpCode::DataIncrement, offset, delta)
+    }
+}
+
+/// pointer instructions moving the head by `delta`, a movement larger than
+/// an operand split over several of them, none if not moving
+fn move_ops(delta: i64) -> Vec<BFPackedOp> {
+    let opcode = if delta > 0 {
+        BFOpCode::PointerIncrement
+    } else {
+        BFOpCode::PointerDecrement
+    };
+    let mut left = delta.unsigned_abs();
+    let mut ops = Vec::new();
+    while left > 0 {
+        let n = u32::try_from(left).unwrap_or(u32::MAX);
+        ops.push(BFPackedOp::new(opcode, n));
+        left -= u64::from(n);
+    }
+    ops
+}
+
+/// Replacement of a loop whose body only moves the head and changes cells
+fn idiom_loop(body: &[(BFPackedOp, BFSourceSpan)], src: BFSourceSpan) -> Option<BFOps> {
+    if let [(op, _)] = body {
+        let n = op.operand();
+        let replaced = match op.opcode() {
+            // stepping by an odd amount reaches zero whatever the width
+            BFOpCode::DataIncrement | BFOpCode::DataDecrement if n % 2 == 1 => {
+                BFPackedOp::new(BFOpCode::Clear, 0)
+            }
+            BFOpCode::PointerIncrement => BFPackedOp::new(BFOpCode::ScanRight, n),
+            BFOpCode::PointerDecrement => BFPackedOp::new(BFOpCode::ScanLeft, n),
+            _ => return None,
+        };
+        return Some(vec![(replaced, src)]);
+    }
+
+    let mut pos = 0i64;
+    let mut deltas = BTreeMap::<i64, u32>::new();
+    for (op, _) in body {
+        match op.opcode() {
+            BFOpCode::PointerIncrement => pos += i64::from(op.operand()),
+            BFOpCode::PointerDecrement => pos -= i64::from(op.operand()),
+            BFOpCode::DataIncrement | BFOpCode::DataDecrement => {
+                let delta = deltas.entry(pos).or_insert(0);
+                *delta = delta.wrapping_add(cell_delta(op));
+            }
+            _ => return None,
+        }
+    }
+    if pos != 0 || deltas.remove(&0) != Some(u32::MAX) {
+        return None;
+    }
+
+    let mut replaced = BFOps::new();
+    for (offset, factor) in deltas {
+        let offset = i16::try_from(offset).ok()?;
+        if factor != 0 {
+            let op = BFPackedOp::with_offset(BFOpCode::MulAdd, offset, factor);
+            replaced.push((op, src));
+        }
+    }
+    replaced.push((BFPackedOp::new(BFOpCode::Clear, 0), src));
+    Some(replaced)
+}
+
+/// Replace innermost clear, multiply-copy and scan loops
+fn idiom_loops(ops: BFOps) -> BFOps {
+    let mut out = BFOps::with_capacity(ops.len());
+    let mut open_loop_vec = Vec::<usize>::new();
+    for (op, src) in ops {
+        match op.opcode() {
+            BFOpCode::LoopStart => {
+                open_loop_vec.push(out.len());
+                out.push((op, src));
+            }
+            BFOpCode::LoopTerminate => {
+                let start = open_loop_vec
+                    .pop()
+                    .expect("bft: validated program has balanced loops");
+                match idiom_loop(&out[start + 1..], out[start].1.join(src)) {
+                    Some(replaced) => {
+                        out.truncate(start);
+                        out.extend(replaced);
+                    }
+                    None => out.push((op, src)),
+                }
+            }
+            _ => out.push((op, src)),
+        }
+    }
+    out
+}
+
+/// Fuse pointer movement between cell instructions into their offsets,
+/// the head only moves before IO, loops, scans and multiply-copies
+fn fuse_offsets(ops: BFOps) -> BFOps {
+    let mut out = BFOps::with_capacity(ops.len());
+    let mut pending = 0i64;
+    // pointer instructions of the pending movement, and those since the
+    // last fused instruction which are where it would fall off the tape
+    let mut pending_src = BFSourceSpan::at(0);
+    let mut moved_src: Option<BFSourceSpan> = None;
+
+    let flush = |out: &mut BFOps, pending: &mut i64, src: BFSourceSpan| {
+        out.extend(move_ops(*pending).into_iter().map(|op| (op, src)));
+        *pending = 0;
+    };
+
+    for (op, src) in ops {
+        match op.opcode() {
+            BFOpCode::PointerIncrement | BFOpCode::PointerDecrement => {
+                let delta = if op.opcode() == BFOpCode::PointerIncrement {
+                    i64::from(op.operand())
+                } else {
+                    -i64::from(op.operand())
+                };
+                pending_src = if pending == 0 {
+                    src
+                } else {
+                    pending_src.join(src)
+                };
+                moved_src = Some(moved_src.map_or(src, |moved| moved.join(src)));
+                pending += delta;
+                if i16::try_from(pending).is_err() {
+                    flush(&mut out, &mut pending, pending_src);
+                }
+            }
+            BFOpCode::DataIncrement | BFOpCode::DataDecrement | BFOpCode::Clear | BFOpCode::Set => {
+                match i16::try_from(pending + i64::from(op.offset())) {
+                    Ok(offset) => {
+                        let fused = BFPackedOp::with_offset(op.opcode(), offset, op.operand());
+                        let fused_src = moved_src.take().map_or(src, |moved| moved.join(src));
+                        out.push((fused, fused_src));
+                    }
+                    Err(_) => {
+                        flush(&mut out, &mut pending, pending_src);
+                        moved_src = None;
+                        out.push((op, src));
+                    }
+                }
+            }
+            _ => {
+                flush(&mut out, &mut pending, pending_src);
+                moved_src = None;
+                out.push((op, src));
+            }
+        }
+    }
+    flush(&mut out, &mut pending, pending_src);
+    out
+}
+
+/// Clear of the cells of `last` and the cell of `clear` if they are next
+/// to each other, None otherwise
+fn clear_range(last: &BFPackedOp, clear: &BFPackedOp) -> Option<BFPackedOp> {
+    let len = match last.opcode() {
+        BFOpCode::Clear => 1,
+        BFOpCode::ClearRange => last.operand(),
+        _ => return None,
+    };
+    if clear.opcode() != BFOpCode::Clear {
+        return None;
+    }
+    let (start, offset) = (i64::from(last.offset()), i64::from(clear.offset()));
+    if offset == start + i64::from(len) {
+        Some(BFPackedOp::with_offset(
+            BFOpCode::ClearRange,
+            last.offset(),
+            len + 1,
+        ))
+    } else if offset + 1 == start {
+        Some(BFPackedOp::with_offset(
+            BFOpCode::ClearRange,
+            clear.offset(),
+            len + 1,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Merge adjacent instructions on the same cell and drop loops which
+/// can never be entered because the head cell is known to be zero, the
+/// cells only known to start at zero on a `fresh` tape
+fn fold_constants(ops: BFOps, fresh: bool) -> BFOps {
+    let mut merged = BFOps::with_capacity(ops.len());
+    for (op, src) in ops {
+        if let Some((last, last_src)) = merged.last_mut() {
+            if let Some(range) = clear_range(last, &op) {
+                *last = range;
+                *last_src = last_src.join(src);
+                continue;
+            }
+            if last.offset() == op.offset() {
+                use BFOpCode::*;
+                let folded = match (last.opcode(), op.opcode()) {
+                    (DataIncrement | DataDecrement, DataIncrement | DataDecrement) => {
+                        Some(cell_delta(last).wrapping_add(cell_delta(&op)))
+                            .filter(|&delta| delta != 0)
+                            .map(|delta| delta_op(op.offset(), delta))
+                    }
+                    (Clear, DataIncrement | DataDecrement) => {
+                        Some(BFPackedOp::with_offset(Set, op.offset(), cell_delta(&op)))
+                    }
+                    (Set, DataIncrement | DataDecrement) => Some(BFPackedOp::with_offset(
+                        Set,
+                        op.offset(),
+                        last.operand().wrapping_add(cell_delta(&op)),
+                    )),
+                    (DataIncrement | DataDecrement | Clear | Set, Clear | Set) => Some(op),
+                    _ => {
+                        merged.push((op, src));
+                        continue;
+                    }
+                };
+                match folded {
+                    Some(folded) => {
+                        *last = folded;
+                        *last_src = last_src.join(src);
+                    }
+                    None => {
+                        merged.pop();
+                    }
+                }
+                continue;
+            }
+        }
+        merged.push((op, src));
+    }
+
+    let mut out = BFOps::with_capacity(merged.len());
+    let mut head_zero = fresh;
+    let mut dead_depth = 0usize;
+    for (op, src) in merged {
+        if dead_depth > 0 {
+            match op.opcode() {
+                BFOpCode::LoopStart => dead_depth += 1,
+                BFOpCode::LoopTerminate => dead_depth -= 1,
+                _ => (),
+            }
+            continue;
+        }
+        head_zero = match op.opcode() {
+            BFOpCode::LoopStart if head_zero => {
+                dead_depth = 1;
+                continue;
+            }
+            BFOpCode::LoopTerminate | BFOpCode::ScanRight | BFOpCode::ScanLeft => true,
+            BFOpCode::Clear if op.offset() == 0 => true,
+            BFOpCode::Set if op.offset() == 0 => op.operand() == 0,
+            BFOpCode::ClearRange => {
+                let offset = i64::from(op.offset());
+                head_zero || (offset <= 0 && 0 < offset + i64::from(op.operand()))
+            }
+            BFOpCode::DataOutput | BFOpCode::MulAdd => head_zero,
+            _ if op.offset() != 0 => head_zero,
+            _ => false,
+        };
+        out.push((op, src));
+    }
+    out
+}
+
+/// instructions the prefix of a program may run at compile time
+const PREFIX_STEPS: u32 = 1 << 20;
+
+/// State of the tape while running the prefix of a program
+#[derive(Clone)]
+struct BFPrefixState {
+    cells: Vec<i64>,
+    head: i64,
+}
+
+impl BFPrefixState {
+    /// index of the cell at `offset` from the head, None outside the
+    /// cells `Set` can reach from the first one
+    fn index(&mut self, offset: i64) -> Option<usize> {
+        let index = usize::try_from(self.head + offset).ok()?;
+        if index > i16::MAX as usize {
+            return None;
+        }
+        if index >= self.cells.len() {
+            self.cells.resize(index + 1, 0);
+        }
+        Some(index)
+    }
+
+    /// store `value` into the cell at `offset`, None if it wrapped around
+    fn store(&mut self, offset: i64, value: i64) -> Option<()> {
+        // without wrapping every cell width computes the same values
+        if !(0..=i64::from(u8::MAX)).contains(&value) {
+            return None;
+        }
+        let index = self.index(offset)?;
+        self.cells[index] = value;
+        Some(())
+    }
+
+    /// value of the cell at `offset`
+    fn load(&mut self, offset: i64) -> Option<i64> {
+        let index = self.index(offset)?;
+        Some(self.cells[index])
+    }
+
+    /// run `op` at `pc` returning the next pc, None if it can't run at
+    /// compile time which leaves the state unchanged
+    fn step(&mut self, op: &BFPackedOp, pc: usize, targets: &[usize]) -> Option<usize> {
+        let n = i64::from(op.operand());
+        let offset = i64::from(op.offset());
+        match op.opcode() {
+            BFOpCode::PointerIncrement => self.head += n,
+            BFOpCode::PointerDecrement => self.head -= n,
+            BFOpCode::DataIncrement => {
+                let value = self.load(offset)? + n;
+                self.store(offset, value)?
+            }
+            BFOpCode::DataDecrement => {
+                let value = self.load(offset)? - n;
+                self.store(offset, value)?
+            }
+            BFOpCode::Clear => self.store(offset, 0)?,
+            BFOpCode::Set => self.store(offset, n)?,
+            BFOpCode::ClearRange => {
+                self.index(offset)?;
+                self.index(offset + n - 1)?;
+                for cell in offset..offset + n {
+                    self.store(cell, 0)?;
+                }
+            }
+            BFOpCode::MulAdd => {
+                // factors are wrapping deltas, ie. signed
+                let factor = i64::from(op.operand() as i32);
+                let value = self.load(offset)? + self.load(0)? * factor;
+                self.store(offset, value)?;
+            }
+            BFOpCode::ScanRight | BFOpCode::ScanLeft => {
+                let step = if op.opcode() == BFOpCode::ScanRight {
+                    n
+                } else {
+                    -n
+                };
+                let head = self.head;
+                loop {
+                    match self.load(0) {
+                        Some(0) => break,
+                        Some(_) => self.head += step,
+                        None => {
+                            self.head = head;
+                            return None;
+                        }
+                    }
+                }
+            }
+            BFOpCode::LoopStart if self.load(0)? == 0 => return Some(targets[pc] + 1),
+            BFOpCode::LoopTerminate if self.load(0)? != 0 => return Some(targets[pc] + 1),
+            BFOpCode::LoopStart | BFOpCode::LoopTerminate => (),
+            BFOpCode::DataOutput | BFOpCode::DataInput | BFOpCode::Extension => return None,
+        }
+        Some(pc + 1)
+    }
+}
+
+/// Run the prefix of the program up to its first IO at compile time, every
+/// cell starting at zero, and replace it with `Set` instructions of the
+/// cells it computed. A top-level loop the prefix can't finish, eg. as a
+/// cell wraps around and its value depends on the cell width, is left to
+/// run with the rest of the program.
+fn evaluate_prefix(ops: BFOps) -> BFOps {
+    let mut targets = vec![0; ops.len()];
+    let mut open_loop_vec = Vec::<usize>::new();
+    for (pc, (op, _)) in ops.iter().enumerate() {
+        match op.opcode() {
+            BFOpCode::LoopStart => open_loop_vec.push(pc),
+            BFOpCode::LoopTerminate => {
+                let start = open_loop_vec
+                    .pop()
+                    .expect("bft: validated program has balanced loops");
+                targets[start] = pc;
+                targets[pc] = start;
+            }
+            _ => (),
+        }
+    }
+
+    let mut state = BFPrefixState {
+        cells: Vec::new(),
+        head: 0,
+    };
+    // top-level loop being run with the state before it
+    let mut top_loop: Option<(usize, BFPrefixState)> = None;
+    let (mut pc, mut depth) = (0, 0usize);
+    for _ in 0..PREFIX_STEPS {
+        let Some(&(op, _)) = ops.get(pc) else {
+            break;
+        };
+        if depth == 0 && op.opcode() == BFOpCode::LoopStart {
+            top_loop = Some((pc, state.clone()));
+        }
+        let Some(next) = state.step(&op, pc, &targets) else {
+            break;
+        };
+        match op.opcode() {
+            BFOpCode::LoopStart if next == pc + 1 => depth += 1,
+            BFOpCode::LoopTerminate if next == pc + 1 => depth -= 1,
+            _ => (),
+        }
+        pc = next;
+    }
+    let end = match top_loop {
+        Some((start, before)) if depth > 0 => {
+            state = before;
+            start
+        }
+        _ => pc,
+    };
+    if end == 0 || state.head < 0 {
+        return ops;
+    }
+
+    let src = ops[0].1.join(ops[end - 1].1);
+    let mut out = BFOps::with_capacity(ops.len() - end + state.cells.len());
+    for (index, &value) in state.cells.iter().enumerate() {
+        if value != 0 {
+            let op = BFPackedOp::with_offset(BFOpCode::Set, index as i16, value as u32);
+            out.push((op, src));
+        }
+    }
+    out.extend(move_ops(state.head).into_iter().map(|op| (op, src)));
+    out.extend_from_slice(&ops[end..]);
+    out
+}
+
+/// Rebuild jump targets of loops after instructions moved
+fn relink(ops: BFOps) -> BFPackedProgram {
+    let (mut ops, source): (Vec<BFPackedOp>, Vec<BFSourceSpan>) = ops.into_iter().unzip();
+    let mut open_loop_vec = Vec::<usize>::new();
+    for pc in 0..ops.len() {
+        match ops[pc].opcode() {
+            BFOpCode::LoopStart => open_loop_vec.push(pc),
+            BFOpCode::LoopTerminate => {
+                let start = open_loop_vec
+                    .pop()
+                    .expect("bft: validated program has balanced loops");
+                ops[start] = BFPackedOp::new(BFOpCode::LoopStart, pc as u32);
+                ops[pc] = BFPackedOp::new(BFOpCode::LoopTerminate, start as u32);
+            }
+            _ => (),
+        }
+    }
+    BFPackedProgram::from_parts(ops, source)
+}
+
+impl BFPackedProgram {
+    /// Pack a validated brainfuck program optimized at `level`
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::{BFOpCode, BFOptLevel, BFPackedProgram};
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "+[-]").validate().unwrap();
+    /// let packed = BFPackedProgram::optimize(&bf_info, BFOptLevel::O2);
+    /// assert_eq!(packed.ops()[1].opcode(), BFOpCode::Clear);
+    /// ```
+    pub fn optimize(bf_info: &BFValidProgram, level: BFOptLevel) -> Self {
+        Self::optimize_on(bf_info, level, true)
+    }
+
+    /// Pack a validated brainfuck program optimized at `level`, running on
+    /// a `fresh` tape of zero cells with the head on the first one or on
+    /// the tape left by an earlier program
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::{BFOptLevel, BFPackedProgram};
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", "[.]").validate().unwrap();
+    /// let fresh = BFPackedProgram::optimize_on(&bf_info, BFOptLevel::O3, true);
+    /// assert!(fresh.ops().is_empty());
+    /// let reused = BFPackedProgram::optimize_on(&bf_info, BFOptLevel::O3, false);
+    /// assert_eq!(reused.ops().len(), 3);
+    /// ```
+    pub fn optimize_on(bf_info: &BFValidProgram, level: BFOptLevel, fresh: bool) -> Self {
+        let packed = Self::new(bf_info);
+        if level == BFOptLevel::O0 {
+            return packed;
+        }
+        let folded = packed.fold_runs();
+        if level == BFOptLevel::O1 {
+            return folded;
+        }
+
+        let mut ops = idiom_loops(folded.parts().collect());
+        if level >= BFOptLevel::O3 {
+            ops = fuse_offsets(ops);
+            if fresh {
+                ops = evaluate_prefix(ops);
+            }
+            ops = fold_constants(ops, fresh);
+        }
+        relink(ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    fn optimize(program: &str, level: BFOptLevel) -> Vec<BFPackedOp> {
+        let bf_info = BFProgram::new("", program).validate().unwrap();
+        BFPackedProgram::optimize(&bf_info, level).ops().to_vec()
+    }
+
+    #[test]
+    fn test_opt_levels() {
+        assert_eq!(optimize("++", BFOptLevel::O0).len(), 2);
+        assert_eq!(optimize("++", BFOptLevel::O1).len(), 1);
+        assert_eq!(BFOptLevel::O3.to_string(), "-O3");
+        assert!(BFOptLevel::O2 > BFOptLevel::default());
+    }
+
+    #[test]
+    fn test_idiom_loops() {
+        let target = [
+            BFPackedOp::new(BFOpCode::DataIncrement, 1),
+            BFPackedOp::new(BFOpCode::Clear, 0),
+            BFPackedOp::new(BFOpCode::ScanRight, 2),
+            BFPackedOp::with_offset(BFOpCode::MulAdd, -1, 2),
+            BFPackedOp::with_offset(BFOpCode::MulAdd, 3, u32::MAX),
+            BFPackedOp::new(BFOpCode::Clear, 0),
+            BFPackedOp::new(BFOpCode::LoopStart, 9),
+            BFPackedOp::new(BFOpCode::DataDecrement, 2),
+            BFPackedOp::new(BFOpCode::ScanLeft, 1),
+            BFPackedOp::new(BFOpCode::LoopTerminate, 6),
+        ];
+        assert_eq!(
+            optimize("+[---][>>][-<++>>>>-<<<][--[<]]", BFOptLevel::O2),
+            target
+        );
+    }
+
+    #[test]
+    fn test_move_ops() {
+        assert!(move_ops(0).is_empty());
+        assert_eq!(
+            move_ops(-3),
+            [BFPackedOp::new(BFOpCode::PointerDecrement, 3)]
+        );
+        // a movement past the operand is split
+        assert_eq!(
+            move_ops(i64::from(u32::MAX) * 2 + 5),
+            [
+                BFPackedOp::new(BFOpCode::PointerIncrement, u32::MAX),
+                BFPackedOp::new(BFOpCode::PointerIncrement, u32::MAX),
+                BFPackedOp::new(BFOpCode::PointerIncrement, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clear_range() {
+        // the input keeps the prefix from being evaluated
+        let target = [
+            BFPackedOp::new(BFOpCode::DataInput, 1),
+            BFPackedOp::with_offset(BFOpCode::DataIncrement, 1, 1),
+            BFPackedOp::with_offset(BFOpCode::ClearRange, 2, 3),
+            BFPackedOp::with_offset(BFOpCode::ClearRange, -2, 2),
+            BFPackedOp::new(BFOpCode::PointerIncrement, 5),
+        ];
+        assert_eq!(
+            optimize(",>+>[-]>[-]>[-]<<<<<[-]<[-]>>>>>>>", BFOptLevel::O3),
+            target
+        );
+    }
+
+    #[test]
+    fn test_fuse_and_fold() {
+        let target = [
+            BFPackedOp::new(BFOpCode::DataInput, 1),
+            BFPackedOp::with_offset(BFOpCode::DataIncrement, 1, 1),
+            BFPackedOp::with_offset(BFOpCode::Set, 2, 3),
+            BFPackedOp::new(BFOpCode::PointerIncrement, 1),
+            BFPackedOp::new(BFOpCode::DataOutput, 1),
+            BFPackedOp::new(BFOpCode::Clear, 0),
+            BFPackedOp::new(BFOpCode::ScanRight, 1),
+            BFPackedOp::with_offset(BFOpCode::DataDecrement, -1, 1),
+            BFPackedOp::new(BFOpCode::PointerDecrement, 1),
+        ];
+        assert_eq!(
+            optimize("[.],>+>[-]+++<+-.[-][>]<-", BFOptLevel::O3),
+            target
+        );
+    }
+
+    #[test]
+    fn test_evaluate_prefix() {
+        let target = [
+            BFPackedOp::with_offset(BFOpCode::Set, 1, 65),
+            BFPackedOp::new(BFOpCode::PointerIncrement, 1),
+            BFPackedOp::new(BFOpCode::DataOutput, 1),
+            BFPackedOp::new(BFOpCode::DataInput, 1),
+        ];
+        assert_eq!(
+            optimize("++++++++[>++++++++<-]>+.,", BFOptLevel::O3),
+            target
+        );
+
+        // a loop wrapping a cell around stays with the state before it
+        let target = [
+            BFPackedOp::new(BFOpCode::Set, 1),
+            BFPackedOp::new(BFOpCode::LoopStart, 3),
+            BFPackedOp::new(BFOpCode::DataIncrement, 2),
+            BFPackedOp::new(BFOpCode::LoopTerminate, 1),
+            BFPackedOp::new(BFOpCode::DataOutput, 1),
+        ];
+        assert_eq!(optimize("+[++].", BFOptLevel::O3), target);
+
+        // the whole program runs at compile time
+        assert_eq!(
+            optimize("+>++<-", BFOptLevel::O3),
+            [BFPackedOp::with_offset(BFOpCode::Set, 1, 2)]
+        );
+        assert!(optimize("+-", BFOptLevel::O3).is_empty());
+    }
+
+    #[test]
+    fn test_source_spans() {
+        let bf_info = BFProgram::new("", ",>>+<[->+<]+++").validate().unwrap();
+        let packed = BFPackedProgram::optimize(&bf_info, BFOptLevel::O3);
+        let locations: Vec<String> = (0..packed.ops().len())
+            .map(|pc| packed.source_span(pc).location(&bf_info))
+            .collect();
+        // the add fused with the moves before it, the head moved by every
+        // pointer instruction, the multiply-copy loop and its clear folded
+        // with the adds after it
+        assert_eq!(
+            locations,
+            ["1:1", "1:2-1:4", "1:2-1:5", "1:6-1:11", "1:6-1:14"]
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Compact execution-time encoding of brainfuck programs
+//!
+//! Every instruction is packed into an opcode, a cell offset relative to
+//! the head and a u32 operand, so the interpreter only fetches 8 bytes per
+//! step instead of the whole `BFCharInfo`, source positions are kept in a
+//! separate side table.
+//!
+//! The side table maps every instruction to the span of source
+//! instructions it stands for, eg. a folded run, a whole idiom loop or the
+//! pointer moves fused into an offset. Errors report the first instruction
+//! of the span, traces and listings show the whole span.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bft_types::{BFCharCmdName, BFCharInfo, BFValidProgram};
+use core::fmt::Write;
+
+/// Opcode of a packed brainfuck instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BFOpCode {
+    /// move head right by operand cells
+    PointerIncrement,
+
+    /// move head left by operand cells
+    PointerDecrement,
+
+    /// add operand to the head cell
+    DataIncrement,
+
+    /// subtract operand from the head cell
+    DataDecrement,
+
+    /// output the head cell
+    DataOutput,
+
+    /// input into the head cell
+    DataInput,
+
+    /// jump to operand if the head cell is zero
+    LoopStart,
+
+    /// jump to operand if the head cell is non-zero
+    LoopTerminate,
+
+    /// set the cell at offset to zero
+    Clear,
+
+    /// set the cell at offset to operand
+    Set,
+
+    /// if the head cell is non-zero, add it times operand to the cell at offset
+    MulAdd,
+
+    /// move head right by operand cells until the head cell is zero
+    ScanRight,
+
+    /// move head left by operand cells until the head cell is zero
+    ScanLeft,
+
+    /// set operand cells starting at offset to zero
+    ClearRange,
+
+    /// run the handler of the extension command whose character is operand
+    Extension,
+}
+
+/// A packed (opcode, offset, operand) instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFPackedOp {
+    opcode: BFOpCode,
+    offset: i16,
+    operand: u32,
+}
+
+impl BFPackedOp {
+    /// create a new packed instruction working on the head cell
+    pub fn new(opcode: BFOpCode, operand: u32) -> Self {
+        Self::with_offset(opcode, 0, operand)
+    }
+
+    /// create a new packed instruction working on the cell at `offset`
+    pub fn with_offset(opcode: BFOpCode, offset: i16, operand: u32) -> Self {
+        Self {
+            opcode,
+            offset,
+            operand,
+        }
+    }
+
+    /// get the opcode
+    pub fn opcode(&self) -> BFOpCode {
+        self.opcode
+    }
+
+    /// get the offset of the cell worked on relative to the head
+    pub fn offset(&self) -> i16 {
+        self.offset
+    }
+
+    /// get the operand, a repeat count, a value or a jump target
+    pub fn operand(&self) -> u32 {
+        self.operand
+    }
+}
+
+/// First and last source instructions a packed instruction stands for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFSourceSpan {
+    first: u32,
+    last: u32,
+}
+
+impl BFSourceSpan {
+    /// Span of the single source instruction at `index`
+    pub(crate) fn at(index: u32) -> Self {
+        Self {
+            first: index,
+            last: index,
+        }
+    }
+
+    /// Smallest span covering both spans
+    pub(crate) fn join(self, other: Self) -> Self {
+        Self {
+            first: self.first.min(other.first),
+            last: self.last.max(other.last),
+        }
+    }
+
+    /// Index of the first source instruction
+    pub fn first(&self) -> usize {
+        self.first as usize
+    }
+
+    /// Index of the last source instruction
+    pub fn last(&self) -> usize {
+        self.last as usize
+    }
+
+    /// Position of the span as `line:column`, followed by `-line:column` of
+    /// the last instruction if it spans several
+    pub fn location(&self, bf_info: &BFValidProgram) -> String {
+        let instructions = bf_info.instructions();
+        let (first, last) = (instructions.at(self.first()), instructions.at(self.last()));
+        if self.first == self.last {
+            format!("{}:{}", first.line(), first.column())
+        } else {
+            format!(
+                "{}:{}-{}:{}",
+                first.line(),
+                first.column(),
+                last.line(),
+                last.column()
+            )
+        }
+    }
+}
+
+/// Packed instruction stream with a side table back to source positions
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::{BFOpCode, BFPackedProgram};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", "+[-]").validate().unwrap();
+/// let packed = BFPackedProgram::new(&bf_info);
+/// assert_eq!(packed.ops()[1].opcode(), BFOpCode::LoopStart);
+/// assert_eq!(packed.ops()[1].operand(), 3);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BFPackedProgram {
+    ops: Vec<BFPackedOp>,
+    source: Vec<BFSourceSpan>,
+}
+
+impl BFPackedProgram {
+    /// Pack a validated brainfuck program
+    ///
+    /// # Panics
+    ///
+    /// Panics if the program holds more than `u32::MAX` instructions
+    pub fn new(bf_info: &BFValidProgram) -> Self {
+        let to_u32 = |v: usize| u32::try_from(v).expect("bft: program too large to pack");
+        let instructions = bf_info.instructions();
+
+        let ops = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s.get_raw() {
+                BFCharCmdName::PointerIncrement => BFPackedOp::new(BFOpCode::PointerIncrement, 1),
+                BFCharCmdName::PointerDecrement => BFPackedOp::new(BFOpCode::PointerDecrement, 1),
+                BFCharCmdName::DataIncrement => BFPackedOp::new(BFOpCode::DataIncrement, 1),
+                BFCharCmdName::DataDecrement => BFPackedOp::new(BFOpCode::DataDecrement, 1),
+                BFCharCmdName::DataOutput => BFPackedOp::new(BFOpCode::DataOutput, 1),
+                BFCharCmdName::DataInput => BFPackedOp::new(BFOpCode::DataInput, 1),
+                BFCharCmdName::LoopStart(_) => {
+                    BFPackedOp::new(BFOpCode::LoopStart, to_u32(bf_info.jump_target(i)))
+                }
+                BFCharCmdName::LoopTerminate(_) => {
+                    BFPackedOp::new(BFOpCode::LoopTerminate, to_u32(bf_info.jump_target(i)))
+                }
+                BFCharCmdName::Extension(ch) => BFPackedOp::new(BFOpCode::Extension, ch.into()),
+            })
+            .collect();
+
+        Self {
+            ops,
+            source: (0..to_u32(instructions.len()))
+                .map(BFSourceSpan::at)
+                .collect(),
+        }
+    }
+
+    /// Build a packed program from instructions and their source spans
+    pub(crate) fn from_parts(ops: Vec<BFPackedOp>, source: Vec<BFSourceSpan>) -> Self {
+        Self { ops, source }
+    }
+
+    /// Instructions paired with their source spans
+    pub(crate) fn parts(&self) -> impl Iterator<Item = (BFPackedOp, BFSourceSpan)> + '_ {
+        self.ops.iter().copied().zip(self.source.iter().copied())
+    }
+
+    /// Fold runs of the same pointer or data instruction into a single
+    /// instruction with the run length as operand, jump targets are rebuilt
+    pub fn fold_runs(&self) -> Self {
+        let mut ops = Vec::<BFPackedOp>::with_capacity(self.ops.len());
+        let mut source = Vec::<BFSourceSpan>::with_capacity(self.source.len());
+        let mut open_loop_vec = Vec::<usize>::new();
+
+        for (op, &src) in self.ops.iter().zip(&self.source) {
+            match op.opcode {
+                BFOpCode::PointerIncrement
+                | BFOpCode::PointerDecrement
+                | BFOpCode::DataIncrement
+                | BFOpCode::DataDecrement => {
+                    if let Some(last) = ops.last_mut() {
+                        if last.opcode == op.opcode && last.offset == op.offset {
+                            if let Some(n) = last.operand.checked_add(op.operand) {
+                                last.operand = n;
+                                let span = source.last_mut().expect("bft: one span per op");
+                                *span = span.join(src);
+                                continue;
+                            }
+                        }
+                    }
+                    ops.push(*op);
+                }
+                BFOpCode::LoopStart => {
+                    open_loop_vec.push(ops.len());
+                    ops.push(*op);
+                }
+                BFOpCode::LoopTerminate => {
+                    let start = open_loop_vec
+                        .pop()
+                        .expect("bft: validated program has balanced loops");
+                    ops[start].operand = ops.len() as u32;
+                    ops.push(BFPackedOp::new(BFOpCode::LoopTerminate, start as u32));
+                }
+                _ => ops.push(*op),
+            }
+            source.push(src);
+        }
+
+        Self { ops, source }
+    }
+
+    /// A reference of packed instructions
+    pub fn ops(&self) -> &[BFPackedOp] {
+        &self.ops
+    }
+
+    /// Take the instructions out, leaving the source spans, so a run can
+    /// borrow them while reporting errors through `source_info`
+    pub(crate) fn take_ops(&mut self) -> Vec<BFPackedOp> {
+        core::mem::take(&mut self.ops)
+    }
+
+    /// Put back the instructions taken by `take_ops`
+    pub(crate) fn restore_ops(&mut self, ops: Vec<BFPackedOp>) {
+        self.ops = ops;
+    }
+
+    /// Index into the source instructions of the packed instruction at `pc`,
+    /// the first one of its span
+    pub fn source_index(&self, pc: usize) -> usize {
+        self.source[pc].first()
+    }
+
+    /// Source instructions the packed instruction at `pc` stands for
+    pub fn source_span(&self, pc: usize) -> BFSourceSpan {
+        self.source[pc]
+    }
+
+    /// First source instruction of the packed instruction at `pc`
+    pub fn source_info(&self, bf_info: &BFValidProgram, pc: usize) -> BFCharInfo {
+        bf_info.instructions().at(self.source_index(pc))
+    }
+
+    /// Human readable listing of the instructions, one per line with
+    /// the source instruction it comes from and the end of its span
+    pub fn listing(&self, bf_info: &BFValidProgram) -> String {
+        let mut out = String::new();
+        for (pc, op) in self.ops.iter().enumerate() {
+            let opcode = format!("{:?}", op.opcode);
+            let _ = write!(out, "{:>5}  {:<16} {:>10}", pc, opcode, op.operand);
+            if op.offset != 0 {
+                let _ = write!(out, " @{:+}", op.offset);
+            } else {
+                out.push_str("   ");
+            }
+            let _ = write!(out, "  ; {}", self.source_info(bf_info, pc));
+            let span = self.source[pc];
+            if span.first != span.last {
+                let last = bf_info.instructions().at(span.last());
+                let _ = write!(out, " (to {}:{})", last.line(), last.column());
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_packed_size() {
+        assert_eq!(std::mem::size_of::<BFPackedOp>(), 8);
+    }
+
+    #[test]
+    fn test_pack_program() {
+        let bf_info = BFProgram::new("", "a+\n[>.<,]").validate().unwrap();
+        let packed = BFPackedProgram::new(&bf_info);
+        let target = [
+            BFPackedOp::new(BFOpCode::DataIncrement, 1),
+            BFPackedOp::new(BFOpCode::LoopStart, 6),
+            BFPackedOp::new(BFOpCode::PointerIncrement, 1),
+            BFPackedOp::new(BFOpCode::DataOutput, 1),
+            BFPackedOp::new(BFOpCode::PointerDecrement, 1),
+            BFPackedOp::new(BFOpCode::DataInput, 1),
+            BFPackedOp::new(BFOpCode::LoopTerminate, 1),
+        ];
+        assert_eq!(packed.ops(), &target);
+        assert_eq!(packed.source_index(4), 4);
+        assert_eq!(packed.source_info(&bf_info, 3).line(), 2);
+    }
+
+    #[test]
+    fn test_fold_runs() {
+        let bf_info = BFProgram::new("", "+++[->>+<<]--.").validate().unwrap();
+        let packed = BFPackedProgram::new(&bf_info).fold_runs();
+        let target = [
+            BFPackedOp::new(BFOpCode::DataIncrement, 3),
+            BFPackedOp::new(BFOpCode::LoopStart, 6),
+            BFPackedOp::new(BFOpCode::DataDecrement, 1),
+            BFPackedOp::new(BFOpCode::PointerIncrement, 2),
+            BFPackedOp::new(BFOpCode::DataIncrement, 1),
+            BFPackedOp::new(BFOpCode::PointerDecrement, 2),
+            BFPackedOp::new(BFOpCode::LoopTerminate, 1),
+            BFPackedOp::new(BFOpCode::DataDecrement, 2),
+            BFPackedOp::new(BFOpCode::DataOutput, 1),
+        ];
+        assert_eq!(packed.ops(), &target);
+        assert_eq!(packed.source_index(7), 11);
+        assert_eq!(packed.source_span(7).last(), 12);
+        assert_eq!(packed.source_span(7).location(&bf_info), "1:12-1:13");
+        assert_eq!(packed.source_span(1).location(&bf_info), "1:4");
+    }
+
+    #[test]
+    fn test_listing() {
+        let bf_info = BFProgram::new("", "++\n[-]").validate().unwrap();
+        let packed = BFPackedProgram::new(&bf_info).fold_runs();
+        let listing = packed.listing(&bf_info);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("    0  DataIncrement             2"));
+        assert!(lines[1].ends_with("> Start looping"));
+        assert!(lines[1].contains(";     2:1"));
+    }
+}
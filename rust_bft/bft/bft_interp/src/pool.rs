@@ -0,0 +1,164 @@
+//! Reusing virtual machines
+//!
+//! A service running thousands of short programs spends a noticeable part
+//! of every run allocating and freeing tapes. A `BFVmPool` keeps the tapes
+//! of the virtual machines handed back to it: the next virtual machine it
+//! hands out, running the same program or any other one, gets a spare tape
+//! reset to zero instead of a new allocation.
+//!
+//! Only the tape is reused, the options of a virtual machine, its fuel,
+//! handlers and hooks, are back to their defaults for every program.
+
+use crate::{BFOwnedVirtualMachine, BFProgramRef, BFVirtualMachine, CellKind};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bft_types::BFValidProgram;
+
+/// Pool of tapes handing out virtual machines
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::pool::BFVmPool;
+/// use bft_types::BFProgram;
+///
+/// let mut pool = BFVmPool::<u8>::new(16, false);
+/// for source in ["+++.", ">++.", "+."] {
+///     let bf_info = BFProgram::new("", source).validate().unwrap();
+///     let mut bf_vm = pool.acquire(&bf_info);
+///     bf_vm.set_final_newline(false);
+///     let mut output = Vec::new();
+///     bf_vm.interpret(&mut &b""[..], &mut output).unwrap();
+///     assert_eq!(output.len(), 1);
+///     pool.release(bf_vm);
+/// }
+/// // the three runs shared a single tape
+/// assert_eq!(pool.allocations(), 1);
+/// ```
+#[derive(Debug)]
+pub struct BFVmPool<T> {
+    /// number of cells of the tapes
+    len: usize,
+
+    /// extendable flag for the tapes
+    extendable: bool,
+
+    /// spare tapes reset to zero
+    idle: Vec<Vec<T>>,
+
+    /// number of spare tapes kept, the others are freed
+    max_idle: usize,
+
+    /// tapes allocated so far
+    allocations: usize,
+}
+
+impl<T> BFVmPool<T>
+where
+    T: Default + CellKind,
+{
+    /// Create an empty pool of tapes of `len` cells, see
+    /// `BFVirtualMachine::new`
+    pub fn new(len: usize, extendable: bool) -> Self {
+        Self {
+            len: if len > 0 { len } else { 30000 },
+            extendable,
+            idle: Vec::new(),
+            max_idle: 64,
+            allocations: 0,
+        }
+    }
+
+    /// Limit how many spare tapes are kept, 64 by default
+    pub fn set_max_idle(&mut self, max_idle: usize) {
+        self.max_idle = max_idle;
+        self.idle.truncate(max_idle);
+    }
+
+    /// spare tape, or a new one if there is none
+    fn tape(&mut self) -> Vec<T> {
+        match self.idle.pop() {
+            Some(tape) => tape,
+            None => {
+                self.allocations += 1;
+                crate::tape_with_len(self.len)
+            }
+        }
+    }
+
+    /// Hand out a virtual machine running `bf_info`
+    pub fn acquire<'a>(&mut self, bf_info: &'a BFValidProgram) -> BFVirtualMachine<'a, T> {
+        let tape = self.tape();
+        BFVirtualMachine::with_tape(tape, self.extendable, BFProgramRef::Borrowed(bf_info))
+    }
+
+    /// Hand out a virtual machine sharing ownership of `bf_info`, see
+    /// `BFVirtualMachine::new_shared`
+    pub fn acquire_shared(&mut self, bf_info: Arc<BFValidProgram>) -> BFOwnedVirtualMachine<T> {
+        let tape = self.tape();
+        BFVirtualMachine::with_tape(tape, self.extendable, BFProgramRef::Shared(bf_info))
+    }
+
+    /// Take back the tape of `bf_vm`, whether its program halted or not
+    ///
+    /// A tape the program extended is cut back to the length of the pool.
+    pub fn release(&mut self, mut bf_vm: BFVirtualMachine<'_, T>) {
+        if self.idle.len() >= self.max_idle {
+            return;
+        }
+        let mut tape = core::mem::take(&mut bf_vm.cells);
+        tape.truncate(self.len);
+        tape.fill_with(T::default);
+        self.idle.push(tape);
+    }
+
+    /// Number of spare tapes
+    pub fn idle(&self) -> usize {
+        self.idle.len()
+    }
+
+    /// Number of tapes allocated since the pool was created
+    pub fn allocations(&self) -> usize {
+        self.allocations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_pool() {
+        let mut pool = BFVmPool::<u8>::new(4, true);
+        let first = BFProgram::new("", "+>++>+++>++++>+++++")
+            .validate()
+            .unwrap();
+        let mut bf_vm = pool.acquire(&first);
+        bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(bf_vm.cells(), [1, 2, 3, 4, 5]);
+        pool.release(bf_vm);
+        assert_eq!(pool.idle(), 1);
+
+        // the reused tape is zeroed and back to the length of the pool
+        let second = Arc::new(BFProgram::new("", ">>-").validate().unwrap());
+        let mut bf_vm = pool.acquire_shared(second);
+        assert_eq!(bf_vm.cells(), [0, 0, 0, 0]);
+        bf_vm.interpret(&mut &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(bf_vm.cells(), [0, 0, 255, 0]);
+        assert_eq!(pool.idle(), 0);
+
+        let third = pool.acquire(&first);
+        assert_eq!((pool.allocations(), third.cells().len()), (2, 4));
+        pool.release(bf_vm);
+        pool.release(third);
+        assert_eq!(pool.idle(), 2);
+
+        pool.set_max_idle(1);
+        assert_eq!(pool.idle(), 1);
+        let fourth = pool.acquire(&first);
+        pool.release(fourth);
+        pool.release(BFVirtualMachine::new(4, true, &first));
+        assert_eq!((pool.idle(), pool.allocations()), (1, 2));
+    }
+}
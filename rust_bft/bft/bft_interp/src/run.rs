@@ -0,0 +1,224 @@
+//! Running a program in one call
+//!
+//! Builds the virtual machine from options, feeds it an input buffer and
+//! collects everything it writes, for embedders and tests which only need
+//! the output of a whole run.
+
+use crate::{
+    BFBackend, BFCellWidth, BFEofBehavior, BFHeadMode, BFNewline, BFOptLevel, BFRunStats,
+    BFVirtualMachine, BFVmErr, CellKind,
+};
+use alloc::vec::Vec;
+use bft_types::BFValidProgram;
+use core::time::Duration;
+
+/// Configuration of the virtual machine running the program
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFRunOptions {
+    /// number of cells allocated for the tape
+    pub cells: usize,
+
+    /// whether the tape extends when the head moves past its end
+    pub extensible: bool,
+
+    /// width of each cell
+    pub cell_width: BFCellWidth,
+
+    /// what reading does once input is exhausted
+    pub eof: BFEofBehavior,
+
+    /// what moving the head off the tape does
+    pub head_mode: BFHeadMode,
+
+    /// interpreter backend running the program
+    pub backend: BFBackend,
+
+    /// optimization level of the packed program
+    pub opt_level: BFOptLevel,
+
+    /// instruction budget, unlimited if None
+    pub fuel: Option<u64>,
+
+    /// print a newline after the output if it doesn't end with one
+    pub final_newline: bool,
+
+    /// print a prompt to stdout before reading a value
+    pub prompt: bool,
+
+    /// translation of the line endings read by the program
+    pub newline: BFNewline,
+
+    /// bytes the program may write, unlimited if None
+    pub max_output: Option<u64>,
+
+    /// wall-clock time the program may run for, unlimited if None, only
+    /// enforced with the `std` feature
+    pub timeout: Option<Duration>,
+}
+
+impl Default for BFRunOptions {
+    fn default() -> Self {
+        Self {
+            cells: 30000,
+            extensible: false,
+            cell_width: BFCellWidth::default(),
+            eof: BFEofBehavior::default(),
+            head_mode: BFHeadMode::default(),
+            backend: BFBackend::default(),
+            opt_level: BFOptLevel::default(),
+            fuel: None,
+            final_newline: true,
+            prompt: true,
+            newline: BFNewline::default(),
+            max_output: None,
+            timeout: None,
+        }
+    }
+}
+
+impl BFRunOptions {
+    /// Options of the strict compliance profile: a fixed tape of 30000
+    /// byte cells, reading past the input leaves the cell unchanged, and
+    /// the output is exactly what the program wrote, without any prompt
+    pub fn strict() -> Self {
+        Self {
+            cells: 30000,
+            extensible: false,
+            cell_width: BFCellWidth::U8,
+            eof: BFEofBehavior::Unchanged,
+            head_mode: BFHeadMode::Strict,
+            final_newline: false,
+            prompt: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Everything the program wrote and how it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFRunOutput {
+    output: Vec<u8>,
+    stats: BFRunStats,
+}
+
+impl BFRunOutput {
+    /// gather the output and statistics of a run
+    pub(crate) fn new(output: Vec<u8>, stats: BFRunStats) -> Self {
+        Self { output, stats }
+    }
+
+    /// get the bytes written by the program
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// get the statistics of the run
+    pub fn stats(&self) -> BFRunStats {
+        self.stats
+    }
+
+    /// take the bytes written by the program
+    pub fn into_output(self) -> Vec<u8> {
+        self.output
+    }
+}
+
+/// run the program on a virtual machine with cells of type `T`, appending
+/// what it wrote to `output` even if it failed
+fn run_cells<T: Default + CellKind>(
+    bf_info: &BFValidProgram,
+    input: &[u8],
+    options: &BFRunOptions,
+    output: &mut Vec<u8>,
+) -> Result<BFRunStats, BFVmErr> {
+    let mut bf_vm = BFVirtualMachine::<T>::new(options.cells, options.extensible, bf_info);
+    bf_vm.set_eof(options.eof);
+    bf_vm.set_head_mode(options.head_mode);
+    bf_vm.set_opt_level(options.opt_level);
+    bf_vm.set_fuel(options.fuel);
+    bf_vm.set_final_newline(options.final_newline);
+    bf_vm.set_prompt(options.prompt);
+    bf_vm.set_newline(options.newline);
+    bf_vm.set_max_output(options.max_output);
+    #[cfg(feature = "std")]
+    if let Some(timeout) = options.timeout {
+        bf_vm.set_deadline(std::time::Instant::now().checked_add(timeout));
+    }
+
+    let mut input = input;
+    bf_vm.interpret_with(options.backend, &mut input, output)?;
+    Ok(bf_vm.stats())
+}
+
+/// run the program with the cell width of `options`, appending what it
+/// wrote to `output` even if it failed
+pub(crate) fn run_into(
+    bf_info: &BFValidProgram,
+    input: &[u8],
+    options: &BFRunOptions,
+    output: &mut Vec<u8>,
+) -> Result<BFRunStats, BFVmErr> {
+    match options.cell_width {
+        BFCellWidth::U8 => run_cells::<u8>(bf_info, input, options, output),
+        BFCellWidth::U16 => run_cells::<u16>(bf_info, input, options, output),
+        BFCellWidth::U32 => run_cells::<u32>(bf_info, input, options, output),
+    }
+}
+
+/// Run a validated program on `input`, returning what it wrote
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::{run, BFRunOptions};
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", ",+.").validate().unwrap();
+/// let output = run(&bf_info, b"a", &BFRunOptions::default()).unwrap();
+/// assert_eq!(output.output(), b"b\n");
+/// ```
+pub fn run(
+    bf_info: &BFValidProgram,
+    input: &[u8],
+    options: &BFRunOptions,
+) -> Result<BFRunOutput, BFVmErr> {
+    let mut output = Vec::new();
+    let stats = run_into(bf_info, input, options, &mut output)?;
+    Ok(BFRunOutput::new(output, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bft_types::BFProgram;
+
+    #[test]
+    fn test_run_options() {
+        let bf_info = BFProgram::new("", "-.>,.,.").validate().unwrap();
+        let options = BFRunOptions {
+            cells: 2,
+            cell_width: BFCellWidth::U16,
+            eof: BFEofBehavior::Zero,
+            opt_level: BFOptLevel::O3,
+            final_newline: false,
+            ..BFRunOptions::default()
+        };
+        let output = run(&bf_info, b"x", &options).unwrap();
+        assert_eq!(output.output(), &[0xff, b'x', 0]);
+        assert_eq!(output.stats().opt_level(), BFOptLevel::O3);
+
+        let options = BFRunOptions {
+            cells: 1,
+            ..BFRunOptions::default()
+        };
+        let result = run(&bf_info, b"x", &options);
+        assert!(matches!(result, Err(BFVmErr::HeadInvalidPositionErr(_))));
+
+        let options = BFRunOptions {
+            max_output: Some(1),
+            ..BFRunOptions::default()
+        };
+        let result = run(&bf_info, b"x", &options);
+        assert!(matches!(result, Err(BFVmErr::LimitErr(..))));
+    }
+}
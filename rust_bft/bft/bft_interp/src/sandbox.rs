@@ -0,0 +1,127 @@
+//! Running untrusted programs
+//!
+//! A sandbox bundles every limit a hostile or buggy program could run
+//! past: fuel, wall-clock time, tape size, output size and reading. All of
+//! them are enforced by the virtual machine itself, a program hitting one
+//! stops with a `BFVmErr::FuelExhaustedErr` or `BFVmErr::LimitErr`.
+//!
+//! The program only ever reads the input given to `run_sandboxed`, never an
+//! interactive stream, and reading past its end is an IO error.
+
+use crate::{BFRunOutput, BFVirtualMachine, BFVmErr};
+use bft_types::BFValidProgram;
+use std::time::{Duration, Instant};
+
+/// Limits a sandboxed program runs under
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFSandboxConfig {
+    /// instructions the program may execute
+    pub fuel: u64,
+
+    /// wall-clock time the program may run for
+    pub timeout: Duration,
+
+    /// cells the tape may grow to, at least one
+    pub max_cells: usize,
+
+    /// bytes the program may write
+    pub max_output: usize,
+
+    /// refuse reading altogether, even the given input
+    pub deny_input: bool,
+}
+
+impl Default for BFSandboxConfig {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            timeout: Duration::from_secs(1),
+            max_cells: 30000,
+            max_output: 1 << 16,
+            deny_input: false,
+        }
+    }
+}
+
+/// Run a validated program on `input` within the limits of `config`,
+/// returning what it wrote
+///
+/// The tape starts with up to 30000 byte cells and extends up to
+/// `max_cells`, the other settings of the virtual machine are the defaults
+/// of `run`.
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::sandbox::{run_sandboxed, BFSandboxConfig};
+/// use bft_interp::BFVmErr;
+/// use bft_types::BFProgram;
+///
+/// let config = BFSandboxConfig::default();
+/// let bf_info = BFProgram::new("", ",+.").validate().unwrap();
+/// let output = run_sandboxed(&bf_info, b"a", &config).unwrap();
+/// assert_eq!(output.output(), b"b\n");
+///
+/// let bf_info = BFProgram::new("", "+[]").validate().unwrap();
+/// let result = run_sandboxed(&bf_info, b"", &config);
+/// assert!(matches!(result, Err(BFVmErr::FuelExhaustedErr(_))));
+/// ```
+pub fn run_sandboxed(
+    bf_info: &BFValidProgram,
+    input: &[u8],
+    config: &BFSandboxConfig,
+) -> Result<BFRunOutput, BFVmErr> {
+    let max_cells = config.max_cells.max(1);
+    let mut bf_vm = BFVirtualMachine::<u8>::new(max_cells.min(30000), true, bf_info);
+    bf_vm.set_fuel(Some(config.fuel));
+    bf_vm.set_max_cells(Some(max_cells));
+    bf_vm.set_max_output(Some(config.max_output as u64));
+    bf_vm.deny_input(config.deny_input);
+    bf_vm.set_deadline(Instant::now().checked_add(config.timeout));
+
+    let mut input = input;
+    let mut output = Vec::new();
+    bf_vm.interpret(&mut input, &mut output)?;
+    Ok(BFRunOutput::new(output, bf_vm.stats()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BFLimit;
+    use bft_types::BFProgram;
+
+    fn limit(source: &str, input: &[u8], config: &BFSandboxConfig) -> Option<BFLimit> {
+        let bf_info = BFProgram::new("", source).validate().unwrap();
+        match run_sandboxed(&bf_info, input, config) {
+            Err(BFVmErr::LimitErr(limit, _)) => Some(limit),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_sandbox_limits() {
+        let config = BFSandboxConfig {
+            fuel: u64::MAX,
+            timeout: Duration::from_millis(20),
+            max_cells: 4,
+            max_output: 3,
+            deny_input: true,
+        };
+        assert_eq!(limit("+[]", b"", &config), Some(BFLimit::Timeout));
+        assert_eq!(limit(">>>>", b"", &config), Some(BFLimit::Tape));
+        assert_eq!(limit(">>>.", b"", &config), None);
+        assert_eq!(limit("....", b"", &config), Some(BFLimit::Output));
+        assert_eq!(limit(",", b"a", &config), Some(BFLimit::Input));
+
+        let config = BFSandboxConfig {
+            deny_input: false,
+            ..config
+        };
+        let bf_info = BFProgram::new("", ",.,.").validate().unwrap();
+        let output = run_sandboxed(&bf_info, b"ok", &config).unwrap();
+        assert_eq!(output.output(), b"ok\n");
+        let result = run_sandboxed(&bf_info, b"o", &config);
+        assert!(matches!(result, Err(BFVmErr::IOErr(_))));
+    }
+}
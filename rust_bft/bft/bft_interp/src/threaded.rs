@@ -0,0 +1,141 @@
+//! Threaded-code interpreter backend
+//!
+//! The packed program is pre-compiled into an array of function pointers,
+//! each paired with its packed instruction, and executed sequentially, so there is no
+//! per-step match on the opcode.
+
+use crate::{BFOpCode, BFPackedOp, BFRead, BFVirtualMachine, BFVmErr, BFWrite, CellKind};
+use alloc::vec::Vec;
+
+/// Handler of one pre-compiled instruction, called with the instruction
+type BFThreadedFn<'a, T, R, W> =
+    fn(&mut BFVirtualMachine<'a, T>, &mut R, &mut W, BFPackedOp) -> Result<(), BFVmErr>;
+
+impl<'a, T> BFVirtualMachine<'a, T>
+where
+    T: Default + CellKind,
+{
+    /// Compile the packed program into handlers with their instructions
+    fn compile_threaded<R, W>(&self) -> Vec<(BFThreadedFn<'a, T, R, W>, BFPackedOp)>
+    where
+        R: BFRead,
+        W: BFWrite,
+    {
+        self.code
+            .ops()
+            .iter()
+            .map(|op| {
+                let handler: BFThreadedFn<'a, T, R, W> = match op.opcode() {
+                    BFOpCode::PointerIncrement => {
+                        |vm, _, _, op| vm.move_head_right_by(op.operand() as usize)
+                    }
+                    BFOpCode::PointerDecrement => {
+                        |vm, _, _, op| vm.move_head_left_by(op.operand() as usize)
+                    }
+                    BFOpCode::DataIncrement if op.offset() == 0 => |vm, _, _, op| {
+                        vm.add_head_by(op.operand());
+                        Ok(())
+                    },
+                    BFOpCode::DataDecrement if op.offset() == 0 => |vm, _, _, op| {
+                        vm.minus_head_by(op.operand());
+                        Ok(())
+                    },
+                    BFOpCode::DataOutput => |vm, _, writer, _| vm.write_value(writer),
+                    BFOpCode::DataInput => |vm, reader, _, _| vm.read_value(reader),
+                    BFOpCode::Extension => {
+                        |vm, reader, writer, op| vm.execute_extension(op, reader, writer)
+                    }
+                    BFOpCode::LoopStart => |vm, _, _, op| {
+                        vm.start_loop(op.operand() as usize);
+                        Ok(())
+                    },
+                    BFOpCode::LoopTerminate => |vm, _, _, op| {
+                        vm.stop_loop(op.operand() as usize);
+                        Ok(())
+                    },
+                    BFOpCode::DataIncrement
+                    | BFOpCode::DataDecrement
+                    | BFOpCode::Clear
+                    | BFOpCode::Set
+                    | BFOpCode::MulAdd
+                    | BFOpCode::ScanRight
+                    | BFOpCode::ScanLeft
+                    | BFOpCode::ClearRange => |vm, _, _, op| vm.execute_cell_op(op),
+                };
+                (handler, *op)
+            })
+            .collect()
+    }
+
+    /// run brainfuck program on the virtual machine with threaded code
+    pub fn interpret_threaded<R, W>(
+        &mut self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<(), BFVmErr>
+    where
+        R: BFRead,
+        W: BFWrite,
+    {
+        let threaded = self.compile_threaded::<R, W>();
+        while let Some(&(handler, op)) = threaded.get(self.program_cnt) {
+            self.burn_fuel()?;
+            handler(self, reader, writer, op)?;
+            self.program_cnt += 1;
+        }
+        self.finish_output(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BFBackend, BFOptLevel, BFVirtualMachine};
+    use bft_types::BFProgram;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_threaded_matches_loop() {
+        let bf_info = BFProgram::new("", ",[->+>+<<]>[-<+>]>.<<.")
+            .validate()
+            .unwrap();
+        let mut outputs = Vec::new();
+
+        for backend in [BFBackend::Loop, BFBackend::Threaded] {
+            let mut vm = BFVirtualMachine::<u8>::new(10, false, &bf_info);
+            let mut r_buf = Cursor::new(vec![42]);
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            vm.interpret_with(backend, &mut r_buf, &mut w_buf).unwrap();
+            outputs.push(w_buf.into_inner());
+        }
+        assert_eq!(outputs[0], vec![42, 42, 10]);
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_threaded_optimized() {
+        let bf_info = BFProgram::new("", ",[->+>+<<]>[-<+>]<[>]>.")
+            .validate()
+            .unwrap();
+        let mut outputs = Vec::new();
+
+        for backend in [BFBackend::Loop, BFBackend::Threaded] {
+            let mut vm = BFVirtualMachine::<u8>::new(10, false, &bf_info);
+            vm.set_opt_level(BFOptLevel::O3);
+            let mut r_buf = Cursor::new(vec![42]);
+            let mut w_buf = Cursor::new(Vec::<u8>::new());
+            vm.interpret_with(backend, &mut r_buf, &mut w_buf).unwrap();
+            outputs.push((w_buf.into_inner(), vm.head(), vm.stats()));
+        }
+        assert_eq!(outputs[0].0, vec![42, 10]);
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_threaded_head_error() {
+        let bf_info = BFProgram::new("", "+<").validate().unwrap();
+        let mut vm = BFVirtualMachine::<u8>::new(10, false, &bf_info);
+        let mut r_buf = Cursor::new(Vec::<u8>::new());
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        assert!(vm.interpret_threaded(&mut r_buf, &mut w_buf).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Execution traces
+//!
+//! A traced run writes one line per executed packed instruction: the step
+//! number counted from 1, the source position, the opcode with its operand,
+//! the head before and after, and the head cell before and after, eg.
+//!
+//! ```text
+//! 2 1:3 DataInput 1 head 0->0 cell 2->97
+//! 3 1:4-1:8 Clear 0 head 0->0 cell 97->0
+//! ```
+//!
+//! An instruction the optimizer built out of several source instructions
+//! is written with the span of all of them.
+//!
+//! Traces of big runs are kept small with a `BFTraceFilter` choosing which
+//! instructions and which steps are written.
+
+use crate::{
+    BFOpCode, BFRead, BFVirtualMachine, BFVirtualMachineIOErr, BFVmErr, BFWrite, CellKind,
+};
+use std::fmt;
+use std::io::Write;
+
+/// Instructions written to a trace
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BFTraceOps {
+    /// every instruction
+    #[default]
+    All,
+
+    /// input, output and extension commands
+    Io,
+
+    /// loop boundaries and the scans replacing whole loops
+    Loops,
+}
+
+impl BFTraceOps {
+    /// whether instructions with `opcode` are written
+    fn keeps(self, opcode: BFOpCode) -> bool {
+        match self {
+            BFTraceOps::All => true,
+            BFTraceOps::Io => matches!(
+                opcode,
+                BFOpCode::DataInput | BFOpCode::DataOutput | BFOpCode::Extension
+            ),
+            BFTraceOps::Loops => matches!(
+                opcode,
+                BFOpCode::LoopStart
+                    | BFOpCode::LoopTerminate
+                    | BFOpCode::ScanLeft
+                    | BFOpCode::ScanRight
+            ),
+        }
+    }
+}
+
+/// Which executed instructions are written to a trace
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BFTraceFilter {
+    /// kind of instructions written
+    pub ops: BFTraceOps,
+
+    /// first step written
+    pub first: u64,
+
+    /// step after the last one written, None up to the end of the run
+    pub end: Option<u64>,
+}
+
+impl BFTraceFilter {
+    /// whether step `step` running `opcode` is written
+    fn keeps(&self, step: u64, opcode: BFOpCode) -> bool {
+        step >= self.first && self.end.is_none_or(|end| step < end) && self.ops.keeps(opcode)
+    }
+}
+
+/// run the program step by step like `interpret`, writing the trace of the
+/// instructions kept by `filter` to `trace`
+///
+/// Memory-mapped cells are traced with the values seen by the virtual
+/// machine, a failure writing the trace stops the program as an IO error of
+/// the instruction.
+///
+/// # Examples:
+///
+/// ```
+/// use bft_interp::trace::{interpret_traced, BFTraceFilter, BFTraceOps};
+/// use bft_interp::BFVirtualMachine;
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", "++,.").validate().unwrap();
+/// let mut bf_vm = BFVirtualMachine::<u8>::new(1, false, &bf_info);
+/// let filter = BFTraceFilter {
+///     ops: BFTraceOps::Io,
+///     ..BFTraceFilter::default()
+/// };
+/// let mut trace = Vec::new();
+/// interpret_traced(&mut bf_vm, &mut &b"a"[..], &mut Vec::new(), &filter, &mut trace).unwrap();
+/// let trace = String::from_utf8(trace).unwrap();
+/// assert_eq!(trace.lines().next(), Some("2 1:3 DataInput 1 head 0->0 cell 2->97"));
+/// ```
+pub fn interpret_traced<T>(
+    bf_vm: &mut BFVirtualMachine<T>,
+    reader: &mut impl BFRead,
+    writer: &mut impl BFWrite,
+    filter: &BFTraceFilter,
+    trace: &mut dyn Write,
+) -> Result<(), BFVmErr>
+where
+    T: Default + CellKind + Copy + fmt::Display,
+{
+    let mut running = true;
+    while running {
+        let pc = bf_vm.program_cnt;
+        let Some(&op) = bf_vm.code.ops().get(pc) else {
+            break;
+        };
+        let step = bf_vm.steps + 1;
+        let head = bf_vm.head;
+        let cell = bf_vm.cells[head];
+        let result = bf_vm.step(reader, writer);
+        if filter.keeps(step, op.opcode()) {
+            let info = bf_vm.code.source_info(&bf_vm.program, pc);
+            let location = bf_vm.code.source_span(pc).location(&bf_vm.program);
+            let after = match bf_vm.cells.get(bf_vm.head) {
+                Some(value) => value.to_string(),
+                None => "-".into(),
+            };
+            writeln!(
+                trace,
+                "{} {} {:?} {} head {}->{} cell {}->{}",
+                step,
+                location,
+                op.opcode(),
+                op.operand(),
+                head,
+                bf_vm.head,
+                cell,
+                after
+            )
+            .map_err(|err| BFVirtualMachineIOErr { err, cmd: info })?;
+        }
+        running = result?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BFOptLevel;
+    use bft_types::BFProgram;
+
+    fn traced_at(
+        source: &str,
+        level: BFOptLevel,
+        filter: &BFTraceFilter,
+    ) -> (Result<(), BFVmErr>, Vec<String>) {
+        let bf_info = BFProgram::new("", source).validate().unwrap();
+        let mut bf_vm = BFVirtualMachine::<u8>::new(2, false, &bf_info);
+        bf_vm.set_opt_level(level);
+        let mut trace = Vec::new();
+        let result = interpret_traced(
+            &mut bf_vm,
+            &mut &b""[..],
+            &mut Vec::new(),
+            filter,
+            &mut trace,
+        );
+        let lines = String::from_utf8(trace).unwrap();
+        (result, lines.lines().map(String::from).collect())
+    }
+
+    fn traced(source: &str, filter: &BFTraceFilter) -> (Result<(), BFVmErr>, Vec<String>) {
+        traced_at(source, BFOptLevel::O0, filter)
+    }
+
+    #[test]
+    fn test_trace_filters() {
+        let (result, lines) = traced("++[->+<]", &BFTraceFilter::default());
+        assert!(result.is_ok());
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[4], "5 1:5 PointerIncrement 1 head 0->1 cell 1->0");
+
+        let filter = BFTraceFilter {
+            ops: BFTraceOps::Loops,
+            first: 3,
+            end: Some(13),
+        };
+        let (_, lines) = traced("++[->+<]", &filter);
+        assert_eq!(
+            lines,
+            [
+                "3 1:3 LoopStart 7 head 0->0 cell 2->2",
+                "8 1:8 LoopTerminate 2 head 0->0 cell 1->1",
+            ]
+        );
+
+        // the failing instruction is still traced
+        let (result, lines) = traced("<", &BFTraceFilter::default());
+        assert!(matches!(result, Err(BFVmErr::HeadInvalidPositionErr(_))));
+        assert_eq!(lines, ["1 1:1 PointerDecrement 1 head 0->0 cell 0->0"]);
+
+        // optimized instructions are traced with their whole span
+        let (_, lines) = traced_at(">\n+++[-]", BFOptLevel::O2, &BFTraceFilter::default());
+        assert_eq!(lines[1], "2 2:1-2:3 DataIncrement 3 head 1->1 cell 0->3");
+        assert_eq!(lines[2], "3 2:4-2:6 Clear 0 head 1->1 cell 3->0");
+    }
+}
@@ -0,0 +1,468 @@
+//! Differential checking between interpreter backends
+//!
+//! The same program runs under two configurations on the same input and
+//! everything observable is compared: IO events in execution order, the
+//! outcome of the run, the final head and the final tape. The first
+//! difference is reported with the source position when one is known.
+//!
+//! A program can also run under every cell width and EOF behavior, see
+//! `BFSemanticsReport`, to find out which assumptions its output depends on.
+
+use crate::{
+    run, BFBackend, BFCellWidth, BFEofBehavior, BFIoEvent, BFRunOptions, BFVirtualMachine, CellKind,
+};
+use bft_types::BFValidProgram;
+use core::fmt;
+use std::io::Cursor;
+
+/// Everything observable about one run of a program
+#[derive(Debug, Clone)]
+pub struct BFRunTrace<T> {
+    events: Vec<BFIoEvent>,
+    outcome: Result<(), String>,
+    head: usize,
+    tape: Vec<T>,
+}
+
+impl<T> BFRunTrace<T>
+where
+    T: Default + CellKind + Copy,
+{
+    /// Run the virtual machine with `backend` on `input` and record its trace,
+    /// the prompt turned off so reads print nothing
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::verify::BFRunTrace;
+    /// use bft_interp::{BFBackend, BFVirtualMachine};
+    /// use bft_types::BFProgram;
+    ///
+    /// let bf_info = BFProgram::new("", ",+.").validate().unwrap();
+    /// let mut bf_vm = BFVirtualMachine::<u8>::new(10, false, &bf_info);
+    /// let trace = BFRunTrace::record(&mut bf_vm, BFBackend::Loop, b"a");
+    /// assert_eq!(trace.events().len(), 2);
+    /// ```
+    pub fn record(bf_vm: &mut BFVirtualMachine<T>, backend: BFBackend, input: &[u8]) -> Self {
+        bf_vm.record_io(true);
+        bf_vm.set_prompt(false);
+        let outcome = bf_vm
+            .interpret_with(backend, &mut Cursor::new(input), &mut Vec::<u8>::new())
+            .map_err(|e| e.to_string());
+        Self {
+            events: bf_vm.io_log().to_vec(),
+            outcome,
+            head: bf_vm.head(),
+            tape: bf_vm.cells().to_vec(),
+        }
+    }
+
+    /// IO events in execution order
+    pub fn events(&self) -> &[BFIoEvent] {
+        &self.events
+    }
+
+    /// how the run ended, the error message if it failed
+    pub fn outcome(&self) -> &Result<(), String> {
+        &self.outcome
+    }
+
+    /// position of the head when the run ended
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// tape when the run ended
+    pub fn tape(&self) -> &[T] {
+        &self.tape
+    }
+}
+
+/// First observable difference between two runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BFDivergence<T> {
+    /// the `index`-th IO event differs, None if a run stopped earlier
+    Io {
+        index: usize,
+        baseline: Option<BFIoEvent>,
+        candidate: Option<BFIoEvent>,
+    },
+
+    /// the runs ended differently
+    Outcome {
+        baseline: Result<(), String>,
+        candidate: Result<(), String>,
+    },
+
+    /// the head ended on different cells
+    Head { baseline: usize, candidate: usize },
+
+    /// the final tape differs at `cell`
+    Tape {
+        cell: usize,
+        baseline: T,
+        candidate: T,
+    },
+}
+
+impl<T: fmt::Debug> fmt::Display for BFDivergence<T> {
+    /// print divergence in human readable format
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let event = |e: &Option<BFIoEvent>| match e {
+            Some(e) => e.to_string(),
+            None => "no more IO".to_string(),
+        };
+        let outcome = |o: &Result<(), String>| match o {
+            Ok(()) => "finished".to_string(),
+            Err(e) => e.clone(),
+        };
+        match self {
+            Self::Io {
+                index,
+                baseline,
+                candidate,
+            } => write!(
+                f,
+                "IO event {} diverges: baseline {}, candidate {}",
+                index,
+                event(baseline),
+                event(candidate)
+            ),
+            Self::Outcome {
+                baseline,
+                candidate,
+            } => write!(
+                f,
+                "outcome diverges: baseline {}, candidate {}",
+                outcome(baseline),
+                outcome(candidate)
+            ),
+            Self::Head {
+                baseline,
+                candidate,
+            } => write!(
+                f,
+                "final head diverges: baseline {}, candidate {}",
+                baseline, candidate
+            ),
+            Self::Tape {
+                cell,
+                baseline,
+                candidate,
+            } => write!(
+                f,
+                "final tape diverges at cell {}: baseline {:?}, candidate {:?}",
+                cell, baseline, candidate
+            ),
+        }
+    }
+}
+
+/// Compare two traces, returning the first observable difference
+///
+/// IO events are compared by direction and byte, tapes are compared as if
+/// padded with zero cells so differently grown tapes still match.
+pub fn first_divergence<T>(
+    baseline: &BFRunTrace<T>,
+    candidate: &BFRunTrace<T>,
+) -> Option<BFDivergence<T>>
+where
+    T: Default + Copy + PartialEq,
+{
+    let io_len = baseline.events.len().max(candidate.events.len());
+    for index in 0..io_len {
+        let (b, c) = (baseline.events.get(index), candidate.events.get(index));
+        let same = match (b, c) {
+            (Some(b), Some(c)) => b.kind() == c.kind() && b.value() == c.value(),
+            _ => false,
+        };
+        if !same {
+            return Some(BFDivergence::Io {
+                index,
+                baseline: b.copied(),
+                candidate: c.copied(),
+            });
+        }
+    }
+
+    if baseline.outcome != candidate.outcome {
+        return Some(BFDivergence::Outcome {
+            baseline: baseline.outcome.clone(),
+            candidate: candidate.outcome.clone(),
+        });
+    }
+
+    if baseline.head != candidate.head {
+        return Some(BFDivergence::Head {
+            baseline: baseline.head,
+            candidate: candidate.head,
+        });
+    }
+
+    let tape_len = baseline.tape.len().max(candidate.tape.len());
+    (0..tape_len).find_map(|cell| {
+        let b = baseline.tape.get(cell).copied().unwrap_or_default();
+        let c = candidate.tape.get(cell).copied().unwrap_or_default();
+        (b != c).then_some(BFDivergence::Tape {
+            cell,
+            baseline: b,
+            candidate: c,
+        })
+    })
+}
+
+/// Cell width and EOF behavior a program runs under
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFSemantics {
+    /// width of each cell
+    pub cell_width: BFCellWidth,
+
+    /// what reading does once input is exhausted
+    pub eof: BFEofBehavior,
+}
+
+impl BFSemantics {
+    /// every cell width with every EOF behavior
+    pub fn all() -> Vec<Self> {
+        let widths = [BFCellWidth::U8, BFCellWidth::U16, BFCellWidth::U32];
+        let eofs = [
+            BFEofBehavior::Zero,
+            BFEofBehavior::MinusOne,
+            BFEofBehavior::Unchanged,
+            BFEofBehavior::Error,
+        ];
+        widths
+            .into_iter()
+            .flat_map(|cell_width| eofs.map(|eof| Self { cell_width, eof }))
+            .collect()
+    }
+}
+
+impl fmt::Display for BFSemantics {
+    /// print semantics in human readable format
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let eof = match self.eof {
+            BFEofBehavior::Zero => "zero",
+            BFEofBehavior::MinusOne => "minus-one",
+            BFEofBehavior::Unchanged => "unchanged",
+            BFEofBehavior::Error => "error",
+        };
+        write!(f, "u{} cells, EOF {}", self.cell_width.bits(), eof)
+    }
+}
+
+/// What a program wrote under some semantics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFSemanticsRun {
+    semantics: BFSemantics,
+    output: Vec<u8>,
+    outcome: Result<(), String>,
+}
+
+impl BFSemanticsRun {
+    /// semantics of the run
+    pub fn semantics(&self) -> BFSemantics {
+        self.semantics
+    }
+
+    /// bytes written by the program, up to the error if it failed
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// how the run ended, the error message if it failed
+    pub fn outcome(&self) -> &Result<(), String> {
+        &self.outcome
+    }
+
+    /// whether both runs wrote the same bytes and ended the same way
+    pub fn agrees_with(&self, other: &Self) -> bool {
+        self.output == other.output && self.outcome == other.outcome
+    }
+
+    /// offset of the first output byte differing from `other`, the length of
+    /// the shorter output if one is a prefix of the other, None if equal
+    pub fn output_divergence(&self, other: &Self) -> Option<usize> {
+        let mut pairs = self.output.iter().zip(&other.output);
+        match pairs.position(|(a, b)| a != b) {
+            Some(offset) => Some(offset),
+            None if self.output.len() == other.output.len() => None,
+            None => Some(self.output.len().min(other.output.len())),
+        }
+    }
+}
+
+/// Runs of a program on the same input under several semantics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFSemanticsReport {
+    runs: Vec<BFSemanticsRun>,
+}
+
+impl BFSemanticsReport {
+    /// Run the program on `input` under each of `semantics`, the other
+    /// settings are taken from `options`
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_interp::verify::{BFSemantics, BFSemanticsReport};
+    /// use bft_interp::BFRunOptions;
+    /// use bft_types::BFProgram;
+    ///
+    /// // 256 is zero in a byte cell, the final read fails with EOF errors
+    /// let source = "++++++++++++++++[>++++++++++++++++<-]>[>+<[-]]>.,";
+    /// let bf_info = BFProgram::new("", source).validate().unwrap();
+    /// let options = BFRunOptions::default();
+    /// let report = BFSemanticsReport::run(&bf_info, b"", &BFSemantics::all(), &options);
+    /// assert_eq!(report.runs().len(), 12);
+    /// assert_eq!(report.groups().len(), 4);
+    /// ```
+    pub fn run(
+        bf_info: &BFValidProgram,
+        input: &[u8],
+        semantics: &[BFSemantics],
+        options: &BFRunOptions,
+    ) -> Self {
+        let runs = semantics
+            .iter()
+            .map(|&semantics| {
+                let options = BFRunOptions {
+                    cell_width: semantics.cell_width,
+                    eof: semantics.eof,
+                    final_newline: false,
+                    prompt: false,
+                    ..*options
+                };
+                let mut output = Vec::new();
+                let outcome = run::run_into(bf_info, input, &options, &mut output);
+                BFSemanticsRun {
+                    semantics,
+                    output,
+                    outcome: outcome.map(|_| ()).map_err(|e| e.to_string()),
+                }
+            })
+            .collect();
+        Self { runs }
+    }
+
+    /// every run in the order of the semantics
+    pub fn runs(&self) -> &[BFSemanticsRun] {
+        &self.runs
+    }
+
+    /// runs grouped by agreement, in the order of their first run
+    pub fn groups(&self) -> Vec<Vec<&BFSemanticsRun>> {
+        let mut groups: Vec<Vec<&BFSemanticsRun>> = Vec::new();
+        for run in &self.runs {
+            match groups.iter_mut().find(|group| group[0].agrees_with(run)) {
+                Some(group) => group.push(run),
+                None => groups.push(vec![run]),
+            }
+        }
+        groups
+    }
+
+    /// whether every run agrees
+    pub fn agree(&self) -> bool {
+        self.groups().len() <= 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BFEofBehavior, BFIoKind};
+    use bft_types::BFProgram;
+
+    fn trace(program: &str, backend: BFBackend, input: &[u8]) -> BFRunTrace<u8> {
+        let bf_info = BFProgram::new("", program).validate().unwrap();
+        let mut bf_vm = BFVirtualMachine::<u8>::new(8, true, &bf_info);
+        bf_vm.set_eof(BFEofBehavior::Zero);
+        BFRunTrace::record(&mut bf_vm, backend, input)
+    }
+
+    #[test]
+    fn test_backends_agree() {
+        let program = ",[.>,]>>>>>>>>>+.";
+        let baseline = trace(program, BFBackend::Loop, b"abc");
+        let candidate = trace(program, BFBackend::Threaded, b"abc");
+        assert_eq!(baseline.events().len(), 8);
+        assert_eq!(baseline.events()[1].kind(), BFIoKind::Output);
+        assert_eq!(baseline.events()[6].value(), None);
+        assert_eq!(baseline.tape().len(), 13);
+        assert_eq!(first_divergence(&baseline, &candidate), None);
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_jit_agrees() {
+        let program = ",[.>,]>>>>>>>>>+.<<<<<<<<<<<<<<";
+        let baseline = trace(program, BFBackend::Loop, b"abc");
+        let candidate = trace(program, BFBackend::Jit, b"abc");
+        assert!(baseline.outcome().is_err());
+        assert_eq!(first_divergence(&baseline, &candidate), None);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let baseline = trace(",.,.", BFBackend::Loop, b"ab");
+        let candidate = trace(",.,.", BFBackend::Loop, b"ac");
+        match first_divergence(&baseline, &candidate) {
+            Some(BFDivergence::Io {
+                index,
+                baseline: Some(b),
+                candidate: Some(c),
+            }) => {
+                assert_eq!(index, 2);
+                assert_eq!((b.value(), c.value()), (Some(b'b'), Some(b'c')));
+                assert_eq!(b.cmd().column(), 3);
+            }
+            other => panic!("unexpected divergence {:?}", other),
+        }
+
+        let candidate = trace(",.<", BFBackend::Loop, b"a");
+        let divergence = first_divergence(&baseline, &candidate).unwrap();
+        assert!(divergence.to_string().starts_with("IO event 2 diverges"));
+
+        let baseline = trace("+>++", BFBackend::Loop, b"");
+        let candidate = trace("+>+++", BFBackend::Loop, b"");
+        assert_eq!(
+            first_divergence(&baseline, &candidate),
+            Some(BFDivergence::Tape {
+                cell: 1,
+                baseline: 2,
+                candidate: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_semantics_report() {
+        // echo the input then whatever the cell holds after EOF
+        let bf_info = BFProgram::new("", ",.,.").validate().unwrap();
+        let report = BFSemanticsReport::run(
+            &bf_info,
+            b"a",
+            &BFSemantics::all(),
+            &BFRunOptions::default(),
+        );
+        assert!(!report.agree());
+        let groups = report.groups();
+        let outputs: Vec<&[u8]> = groups.iter().map(|group| group[0].output()).collect();
+        assert_eq!(outputs, [&b"a\0"[..], b"a\xff", b"aa", b"a"]);
+        assert_eq!(groups[0].len(), 3);
+        assert!(groups[3][0].outcome().is_err());
+        assert_eq!(groups[1][0].output_divergence(groups[0][0]), Some(1));
+        assert_eq!(groups[3][0].output_divergence(groups[0][0]), Some(1));
+        assert_eq!(groups[0][1].semantics().to_string(), "u16 cells, EOF zero");
+
+        let bf_info = BFProgram::new("", ",.").validate().unwrap();
+        let report = BFSemanticsReport::run(
+            &bf_info,
+            b"a",
+            &BFSemantics::all(),
+            &BFRunOptions::default(),
+        );
+        assert!(report.agree());
+    }
+}
@@ -0,0 +1,235 @@
+//! wasm-bindgen bindings for a browser playground, enabled with the `wasm` feature
+//!
+//! Input and output go through JavaScript callbacks working on strings.
+//! The program runs in slices of `step_interval` instructions, after each
+//! slice the step callback is told how far the program got and may stop it
+//! by returning `false`.
+
+use crate::{BFOptLevel, BFVirtualMachine, BFVmErr};
+use bft_types::{BFProgram, BFValidProgram};
+use js_sys::Function;
+use std::io::{self, Read, Write};
+use wasm_bindgen::prelude::*;
+
+/// Configuration of a playground run
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFWasmOptions {
+    /// number of cells allocated for the tape
+    pub cells: usize,
+
+    /// whether the tape extends when the head moves past its end
+    pub extensible: bool,
+
+    /// optimization level from 0 to 3
+    pub opt_level: u8,
+
+    /// instruction budget of the whole run, unlimited if undefined
+    pub fuel: Option<u64>,
+
+    /// instructions between two calls of the step callback
+    pub step_interval: u64,
+}
+
+#[wasm_bindgen]
+impl BFWasmOptions {
+    /// create the default options
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for BFWasmOptions {
+    fn default() -> Self {
+        Self {
+            cells: 30000,
+            extensible: false,
+            opt_level: 1,
+            fuel: None,
+            step_interval: 100_000,
+        }
+    }
+}
+
+/// Statistics of a finished playground run
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BFWasmStats {
+    steps: u64,
+    ops: usize,
+}
+
+#[wasm_bindgen]
+impl BFWasmStats {
+    /// get the number of instructions executed
+    #[wasm_bindgen(getter)]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// get the number of packed instructions
+    #[wasm_bindgen(getter)]
+    pub fn ops(&self) -> usize {
+        self.ops
+    }
+}
+
+/// Reader pulling strings from a JavaScript callback,
+/// `null` or `undefined` from the callback ends the input
+struct BFJsReader<'f> {
+    read: &'f Function,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for BFJsReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            let chunk = self
+                .read
+                .call0(&JsValue::NULL)
+                .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+            match chunk.as_string() {
+                Some(chunk) => self.buf = chunk.into_bytes(),
+                None => self.buf.clear(),
+            }
+            self.pos = 0;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Writer pushing strings to a JavaScript callback, bytes of an incomplete
+/// UTF-8 character are held back until the character is complete
+struct BFJsWriter<'f> {
+    write: &'f Function,
+    pending: Vec<u8>,
+}
+
+impl BFJsWriter<'_> {
+    /// hand over `len` pending bytes to the callback
+    fn emit(&mut self, len: usize) -> io::Result<()> {
+        let text = String::from_utf8_lossy(&self.pending[..len]).into_owned();
+        self.pending.drain(..len);
+        self.write
+            .call1(&JsValue::NULL, &JsValue::from_str(&text))
+            .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        Ok(())
+    }
+}
+
+impl Write for BFJsWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let len = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_some() => self.pending.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        if len > 0 {
+            self.emit(len)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            self.emit(self.pending.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// parse and validate brainfuck `source`
+fn validate(source: &str) -> Result<BFValidProgram, JsError> {
+    BFProgram::new("playground", source)
+        .validate()
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Check that brainfuck `source` is a valid program, the error tells
+/// which bracket doesn't match
+#[wasm_bindgen]
+pub fn check(source: &str) -> Result<(), JsError> {
+    validate(source).map(|_| ())
+}
+
+/// Run brainfuck `source`
+///
+/// `read()` returns the next input string, `write(text)` receives output,
+/// `on_step(steps, line, column)` is called after every `step_interval`
+/// instructions with the position about to run and stops the program if
+/// it returns `false`.
+#[wasm_bindgen]
+pub fn run(
+    source: &str,
+    read: &Function,
+    write: &Function,
+    on_step: Option<Function>,
+    options: &BFWasmOptions,
+) -> Result<BFWasmStats, JsError> {
+    let bf_info = validate(source)?;
+    let mut bf_vm = BFVirtualMachine::<u8>::new(options.cells, options.extensible, &bf_info);
+    bf_vm.set_opt_level(match options.opt_level {
+        0 => BFOptLevel::O0,
+        1 => BFOptLevel::O1,
+        2 => BFOptLevel::O2,
+        _ => BFOptLevel::O3,
+    });
+
+    let mut reader = BFJsReader {
+        read,
+        buf: Vec::new(),
+        pos: 0,
+    };
+    let mut writer = BFJsWriter {
+        write,
+        pending: Vec::new(),
+    };
+    let interval = on_step.as_ref().map(|_| options.step_interval.max(1));
+    let mut remaining = options.fuel;
+
+    loop {
+        let slice = match (interval, remaining) {
+            (Some(i), Some(r)) => Some(i.min(r)),
+            (i, r) => i.or(r),
+        };
+        bf_vm.set_fuel(slice);
+        let result = bf_vm.interpret(&mut reader, &mut writer);
+        if let (Some(r), Some(s)) = (remaining.as_mut(), slice) {
+            *r -= s - bf_vm.fuel().unwrap_or(0);
+        }
+
+        match result {
+            Ok(()) => break,
+            Err(BFVmErr::FuelExhaustedErr(cmd)) if remaining != Some(0) => {
+                let on_step = on_step
+                    .as_ref()
+                    .expect("bft: slices only run with a step callback");
+                let go_on = on_step
+                    .call3(
+                        &JsValue::NULL,
+                        &JsValue::from(bf_vm.stats().steps()),
+                        &JsValue::from(cmd.line()),
+                        &JsValue::from(cmd.column()),
+                    )
+                    .map_err(|e| JsError::new(&format!("{:?}", e)))?;
+                if go_on == JsValue::FALSE {
+                    return Err(JsError::new(&format!("Stopped by {}", cmd)));
+                }
+            }
+            Err(e) => return Err(JsError::new(&e.to_string())),
+        }
+    }
+    writer.flush().map_err(|e| JsError::new(&e.to_string()))?;
+
+    let stats = bf_vm.stats();
+    Ok(BFWasmStats {
+        steps: stats.steps(),
+        ops: stats.ops(),
+    })
+}
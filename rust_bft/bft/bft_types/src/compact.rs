@@ -0,0 +1,464 @@
+//! Compact storage of the instructions of a program
+//!
+//! A `BFCharInfo` takes 32 bytes, too much for programs of tens of millions
+//! of instructions. `BFInstructions` keeps a byte per instruction for its
+//! command and its position as a varint delta from the previous one, the
+//! absolute position every `CHECKPOINT` instructions to reach any of them
+//! quickly, and the bracket partners, extension characters and files in
+//! side tables only growing with the instructions needing them. A typical
+//! program takes 3 to 4 bytes per instruction.
+
+use crate::{BFCharCmdName, BFCharInfo};
+use alloc::vec::Vec;
+
+/// Instructions between two absolute positions
+const CHECKPOINT: usize = 64;
+
+/// Partner of an unmatched bracket
+const UNMATCHED: u32 = u32::MAX;
+
+/// Commands of the instructions as stored
+const POINTER_INCREMENT: u8 = 0;
+const POINTER_DECREMENT: u8 = 1;
+const DATA_INCREMENT: u8 = 2;
+const DATA_DECREMENT: u8 = 3;
+const DATA_OUTPUT: u8 = 4;
+const DATA_INPUT: u8 = 5;
+const LOOP_START: u8 = 6;
+const LOOP_TERMINATE: u8 = 7;
+const EXTENSION: u8 = 8;
+
+/// Position an instruction is decoded from
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+struct BFCheckpoint {
+    line: usize,
+    column: usize,
+
+    /// offset of the delta of the instruction in `deltas`
+    offset: usize,
+
+    /// brackets and extension commands before the instruction
+    brackets: u32,
+    extensions: u32,
+}
+
+/// Instructions of a program, a few bytes each
+///
+/// # Examples:
+///
+/// ```
+/// use bft_types::BFProgram;
+///
+/// let bf_info = BFProgram::new("", "+[>\n-]");
+/// let instructions = bf_info.instructions();
+/// assert_eq!(instructions.len(), 5);
+/// assert_eq!((instructions.at(3).line(), instructions.at(3).column()), (2, 1));
+/// assert_eq!(instructions.iter().filter(|info| info.line() == 1).count(), 3);
+/// assert!(instructions.get(5).is_none());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BFInstructions {
+    /// command of every instruction
+    ops: Vec<u8>,
+
+    /// position of every instruction as a delta from the previous one
+    deltas: Vec<u8>,
+
+    /// position before every `CHECKPOINT`th instruction
+    checkpoints: Vec<BFCheckpoint>,
+
+    /// index of the matching bracket of every bracket in order,
+    /// `UNMATCHED` if none
+    brackets: Vec<u32>,
+
+    /// character of every extension command in order
+    extensions: Vec<char>,
+
+    /// first index and file of every run of instructions of the same file
+    files: Vec<(usize, u32)>,
+
+    /// position of the last instruction
+    last: (usize, usize),
+}
+
+impl BFInstructions {
+    /// Create an empty instruction stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of instructions
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether there is no instruction
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Instruction at `index`, None if out of bounds
+    pub fn get(&self, index: usize) -> Option<BFCharInfo> {
+        let op = *self.ops.get(index)?;
+        let checkpoint = self.checkpoints[index / CHECKPOINT];
+        let (mut line, mut column) = (checkpoint.line, checkpoint.column);
+        let mut offset = checkpoint.offset;
+        let (mut bracket, mut extension) = (checkpoint.brackets, checkpoint.extensions);
+        for &before in &self.ops[index - index % CHECKPOINT..index] {
+            (line, column) = decode(&self.deltas, &mut offset, line, column);
+            match before {
+                LOOP_START | LOOP_TERMINATE => bracket += 1,
+                EXTENSION => extension += 1,
+                _ => (),
+            }
+        }
+        (line, column) = decode(&self.deltas, &mut offset, line, column);
+        Some(BFCharInfo {
+            raw: self.raw(op, bracket as usize, extension as usize),
+            line,
+            column,
+            file: self.file(index),
+        })
+    }
+
+    /// Instruction at `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, like indexing a slice
+    pub fn at(&self, index: usize) -> BFCharInfo {
+        match self.get(index) {
+            Some(info) => info,
+            None => panic!(
+                "bft: instruction {} out of {} instructions",
+                index,
+                self.len()
+            ),
+        }
+    }
+
+    /// Instructions in order
+    pub fn iter(&self) -> BFInstructionsIter<'_> {
+        BFInstructionsIter {
+            instructions: self,
+            index: 0,
+            offset: 0,
+            position: (1, 0),
+            bracket: 0,
+            extension: 0,
+            file: 0,
+        }
+    }
+
+    /// Free the capacity left over by pushing the instructions
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.ops.shrink_to_fit();
+        self.deltas.shrink_to_fit();
+        self.checkpoints.shrink_to_fit();
+        self.brackets.shrink_to_fit();
+        self.extensions.shrink_to_fit();
+        self.files.shrink_to_fit();
+    }
+
+    /// Copy of the instructions as a vector
+    pub fn to_vec(&self) -> Vec<BFCharInfo> {
+        self.iter().collect()
+    }
+
+    /// Bytes taken on the heap
+    pub fn heap_size(&self) -> usize {
+        use core::mem::size_of;
+        self.ops.capacity()
+            + self.deltas.capacity()
+            + self.checkpoints.capacity() * size_of::<BFCheckpoint>()
+            + self.brackets.capacity() * size_of::<u32>()
+            + self.extensions.capacity() * size_of::<char>()
+            + self.files.capacity() * size_of::<(usize, u32)>()
+    }
+
+    /// Append `info`, its brackets matching as recorded
+    fn push(&mut self, info: BFCharInfo) {
+        let index = self.ops.len();
+        let (line, column) = if index == 0 { (1, 0) } else { self.last };
+        if index.is_multiple_of(CHECKPOINT) {
+            self.checkpoints.push(BFCheckpoint {
+                line,
+                column,
+                offset: self.deltas.len(),
+                brackets: self.brackets.len() as u32,
+                extensions: self.extensions.len() as u32,
+            });
+        }
+        encode(&mut self.deltas, (line, column), (info.line, info.column));
+        self.last = (info.line, info.column);
+
+        let op = match info.raw {
+            BFCharCmdName::PointerIncrement => POINTER_INCREMENT,
+            BFCharCmdName::PointerDecrement => POINTER_DECREMENT,
+            BFCharCmdName::DataIncrement => DATA_INCREMENT,
+            BFCharCmdName::DataDecrement => DATA_DECREMENT,
+            BFCharCmdName::DataOutput => DATA_OUTPUT,
+            BFCharCmdName::DataInput => DATA_INPUT,
+            BFCharCmdName::LoopStart(r) => {
+                self.brackets.push(partner_of(r));
+                LOOP_START
+            }
+            BFCharCmdName::LoopTerminate(r) => {
+                self.brackets.push(partner_of(r));
+                LOOP_TERMINATE
+            }
+            BFCharCmdName::Extension(ch) => {
+                self.extensions.push(ch);
+                EXTENSION
+            }
+        };
+        self.ops.push(op);
+        if self.files.last().map(|(_, file)| *file) != Some(info.file) {
+            self.files.push((index, info.file));
+        }
+    }
+
+    /// Record the matching bracket of every loop instruction, unmatched
+    /// ones are left as None
+    pub(crate) fn match_brackets(&mut self) {
+        // brackets still open with their index
+        let mut open = Vec::new();
+        let mut bracket = 0;
+        for (index, op) in self.ops.iter().enumerate() {
+            match *op {
+                LOOP_START => open.push((bracket, index)),
+                LOOP_TERMINATE => match open.pop() {
+                    Some((start, start_index)) => {
+                        self.brackets[start] = partner_of(Some(index));
+                        self.brackets[bracket] = partner_of(Some(start_index));
+                    }
+                    None => self.brackets[bracket] = UNMATCHED,
+                },
+                _ => continue,
+            }
+            bracket += 1;
+        }
+        for (start, _) in open {
+            self.brackets[start] = UNMATCHED;
+        }
+    }
+
+    /// command stored as `op`, the `bracket`th bracket or `extension`th
+    /// extension command if it's one
+    fn raw(&self, op: u8, bracket: usize, extension: usize) -> BFCharCmdName {
+        let partner = || match self.brackets[bracket] {
+            UNMATCHED => None,
+            partner => Some(partner as usize),
+        };
+        match op {
+            POINTER_INCREMENT => BFCharCmdName::PointerIncrement,
+            POINTER_DECREMENT => BFCharCmdName::PointerDecrement,
+            DATA_INCREMENT => BFCharCmdName::DataIncrement,
+            DATA_DECREMENT => BFCharCmdName::DataDecrement,
+            DATA_OUTPUT => BFCharCmdName::DataOutput,
+            DATA_INPUT => BFCharCmdName::DataInput,
+            LOOP_START => BFCharCmdName::LoopStart(partner()),
+            LOOP_TERMINATE => BFCharCmdName::LoopTerminate(partner()),
+            _ => BFCharCmdName::Extension(self.extensions[extension]),
+        }
+    }
+
+    /// file of the instruction at `index`
+    fn file(&self, index: usize) -> u32 {
+        let run = self.files.partition_point(|(first, _)| *first <= index);
+        self.files[run - 1].1
+    }
+}
+
+/// Iterator of `BFInstructions`, see `iter`
+#[derive(Debug, Clone)]
+pub struct BFInstructionsIter<'a> {
+    instructions: &'a BFInstructions,
+    index: usize,
+
+    /// offset of the next delta
+    offset: usize,
+    position: (usize, usize),
+
+    /// next entries of the side tables
+    bracket: usize,
+    extension: usize,
+    file: usize,
+}
+
+impl Iterator for BFInstructionsIter<'_> {
+    type Item = BFCharInfo;
+
+    fn next(&mut self) -> Option<BFCharInfo> {
+        let instructions = self.instructions;
+        let op = *instructions.ops.get(self.index)?;
+        let (line, column) = self.position;
+        self.position = decode(&instructions.deltas, &mut self.offset, line, column);
+        let raw = instructions.raw(op, self.bracket, self.extension);
+        match op {
+            LOOP_START | LOOP_TERMINATE => self.bracket += 1,
+            EXTENSION => self.extension += 1,
+            _ => (),
+        }
+        if instructions
+            .files
+            .get(self.file + 1)
+            .map(|(first, _)| *first)
+            == Some(self.index)
+        {
+            self.file += 1;
+        }
+        let info = BFCharInfo {
+            raw,
+            line: self.position.0,
+            column: self.position.1,
+            file: instructions.files[self.file].1,
+        };
+        self.index += 1;
+        Some(info)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.instructions.len() - self.index;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for BFInstructionsIter<'_> {}
+
+impl<'a> IntoIterator for &'a BFInstructions {
+    type Item = BFCharInfo;
+    type IntoIter = BFInstructionsIter<'a>;
+
+    fn into_iter(self) -> BFInstructionsIter<'a> {
+        self.iter()
+    }
+}
+
+impl Extend<BFCharInfo> for BFInstructions {
+    fn extend<I: IntoIterator<Item = BFCharInfo>>(&mut self, iter: I) {
+        for info in iter {
+            self.push(info);
+        }
+    }
+}
+
+impl FromIterator<BFCharInfo> for BFInstructions {
+    fn from_iter<I: IntoIterator<Item = BFCharInfo>>(iter: I) -> Self {
+        let mut instructions = Self::new();
+        instructions.extend(iter);
+        instructions
+    }
+}
+
+/// stored partner of a bracket matching the bracket at `index`
+fn partner_of(index: Option<usize>) -> u32 {
+    match index {
+        Some(index) => u32::try_from(index)
+            .ok()
+            .filter(|index| *index != UNMATCHED)
+            .expect("bft: program too large to store"),
+        None => UNMATCHED,
+    }
+}
+
+/// append the varint `value` to `out`
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// varint at `offset` of `bytes`, moving `offset` past it
+fn read_varint(bytes: &[u8], offset: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*offset];
+        *offset += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+/// append the delta from `from` to `to` to `out`, the column moved right
+/// on the same line, or the line moved by a zigzag count and the column
+fn encode(out: &mut Vec<u8>, from: (usize, usize), to: (usize, usize)) {
+    if to.0 == from.0 && to.1 > from.1 {
+        push_varint(out, ((to.1 - from.1) as u64) << 1);
+    } else {
+        let delta = to.0 as i64 - from.0 as i64;
+        let zigzag = ((delta << 1) ^ (delta >> 63)) as u64;
+        push_varint(out, (zigzag << 1) | 1);
+        push_varint(out, to.1 as u64);
+    }
+}
+
+/// position after the delta at `offset` of `deltas` from `line:column`,
+/// moving `offset` past it
+fn decode(deltas: &[u8], offset: &mut usize, line: usize, column: usize) -> (usize, usize) {
+    let tag = read_varint(deltas, offset);
+    if tag & 1 == 0 {
+        return (line, column + (tag >> 1) as usize);
+    }
+    let zigzag = tag >> 1;
+    let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+    let column = read_varint(deltas, offset) as usize;
+    ((line as i64 + delta) as usize, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BFProgram;
+    use alloc::string::String;
+
+    #[test]
+    fn test_instructions() {
+        let mut source = String::new();
+        for line in 0..200 {
+            source.push_str(&"+[->+<]".repeat(line % 7));
+            source.push_str("  comment ?\n");
+        }
+        let bf_info = BFProgram::with_extensions("", &source, &['?']);
+        let instructions = bf_info.instructions();
+        let decoded = instructions.to_vec();
+        assert_eq!(decoded.len(), instructions.len());
+        for (index, info) in decoded.iter().enumerate() {
+            assert_eq!(instructions.at(index), *info);
+        }
+        assert_eq!(
+            decoded.iter().copied().collect::<BFInstructions>(),
+            *instructions
+        );
+        assert_eq!(decoded[2].get_raw(), BFCharCmdName::LoopStart(Some(7)));
+        assert_eq!(
+            decoded.last().unwrap().get_raw(),
+            BFCharCmdName::Extension('?')
+        );
+
+        // lines going back, eg. from one file to the next, round trip too
+        let parts = [("a.bf", "\n\n+?+"), ("b.bf", "-"), ("a.bf", "\n>")];
+        let bf_info = BFProgram::compose("a.bf", &parts, &['?']);
+        let positions: Vec<_> = bf_info
+            .instructions()
+            .iter()
+            .map(|info| (info.line(), info.column(), info.file()))
+            .collect();
+        assert_eq!(
+            positions,
+            [(3, 1, 0), (3, 2, 0), (3, 3, 0), (1, 1, 1), (2, 1, 0)]
+        );
+        assert_eq!(bf_info.instructions().at(3).file(), 1);
+
+        // a few bytes per instruction of a large program
+        let large = BFProgram::new("", &"++[>+<-]\n".repeat(100_000));
+        let per_instruction = large.instructions().heap_size() / large.instructions().len();
+        assert!(per_instruction <= 4, "{} bytes", per_instruction);
+    }
+}
@@ -0,0 +1,209 @@
+//! Spellings of the brainfuck commands
+//!
+//! A dialect spells each of the eight commands with a token of its own, eg.
+//! `Ook. Ook?` for `>` in Ook!. Reading a program written in a dialect
+//! takes its tokens from left to right, a space of a token matching any
+//! run of whitespace, and skips whatever isn't a token the way brainfuck
+//! skips comments. Writing a program in a dialect writes the token of
+//! every command, comments are dropped as they could hold tokens.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The eight commands, in the order of the tokens of a dialect
+pub const COMMANDS: [char; 8] = ['+', '-', '<', '>', ',', '.', '[', ']'];
+
+/// Tokens of the commands of a dialect
+///
+/// # Examples:
+///
+/// ```
+/// use bft_types::dialect::BFDialect;
+///
+/// let ook = BFDialect::ook();
+/// let source = "Ook. Ook. Ook! Ook?\nOok! Ook! Ook? Ook!";
+/// assert_eq!(ook.decode(source), "+[-]");
+/// assert_eq!(ook.encode("+[-] comment"), "Ook. Ook. Ook! Ook? Ook! Ook! Ook? Ook!");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BFDialect {
+    /// tokens of the commands in the order of `COMMANDS`
+    tokens: [String; 8],
+
+    /// written between tokens
+    separator: String,
+}
+
+impl BFDialect {
+    /// Create a dialect spelling the commands `+-<>,.[]` with `tokens`, in
+    /// this order, and writing `separator` between them, error if a token
+    /// is blank or spells several commands
+    ///
+    /// The whitespace of a token is collapsed to a single space.
+    pub fn new(tokens: [&str; 8], separator: &str) -> Result<Self, String> {
+        let tokens = tokens.map(|token| token.split_whitespace().collect::<Vec<_>>().join(" "));
+        for (index, token) in tokens.iter().enumerate() {
+            if token.is_empty() {
+                return Err(format!("the token of `{}` is blank", COMMANDS[index]));
+            }
+            if let Some(other) = tokens[..index].iter().position(|other| other == token) {
+                return Err(format!(
+                    "`{}` and `{}` share the token `{}`",
+                    COMMANDS[other], COMMANDS[index], token
+                ));
+            }
+        }
+        Ok(Self {
+            tokens,
+            separator: separator.into(),
+        })
+    }
+
+    /// Brainfuck itself
+    pub fn brainfuck() -> Self {
+        Self {
+            tokens: COMMANDS.map(String::from),
+            separator: String::new(),
+        }
+    }
+
+    /// Ook!, every command a pair of `Ook.`, `Ook?` or `Ook!`
+    pub fn ook() -> Self {
+        let tokens = [
+            "Ook. Ook.",
+            "Ook! Ook!",
+            "Ook? Ook.",
+            "Ook. Ook?",
+            "Ook. Ook!",
+            "Ook! Ook.",
+            "Ook! Ook?",
+            "Ook? Ook!",
+        ];
+        Self {
+            tokens: tokens.map(String::from),
+            separator: " ".into(),
+        }
+    }
+
+    /// Token of `command`, None if it isn't one of the eight commands
+    pub fn token(&self, command: char) -> Option<&str> {
+        let index = COMMANDS.iter().position(|c| *c == command)?;
+        Some(&self.tokens[index])
+    }
+
+    /// Brainfuck commands of `source` written in the dialect, the longest
+    /// token matching first
+    pub fn decode(&self, source: &str) -> String {
+        let mut order: Vec<usize> = (0..COMMANDS.len()).collect();
+        order.sort_by_key(|index| core::cmp::Reverse(self.tokens[*index].len()));
+
+        let mut commands = String::new();
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            let matched = order
+                .iter()
+                .find_map(|index| Some((*index, match_token(rest, &self.tokens[*index])?)));
+            match matched {
+                Some((index, len)) => {
+                    commands.push(COMMANDS[index]);
+                    rest = &rest[len..];
+                }
+                None => rest = &rest[c.len_utf8()..],
+            }
+        }
+        commands
+    }
+
+    /// brainfuck `source` written in the dialect, on a single line
+    pub fn encode(&self, source: &str) -> String {
+        let tokens: Vec<&str> = source.chars().filter_map(|c| self.token(c)).collect();
+        tokens.join(&self.separator)
+    }
+
+    /// brainfuck `source` written in the dialect, in lines of at most
+    /// `width` characters unless a token is longer
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_types::dialect::BFDialect;
+    ///
+    /// let wrapped = BFDialect::ook().encode_wrapped("+++", 20);
+    /// assert_eq!(wrapped, "Ook. Ook. Ook. Ook.\nOok. Ook.\n");
+    /// ```
+    pub fn encode_wrapped(&self, source: &str, width: usize) -> String {
+        let mut out = String::new();
+        let mut line = String::new();
+        for token in source.chars().filter_map(|c| self.token(c)) {
+            let len = line.len() + self.separator.len() + token.len();
+            if !line.is_empty() && len > width {
+                out.push_str(line.trim_end());
+                out.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push_str(&self.separator);
+            }
+            line.push_str(token);
+        }
+        if !line.is_empty() {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// length of the start of `source` matching `token`, a space of the token
+/// matching any run of whitespace
+fn match_token(source: &str, token: &str) -> Option<usize> {
+    let mut len = 0;
+    for expected in token.chars() {
+        let rest = &source[len..];
+        if expected == ' ' {
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() {
+                return None;
+            }
+            len += rest.len() - trimmed.len();
+        } else if rest.starts_with(expected) {
+            len += expected.len_utf8();
+        } else {
+            return None;
+        }
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialects() {
+        let source = "++[->+<]>. copy, done";
+        let bf = BFDialect::brainfuck();
+        assert_eq!(bf.decode(source), "++[->+<]>.,");
+        assert_eq!(bf.encode(source), "++[->+<]>.,");
+
+        // the tokens of a dialect round trip any program
+        let ook = BFDialect::ook();
+        assert_eq!(ook.decode(&ook.encode(source)), bf.decode(source));
+        assert_eq!(
+            ook.decode(&ook.encode_wrapped(source, 30)),
+            bf.decode(source)
+        );
+        assert_eq!(ook.token('<'), Some("Ook? Ook."));
+        assert_eq!(ook.token('x'), None);
+
+        // the longest token wins, whitespace in tokens is collapsed
+        let blub = BFDialect::new(["a", "b", "c", "d", "e", "f", "g", "aa  b"], ",").unwrap();
+        assert_eq!(blub.decode("aa\n b a x aa b"), "]+]");
+        assert_eq!(blub.encode("+]"), "a,aa b");
+
+        assert!(BFDialect::new(["a", "b", "c", "d", "e", "f", " ", "h"], "").is_err());
+        let shared = BFDialect::new(["a", "b", "c", "d", "e", "f", "g", "a"], "");
+        assert_eq!(shared.unwrap_err(), "`+` and `]` share the token `a`");
+    }
+}
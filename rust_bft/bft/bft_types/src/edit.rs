@@ -0,0 +1,219 @@
+//! Incremental parsing of a program being edited
+//!
+//! `BFEditBuffer` keeps the source text next to its parsed program. An edit
+//! only lexes again the lines it touches, the instructions after them are
+//! shifted to their new lines, and the bracket indices are patched as long
+//! as the replaced and the inserted text are each balanced. Anything else,
+//! eg. typing a lone `[`, matches the brackets again over the instructions
+//! without lexing the rest of the source.
+
+use crate::{lex, BFCharCmdName, BFCharInfo, BFProgram, BFValidProgram, Path};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::ops::Range;
+
+/// Source text of a program and its parsed instructions, kept in sync
+///
+/// # Examples:
+///
+/// ```
+/// use bft_types::edit::BFEditBuffer;
+///
+/// let mut buffer = BFEditBuffer::new("", "+[-]\n>.", &[]);
+/// buffer.edit(1..1, "[>+<-]");
+/// assert_eq!(buffer.source(), "+[>+<-][-]\n>.");
+/// assert_eq!(buffer.program().instructions().at(11).line(), 2);
+/// assert_eq!(buffer.validate().unwrap().jump_target(7), 9);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BFEditBuffer {
+    source: String,
+    extensions: Vec<char>,
+    program: BFProgram,
+}
+
+impl BFEditBuffer {
+    /// Parse `source` keeping the characters of `extensions` as extension
+    /// commands, like `BFProgram::with_extensions`
+    pub fn new(path: impl AsRef<Path>, source: &str, extensions: &[char]) -> Self {
+        Self {
+            source: source.into(),
+            extensions: extensions.to_vec(),
+            program: BFProgram::with_extensions(path, source, extensions),
+        }
+    }
+
+    /// Source text of the program
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Program parsed out of the current source
+    pub fn program(&self) -> &BFProgram {
+        &self.program
+    }
+
+    /// Validate a copy of the current program
+    pub fn validate(&self) -> Result<BFValidProgram, Box<dyn Error>> {
+        self.program.clone().validate()
+    }
+
+    /// Replace the bytes of the source in `range` with `text`, returning the
+    /// indices of the instructions parsed again
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bounds of `range` aren't on char boundaries of the
+    /// source, like `String::replace_range`
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Range<usize> {
+        let Range { start, end } = range;
+        let newlines = |text: &str| text.bytes().filter(|&b| b == b'\n').count();
+
+        // whole lines from the one holding `start` to the one holding `end`
+        let region_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let tail = self.source[end..]
+            .find('\n')
+            .map_or(self.source.len() - end, |i| i + 1);
+        let first_line = newlines(&self.source[..region_start]) + 1;
+        let old_lines = newlines(&self.source[region_start..end]);
+        self.source.replace_range(start..end, text);
+        let new_end = start + text.len();
+        let new_lines = newlines(&self.source[region_start..new_end]);
+
+        // patched uncompressed, the instructions are stored again after
+        let mut instructions = self.program.instructions.to_vec();
+        let lo = instructions.partition_point(|s| s.line < first_line);
+        let hi = instructions.partition_point(|s| s.line <= first_line + old_lines);
+        let mut inserted = Vec::new();
+        let region = &self.source[region_start..new_end + tail];
+        lex(region, first_line, 0, &self.extensions, &mut inserted);
+        let patchable = balanced(&instructions[lo..hi]) && balanced(&inserted);
+        let new_hi = lo + inserted.len();
+        instructions.splice(lo..hi, inserted);
+
+        for info in &mut instructions[new_hi..] {
+            info.line = info.line + new_lines - old_lines;
+        }
+        if patchable {
+            // brackets outside pair as before, only those after the
+            // region moved
+            let shift = |r: usize| if r >= hi { r + new_hi - hi } else { r };
+            let (before, after) = instructions.split_at_mut(new_hi);
+            for info in before[..lo].iter_mut().chain(after) {
+                info.raw = match info.raw {
+                    BFCharCmdName::LoopStart(r) => BFCharCmdName::LoopStart(r.map(shift)),
+                    BFCharCmdName::LoopTerminate(r) => BFCharCmdName::LoopTerminate(r.map(shift)),
+                    raw => raw,
+                };
+            }
+            match_brackets(&mut instructions[lo..new_hi]);
+            for info in &mut instructions[lo..new_hi] {
+                info.raw = match info.raw {
+                    BFCharCmdName::LoopStart(r) => BFCharCmdName::LoopStart(r.map(|r| r + lo)),
+                    BFCharCmdName::LoopTerminate(r) => {
+                        BFCharCmdName::LoopTerminate(r.map(|r| r + lo))
+                    }
+                    raw => raw,
+                };
+            }
+        } else {
+            match_brackets(&mut instructions);
+        }
+        self.program.instructions = instructions.into_iter().collect();
+        self.program.instructions.shrink_to_fit();
+        lo..new_hi
+    }
+}
+
+/// Record the matching bracket of every loop instruction, unmatched ones
+/// are left as None
+fn match_brackets(instructions: &mut [BFCharInfo]) {
+    let mut open_square_bracket_vec = Vec::<usize>::new();
+    for index in 0..instructions.len() {
+        match instructions[index].raw {
+            BFCharCmdName::LoopStart(_) => {
+                instructions[index].raw = BFCharCmdName::LoopStart(None);
+                open_square_bracket_vec.push(index);
+            }
+            BFCharCmdName::LoopTerminate(_) => {
+                instructions[index].raw = BFCharCmdName::LoopTerminate(None);
+                if let Some(r) = open_square_bracket_vec.pop() {
+                    instructions[index].raw = BFCharCmdName::LoopTerminate(Some(r));
+                    instructions[r].raw = BFCharCmdName::LoopStart(Some(index));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Whether the brackets of `instructions` all pair with each other, so
+/// they pair the same way whatever surrounds them
+fn balanced(instructions: &[BFCharInfo]) -> bool {
+    let mut depth = 0usize;
+    for info in instructions {
+        match info.raw {
+            BFCharCmdName::LoopStart(_) => depth += 1,
+            BFCharCmdName::LoopTerminate(_) => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => (),
+        }
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// apply `edits` one by one checking the buffer against a full parse
+    fn check(source: &str, edits: &[(Range<usize>, &str)]) {
+        let mut buffer = BFEditBuffer::new("", source, &['#']);
+        for (range, text) in edits {
+            buffer.edit(range.clone(), text);
+            let full = BFProgram::with_extensions("", buffer.source(), &['#']);
+            assert_eq!(buffer.program().instructions(), full.instructions());
+        }
+    }
+
+    #[test]
+    fn test_edit_buffer() {
+        // balanced edits inside, before and after loops
+        check(
+            "+[>+\n<-]\n\n.#[,]",
+            &[
+                (2..3, "[-]"),
+                (0..0, "[]\n"),
+                (9..10, ""),
+                (13..13, "x\n\n>"),
+            ],
+        );
+        // unbalanced edits re-matching the brackets
+        check(
+            "++[>+<-]\r\n.[",
+            &[(2..3, ""), (0..0, "["), (11..12, "]]\n+"), (0..9, "")],
+        );
+        // joining and splitting lines
+        check(
+            "+\n+\n[\n-\n]\n",
+            &[(1..2, ""), (3..3, "\n\n"), (0..10, "[.")],
+        );
+    }
+
+    #[test]
+    fn test_edit_range() {
+        let mut buffer = BFEditBuffer::new("", "+++\n---\n...", &[]);
+        assert_eq!(buffer.edit(5..6, ">"), 3..6);
+        assert_eq!(
+            buffer.program().instructions().at(4).get_raw(),
+            BFCharCmdName::PointerIncrement
+        );
+        assert!(buffer.validate().is_ok());
+        buffer.edit(0..0, "]");
+        assert!(buffer.validate().is_err());
+    }
+}
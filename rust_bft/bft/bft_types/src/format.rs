@@ -0,0 +1,181 @@
+//! Canonical layout of brainfuck source
+//!
+//! Commands are laid out in lines of at most `WIDTH` characters. A loop
+//! holding other loops or comments, or too long to stay on a line, opens
+//! and closes on lines of its own with its body indented by two spaces,
+//! shorter loops stay inline, eg. `[-]`. Comments are kept on lines of
+//! their own with their whitespace collapsed. Formatting never changes the
+//! commands of a program and formatting its output again gives it back.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Maximum number of commands on a line, besides the indentation
+pub const WIDTH: usize = 64;
+
+/// Longest loop kept inline, brackets included
+const INLINE_LOOP: usize = 32;
+
+/// Part of a program
+enum BFItem {
+    /// commands without brackets
+    Run(String),
+
+    /// loop body, and whether the loop is closed
+    Loop(Vec<BFItem>, bool),
+
+    /// comment with its whitespace collapsed
+    Comment(String),
+}
+
+/// items of `chars` up to the bracket closing the current loop or the end,
+/// with whether the closing bracket was found, an unmatched `]` is kept as
+/// a command
+fn parse(chars: &mut core::str::Chars, nested: bool) -> (Vec<BFItem>, bool) {
+    let mut items = Vec::new();
+    let mut comment = String::new();
+    while let Some(c) = chars.next() {
+        if !"+-<>,.[]".contains(c) {
+            comment.push(c);
+            continue;
+        }
+        push_comment(&mut items, &mut comment);
+        match c {
+            '[' => {
+                let (body, closed) = parse(chars, true);
+                items.push(BFItem::Loop(body, closed));
+            }
+            ']' if nested => return (items, true),
+            _ => match items.last_mut() {
+                Some(BFItem::Run(run)) => run.push(c),
+                _ => items.push(BFItem::Run(c.into())),
+            },
+        }
+    }
+    push_comment(&mut items, &mut comment);
+    (items, false)
+}
+
+/// push the collapsed `comment` if it isn't blank, emptying it
+fn push_comment(items: &mut Vec<BFItem>, comment: &mut String) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    if !words.is_empty() {
+        items.push(BFItem::Comment(words.join(" ")));
+    }
+    comment.clear();
+}
+
+/// `body` on a single line if it can stay inline
+fn inline(body: &[BFItem]) -> Option<String> {
+    let mut line = String::from("[");
+    for item in body {
+        match item {
+            BFItem::Run(run) => line.push_str(run),
+            BFItem::Loop(..) | BFItem::Comment(_) => return None,
+        }
+    }
+    line.push(']');
+    (line.len() <= INLINE_LOOP).then_some(line)
+}
+
+/// write `commands` in lines of `WIDTH` at `depth`
+fn write_commands(out: &mut String, commands: &str, depth: usize) {
+    for chunk in commands.as_bytes().chunks(WIDTH) {
+        write_line(out, core::str::from_utf8(chunk).unwrap_or_default(), depth);
+    }
+}
+
+/// write `line` indented for `depth`
+fn write_line(out: &mut String, line: &str, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// write `items` and the loops they hold at `depth`
+fn write_items(out: &mut String, items: &[BFItem], depth: usize) {
+    // commands and inline loops waiting for a line
+    let mut commands = String::new();
+    for item in items {
+        match item {
+            BFItem::Run(run) => commands.push_str(run),
+            BFItem::Loop(body, closed) => match inline(body).filter(|_| *closed) {
+                Some(line) => commands.push_str(&line),
+                None => {
+                    write_commands(out, &commands, depth);
+                    commands.clear();
+                    write_line(out, "[", depth);
+                    write_items(out, body, depth + 1);
+                    if *closed {
+                        write_line(out, "]", depth);
+                    }
+                }
+            },
+            BFItem::Comment(comment) => {
+                write_commands(out, &commands, depth);
+                commands.clear();
+                write_line(out, comment, depth);
+            }
+        }
+    }
+    write_commands(out, &commands, depth);
+}
+
+/// `source` in the canonical layout
+///
+/// # Examples:
+///
+/// ```
+/// use bft_types::format::format_source;
+///
+/// let source = "copy   cell zero\n++[->+<]>[-]  [>[-]+<-]";
+/// assert_eq!(
+///     format_source(source),
+///     "copy cell zero\n++[->+<]>[-]\n[\n  >[-]+<-\n]\n"
+/// );
+/// ```
+pub fn format_source(source: &str) -> String {
+    let (items, _) = parse(&mut source.chars(), false);
+    let mut out = String::new();
+    write_items(&mut out, &items, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(source: &str) -> String {
+        source.chars().filter(|c| "+-<>,.[]".contains(*c)).collect()
+    }
+
+    #[test]
+    fn test_format_source() {
+        assert_eq!(format_source(""), "");
+        assert_eq!(format_source("  \n "), "");
+        assert_eq!(format_source("+[-]"), "+[-]\n");
+        // an unclosed loop opens on its own line and is left open
+        assert_eq!(format_source("+[>[-]"), "+\n[\n  >[-]\n");
+
+        let long: String = core::iter::repeat_n('+', WIDTH + 3).collect();
+        let formatted = format_source(&long);
+        assert_eq!(
+            formatted.lines().map(str::len).collect::<Vec<_>>(),
+            [WIDTH, 3]
+        );
+
+        let sources = [
+            "hello, world! ++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.",
+            "a [ b [ c ] d ] e ]+[",
+            &long,
+            "[[[[[[]]]]]]  comment with\ttabs\n\n and lines [-]",
+        ];
+        for source in sources {
+            let formatted = format_source(source);
+            assert_eq!(commands(&formatted), commands(source));
+            assert_eq!(format_source(&formatted), formatted, "{:?}", source);
+        }
+    }
+}
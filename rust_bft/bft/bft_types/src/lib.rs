@@ -2,18 +2,50 @@
 //!
 //! Parse and store brainfuck programs into data structures,
 //! and provide interface to be consumed by brainfuck interpreter
+//!
+//! Without the default `std` feature the crate is `no_std` and only needs
+//! `alloc`, the filename is then a plain string and can't be read from disk.
+//! The `url` feature loads programs from `http://` and `https://` URLs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
-use std::default::Default;
-use std::error::Error;
-use std::fmt;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::default::Default;
+use core::error::Error;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 
+/// Name of the file a program is loaded from
+#[cfg(not(feature = "std"))]
+type Path = str;
+#[cfg(not(feature = "std"))]
+type PathBuf = alloc::string::String;
+
+pub mod compact;
+
+pub mod dialect;
+
+pub mod edit;
+
+pub mod format;
+
+#[cfg(feature = "url")]
+pub mod url;
+
+pub use compact::BFInstructions;
+
 /// Brainfuck raw command definitions
 ///
 /// There are eight raw commands in brainfuck, each consist of
 /// a single character, define them into human readable names.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BFCharCmdName {
     /// '>' character, increment the data pointer
     /// to next cell of brainfuck virtual machine to the right
@@ -44,14 +76,20 @@ pub enum BFCharCmdName {
     /// ']' character, loop terminated, must match exactly with LoopStart
     /// with an Option parameter to recorded its matched LoopStart index
     LoopTerminate(Option<usize>),
+
+    /// extra command character, only parsed when asked for with
+    /// `BFProgram::with_extensions`, executed by a handler of the host
+    Extension(char),
 }
 
 /// Each brainfuck instruction is recorded with line and column information
-#[derive(Debug, Copy, Clone)]
+/// and the file it came from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct BFCharInfo {
     raw: BFCharCmdName,
     line: usize,
     column: usize,
+    file: u32,
 }
 
 impl fmt::Display for BFCharInfo {
@@ -66,6 +104,13 @@ impl fmt::Display for BFCharInfo {
             BFCharCmdName::DataInput => "Type into current data",
             BFCharCmdName::LoopStart(_) => "Start looping",
             BFCharCmdName::LoopTerminate(_) => "End looping",
+            BFCharCmdName::Extension(ch) => {
+                return write!(
+                    f,
+                    "{:>5}:{:<5}> Run extension '{}'",
+                    self.line, self.column, ch
+                )
+            }
         };
         write!(f, "{:>5}:{:<5}> {}", self.line, self.column, fmt_info)
     }
@@ -76,12 +121,76 @@ impl BFCharInfo {
     pub fn get_raw(&self) -> BFCharCmdName {
         self.raw
     }
+
+    /// return line number in the source, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// return column number in the source, starting from 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// return id of the file of the instruction, 0 for the file of the
+    /// program, see `BFProgram::file_name`
+    pub fn file(&self) -> usize {
+        self.file as usize
+    }
+}
+
+/// Append the instructions of `bf_str` read from the file `file` to `out`,
+/// its first line being line `first_line`, brackets are left unmatched
+fn lex(
+    bf_str: &str,
+    first_line: usize,
+    file: u32,
+    extensions: &[char],
+    out: &mut impl Extend<BFCharInfo>,
+) {
+    // Transfer brainfuck raw character to human readable names
+    // only reserve meaningful brainfuck characters
+    let raw_instruction = |input_ch: char| -> Option<BFCharCmdName> {
+        match input_ch {
+            '>' => Some(BFCharCmdName::PointerIncrement),
+            '<' => Some(BFCharCmdName::PointerDecrement),
+            '+' => Some(BFCharCmdName::DataIncrement),
+            '-' => Some(BFCharCmdName::DataDecrement),
+            '.' => Some(BFCharCmdName::DataOutput),
+            ',' => Some(BFCharCmdName::DataInput),
+            '[' => Some(BFCharCmdName::LoopStart(None)),
+            ']' => Some(BFCharCmdName::LoopTerminate(None)),
+            ch if extensions.contains(&ch) => Some(BFCharCmdName::Extension(ch)),
+            _ => None,
+        }
+    };
+
+    for (line_idx, line) in bf_str.lines().enumerate() {
+        for (col_idx, ch) in line.chars().enumerate() {
+            if let Some(r) = raw_instruction(ch) {
+                out.extend(Some(BFCharInfo {
+                    raw: r,
+                    line: line_idx + first_line,
+                    column: col_idx + 1,
+                    file,
+                }));
+            }
+        }
+    }
+}
+
+/// `path` in a printable form, with or without the `std` feature
+fn display_path(path: &Path) -> impl fmt::Display + '_ {
+    #[cfg(feature = "std")]
+    return path.display();
+    #[cfg(not(feature = "std"))]
+    return path;
 }
 
 /// Record whole brainfuck program information
 ///
-/// Store all brainfuck raw character information in a vector
-/// with filename where the information is loaded from.
+/// Store all brainfuck raw character information in a compact
+/// `BFInstructions` with filename where the information is loaded from.
 /// # Examples:
 ///
 /// ```
@@ -92,108 +201,153 @@ impl BFCharInfo {
 /// let result = bf_info.match_square_bracket();
 ///
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BFProgram {
     filename: PathBuf,
-    instructions: Vec<BFCharInfo>,
+
+    /// other files instructions came from, file `n` being `files[n - 1]`
+    files: Vec<PathBuf>,
+    instructions: BFInstructions,
 }
 
 impl BFProgram {
     /// Constructor for BFProgram
     pub fn new(path: impl AsRef<Path>, bf_str: &str) -> Self {
-        /// Transfer brainfuck raw character to human readable names
-        /// only reserve meaningful brainfuck characters
-        fn raw_instruction(input_ch: char) -> Option<BFCharCmdName> {
-            match input_ch {
-                '>' => Some(BFCharCmdName::PointerIncrement),
-                '<' => Some(BFCharCmdName::PointerDecrement),
-                '+' => Some(BFCharCmdName::DataIncrement),
-                '-' => Some(BFCharCmdName::DataDecrement),
-                '.' => Some(BFCharCmdName::DataOutput),
-                ',' => Some(BFCharCmdName::DataInput),
-                '[' => Some(BFCharCmdName::LoopStart(None)),
-                ']' => Some(BFCharCmdName::LoopTerminate(None)),
-                _ => None,
-            }
-        }
-
-        let mut bf_char_info = Vec::<BFCharInfo>::new();
-        let mut line_number = 1;
-
-        let mut index = 0;
-        let mut open_square_bracket_vec = Vec::<usize>::new();
-
-        for line in bf_str.lines() {
-            let mut col_number = 1;
-            for ch in line.chars() {
-                if let Some(r) = raw_instruction(ch) {
-                    bf_char_info.push(BFCharInfo {
-                        raw: r,
-                        line: line_number,
-                        column: col_number,
-                    });
-
-                    if ch == '[' {
-                        open_square_bracket_vec.push(index);
-                    } else if ch == ']' {
-                        if let Some(r) = open_square_bracket_vec.pop() {
-                            bf_char_info[index].raw = BFCharCmdName::LoopTerminate(Some(r));
-                            bf_char_info[r].raw = BFCharCmdName::LoopStart(Some(index));
-                        }
-                    }
+        Self::with_extensions(path, bf_str, &[])
+    }
 
-                    index += 1;
-                }
-                col_number += 1;
-            }
-            line_number += 1;
-        }
+    /// Constructor for BFProgram keeping the characters of `extensions`
+    /// as extension commands, the eight core commands can't be overridden
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_types::{BFCharCmdName, BFProgram};
+    ///
+    /// let bf_info = BFProgram::with_extensions("", "+%$", &['%']);
+    /// assert_eq!(bf_info.instructions().len(), 2);
+    /// assert_eq!(bf_info.instructions().at(1).get_raw(), BFCharCmdName::Extension('%'));
+    /// ```
+    pub fn with_extensions(path: impl AsRef<Path>, bf_str: &str, extensions: &[char]) -> Self {
+        let mut bf_char_info = BFInstructions::new();
+        lex(bf_str, 1, 0, extensions, &mut bf_char_info);
+        bf_char_info.match_brackets();
+        bf_char_info.shrink_to_fit();
 
         BFProgram {
-            filename: path.as_ref().to_path_buf(),
+            filename: path.as_ref().into(),
+            files: Vec::new(),
             instructions: bf_char_info,
         }
     }
 
+    /// Constructor for BFProgram named `path` made of the sources of
+    /// `parts` one after another, every instruction keeping the file and
+    /// line of its part
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use bft_types::BFProgram;
+    /// use std::path::Path;
+    ///
+    /// let parts = [("main.bf", "+[\n"), ("lib.bf", "-]"), ("main.bf", ".")];
+    /// let bf_info = BFProgram::compose("main.bf", &parts, &[]);
+    /// let close = bf_info.instructions().at(3);
+    /// assert_eq!((close.line(), close.column()), (1, 2));
+    /// assert_eq!(bf_info.file_name(close.file()), Path::new("lib.bf"));
+    /// assert_eq!(bf_info.instructions().at(4).file(), 0);
+    /// ```
+    pub fn compose<P: AsRef<Path>>(
+        path: impl AsRef<Path>,
+        parts: &[(P, &str)],
+        extensions: &[char],
+    ) -> Self {
+        let mut bf_info = BFProgram {
+            filename: path.as_ref().into(),
+            files: Vec::new(),
+            instructions: BFInstructions::new(),
+        };
+        for (name, source) in parts {
+            let name = name.as_ref();
+            let file = if name == &*bf_info.filename {
+                0
+            } else {
+                match bf_info.files.iter().position(|file| name == &**file) {
+                    Some(index) => index + 1,
+                    None => {
+                        bf_info.files.push(name.into());
+                        bf_info.files.len()
+                    }
+                }
+            };
+            lex(
+                source,
+                1,
+                file as u32,
+                extensions,
+                &mut bf_info.instructions,
+            );
+        }
+        bf_info.instructions.match_brackets();
+        bf_info.instructions.shrink_to_fit();
+        bf_info
+    }
+
     /// Constructor for BFProgram from file, may encounter IO error
+    #[cfg(feature = "std")]
     pub fn from_file(filename: impl AsRef<Path>) -> std::io::Result<BFProgram> {
         let path = filename.as_ref();
         Ok(BFProgram::new(path, &fs::read_to_string(path)?))
     }
 
     /// A reference of brainfuck instructions
-    pub fn instructions(&self) -> &[BFCharInfo] {
+    pub fn instructions(&self) -> &BFInstructions {
         &self.instructions
     }
 
+    /// Filename where the program is loaded from
+    pub fn filename(&self) -> &Path {
+        &self.filename
+    }
+
+    /// Filename in a printable form, with or without the `std` feature
+    pub fn display_filename(&self) -> impl fmt::Display + '_ {
+        display_path(&self.filename)
+    }
+
+    /// Name of the file of id `file`, see `BFCharInfo::file`
+    pub fn file_name(&self, file: usize) -> &Path {
+        match file {
+            0 => &self.filename,
+            _ => &self.files[file - 1],
+        }
+    }
+
     /// Brainfuck program must be balanced of open and close square-bracket
     /// Check to ensure it's a valid brainfuck program
     pub fn match_square_bracket(&mut self) -> Result<(), Box<dyn Error>> {
         for s in self.instructions() {
             match s.raw {
-                BFCharCmdName::LoopStart(r) => {
-                    if r.is_none() {
-                        return Err(format!(
-                            "bft: Error in input file {}, no open bracket \
-                            found matching at line {} column {}",
-                            self.filename.display(),
-                            s.line,
-                            s.column
-                        )
-                        .into());
-                    }
+                BFCharCmdName::LoopStart(None) => {
+                    return Err(format!(
+                        "bft: Error in input file {}, no open bracket \
+                        found matching at line {} column {}",
+                        display_path(self.file_name(s.file())),
+                        s.line,
+                        s.column
+                    )
+                    .into());
                 }
-                BFCharCmdName::LoopTerminate(r) => {
-                    if r.is_none() {
-                        return Err(format!(
-                            "bft: Error in input file {}, no close bracket \
-                            found matching at line {} column {}",
-                            self.filename.display(),
-                            s.line,
-                            s.column
-                        )
-                        .into());
-                    }
+                BFCharCmdName::LoopTerminate(None) => {
+                    return Err(format!(
+                        "bft: Error in input file {}, no close bracket \
+                        found matching at line {} column {}",
+                        display_path(self.file_name(s.file())),
+                        s.line,
+                        s.column
+                    )
+                    .into());
                 }
                 _ => (),
             }
@@ -203,11 +357,75 @@ impl BFProgram {
     }
 
     /// Print out BFProgram data
+    #[cfg(feature = "std")]
     pub fn print_info(&mut self) {
         for cur_cmd in self.instructions() {
-            println!("{}: {}", self.filename.display(), cur_cmd);
+            println!("{}: {}", self.display_filename(), cur_cmd);
         }
     }
+
+    /// Validate square brackets and build the jump table for execution
+    pub fn validate(mut self) -> Result<BFValidProgram, Box<dyn Error>> {
+        self.match_square_bracket()?;
+
+        let jump_table = self
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s.raw {
+                BFCharCmdName::LoopStart(Some(r)) | BFCharCmdName::LoopTerminate(Some(r)) => r,
+                _ => i,
+            })
+            .collect();
+
+        Ok(BFValidProgram {
+            program: self,
+            jump_table,
+        })
+    }
+}
+
+/// Brainfuck program with balanced square brackets
+///
+/// Only constructed by `BFProgram::validate`, every loop instruction has
+/// its matching bracket index recorded in a dense jump table, so executing
+/// the program never needs to handle an unmatched bracket.
+/// # Examples:
+///
+/// ```
+/// use bft_types::BFProgram;
+///
+/// let bf_valid = BFProgram::new("", "+[->+<]").validate().unwrap();
+/// assert_eq!(bf_valid.jump_target(1), 6);
+/// assert_eq!(bf_valid.jump_target(6), 1);
+/// ```
+#[derive(Debug)]
+pub struct BFValidProgram {
+    program: BFProgram,
+    jump_table: Vec<usize>,
+}
+
+impl BFValidProgram {
+    /// A reference of the validated brainfuck program
+    pub fn program(&self) -> &BFProgram {
+        &self.program
+    }
+
+    /// A reference of brainfuck instructions
+    pub fn instructions(&self) -> &BFInstructions {
+        self.program.instructions()
+    }
+
+    /// Dense jump table, the matching bracket index for loop instructions
+    /// and the instruction's own index for anything else
+    pub fn jump_table(&self) -> &[usize] {
+        &self.jump_table
+    }
+
+    /// Matching bracket index of the loop instruction at `index`
+    pub fn jump_target(&self, index: usize) -> usize {
+        self.jump_table[index]
+    }
 }
 
 #[cfg(test)]
@@ -225,21 +443,25 @@ mod tests {
                 raw: BFCharCmdName::DataIncrement,
                 line: 1,
                 column: 8,
+                file: 0,
             },
             BFCharInfo {
                 raw: BFCharCmdName::DataIncrement,
                 line: 1,
                 column: 9,
+                file: 0,
             },
             BFCharInfo {
                 raw: BFCharCmdName::DataDecrement,
                 line: 1,
                 column: 18,
+                file: 0,
             },
             BFCharInfo {
                 raw: BFCharCmdName::DataDecrement,
                 line: 1,
                 column: 19,
+                file: 0,
             },
         ];
 
@@ -256,4 +478,34 @@ mod tests {
         let mut bf_info = BFProgram::new("", "test001++  hello --[>,<+>--,[]");
         assert!(bf_info.match_square_bracket().is_err());
     }
+
+    #[test]
+    fn test_compose() {
+        let parts = [("a.bf", "+\n["), ("b.bf", "\n\n>-"), ("a.bf", "]")];
+        let bf_info = BFProgram::compose("a.bf", &parts, &[]);
+        let files: Vec<_> = bf_info
+            .instructions()
+            .iter()
+            .map(|info| info.file())
+            .collect();
+        assert_eq!(files, [0, 0, 1, 1, 0]);
+        assert_eq!(bf_info.instructions().at(2).line(), 3);
+        assert_eq!(bf_info.file_name(1), Path::new("b.bf"));
+        assert!(bf_info.validate().is_ok());
+
+        // an unmatched bracket is reported in its own file
+        let parts = [("a.bf", "+"), ("b.bf", "\n]")];
+        let mut bf_info = BFProgram::compose("a.bf", &parts, &[]);
+        let e = bf_info.match_square_bracket().unwrap_err().to_string();
+        assert!(e.contains("input file b.bf, no close bracket found matching at line 2"));
+    }
+
+    #[test]
+    fn test_validate_jump_table() {
+        assert!(BFProgram::new("", "[[]").validate().is_err());
+        assert!(BFProgram::new("", "[]]").validate().is_err());
+
+        let bf_valid = BFProgram::new("", "+[>[-]<\n-]").validate().unwrap();
+        assert_eq!(bf_valid.jump_table(), &[0, 8, 2, 5, 4, 3, 6, 7, 1]);
+    }
 }
@@ -0,0 +1,524 @@
+//! Programs loaded over the network
+//!
+//! `http://` and `https://` are spoken, with a small HTTP/1.1 client on top
+//! of `std::net` and rustls checking servers against the Mozilla root
+//! certificates of `webpki-roots`, other schemes are refused with
+//! `BFFetchError::UnsupportedScheme`. Redirects are followed a few times,
+//! never from `https` back to `http`, and the body is capped,
+//! `MAX_SOURCE_LEN` by default, so a misbehaving server can't exhaust memory.
+
+use crate::BFProgram;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Largest program fetched by `BFProgram::from_url`, in bytes
+pub const MAX_SOURCE_LEN: usize = 16 << 20;
+
+/// redirects followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// timeout of connecting and of every read or write
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Failure fetching a program
+#[derive(Debug)]
+pub enum BFFetchError {
+    /// the URL isn't `scheme://host[:port][/path]`
+    InvalidUrl(String),
+
+    /// the scheme of the URL isn't `http` or `https`
+    UnsupportedScheme(String),
+
+    /// an `https` URL redirected to this `http` one
+    InsecureRedirect(String),
+
+    /// connecting to the server or talking to it failed
+    Io(io::Error),
+
+    /// the server answered with another status than 200 OK
+    Status(u16),
+
+    /// the server answered something that isn't HTTP
+    Protocol(&'static str),
+
+    /// the program is larger than the limit, in bytes
+    TooLarge(usize),
+
+    /// the server kept redirecting
+    TooManyRedirects,
+
+    /// the program isn't UTF-8 text
+    NotUtf8,
+}
+
+impl fmt::Display for BFFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "bft: invalid URL {}", url),
+            Self::UnsupportedScheme(scheme) => {
+                write!(
+                    f,
+                    "bft: unsupported URL scheme {}, only http and https are",
+                    scheme
+                )
+            }
+            Self::InsecureRedirect(url) => {
+                write!(f, "bft: refusing the redirect from https to {}", url)
+            }
+            Self::Io(err) => write!(f, "bft: fetching the program failed, {}", err),
+            Self::Status(status) => write!(f, "bft: server answered with status {}", status),
+            Self::Protocol(what) => write!(f, "bft: malformed HTTP response, {}", what),
+            Self::TooLarge(limit) => write!(f, "bft: program larger than {} bytes", limit),
+            Self::TooManyRedirects => write!(f, "bft: more than {} redirects", MAX_REDIRECTS),
+            Self::NotUtf8 => write!(f, "bft: program isn't UTF-8 text"),
+        }
+    }
+}
+
+impl Error for BFFetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BFFetchError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Whether `name` is a URL rather than a path
+pub fn is_url(name: &str) -> bool {
+    name.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphabetic())
+    })
+}
+
+/// Parts of an `http://` or `https://` URL
+#[derive(Debug, PartialEq, Eq)]
+struct BFUrl<'a> {
+    tls: bool,
+
+    /// host name or address, without the brackets of an IPv6 address
+    host: &'a str,
+    port: u16,
+
+    /// host and port as written in the URL, for the `Host` header
+    authority: &'a str,
+    path: &'a str,
+}
+
+/// Split `url` into its parts, the port defaulting to the one of its scheme
+fn split_url(url: &str) -> Result<BFUrl<'_>, BFFetchError> {
+    let invalid = || BFFetchError::InvalidUrl(url.into());
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+    let (tls, default_port) = if scheme.eq_ignore_ascii_case("http") {
+        (false, 80)
+    } else if scheme.eq_ignore_ascii_case("https") {
+        (true, 443)
+    } else {
+        return Err(BFFetchError::UnsupportedScheme(scheme.into()));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    // an IPv6 address holds colons of its own, it's bracketed before the port
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, after) = bracketed.split_once(']').ok_or_else(invalid)?;
+            match after {
+                "" => (host, None),
+                _ => (host, Some(after.strip_prefix(':').ok_or_else(invalid)?)),
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, _)) if host.contains(':') => return Err(invalid()),
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid())?,
+        None => default_port,
+    };
+    if host.is_empty() || host.contains('@') {
+        return Err(invalid());
+    }
+    Ok(BFUrl {
+        tls,
+        host,
+        port,
+        authority,
+        path,
+    })
+}
+
+/// Read the body of a chunked response, up to `limit` bytes
+fn read_chunked(reader: &mut impl BufRead, limit: usize) -> Result<Vec<u8>, BFFetchError> {
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let size = line.trim_end().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size.trim(), 16)
+            .map_err(|_| BFFetchError::Protocol("bad chunk size"))?;
+        if size == 0 {
+            return Ok(body);
+        }
+        if body.len() + size > limit {
+            return Err(BFFetchError::TooLarge(limit));
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = [0; 2];
+        reader.read_exact(&mut crlf)?;
+    }
+}
+
+/// Outcome of one request
+enum BFResponse {
+    Body(Vec<u8>),
+    Redirect(String),
+}
+
+/// Client side of a TLS connection to `host` over `stream`, checked against
+/// the root certificates of `webpki-roots`
+fn tls_stream(
+    host: &str,
+    stream: TcpStream,
+) -> Result<rustls::StreamOwned<rustls::ClientConnection, TcpStream>, BFFetchError> {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|_| BFFetchError::InvalidUrl(host.into()))?;
+    let connection =
+        rustls::ClientConnection::new(Arc::new(config), name).map_err(io::Error::other)?;
+    Ok(rustls::StreamOwned::new(connection, stream))
+}
+
+/// Connect to the first address of the host of `url` answering within
+/// `TIMEOUT`, the error of the last one if none does
+fn connect(url: &BFUrl) -> io::Result<TcpStream> {
+    let mut last = None;
+    for addr in (url.host, url.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last = Some(e),
+        }
+    }
+    Err(last.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no address")))
+}
+
+/// GET the path of `url`, reading a body of at most `limit` bytes
+fn get(url: &BFUrl, limit: usize) -> Result<BFResponse, BFFetchError> {
+    let stream = connect(url)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    if url.tls {
+        request(tls_stream(url.host, stream)?, url, limit)
+    } else {
+        request(stream, url, limit)
+    }
+}
+
+/// Send the request of `url` over `stream` and read the response
+fn request(
+    mut stream: impl Read + Write,
+    url: &BFUrl,
+    limit: usize,
+) -> Result<BFResponse, BFFetchError> {
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: bft\r\nAccept-Encoding: identity\r\nConnection: close\r\n\r\n",
+        url.path, url.authority
+    );
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or(BFFetchError::Protocol("bad status line"))?;
+
+    let (mut length, mut chunked, mut location) = (None, false, None);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(BFFetchError::Protocol("headers cut short"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(BFFetchError::Protocol("bad header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            length = Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|_| BFFetchError::Protocol("bad content length"))?,
+            );
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("location") {
+            location = Some(value.to_string());
+        }
+    }
+
+    match status {
+        200 => (),
+        301 | 302 | 303 | 307 | 308 => {
+            return location
+                .map(BFResponse::Redirect)
+                .ok_or(BFFetchError::Protocol("redirect without location"));
+        }
+        status => return Err(BFFetchError::Status(status)),
+    }
+
+    let body = if chunked {
+        read_chunked(&mut reader, limit)?
+    } else {
+        match length {
+            Some(length) if length > limit => return Err(BFFetchError::TooLarge(limit)),
+            Some(length) => {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body)?;
+                body
+            }
+            None => {
+                let mut body = Vec::new();
+                reader.take(limit as u64 + 1).read_to_end(&mut body)?;
+                if body.len() > limit {
+                    return Err(BFFetchError::TooLarge(limit));
+                }
+                body
+            }
+        }
+    };
+    Ok(BFResponse::Body(body))
+}
+
+/// URL a redirect of `url` to `location` points to, a relative location
+/// resolved against the URL
+fn resolve(url: &BFUrl, location: &str) -> String {
+    let scheme = if url.tls { "https" } else { "http" };
+    if is_url(location) {
+        location.to_string()
+    } else if location.starts_with("//") {
+        format!("{}:{}", scheme, location)
+    } else if location.starts_with('/') {
+        format!("{}://{}{}", scheme, url.authority, location)
+    } else {
+        let path = url.path.split(['?', '#']).next().unwrap_or_default();
+        let dir = path.rfind('/').map_or("/", |i| &path[..=i]);
+        format!("{}://{}{}{}", scheme, url.authority, dir, location)
+    }
+}
+
+/// Fetch the source of the program at `url`, at most `limit` bytes
+///
+/// # Examples:
+///
+/// ```
+/// use bft_types::url::{fetch_source, BFFetchError};
+///
+/// let result = fetch_source("ftp://example.com/hello.b", 1024);
+/// assert!(matches!(result, Err(BFFetchError::UnsupportedScheme(_))));
+/// ```
+pub fn fetch_source(url: &str, limit: usize) -> Result<String, BFFetchError> {
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let parts = split_url(&url)?;
+        let location = match get(&parts, limit)? {
+            BFResponse::Body(body) => {
+                return String::from_utf8(body).map_err(|_| BFFetchError::NotUtf8)
+            }
+            BFResponse::Redirect(location) => resolve(&parts, &location),
+        };
+        if parts.tls && !split_url(&location)?.tls {
+            return Err(BFFetchError::InsecureRedirect(location));
+        }
+        url = location;
+    }
+    Err(BFFetchError::TooManyRedirects)
+}
+
+impl BFProgram {
+    /// Constructor for BFProgram from an `http://` or `https://` URL, the
+    /// source being at most `MAX_SOURCE_LEN` bytes
+    pub fn from_url(url: &str) -> Result<BFProgram, BFFetchError> {
+        Ok(BFProgram::new(url, &fetch_source(url, MAX_SOURCE_LEN)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// serve every response of `responses` to one connection each
+    fn serve(responses: Vec<&'static str>) -> u16 {
+        serve_on(TcpListener::bind("127.0.0.1:0").unwrap(), responses)
+    }
+
+    /// serve every response of `responses` to one connection each of
+    /// `listener`
+    fn serve_on(listener: TcpListener, responses: Vec<&'static str>) -> u16 {
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0];
+                    stream.read_exact(&mut byte).unwrap();
+                    request.push(byte[0]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_fetch_source() {
+        let port = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /programs/\r\n\r\n",
+            "HTTP/1.1 302 Found\r\nLocation: hello.b\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n+[-\r\n2\r\n].\r\n0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n+++++",
+            "HTTP/1.0 404 Not Found\r\n\r\n",
+        ]);
+        let url = format!("http://127.0.0.1:{}/", port);
+        let program = BFProgram::from_url(&url).unwrap();
+        assert_eq!(program.instructions().len(), 5);
+        assert!(matches!(
+            fetch_source(&url, 4),
+            Err(BFFetchError::TooLarge(4))
+        ));
+        assert!(matches!(
+            fetch_source(&url, 4),
+            Err(BFFetchError::Status(404))
+        ));
+
+        assert!(is_url("http://example.com/a.b"));
+        assert!(!is_url("programs/a.b"));
+        assert!(matches!(
+            fetch_source("http://:80/a.b", 4),
+            Err(BFFetchError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_url() {
+        let url = split_url("http://[::1]:8080/a.b").unwrap();
+        assert_eq!(
+            url,
+            BFUrl {
+                tls: false,
+                host: "::1",
+                port: 8080,
+                authority: "[::1]:8080",
+                path: "/a.b",
+            }
+        );
+        let url = split_url("https://[2001:db8::1]/a.b").unwrap();
+        assert_eq!((url.tls, url.host, url.port), (true, "2001:db8::1", 443));
+        let url = split_url("HTTPS://example.com").unwrap();
+        assert_eq!((url.host, url.port, url.path), ("example.com", 443, "/"));
+        let url = split_url("http://example.com:8000/a.b").unwrap();
+        assert_eq!((url.host, url.port), ("example.com", 8000));
+
+        for url in [
+            "http://::1/a.b",
+            "http://[::1/a.b",
+            "http://[::1]8080/a.b",
+            "http://[::1]:port/a.b",
+            "http://[]/a.b",
+        ] {
+            assert!(
+                matches!(split_url(url), Err(BFFetchError::InvalidUrl(_))),
+                "{}",
+                url
+            );
+        }
+        assert!(matches!(
+            split_url("ftp://example.com/a.b"),
+            Err(BFFetchError::UnsupportedScheme(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let url = split_url("https://example.com:8443/dir/a.b?x=/y").unwrap();
+        assert_eq!(
+            resolve(&url, "http://other.org/b.b"),
+            "http://other.org/b.b"
+        );
+        assert_eq!(resolve(&url, "//other.org/b.b"), "https://other.org/b.b");
+        assert_eq!(resolve(&url, "/b.b"), "https://example.com:8443/b.b");
+        assert_eq!(resolve(&url, "b.b"), "https://example.com:8443/dir/b.b");
+        let url = split_url("http://example.com").unwrap();
+        assert_eq!(resolve(&url, "b.b"), "http://example.com/b.b");
+    }
+
+    #[test]
+    fn test_fetch_source_ipv6() {
+        // hosts without IPv6 can't run this one
+        let Ok(listener) = TcpListener::bind("[::1]:0") else {
+            return;
+        };
+        let port = serve_on(
+            listener,
+            vec!["HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n+.+"],
+        );
+        let source = fetch_source(&format!("http://[::1]:{}/a.b", port), 16).unwrap();
+        assert_eq!(source, "+.+");
+    }
+
+    #[test]
+    fn test_fetch_source_https() {
+        // a plain HTTP server, the TLS handshake fails on its answer
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tls_port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut hello = [0; 5];
+            stream.read_exact(&mut hello).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+            hello[0]
+        });
+        let redirect = format!(
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: https://127.0.0.1:{}/a.b\r\n\r\n",
+            tls_port
+        );
+        let port = serve(vec![Box::leak(redirect.into_boxed_str())]);
+
+        let result = fetch_source(&format!("http://127.0.0.1:{}/a.b", port), 16);
+        assert!(matches!(result, Err(BFFetchError::Io(_))), "{:?}", result);
+        // a TLS handshake record, the client hello
+        assert_eq!(server.join().unwrap(), 0x16);
+    }
+}
@@ -0,0 +1,461 @@
+//! Programs packed into archives
+//!
+//! `run-all` takes zip and tar archives, eg. a test corpus distributed as a
+//! single file, next to directories and glob patterns. Every `.b` or `.bf`
+//! entry is a program, with the entries of the same stem ending in `.in`
+//! and `.out` as its input and expected output like in a directory.
+//!
+//! Zip entries are stored or deflated, tar archives are ustar, GNU or pax,
+//! plain or gzip compressed. Entries and decompressed archives are capped so
+//! a crafted archive can't exhaust memory.
+
+use bft_interp::batch::BFBatchJob;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Largest entry read out of an archive, in bytes
+const MAX_ENTRY_LEN: usize = 64 << 20;
+
+/// Largest tar archive decompressed out of gzip, in bytes
+const MAX_TAR_LEN: usize = 256 << 20;
+
+/// Whether `path` names an archive `read_archive` understands
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Jobs of every program of the archive at `path`, sorted by name
+pub fn archive_jobs(path: &Path) -> Result<Vec<BFBatchJob>, Box<dyn Error>> {
+    let entries = read_archive(path)?;
+    let mut jobs = Vec::new();
+    for (name, data) in &entries {
+        let Some(stem) = name.strip_suffix(".bf").or_else(|| name.strip_suffix(".b")) else {
+            continue;
+        };
+        jobs.push(BFBatchJob {
+            name: format!("{}:{}", path.display(), name),
+            source: String::from_utf8(data.clone())
+                .map_err(|_| format!("{}:{} isn't UTF-8 text", path.display(), name))?,
+            input: entries
+                .get(&format!("{}.in", stem))
+                .cloned()
+                .unwrap_or_default(),
+            expected: entries.get(&format!("{}.out", stem)).cloned(),
+        });
+    }
+    if jobs.is_empty() {
+        return Err(format!("no program in {}", path.display()).into());
+    }
+    Ok(jobs)
+}
+
+/// Files of the archive at `path` by name
+fn read_archive(path: &Path) -> Result<BTreeMap<String, Vec<u8>>, Box<dyn Error>> {
+    let data = fs::read(path)?;
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        read_zip(&data)
+    } else if data.starts_with(&[0x1f, 0x8b]) {
+        read_tar(&gunzip(&data)?)
+    } else {
+        read_tar(&data)
+    }
+}
+
+/// little endian integer of `len` bytes at `at`
+fn le(data: &[u8], at: usize, len: usize) -> Result<usize, Box<dyn Error>> {
+    let bytes = data.get(at..at + len).ok_or("archive cut short")?;
+    Ok(bytes.iter().rev().fold(0, |n, &b| n << 8 | usize::from(b)))
+}
+
+/// Files of a zip archive, read through its central directory
+fn read_zip(data: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, Box<dyn Error>> {
+    // the end of central directory record is followed by a comment of at
+    // most 64KiB
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .take(u16::MAX as usize + 22)
+        .find(|&at| data[at..].starts_with(b"PK\x05\x06"))
+        .ok_or("not a zip archive")?;
+    let count = le(data, end + 10, 2)?;
+    let mut at = le(data, end + 16, 4)?;
+    if count == 0xffff || at == 0xffff_ffff {
+        return Err("zip64 archives aren't supported".into());
+    }
+
+    let mut files = BTreeMap::new();
+    for _ in 0..count {
+        if !data
+            .get(at..)
+            .is_some_and(|rest| rest.starts_with(b"PK\x01\x02"))
+        {
+            return Err("bad zip central directory".into());
+        }
+        let method = le(data, at + 10, 2)?;
+        let crc = le(data, at + 16, 4)? as u32;
+        let size = le(data, at + 20, 4)?;
+        let len = le(data, at + 24, 4)?;
+        let name_len = le(data, at + 28, 2)?;
+        let skip = name_len + le(data, at + 30, 2)? + le(data, at + 32, 2)?;
+        let offset = le(data, at + 42, 4)?;
+        let name = String::from_utf8_lossy(
+            data.get(at + 46..at + 46 + name_len)
+                .ok_or("archive cut short")?,
+        )
+        .into_owned();
+        at += 46 + skip;
+        if name.ends_with('/') {
+            continue;
+        }
+        if len > MAX_ENTRY_LEN {
+            return Err(format!("{} is larger than {} bytes", name, MAX_ENTRY_LEN).into());
+        }
+
+        if !data
+            .get(offset..)
+            .is_some_and(|rest| rest.starts_with(b"PK\x03\x04"))
+        {
+            return Err(format!("bad zip header of {}", name).into());
+        }
+        let start = offset + 30 + le(data, offset + 26, 2)? + le(data, offset + 28, 2)?;
+        let stored = data.get(start..start + size).ok_or("archive cut short")?;
+        let file = match method {
+            0 => stored.to_vec(),
+            8 => inflate(stored, len)?,
+            method => {
+                return Err(format!("{} uses unsupported compression {}", name, method).into())
+            }
+        };
+        if file.len() != len || crc32(&file) != crc {
+            return Err(format!("{} is corrupted", name).into());
+        }
+        files.insert(name, file);
+    }
+    Ok(files)
+}
+
+/// Files of a tar archive
+fn read_tar(data: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, Box<dyn Error>> {
+    let field = |header: &[u8], at: usize, len: usize| -> String {
+        let bytes = &header[at..at + len];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    };
+
+    let mut files = BTreeMap::new();
+    let mut long_name = None;
+    let mut at = 0;
+    while let Some(header) = data.get(at..at + 512) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = usize::from_str_radix(field(header, 124, 12).trim(), 8)
+            .map_err(|_| "bad tar header")?;
+        let body = data
+            .get(at + 512..at + 512 + size)
+            .ok_or("archive cut short")?;
+        at += 512 + size.div_ceil(512) * 512;
+
+        let mut name = field(header, 0, 100);
+        if &header[257..262] == b"ustar" {
+            let prefix = field(header, 345, 155);
+            if !prefix.is_empty() {
+                name = format!("{}/{}", prefix, name);
+            }
+        }
+        match header[156] {
+            // names too long for the header, of the next entry
+            b'L' => long_name = Some(field(body, 0, body.len())),
+            b'x' => {
+                let records = String::from_utf8_lossy(body);
+                long_name = records
+                    .lines()
+                    .find_map(|record| record.split_once(" path="))
+                    .map(|(_, path)| path.to_string());
+            }
+            b'0' | 0 => {
+                if size > MAX_ENTRY_LEN {
+                    return Err(format!("{} is larger than {} bytes", name, MAX_ENTRY_LEN).into());
+                }
+                let name = long_name.take().unwrap_or(name);
+                files.insert(name.trim_start_matches("./").to_string(), body.to_vec());
+            }
+            _ => long_name = None,
+        }
+    }
+    Ok(files)
+}
+
+/// Data of a gzip stream
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let flags = *data.get(3).ok_or("archive cut short")?;
+    let mut at = 10;
+    if flags & 0x04 != 0 {
+        at += 2 + le(data, at, 2)?;
+    }
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let len = data
+                .get(at..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0));
+            at += len.ok_or("archive cut short")? + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        at += 2;
+    }
+    let inflated = inflate(data.get(at..).ok_or("archive cut short")?, MAX_TAR_LEN)?;
+    if crc32(&inflated) != le(data, data.len().saturating_sub(8), 4)? as u32 {
+        return Err("gzip stream is corrupted".into());
+    }
+    Ok(inflated)
+}
+
+/// CRC-32 of zip and gzip
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Bits of a deflate stream, least significant first
+struct BftBits<'a> {
+    data: &'a [u8],
+    at: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl BftBits<'_> {
+    fn bits(&mut self, n: u32) -> Result<usize, Box<dyn Error>> {
+        while self.count < n {
+            let byte = *self.data.get(self.at).ok_or("deflate stream cut short")?;
+            self.buffer |= u64::from(byte) << self.count;
+            self.at += 1;
+            self.count += 8;
+        }
+        let bits = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(bits as usize)
+    }
+}
+
+/// Canonical Huffman code of deflate
+struct BftHuffman {
+    counts: [usize; 16],
+    symbols: Vec<usize>,
+}
+
+impl BftHuffman {
+    fn new(lengths: &[usize]) -> Self {
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[len] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<usize> = (0..lengths.len()).filter(|&s| lengths[s] != 0).collect();
+        symbols.sort_by_key(|&s| lengths[s]);
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut BftBits) -> Result<usize, Box<dyn Error>> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for len in 1..16 {
+            code |= bits.bits(1)?;
+            let count = self.counts[len];
+            if code < first + count {
+                return Ok(self.symbols[index + code - first]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("bad deflate code".into())
+    }
+}
+
+/// base and extra bits of length codes 257 to 285
+const LENGTHS: [(usize, u32); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// base and extra bits of distance codes
+const DISTANCES: [(usize, u32); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+/// Data of a raw deflate stream, at most `limit` bytes
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bits = BftBits {
+        data,
+        at: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.bits(1)? == 1;
+        let (literals, distances) = match bits.bits(2)? {
+            0 => {
+                // stored blocks start on a byte boundary
+                bits.buffer = 0;
+                bits.count = 0;
+                let len = bits.bits(16)?;
+                if bits.bits(16)? != !len & 0xffff {
+                    return Err("bad stored deflate block".into());
+                }
+                let block = data
+                    .get(bits.at..bits.at + len)
+                    .ok_or("deflate stream cut short")?;
+                out.extend_from_slice(block);
+                bits.at += len;
+                if out.len() > limit {
+                    return Err(format!("entry larger than {} bytes", limit).into());
+                }
+                if last {
+                    return Ok(out);
+                }
+                continue;
+            }
+            1 => {
+                let mut lengths = [8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                (BftHuffman::new(&lengths), BftHuffman::new(&[5; 30]))
+            }
+            2 => {
+                let literals = bits.bits(5)? + 257;
+                let distances = bits.bits(5)? + 1;
+                let codes = bits.bits(4)? + 4;
+                let mut code_lengths = [0; 19];
+                for &symbol in &[
+                    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+                ][..codes]
+                {
+                    code_lengths[symbol] = bits.bits(3)?;
+                }
+                let code = BftHuffman::new(&code_lengths);
+                let mut lengths = Vec::with_capacity(literals + distances);
+                while lengths.len() < literals + distances {
+                    let (len, repeat) = match code.decode(&mut bits)? {
+                        len @ 0..=15 => (len, 1),
+                        16 => (
+                            *lengths.last().ok_or("bad deflate lengths")?,
+                            3 + bits.bits(2)?,
+                        ),
+                        17 => (0, 3 + bits.bits(3)?),
+                        _ => (0, 11 + bits.bits(7)?),
+                    };
+                    lengths.extend(std::iter::repeat_n(len, repeat));
+                }
+                if lengths.len() > literals + distances {
+                    return Err("bad deflate lengths".into());
+                }
+                (
+                    BftHuffman::new(&lengths[..literals]),
+                    BftHuffman::new(&lengths[literals..]),
+                )
+            }
+            _ => return Err("bad deflate block type".into()),
+        };
+
+        loop {
+            match literals.decode(&mut bits)? {
+                byte @ 0..=255 => out.push(byte as u8),
+                256 => break,
+                symbol => {
+                    let &(base, extra) = LENGTHS.get(symbol - 257).ok_or("bad deflate length")?;
+                    let len = base + bits.bits(extra)?;
+                    let symbol = distances.decode(&mut bits)?;
+                    let &(base, extra) = DISTANCES.get(symbol).ok_or("bad deflate distance")?;
+                    let distance = base + bits.bits(extra)?;
+                    let start = out
+                        .len()
+                        .checked_sub(distance)
+                        .ok_or("bad deflate distance")?;
+                    for i in 0..len {
+                        out.push(out[start + i]);
+                    }
+                }
+            }
+            if out.len() > limit {
+                return Err(format!("entry larger than {} bytes", limit).into());
+            }
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Benchmarking the interpreter
+//!
+//! A workload is run several times under every chosen backend and
+//! optimization level. Each configuration reports its mean wall-clock time
+//! and its rate in source instructions per second, ie. the instructions a
+//! plain `O0` run of the workload executes, so configurations packing the
+//! program differently still compare on the same work. The output of every
+//! run is checked, against the expected output of the built-in workloads or
+//! against the first configuration for a given program.
+
+use crate::cli::bft_cli_mod::BftBenchArgs;
+use bft_interp::{run, BFBackend, BFOptLevel, BFRunOptions};
+use bft_types::{BFProgram, BFValidProgram};
+use std::error::Error;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Built-in workloads: name, program and expected output
+const SUITE: [(&str, &str, &str); 3] = [
+    (
+        "hello",
+        include_str!("bench/hello.bf"),
+        include_str!("bench/hello.out"),
+    ),
+    (
+        "mandelbrot",
+        include_str!("bench/mandelbrot.bf"),
+        include_str!("bench/mandelbrot.out"),
+    ),
+    (
+        "hanoi",
+        include_str!("bench/hanoi.bf"),
+        include_str!("bench/hanoi.out"),
+    ),
+];
+
+/// A program to benchmark
+struct BftWorkload {
+    name: String,
+    program: BFValidProgram,
+    input: Vec<u8>,
+    expected: Option<Vec<u8>>,
+}
+
+/// Timing of a workload under one configuration
+struct BftMeasure {
+    backend: BFBackend,
+    level: BFOptLevel,
+    mean: Duration,
+}
+
+/// options of a benchmark run, without anything the run doesn't need
+fn options(backend: BFBackend, level: BFOptLevel) -> BFRunOptions {
+    BFRunOptions {
+        backend,
+        opt_level: level,
+        final_newline: false,
+        prompt: false,
+        ..BFRunOptions::default()
+    }
+}
+
+/// mean time of `rounds` runs of `workload`, checking every output
+fn measure(
+    workload: &mut BftWorkload,
+    backend: BFBackend,
+    level: BFOptLevel,
+    rounds: u32,
+) -> Result<Duration, Box<dyn Error>> {
+    let options = options(backend, level);
+    let mut total = Duration::ZERO;
+    for _ in 0..rounds {
+        let start = Instant::now();
+        let output = run(&workload.program, &workload.input, &options)?;
+        total += start.elapsed();
+        match &workload.expected {
+            Some(expected) if expected.as_slice() != output.output() => {
+                return Err(format!(
+                    "{} {:?} {} wrote an unexpected output",
+                    workload.name, backend, level
+                )
+                .into());
+            }
+            Some(_) => {}
+            None => workload.expected = Some(output.into_output()),
+        }
+    }
+    Ok(total / rounds)
+}
+
+/// benchmark `workload` under every configuration and print the results
+fn bench_workload(
+    workload: &mut BftWorkload,
+    backends: &[BFBackend],
+    levels: &[BFOptLevel],
+    rounds: u32,
+) -> Result<(), Box<dyn Error>> {
+    let instructions = run(
+        &workload.program,
+        &workload.input,
+        &options(BFBackend::Loop, BFOptLevel::O0),
+    )?
+    .stats()
+    .steps();
+
+    let mut measures = Vec::new();
+    for &backend in backends {
+        for &level in levels {
+            let mean = measure(workload, backend, level, rounds)?;
+            measures.push(BftMeasure {
+                backend,
+                level,
+                mean,
+            });
+        }
+    }
+
+    let best = measures
+        .iter()
+        .map(|m| m.mean)
+        .min()
+        .unwrap_or_default()
+        .max(Duration::from_nanos(1));
+    println!("{} ({} instructions)", workload.name, instructions);
+    for m in &measures {
+        let rate = instructions as f64 / m.mean.as_secs_f64().max(1e-9) / 1e6;
+        println!(
+            "  {:<10}{:<4}{:>12.3?}{:>12.1} Minstr/s{:>8.2}x",
+            format!("{:?}", m.backend),
+            m.level,
+            m.mean,
+            rate,
+            m.mean.as_secs_f64() / best.as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// run the benchmark chosen by the `bench` arguments
+pub fn bench(args: &BftBenchArgs) -> Result<(), Box<dyn Error>> {
+    let mut workloads = Vec::new();
+    match args.name() {
+        Some(path) => {
+            let input = match args.input() {
+                Some(input) => fs::read(input)?,
+                None => Vec::new(),
+            };
+            workloads.push(BftWorkload {
+                name: path.display().to_string(),
+                program: BFProgram::new(path, &crate::load_source(path)?).validate()?,
+                input,
+                expected: None,
+            });
+        }
+        None => {
+            for (name, source, expected) in SUITE {
+                if !args.workloads().is_empty() && !args.workloads().contains(&name) {
+                    continue;
+                }
+                workloads.push(BftWorkload {
+                    name: name.to_string(),
+                    program: BFProgram::new(name, source).validate()?,
+                    input: Vec::new(),
+                    expected: Some(expected.as_bytes().to_vec()),
+                });
+            }
+        }
+    }
+
+    for workload in &mut workloads {
+        bench_workload(
+            workload,
+            &args.backends(),
+            &args.opt_levels(),
+            args.rounds(),
+        )?;
+    }
+    Ok(())
+}
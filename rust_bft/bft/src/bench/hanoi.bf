@@ -0,0 +1,180 @@
+Towers of Hanoi
+
+Prints the moves of seven disks from peg A to peg C; the source and target
+pegs of move m are found with the bitwise and and or of m and m minus one
+
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++>+<[>>[-]<[->+>+<<]>>[-<<+>>]<->[
+-]<<[->>+>>>+<<<<<]>>>>>[-<<<<<+>>>>>]<<[-]>[-]<<[->>+>>+<<<<]>>>>[-<<<<+>>>>][-
+]+<<[->>>+>>+<<<<<]>>>>>[-<<<<<+>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<
+[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<--<+>>>[-]+<<[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]
+<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<[->
++>+<<]>>[-<<+>>]<<<<[-]>[-<+>>>>+<<<]>>>[-<<<+>>>]<<<[-]>[-]<<[->>+>>>+<<<<<]>>>
+>>[-<<<<<+>>>>>][-]+<<<[->>>>+>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<++[<[->>+>+<<<]>>
+>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<--<+>>>>[-]+<<<[->>>>>
++>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-
+]>>>>[-]]<<<-]>[-]<<][-]<[-]<<[->>+>+<<<]>>>[-<<<+>>>]<<<<<[-]>[-<+>>>>>+<<<<]>>
+>>[-<<<<+>>>>]<<<<[-]>[-]<<[->>+>>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>][-]+<<<<[->>>>
+>+>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<
+[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<--<+>>>>>[-]+<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-
+<<<<<<<+>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[
+-]<<][-]<[-]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<<<<<<[-]>[-<+>>>>>>+<<<<<]>>>>>[-<<
+<<<+>>>>>]<<<<<[-]>[-]<<[->>+>>>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>][-]+<<<<<[->
+>>>>>+>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<
+->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<--<+>>>>>>[-]+<<<<<[->>>>>>>+>+<<<<<
+<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-
+]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]<<<<<<<[-]>[-<
++>>>>>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<<<<<[-]>[-]<<[->>+>>>>>>+<<<<<<<<]>>>>>>
+>>[-<<<<<<<<+>>>>>>>>][-]+<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>
+>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<
+<<<<<--<+>>>>>>>[-]+<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]
+<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<
+<[->>>>>+>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<<<<<<<[-]>[-<+>>>>>>>>+<<<<<<<]>>>>>>>
+[-<<<<<<<+>>>>>>>]<<<<<<<[-]>[-]<<[->>+>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>
+>>>>>>>][-]+<<<<<<<[->>>>>>>>+>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<+
++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<--
+<+>>>>>>>>[-]+<<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]
+<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<
+<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<[-]>[-<+>>>>>>>>>+<<<<<<<
+<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<<[-]>[-]<<[->>+>>>>>>>>+<<<<<<<<<<]>>>>>>>>
+>>[-<<<<<<<<<<+>>>>>>>>>>][-]+<<<<<<<<[->>>>>>>>>+>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<
+<<<<<<<<<+>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>
+>[-]]<<-]<[-]<[<<<<<<<<--<+>>>>>>>>>[-]+<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]>>>>>
+>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-
+]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>
+>>>]<<<<<<<<<<[-]>[-<+>>>>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<
+<<<<[-]>[-]>[-][-]<<<[->>>+>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>
+>>>>]<<<<<<<<<[-]<[-]>>[-<<+>>>>>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>
+][-]+<<<<<<<<<<<[->>>>>>>>>>>>+>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+
+>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<
+-]<[-]<[<<<<<<<<<<<-->+>>>>>>>>>>[-]+<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<
+]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[
+-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]
+>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<[-]<[->+>>>>>>>>>+<<<<<<<<<<]>>>>>
+>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<<<<[-]<[-]>>[-<<+>>>>>>>>>>>>+<<<<<<<<<<]>>>
+>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>][-]+<<<<<<<<<<<<[->>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<
+<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]
++<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<-->+>>>>>>>>>>>[-]+<<<<<
+<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>
+>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<]
+[-]<[-]<<<<<<<<<<<[->>>>>>>>>>>+>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>
+>>>>>>]<<<<<<<<<<[-]<[->+>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>
+>>>>]<<<<<<<<<<<[-]<[-]>>[-<<+>>>>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<
++>>>>>>>>>>>][-]+<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>
+>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]
+<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<<-->+>>>>>>>>>>>>[-]+<<<<<<<<<<<<<[-
+>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>
+>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<
+[-]<<<<<<<<<<<<[->>>>>>>>>>>>+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>
+>>>>>>>>]<<<<<<<<<<<[-]<[->+>>>>>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+
+>>>>>>>>>>>>]<<<<<<<<<<<<[-]<[-]>>[-<<+>>>>>>>>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[
+-<<<<<<<<<<<<+>>>>>>>>>>>>][-]+<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<
+<<<]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-
+<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<<<-->+>>>>>>>>>>
+>>>[-]+<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-<
+<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<
+<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>
+>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<<<<<<<<<<<[-]<[->+>>>>>>>>>>>>+<<<<
+<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<<<<<[-]<[-]>>[-<<+
+>>>>>>>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>][-]+<<<
+<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<
+<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<<<<-->+>>>>>>>>>>>>>>[-]+<<<<<<<<<<<<<<<[->>
+>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>
+>>>>>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-
+]<<][-]<[-]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<
+<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<<<<<<<<<<<<[-]<[->+>>>>>>>>>>>>>+<<<<<<<<<<<<<<]>
+>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<<<<<<<<<<<<<<[-]<[-]>>[-<<+>>>>>>>
+>>>>>>>>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>][-]+<<<<<
+<<<<<<<<<<<[->>>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<
+<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]
+]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<<<<<-->+>>>>>>>>>>>>>>>[-]+<<<<<<<<<<<<<
+<<<[->>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<
+<<<<<<+>>>>>>>>>>>>>>>>>>>]<<++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>
+[-]]<<<-]>[-]<<][-]<[-]<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<]>>>>>
+>>>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<[-]<[->+>>>>>>>>>>>
+>>>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<<<<<<<<<<
+<<<<[-]<[-]>>[-<<+>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<
+<<<<+>>>>>>>>>>>>>>>][-]+<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<
+<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>]<++[<[->>
++>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<<<<<<<<<<<
+<-->+>>>>>>>>>>>>>>>>[-]+<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<
+<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>]<<++[>[->
++>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<[-]<<<<<<<<<<<<<
+<<<[->>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>
+>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<[-]<[->+>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<]>>>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<[-]<[-]<[-][-]>[-]>[
+-]+>>>>>>>>>>>>>>>[-]+>[-]<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<]
+>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<[<<<<<<<<<[->>>>>>>>>>+>
++<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<+>
+>]<[<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>
+>+>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<[-
+<+>]<<<<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<[-<+>]<
+[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>[-]]<[-]<<<<<<<<<<<<<<
+<[->>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>
+>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>][-
+]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[-<<<<<<<<<<
+<<<<<<+>>>>>>>>>>>>>>>>]<[<<<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<
+<+>>>>>>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<<++>>>>>>>>>>>>
+>>>>>>>[-]]<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[
+-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<[-<+>]<<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>
+>>[-<<<<<<<<<+>>>>>>>>>]<[-<+>]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<++>>>>>>>
+>>>>>>>>>>>[-]]<[-]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>
+>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<<
+<<<<<<<+>>>>>>>>>>>>>>>>][-]<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>
+>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<[<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>
+>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<
+<++++>>>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>
+>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<[-<+>]<<<<<<<[->>>>>>>+>+<<<<<<<<
+]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[-<+>]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<++++
+>>>>>>>>>>>>>>>>>>[-]]<[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>>>>>>
+>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<
+<<<<<<<<+>>>>>>>>>>>>>>>>][-]<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>>>>
+>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<[<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<
+<<<<<<<+>>>>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<<++++++++>>
+>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>
+>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<[-<+>]<<<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<
+<+>>>>>>>]<[-<+>]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<++++++++>>>>>>>>>>>>>>>
+>>>[-]]<[-]<<<<<<<<<<<<[->>>>>>>>>>>>+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<
+<<+>>>>>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>
+>>>>][-]<<<<<<<<<<<[->>>>>>>>>>>>+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>
+>>>>>>>>>>>>]<[<<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<[-<<+>>]<-]<[->
++>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<<++++++++++++++++>>>>>>>>>>>>>>>>>>>[-]]<<<<
+<<<<<<<<[->>>>>>>>>>>>+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>
+>]<[-<+>]<<<<<[->>>>>+>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<[-<+>]<[->+>+<<]>>[-<<+>>]
+<[<<<<<<<<<<<<<<<<<<++++++++++++++++>>>>>>>>>>>>>>>>>>[-]]<[-]<<<<<<<<<<<[->>>>>
+>>>>>>+>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<[<[-]>[-]]<[->+>+
+<<]>>[-<<+>>]<[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>][-]<<<<<<<<<<[->>>>>>>>>>>+>+<
+<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<[<<<<[->>>>>+>+<<<<<<]>>>>>
+>[-<<<<<<+>>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<<++++++++++
+++++++++++++++++++++++>>>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<<<[->>>>>>>>>>>+>+<<<<<<<<
+<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<[-<+>]<<<<[->>>>+>+<<<<<]>>>>>[-<<
+<<<+>>>>>]<[-<+>]<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<+++++++++++++++++++++++
++++++++++>>>>>>>>>>>>>>>>>>[-]]<[-]<<<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>
+>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<<<<<<<<<+
+>>>>>>>>>>>>>>>>][-]<<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<
++>>>>>>>>>>>]<[<<<[->>>>+>+<<<<<]>>>>>[-<<<<<+>>>>>]<[-<<+>>]<-]<[->+>+<<]>>[-<<
++>>]<[<<<<<<<<<<<<<<<<<<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++>>>>>>>>>>>>>>>>>>>[-]]<<<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>
+[-<<<<<<<<<<<+>>>>>>>>>>>]<[-<+>]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[-<+>]<[->+>+<
+<]>>[-<<+>>]<[<<<<<<<<<<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++>>>>>>>>>>>>>>>>>>[-]]<[-]<<<<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>
+>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<[<[-]>[-]]<[->+>+<<]>>[-<<+>>]<[-<<<<<<<<<<<<<<<<
++>>>>>>>>>>>>>>>>]>>[-]<<[-]<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>+>>>+<<<<<<<<<
+<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>]
+[-]+<<<[->>>>+>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<+++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<
+<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<--->>+>[-]+<<<[->>>>>+>+<<<<<<]>>>>>>
+[-<<<<<<+>>>>>>]<<+++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>
+[-]<<][-]<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>[-]<[-]<<<<<<<<<<<<<<<<<<[->>>>
+>>>>>>>>>>>>>>+>>+<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<
+<+>>>>>>>>>>>>>>>>>>>>][-]+<<[->>>+>>+<<<<<]>>>>>[-<<<<<+>>>>>]<+++[<[->>+>+<<<]
+>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<--->+>[-]+<<[->>>>+>+
+<<<<<]>>>>>[-<<<<<+>>>>>]<<+++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[
+-]]<<<-]>[-]<<][-]<<<<<<<<<<<<<<<<<<<+++++++++++++++++++++++++++++++++++++++++++
++++++.>>>>>>>>>>>>>>>>>>>+++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++.[-]++++++++++++++++++++++++++++++++.[-]<<<++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++.>>>+++++++++++++++++++++++++++++++++++++++++++++
+.[-]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.>>++++++++++.[-]<<<<<<
+<<<<<<<<<<<<[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]>[-]<<<<<<<<<<<<<
+<<<[-]>[-]>[-]>>>>>>>>>>>>>>>[-]>[-]>[-]>[-]<<<<<<<<<<<<<<<<<<<<<<+<-]
@@ -0,0 +1,67 @@
+Mandelbrot set drawn in ASCII
+
+Byte cells hold signed fixed point numbers with three fractional bits and
+the grid spans x from minus two to five eighths and y from minus one to one
+in steps of one eighth; a point escaping after at least three iterations is
+a dot and a point staying bounded for fifteen iterations is a hash
+
++++++++++++++++++>>--------<<[>[-]++++++++++++++++++++++>>[-]----------------<<[
+>>>[-]>[-]>[-]>[-]+[>[-]<<<<[->>>>+>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>
+>]<<<<[-]<<<<[->>>>+>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<[-]+<<<<<<[->>>
+>>>>>>+>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<<<[
+-]>>>>>>[-]]<<-]<[-]<<[-]+<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+
+>>>>>>>>>>]<<+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++[>[->+>+<<]>>[-<<+>
+>]+<[<->>[-]<[-]]>[<<<<<[-]>>>>>[-]]<<<-]>[-]<<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[
+<<<<<[->>>>>>+<<<<<<]>>>>>>[-<<<<<<->>>>>>]<[-]]<<[->>+>+<<<]>>>[-<<<+>>>]<[<<<<
+[->>>>>+<<<<<]>>>>>[-<<<<<->>>>>]<[-]]<[-]+<<<<[->>>>>+>>+<<<<<<<]>>>>>>>[-<<<<<
+<<+>>>>>>>]<++++++++++++++++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]
+>>>>[-]]<<-]<[-]>[-]+<<<<<[->>>>+>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<+++++++++
++++++++[<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]+<[<<<->>>>[-]<[-]]>[<<<[-]>>>[-]]<<-]<<[
+-]>[-<<+>>]<<[->>+>+<<<]>>>[-<<<+>>>]+<[<<<<<<<[-]>>>>>>>>[-]<[-]]>[>>[-]<<<<<<<
+<<[->>>>>>>>>>>+>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<[<<<<<<<
+<<<<[->>>>>>>>>>>>+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<[
+-<<<+>>>]<-]<<<<<<[-]>>>>>[-]<[->+>+<<]>>[-<<+>>][-]+<[->>+>>+<<<<]>>>>[-<<<<+>>
+>>]<++++++++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]
+<[<--------<<<<<+>>>>>>[-]+<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<<++++++++[>[->+>+<<]>>
+[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<<[-]<<<<<<<<[->>>>>>>>>>
++>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<[<<<<<<<<<<[->>>>>>>>>>>+>+
+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<[-<<<+>>>]<-]<<<[-]>>[-]<[
+->+>+<<]>>[-<<+>>][-]+<[->>+>>+<<<<]>>>>[-<<<<+>>>>]<++++++++[<[->>+>+<<<]>>>[-<
+<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<--------<<+>>>[-]+<[->>>+>+<
+<<<]>>>>[-<<<<+>>>>]<<++++++++[>[->+>+<<]>>[-<<+>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[
+-]]<<<-]>[-]<<][-]>[-]<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<
+<<<[->>>>>+>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<[-<+>]<<[-]+>[->+>>+<<<]>>>[-<<<+>>>]
+<+++++++++++++++++++++++++++++++++[<[->>+>+<<<]>>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<
+<<<<[-]>>>>>[-]]<<-]<[-]<[-]<[->>>+<+<<]>>[-<<+>>]+>[<<<<<<<<<<<<<<<[-]>>>>>>>>>
+>>>>>[-]>[-]]<[<<<<[-]<<<<<<<<<[->>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>
+>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<[<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>
++>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<[-<
+<<<<<<<+>>>>>>>>]<-]<[-]<<<<<[-]<[->+>>>>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>][-]
++<<<<<<[->>>>>>>+>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<++++[<[->>+>+<<<]>
+>>[-<<<+>>>]+<[<<->>>[-]<[-]]>[<<<<[-]>>>>[-]]<<-]<[-]<[<<<<<<---->>>>>+>[-]+<<<
+<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<++++[>[->+>+<<]>>[-<<
++>>]+<[<->>[-]<[-]]>[<<<<[-]>>>>[-]]<<<-]>[-]<<][-]<<<<<<<<<<<<<<<<<<<<[-]>>>>>>
+>>>[-<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>
+>>>>]<<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<[-<<<<<<<<<<
+<<<<<<<<<<->>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>+>+<
+<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>
+>>>>>>>>>>>]<[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>][-]<<<<<<<<<<<<<<[->>>>
+>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<
+<<<<<<<<<<<<[->>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+
+>>>>>>>>>>>>>>>]<[-<->]<[->+>+<<]>>[-<<+>>]<[<<[->>>+<<<]>>>[-<<<->>>]<[-]]<[-]<
+<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>
++<<]>>[-<<+>>]<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<
+<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>
+>>>>>]<[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<+>>>>>>>>>
+>>>>>>>>>[-]<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<]>>>>>>>
+>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<---------------[->+>+<<]>
+>[-<<+>>]+<[>[-]<[-]]>[<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>[-]]<<[-]<[-]<<[
+-]]<<<<<<<<[-]>>>[-]>[-]>[-]>[-]<<<<[-]]<<<<<<<[-]>[-]>[-]>[-]>[-]<<<<<]>>>>>+++
++++++++++++++++++++++++++++++<[-]+<<<<<[->>>>+<<+<<]>>[-<<+>>]>+++[>[-<<+>>>>>>+
+<<<<]>>>>[-<<<<+>>>>]+<<<<<<[>>->>>>[-]<<<<<<[-]]>>>>>>[<<<[-]>>>[-]]<<<<<-]>[-]
+>[-<<+>+>]<[->+<]<[>>>++++++++++++++<<<[-]]>>[-]<<<<<[->>>>>+<<+<<<]>>>[-<<<+>>>
+]>>---------------[-<<+>+>]<[->+<]+<[>[-]<[-]]>[>>[-]+++++++++++++++++++++++++++
+++++++++<<[-]]>[-]>.[-]<<<<<<<<<+<<-]>>>>>>>>>>++++++++++.[-]<<<<<<<<<+<<-]
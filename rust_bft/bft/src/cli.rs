@@ -1,17 +1,102 @@
 //! A brainfuck CLI implementation using clap
 pub mod bft_cli_mod {
-    use clap::Parser;
-    use std::num::NonZeroUsize;
+    use bft_interp::emit::BFEmitTarget;
+    use bft_interp::trace::{BFTraceFilter, BFTraceOps};
+    use bft_interp::{
+        BFBackend, BFCellWidth, BFEofBehavior, BFHeadMode, BFNewline, BFOptLevel, BFRunOptions,
+    };
+    use clap::{Args, Command, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+    use clap_complete::Shell;
+    use std::num::{NonZeroU32, NonZeroUsize};
     use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    /// Name of a program given with `--eval`
+    pub const EVAL_NAME: &str = "<eval>";
 
     /// Parse input arguments, should require a positional argument
-    /// called `PROGRAM` or report an error
+    /// called `PROGRAM` or report an error, `PROGRAM` is a path, an
+    /// `http://` or `https://` URL or `-` to read it from stdin, the program
+    /// then reads what follows the first `!` of stdin
     ///
     /// Providing the usual `--help`, `--version` options
-    /// In addition with:
+    /// `bft PROGRAM` is `bft run PROGRAM`, which runs the program, several
+    /// programs run one after another on the same tape and head, in
+    /// addition with:
     ///     `-c`, or `--cells` with a non-zero numeric argument to allocate
     ///     cell memory for the tape, `--cells 0` should output an error
     ///     `-e`, or `--extensible` which will turn on the auto-extending tape
+    ///     `-E`, or `--eval SOURCE` to run SOURCE given on the command line, in
+    ///     place of `PROGRAM`, `-e` being taken by `--extensible`
+    ///     `--cell-width` with 8, 16 or 32 to choose how many bits a cell holds
+    ///     `--auto-size` to size the tape from a static bound of the head
+    ///     `--backend` to choose the interpreter backend running the program
+    ///     `--fuel` to limit how many instructions the program may execute
+    ///     `--timeout SECONDS` to limit how long it may run
+    ///     `-O0` to `-O3` to choose how much the program is optimized
+    ///     `--emit-ir` to print the optimized instructions instead of running
+    ///     `--no-final-newline` to keep the output exactly as the program wrote it
+    ///     `--head-mode` to choose what moving the head off the tape does
+    ///     `--newline` to translate the line endings of the input
+    ///     `--input FILE` to read the input from FILE instead of stdin, and
+    ///     `--eof` to choose what reading does once it is exhausted
+    ///     `-o`, or `--output FILE` to write the output, or the IR, to FILE
+    ///     instead of stdout, prompts and diagnostics are still printed
+    ///     `--tee FILE` to copy the output to FILE while it's written
+    ///     `--exit-cell[=CELL]` to exit with the value of CELL, cell 0 if not given
+    ///     `--strict` to run with the strict compliance semantics
+    ///     `--random` to enable the `?` command storing a random byte,
+    ///     `--seed` to make its bytes reproducible
+    ///     `--trace[=FILE]` to write every executed instruction to FILE, stderr
+    ///     if not given, `--trace-ops` and `--trace-steps N..M` to filter them
+    ///     `--digest` to print a digest of the run comparable across backends
+    ///     and optimization levels
+    ///     `--time` to print how long parsing, optimizing and executing took
+    ///     and how many instructions ran per second
+    ///     `--watch` to run again, after a separator, every time a `PROGRAM`
+    ///     or the `--input` file changes
+    ///     `-v` to log parse timings and the tape size to stderr, `-vv`
+    ///     optimization decisions and tape extensions too, `-vvv` every byte
+    ///     read or written,
+    ///     `-q` to print no warnings or hints
+    ///     `--diagnostics-format json` to print errors and warnings as JSON
+    ///     objects on stderr, with their code and position
+    ///     `--color` with auto, always or never to choose when they are
+    ///     colored, auto coloring a terminal unless `NO_COLOR` is set
+    ///
+    /// Defaults of `--cells`, `--extensible`, `--cell-width`, `--eof` and `-O`
+    /// are read from the TOML file `--config PATH`, or
+    /// `~/.config/bft/config.toml`, `--strict` ignores them
+    /// These options and `--backend`, `--fuel` and `--head-mode` are also
+    /// read from `BFT_CELLS`, `BFT_EXTENSIBLE`, `BFT_CELL_WIDTH`, `BFT_EOF`,
+    /// `BFT_OPT`, `BFT_BACKEND`, `BFT_FUEL` and `BFT_HEAD_MODE` when the
+    /// command line doesn't give them, overriding the config file
+    ///
+    /// Or one of the subcommands:
+    ///     `check` to report the errors and warnings of `PROGRAM` without
+    ///     running it, `--format json` for editors
+    ///     `fmt` to print `PROGRAM` in the canonical layout
+    ///     `translate` to rewrite `PROGRAM` from a dialect `--from` into a
+    ///     dialect `--to`, brainfuck, Ook! or one of the config file
+    ///     `visualize` to print the loops of `PROGRAM` with their positions
+    ///     and sizes, `--format dot` as a Graphviz graph
+    ///     `compile` to transpile `PROGRAM` into a standalone source file
+    ///     `debug` to step through `PROGRAM` with breakpoints
+    ///     `repl` to run lines typed on stdin on the same tape, with `:`
+    ///     commands showing, resetting or undoing the tape and loading files
+    ///     `stats` to print metrics of `PROGRAM`, `--execute` to run it and
+    ///     print the statistics of the run too, `--format json` for scripts
+    ///     `verify` to compare two backends or all semantics running `PROGRAM`
+    ///     `run-all` to run many programs concurrently against expected outputs
+    ///     `test` to check directories of programs against their golden files
+    ///     `serve` to run programs posted over HTTP or streamed over a WebSocket
+    ///     `bench` to time `PROGRAM` or the built-in workloads on each backend
+    ///     `completions` to print the completion script of bash, zsh, fish,
+    ///     elvish or powershell
+    ///
+    /// bft exits with 2 on a usage error, 3 when a program doesn't parse or
+    /// `check` finds errors, 4 when the virtual machine fails, 5 on an IO
+    /// error, 124 once `--timeout` passed and 1 on any other failure
     ///
     /// # Examples:
     ///
@@ -20,8 +105,8 @@ pub mod bft_cli_mod {
     /// pub use crate::cli::bft_cli_mod::BftCli;
     ///
     /// let cli = BftCli::new();
-    /// let program_name = cli.name();
-    /// let cell_size = cli.cells_size();
+    /// let program_name = cli.run_args().name();
+    /// let cell_size = cli.run_args().cells_size();
     ///
     /// ```
     #[derive(Debug, Parser)]
@@ -29,12 +114,155 @@ pub mod bft_cli_mod {
         name = "bft",
         author = "Hao Hu",
         version = "1.0.0",
-        about = "Brainfuck Application"
+        about = "Brainfuck Application",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true
     )]
     pub struct BftCli {
-        /// must be a brainfuck `PROGRAM`
-        #[arg(help = "PROGRAM name", required = true)]
-        name: PathBuf,
+        /// subcommand to run instead of interpreting `PROGRAM`
+        #[command(subcommand)]
+        command: Option<BftCommand>,
+
+        /// file holding defaults of the options
+        #[arg(
+            long = "config",
+            value_name = "PATH",
+            help = "file holding defaults of the options, ~/.config/bft/config.toml if not given",
+            global = true
+        )]
+        config: Option<PathBuf>,
+
+        /// how much is logged to stderr
+        #[arg(
+            short = 'v',
+            long = "verbose",
+            action = clap::ArgAction::Count,
+            help = "log parsing and tape sizing, -vv packing and tape extensions, -vvv every byte read or written",
+            global = true
+        )]
+        verbose: u8,
+
+        /// whether warnings and hints are silenced
+        #[arg(
+            short = 'q',
+            long = "quiet",
+            help = "print no warnings or hints, only errors",
+            conflicts_with = "verbose",
+            global = true
+        )]
+        quiet: bool,
+
+        /// form of the errors and warnings
+        #[arg(
+            long = "diagnostics-format",
+            value_name = "FORMAT",
+            value_enum,
+            default_value_t = BftDiagnosticsFormat::Human,
+            help = "print errors and warnings for humans, or as JSON lines on stderr",
+            global = true
+        )]
+        diagnostics_format: BftDiagnosticsFormat,
+
+        /// when the diagnostics and logs are colored
+        #[arg(
+            long = "color",
+            value_name = "WHEN",
+            value_enum,
+            default_value_t = BftColor::Auto,
+            help = "color the diagnostics and logs on stderr, auto if it's a terminal and NO_COLOR isn't set",
+            global = true
+        )]
+        color: BftColor,
+
+        /// arguments of `run` given without the subcommand
+        #[command(flatten)]
+        run: BftRunArgs,
+    }
+
+    /// Environment variables giving the options of these ids, between the
+    /// command line and the defaults
+    const ENV_VARS: [(&str, &str); 8] = [
+        ("cells", "BFT_CELLS"),
+        ("allow_extend", "BFT_EXTENSIBLE"),
+        ("cell_width", "BFT_CELL_WIDTH"),
+        ("eof", "BFT_EOF"),
+        ("opt_level", "BFT_OPT"),
+        ("backend", "BFT_BACKEND"),
+        ("fuel", "BFT_FUEL"),
+        ("head_mode", "BFT_HEAD_MODE"),
+    ];
+
+    /// `command` and its subcommands with the defaults of their options
+    /// taken from the environment, or else from `defaults`
+    ///
+    /// The environment variables are defaults rather than clap `env`
+    /// values, which would conflict with `--strict` like the options do.
+    fn command_with_defaults(command: Command, defaults: &[(&str, String)]) -> Command {
+        command
+            .mut_args(|arg| {
+                let var = ENV_VARS.iter().find(|(id, _)| arg.get_id() == *id);
+                let value = var
+                    .and_then(|(_, var)| std::env::var(var).ok())
+                    .filter(|value| !value.is_empty());
+                let value = value.or_else(|| {
+                    let default = defaults.iter().find(|(id, _)| arg.get_id() == *id);
+                    default.map(|(_, value)| value.clone())
+                });
+                match value {
+                    Some(value) => arg.default_value(value),
+                    None => arg,
+                }
+            })
+            .mut_subcommands(|command| command_with_defaults(command, defaults))
+    }
+
+    /// Arguments of the `run` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftRunArgs {
+        /// must be brainfuck `PROGRAM`s, run one after another on the same tape
+        #[arg(
+            value_name = "PROGRAM",
+            help = "PROGRAM names, run one after another on the same tape",
+            required_unless_present = "eval"
+        )]
+        names: Vec<PathBuf>,
+
+        /// program given on the command line
+        #[arg(
+            short = 'E',
+            long = "eval",
+            value_name = "SOURCE",
+            help = "run SOURCE instead of reading a PROGRAM, named `<eval>` in diagnostics, \
+                    -E as -e is --extensible",
+            conflicts_with = "names",
+            allow_hyphen_values = true
+        )]
+        eval: Option<String>,
+
+        /// input of the program
+        #[arg(
+            long = "input",
+            value_name = "FILE",
+            help = "file the program reads as input instead of stdin, without prompts"
+        )]
+        input: Option<PathBuf>,
+
+        /// output of the program
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            help = "file the program output is written to instead of stdout, created or truncated"
+        )]
+        output: Option<PathBuf>,
+
+        /// transcript of the output
+        #[arg(
+            long = "tee",
+            value_name = "FILE",
+            help = "also write the program output to FILE, created or truncated"
+        )]
+        tee: Option<PathBuf>,
 
         /// size of cell memory to allocate
         #[arg(
@@ -53,33 +281,1808 @@ pub mod bft_cli_mod {
             default_value_t = false
         )]
         allow_extend: bool,
+
+        /// width of each cell
+        #[arg(
+            long = "cell-width",
+            help = "width of each cell in bits",
+            value_enum,
+            default_value_t = BftCellWidth::W8
+        )]
+        cell_width: BftCellWidth,
+
+        /// size the tape from the program
+        #[arg(
+            long = "auto-size",
+            help = "size the tape from a static bound of the head, extensible if there is none",
+            default_value_t = false,
+            conflicts_with_all = ["cells", "allow_extend", "strict"]
+        )]
+        auto_size: bool,
+
+        /// interpreter backend
+        #[arg(
+            long = "backend",
+            help = "interpreter backend running the program",
+            value_enum,
+            default_value_t = BftBackend::Loop
+        )]
+        backend: BftBackend,
+
+        /// instruction budget
+        #[arg(
+            long = "fuel",
+            help = "maximum number of instructions to execute, unlimited if not given"
+        )]
+        fuel: Option<u64>,
+
+        /// wall-clock budget
+        #[arg(
+            long = "timeout",
+            value_name = "SECONDS",
+            help = "maximum number of seconds the programs may run for, unlimited if not given",
+            value_parser = parse_seconds
+        )]
+        timeout: Option<f64>,
+
+        /// optimization level
+        #[arg(
+            short = 'O',
+            help = "optimization level of the program",
+            value_enum,
+            default_value_t = BftOptLevel::O1
+        )]
+        opt_level: BftOptLevel,
+
+        /// print the packed program instead of running it
+        #[arg(
+            long = "emit-ir",
+            help = "print the optimized instructions instead of running the program",
+            default_value_t = false
+        )]
+        emit_ir: bool,
+
+        /// final newline flag
+        #[arg(
+            long = "no-final-newline",
+            help = "do not print a newline after the output if it doesn't end with one",
+            default_value_t = false
+        )]
+        no_final_newline: bool,
+
+        /// head movement off the tape
+        #[arg(
+            long = "head-mode",
+            help = "what moving the head off the tape does",
+            value_enum,
+            default_value_t = BftHeadMode::Strict
+        )]
+        head_mode: BftHeadMode,
+
+        /// line endings of the input
+        #[arg(
+            long = "newline",
+            help = "how the line endings read by the program are translated",
+            value_enum,
+            default_value_t = BftNewline::Unchanged
+        )]
+        newline: BftNewline,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted, `error` if not given",
+            value_enum
+        )]
+        eof: Option<BftEof>,
+
+        /// cell holding the exit status
+        #[arg(
+            long = "exit-cell",
+            value_name = "CELL",
+            help = "exit with the value of CELL once the program halts, cell 0 if no CELL given",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "0"
+        )]
+        exit_cell: Option<usize>,
+
+        /// strict compliance profile
+        #[arg(
+            long = "strict",
+            help = "strict semantics: 30000 fixed 8-bit cells, EOF leaves the cell unchanged, no prompt nor final newline",
+            default_value_t = false,
+            conflicts_with_all = ["cells", "allow_extend", "cell_width", "eof", "no_final_newline", "head_mode"]
+        )]
+        strict: bool,
+
+        /// random byte extension
+        #[arg(
+            long = "random",
+            help = "enable the `?` command storing a random byte into the head cell",
+            default_value_t = false
+        )]
+        random: bool,
+
+        /// seed of the random bytes
+        #[arg(
+            long = "seed",
+            help = "seed of the random bytes, taken from the clock if not given",
+            requires = "random"
+        )]
+        seed: Option<u64>,
+
+        /// execution trace file
+        #[arg(
+            long = "trace",
+            value_name = "FILE",
+            help = "write a line per executed instruction to FILE, stderr if no FILE given",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "-",
+            conflicts_with = "emit_ir"
+        )]
+        trace: Option<PathBuf>,
+
+        /// instructions written to the trace
+        #[arg(
+            long = "trace-ops",
+            help = "kind of instructions written to the trace",
+            value_enum,
+            default_value_t = BftTraceOps::All,
+            requires = "trace"
+        )]
+        trace_ops: BftTraceOps,
+
+        /// steps written to the trace
+        #[arg(
+            long = "trace-steps",
+            value_name = "N..M",
+            help = "only write steps N to M excluded to the trace, either bound may be left out",
+            value_parser = parse_steps,
+            requires = "trace"
+        )]
+        trace_steps: Option<(u64, Option<u64>)>,
+
+        /// execution digest
+        #[arg(
+            long = "digest",
+            help = "print a digest of the output, the input reads and the final tape to stderr",
+            conflicts_with_all = ["trace", "emit_ir"]
+        )]
+        digest: bool,
+
+        /// timing summary
+        #[arg(
+            long = "time",
+            help = "print the parse, optimize and execute times and the instructions executed per second to stderr",
+            default_value_t = false,
+            conflicts_with = "emit_ir"
+        )]
+        time: bool,
+
+        /// rerun on changes
+        #[arg(
+            long = "watch",
+            help = "run again every time a PROGRAM or the --input file changes, until stopped",
+            default_value_t = false
+        )]
+        watch: bool,
     }
 
-    impl Default for BftCli {
-        fn default() -> Self {
-            Self::new()
+    /// Subcommands of the brainfuck application
+    #[derive(Debug, Subcommand)]
+    pub enum BftCommand {
+        /// Run a brainfuck program with stdin as input, what `bft PROGRAM` does
+        Run(BftRunArgs),
+
+        /// Parse and validate a brainfuck program without running it, reporting
+        /// unmatched brackets and likely mistakes, failing on errors
+        Check(BftCheckArgs),
+
+        /// Print a brainfuck program in the canonical layout
+        Fmt(BftFmtArgs),
+
+        /// Translate a program between brainfuck, Ook! and the dialects of the
+        /// config file
+        Translate(BftTranslateArgs),
+
+        /// Print the loops of a brainfuck program as an indented tree or a
+        /// Graphviz graph
+        Visualize(BftVisualizeArgs),
+
+        /// Transpile a brainfuck program into a standalone source file
+        Compile(BftCompileArgs),
+
+        /// Step through a brainfuck program with breakpoints, reading debugger
+        /// commands from stdin
+        Debug(BftDebugArgs),
+
+        /// Run brainfuck lines typed on stdin one after another on the same tape,
+        /// `:help` listing the commands of the REPL
+        Repl(BftReplArgs),
+
+        /// Print metrics of a brainfuck program, or run it like `run` with
+        /// `--execute` and print the statistics of the run too
+        Stats(BftStatsArgs),
+
+        /// Run a brainfuck program on two backends, or under every cell width and
+        /// EOF behavior, with stdin as input and compare them
+        Verify(BftVerifyArgs),
+
+        /// Run many brainfuck programs concurrently, `NAME.bf` reads `NAME.in`
+        /// and must write `NAME.out` when those files exist
+        RunAll(BftRunAllArgs),
+
+        /// Check every `NAME.b` or `NAME.bf` having a `NAME.expected` file writes
+        /// it, reading `NAME.in` if it exists
+        Test(BftTestArgs),
+
+        /// Serve `POST /run`, the `GET /stream` WebSocket and `GET /metrics`, running programs under strict limits
+        Serve(BftServeArgs),
+
+        /// Time a brainfuck program, or the built-in workloads, on every backend and
+        /// optimization level
+        Bench(BftBenchArgs),
+
+        /// Print a script completing the subcommands and options of bft in a
+        /// shell
+        Completions(BftCompletionsArgs),
+    }
+
+    /// Arguments of the `completions` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftCompletionsArgs {
+        /// shell the script is written for
+        #[arg(value_enum, help = "shell the completion script is written for")]
+        shell: Shell,
+    }
+
+    impl BftCompletionsArgs {
+        /// get shell the script is written for
+        pub fn shell(&self) -> Shell {
+            self.shell
         }
     }
 
-    impl BftCli {
-        /// create a new BftCli struct
-        pub fn new() -> Self {
-            BftCli::parse()
+    /// Arguments of the `stats` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftStatsArgs {
+        /// run the program and measure the run too
+        #[arg(
+            long = "execute",
+            help = "run PROGRAM and print the statistics of the run to stderr too",
+            default_value_t = false
+        )]
+        execute: bool,
+
+        /// form of the statistics
+        #[arg(
+            long = "format",
+            help = "print the statistics as a table or as a JSON object",
+            value_enum,
+            default_value_t = BftStatsFormat::Table
+        )]
+        format: BftStatsFormat,
+
+        /// arguments of the program and of its run
+        #[command(flatten)]
+        run: BftRunArgs,
+    }
+
+    impl BftStatsArgs {
+        /// whether the program runs
+        pub fn execute(&self) -> bool {
+            self.execute
+        }
+
+        /// get form of the statistics
+        pub fn format(&self) -> BftStatsFormat {
+            self.format
+        }
+
+        /// get arguments of the program and of its run
+        pub fn run_args(&self) -> &BftRunArgs {
+            &self.run
         }
+    }
+
+    /// Forms of the statistics selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftStatsFormat {
+        /// a line per statistic
+        Table,
+
+        /// a JSON object
+        Json,
+    }
+
+    /// Arguments of the `visualize` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftVisualizeArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// form of the structure
+        #[arg(
+            long = "format",
+            help = "print the loops as an indented tree or as a Graphviz DOT graph",
+            value_enum,
+            default_value_t = BftVisualizeFormat::Tree
+        )]
+        format: BftVisualizeFormat,
+    }
 
+    impl BftVisualizeArgs {
         /// get application name
         pub fn name(&self) -> &Path {
             &self.name
         }
 
-        /// get type size
-        pub fn cells_size(&self) -> usize {
-            self.cells.get()
+        /// get form of the structure
+        pub fn format(&self) -> BftVisualizeFormat {
+            self.format
         }
+    }
 
-        /// get extensible flag
-        pub fn cells_extensible(&self) -> bool {
-            self.allow_extend
+    /// Forms of the loop structure selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftVisualizeFormat {
+        /// a line per loop, indented by its depth
+        Tree,
+
+        /// a Graphviz DOT graph
+        Dot,
+    }
+
+    /// Arguments of the `check` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftCheckArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// form of the diagnostics
+        #[arg(
+            long = "format",
+            help = "print the diagnostics for humans or as a JSON object",
+            value_enum,
+            default_value_t = BftCheckFormat::Human
+        )]
+        format: BftCheckFormat,
+    }
+
+    impl BftCheckArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// whether the diagnostics are printed as JSON
+        pub fn json(&self) -> bool {
+            self.format == BftCheckFormat::Json
+        }
+    }
+
+    /// Forms of the `check` diagnostics selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftCheckFormat {
+        /// a `PROGRAM:LINE:COLUMN: SEVERITY: MESSAGE` line per diagnostic
+        Human,
+
+        /// a JSON object listing the diagnostics
+        Json,
+    }
+
+    /// Forms of the errors and warnings selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftDiagnosticsFormat {
+        /// severity, message, position and source line of each error or
+        /// warning on stderr
+        Human,
+
+        /// a JSON object per error or warning on stderr
+        Json,
+    }
+
+    /// When to color the diagnostics selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftColor {
+        /// color a terminal unless `NO_COLOR` is set
+        Auto,
+
+        /// always color, even if `NO_COLOR` is set
+        Always,
+
+        /// never color
+        Never,
+    }
+
+    /// Arguments of the `fmt` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftFmtArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// rewrite the program file
+        #[arg(
+            short = 'w',
+            long = "write",
+            help = "write the formatted program back to PROGRAM instead of stdout",
+            default_value_t = false
+        )]
+        write: bool,
+
+        /// only check the layout
+        #[arg(
+            long = "check",
+            help = "fail if PROGRAM isn't in the canonical layout, without printing it",
+            default_value_t = false,
+            conflicts_with = "write"
+        )]
+        check: bool,
+    }
+
+    impl BftFmtArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// whether the formatted program replaces `PROGRAM`
+        pub fn write(&self) -> bool {
+            self.write
+        }
+
+        /// whether the layout is only checked
+        pub fn check(&self) -> bool {
+            self.check
+        }
+    }
+
+    /// Arguments of the `translate` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftTranslateArgs {
+        /// must be a `PROGRAM` written in the `--from` dialect
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// dialect of the program
+        #[arg(
+            long = "from",
+            value_name = "DIALECT",
+            help = "dialect PROGRAM is written in, brainfuck, ook or one of the config file",
+            default_value = "brainfuck"
+        )]
+        from: String,
+
+        /// dialect of the translation
+        #[arg(
+            long = "to",
+            value_name = "DIALECT",
+            help = "dialect of the translation, brainfuck, ook or one of the config file"
+        )]
+        to: String,
+
+        /// file the translation is written to
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            help = "write the translation to FILE instead of stdout"
+        )]
+        output: Option<PathBuf>,
+
+        /// lay the translation out
+        #[arg(
+            long = "pretty",
+            help = "print brainfuck in the canonical layout, other dialects in lines of 64 columns like fmt",
+            default_value_t = false
+        )]
+        pretty: bool,
+    }
+
+    impl BftTranslateArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// get dialect of the program
+        pub fn from(&self) -> &str {
+            &self.from
+        }
+
+        /// get dialect of the translation
+        pub fn to(&self) -> &str {
+            &self.to
+        }
+
+        /// get file the translation is written to, None for stdout
+        pub fn output(&self) -> Option<&Path> {
+            self.output.as_deref()
+        }
+
+        /// whether the translation is laid out
+        pub fn pretty(&self) -> bool {
+            self.pretty
+        }
+    }
+
+    /// Arguments of the `debug` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftDebugArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// input of the program
+        #[arg(
+            long = "input",
+            help = "file the program reads as input, empty if not given"
+        )]
+        input: Option<PathBuf>,
+
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted",
+            value_enum,
+            default_value_t = BftEof::Error
+        )]
+        eof: BftEof,
+
+        /// optimization level
+        #[arg(
+            short = 'O',
+            help = "optimization level of the program, every source instruction is a step at 0",
+            value_enum,
+            default_value_t = BftOptLevel::O0
+        )]
+        opt_level: BftOptLevel,
+    }
+
+    impl BftDebugArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// get input file, None for an empty input
+        pub fn input(&self) -> Option<&Path> {
+            self.input.as_deref()
+        }
+
+        /// get type size
+        pub fn cells_size(&self) -> usize {
+            self.cells.get()
+        }
+
+        /// get extensible flag
+        pub fn cells_extensible(&self) -> bool {
+            self.allow_extend
+        }
+
+        /// get EOF behavior
+        pub fn eof(&self) -> BFEofBehavior {
+            self.eof.into()
+        }
+
+        /// get optimization level
+        pub fn opt_level(&self) -> BFOptLevel {
+            self.opt_level.into()
+        }
+    }
+
+    /// Arguments of the `repl` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftReplArgs {
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+    }
+
+    impl BftReplArgs {
+        /// get type size
+        pub fn cells_size(&self) -> usize {
+            self.cells.get()
+        }
+
+        /// get extensible flag
+        pub fn cells_extensible(&self) -> bool {
+            self.allow_extend
+        }
+    }
+
+    /// Arguments of the `compile` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftCompileArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// emitted source file
+        #[arg(
+            short = 'o',
+            long = "output",
+            help = "file to write the emitted source into, stdout if not given"
+        )]
+        output: Option<PathBuf>,
+
+        /// language of the emitted source
+        #[arg(
+            long = "target",
+            help = "language of the emitted source",
+            value_enum,
+            default_value_t = BftTarget::Rust
+        )]
+        target: BftTarget,
+
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+
+        /// width of the tape cells
+        #[arg(
+            long = "cell-width",
+            help = "width of each cell in bits",
+            value_enum,
+            default_value_t = BftCellWidth::W8
+        )]
+        cell_width: BftCellWidth,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted",
+            value_enum,
+            default_value_t = BftEof::Error
+        )]
+        eof: BftEof,
+
+        /// check the emitted IR against the interpreter
+        #[cfg(feature = "llvm")]
+        #[arg(
+            long = "validate",
+            help = "run the emitted LLVM IR with lli on stdin and compare its output with the interpreter",
+            default_value_t = false
+        )]
+        validate: bool,
+    }
+
+    impl BftCompileArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// get output file
+        pub fn output(&self) -> Option<&Path> {
+            self.output.as_deref()
+        }
+
+        /// get emit target
+        pub fn target(&self) -> BFEmitTarget {
+            self.target.into()
+        }
+
+        /// whether the emitted text should be assembled into a binary
+        pub fn assemble(&self) -> bool {
+            self.target == BftTarget::Wasm
+        }
+
+        /// get type size
+        pub fn cells_size(&self) -> usize {
+            self.cells.get()
+        }
+
+        /// get extensible flag
+        pub fn cells_extensible(&self) -> bool {
+            self.allow_extend
+        }
+
+        /// get cell width
+        pub fn cell_width(&self) -> BFCellWidth {
+            self.cell_width.into()
+        }
+
+        /// get EOF behavior
+        pub fn eof(&self) -> BFEofBehavior {
+            self.eof.into()
+        }
+
+        /// get validate flag
+        #[cfg(feature = "llvm")]
+        pub fn validate(&self) -> bool {
+            self.validate
+        }
+    }
+
+    /// Arguments of the `verify` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftVerifyArgs {
+        /// must be a brainfuck `PROGRAM`
+        #[arg(help = "PROGRAM name")]
+        name: PathBuf,
+
+        /// reference backend
+        #[arg(
+            long = "baseline",
+            help = "backend the candidate is compared against",
+            value_enum,
+            default_value_t = BftBackend::Loop
+        )]
+        baseline: BftBackend,
+
+        /// backend under test
+        #[arg(
+            long = "candidate",
+            help = "backend checked against the baseline",
+            value_enum,
+            default_value_t = BftBackend::Threaded
+        )]
+        candidate: BftBackend,
+
+        /// optimization level of the reference run
+        #[arg(
+            long = "baseline-opt",
+            help = "optimization level of the baseline",
+            value_enum,
+            default_value_t = BftOptLevel::O1
+        )]
+        baseline_opt: BftOptLevel,
+
+        /// optimization level of the run under test
+        #[arg(
+            long = "candidate-opt",
+            help = "optimization level of the candidate",
+            value_enum,
+            default_value_t = BftOptLevel::O1
+        )]
+        candidate_opt: BftOptLevel,
+
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted",
+            value_enum,
+            default_value_t = BftEof::Error
+        )]
+        eof: BftEof,
+
+        /// instruction budget
+        #[arg(
+            long = "fuel",
+            help = "maximum number of instructions to execute on each backend, unlimited if not given"
+        )]
+        fuel: Option<u64>,
+
+        /// head movement off the tape
+        #[arg(
+            long = "head-mode",
+            help = "what moving the head off the tape does",
+            value_enum,
+            default_value_t = BftHeadMode::Strict
+        )]
+        head_mode: BftHeadMode,
+
+        /// compare semantics instead of backends
+        #[arg(
+            long = "semantics",
+            help = "run the baseline under every cell width and EOF behavior and compare the outputs",
+            default_value_t = false,
+            conflicts_with_all = ["eof", "candidate", "candidate_opt"]
+        )]
+        semantics: bool,
+    }
+
+    impl BftVerifyArgs {
+        /// get application name
+        pub fn name(&self) -> &Path {
+            &self.name
+        }
+
+        /// get reference backend
+        pub fn baseline(&self) -> BFBackend {
+            self.baseline.into()
+        }
+
+        /// get backend under test
+        pub fn candidate(&self) -> BFBackend {
+            self.candidate.into()
+        }
+
+        /// get optimization level of the reference run
+        pub fn baseline_opt(&self) -> BFOptLevel {
+            self.baseline_opt.into()
+        }
+
+        /// get optimization level of the run under test
+        pub fn candidate_opt(&self) -> BFOptLevel {
+            self.candidate_opt.into()
+        }
+
+        /// get type size
+        pub fn cells_size(&self) -> usize {
+            self.cells.get()
+        }
+
+        /// get extensible flag
+        pub fn cells_extensible(&self) -> bool {
+            self.allow_extend
+        }
+
+        /// get EOF behavior
+        pub fn eof(&self) -> BFEofBehavior {
+            self.eof.into()
+        }
+
+        /// get instruction budget
+        pub fn fuel(&self) -> Option<u64> {
+            self.fuel
+        }
+
+        /// get head mode
+        pub fn head_mode(&self) -> BFHeadMode {
+            self.head_mode.into()
+        }
+
+        /// whether semantics are compared instead of backends
+        pub fn semantics(&self) -> bool {
+            self.semantics
+        }
+    }
+
+    /// Arguments of the `run-all` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftRunAllArgs {
+        /// directories, glob patterns or archives of brainfuck programs
+        #[arg(
+            help = "directories of .bf files, glob patterns or zip and tar archives",
+            required = true
+        )]
+        paths: Vec<String>,
+
+        /// worker threads
+        #[arg(
+            short = 'j',
+            long = "jobs",
+            help = "how many programs run at the same time, one per CPU if not given"
+        )]
+        jobs: Option<NonZeroUsize>,
+
+        /// JSON report file
+        #[arg(long = "json", help = "file to write a JSON report into")]
+        json: Option<PathBuf>,
+
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+
+        /// width of the tape cells
+        #[arg(
+            long = "cell-width",
+            help = "width of each cell in bits",
+            value_enum,
+            default_value_t = BftCellWidth::W8
+        )]
+        cell_width: BftCellWidth,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted",
+            value_enum,
+            default_value_t = BftEof::Error
+        )]
+        eof: BftEof,
+
+        /// instruction budget
+        #[arg(
+            long = "fuel",
+            help = "maximum number of instructions each program may execute, unlimited if not given"
+        )]
+        fuel: Option<u64>,
+
+        /// optimization level
+        #[arg(
+            short = 'O',
+            help = "optimization level of the programs",
+            value_enum,
+            default_value_t = BftOptLevel::O1
+        )]
+        opt_level: BftOptLevel,
+
+        /// interpreter backend
+        #[arg(
+            long = "backend",
+            help = "interpreter backend running the programs",
+            value_enum,
+            default_value_t = BftBackend::Loop
+        )]
+        backend: BftBackend,
+    }
+
+    impl BftRunAllArgs {
+        /// get directories, glob patterns and archives
+        pub fn paths(&self) -> &[String] {
+            &self.paths
+        }
+
+        /// get number of worker threads, None for one per CPU
+        pub fn jobs(&self) -> Option<usize> {
+            self.jobs.map(NonZeroUsize::get)
+        }
+
+        /// get JSON report file
+        pub fn json(&self) -> Option<&Path> {
+            self.json.as_deref()
+        }
+
+        /// get options every program runs with
+        pub fn run_options(&self) -> BFRunOptions {
+            BFRunOptions {
+                cells: self.cells.get(),
+                extensible: self.allow_extend,
+                cell_width: self.cell_width.into(),
+                eof: self.eof.into(),
+                backend: self.backend.into(),
+                opt_level: self.opt_level.into(),
+                fuel: self.fuel,
+                prompt: false,
+                ..BFRunOptions::default()
+            }
+        }
+    }
+
+    /// Arguments of the `test` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftTestArgs {
+        /// directories searched for golden tests
+        #[arg(
+            help = "directories searched for golden tests, recursively",
+            default_value = "."
+        )]
+        paths: Vec<PathBuf>,
+
+        /// worker threads
+        #[arg(
+            short = 'j',
+            long = "jobs",
+            help = "how many tests run at the same time, one per CPU if not given"
+        )]
+        jobs: Option<NonZeroUsize>,
+
+        /// size of cell memory to allocate
+        #[arg(
+            short = 'c',
+            long = "cells",
+            help = "how many cells allocate for tape, must be greater than 0",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        cells: NonZeroUsize,
+
+        /// tape extensible flag
+        #[arg(
+            short = 'e',
+            long = "extensible",
+            help = "whether the tape is extensible",
+            default_value_t = false
+        )]
+        allow_extend: bool,
+
+        /// width of the tape cells
+        #[arg(
+            long = "cell-width",
+            help = "width of each cell in bits",
+            value_enum,
+            default_value_t = BftCellWidth::W8
+        )]
+        cell_width: BftCellWidth,
+
+        /// what reading does once input is exhausted
+        #[arg(
+            long = "eof",
+            help = "what reading does once input is exhausted",
+            value_enum,
+            default_value_t = BftEof::Error
+        )]
+        eof: BftEof,
+
+        /// instruction budget
+        #[arg(
+            long = "fuel",
+            help = "maximum number of instructions each test may execute",
+            default_value_t = 100_000_000
+        )]
+        fuel: u64,
+
+        /// wall-clock budget
+        #[arg(
+            long = "timeout",
+            value_name = "SECONDS",
+            help = "maximum number of seconds each test may run for",
+            default_value_t = 10.0,
+            value_parser = parse_seconds
+        )]
+        timeout: f64,
+
+        /// output budget
+        #[arg(
+            long = "max-output",
+            help = "maximum number of bytes each test may write",
+            default_value_t = 1 << 20
+        )]
+        max_output: u64,
+
+        /// final newline flag
+        #[arg(
+            long = "no-final-newline",
+            help = "compare the output exactly as the programs wrote it",
+            default_value_t = false
+        )]
+        no_final_newline: bool,
+
+        /// optimization level
+        #[arg(
+            short = 'O',
+            help = "optimization level of the programs",
+            value_enum,
+            default_value_t = BftOptLevel::O1
+        )]
+        opt_level: BftOptLevel,
+
+        /// interpreter backend
+        #[arg(
+            long = "backend",
+            help = "interpreter backend running the programs",
+            value_enum,
+            default_value_t = BftBackend::Loop
+        )]
+        backend: BftBackend,
+    }
+
+    impl BftTestArgs {
+        /// get directories searched for tests
+        pub fn paths(&self) -> &[PathBuf] {
+            &self.paths
+        }
+
+        /// get number of worker threads, None for one per CPU
+        pub fn jobs(&self) -> Option<usize> {
+            self.jobs.map(NonZeroUsize::get)
+        }
+
+        /// get options and limits every test runs with
+        pub fn run_options(&self) -> BFRunOptions {
+            BFRunOptions {
+                cells: self.cells.get(),
+                extensible: self.allow_extend,
+                cell_width: self.cell_width.into(),
+                eof: self.eof.into(),
+                backend: self.backend.into(),
+                opt_level: self.opt_level.into(),
+                fuel: Some(self.fuel),
+                final_newline: !self.no_final_newline,
+                prompt: false,
+                max_output: Some(self.max_output),
+                timeout: Some(Duration::from_secs_f64(self.timeout)),
+                ..BFRunOptions::default()
+            }
+        }
+    }
+
+    /// Arguments of the `bench` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftBenchArgs {
+        /// brainfuck `PROGRAM` to time instead of the built-in workloads
+        #[arg(help = "PROGRAM name, the built-in workloads if not given")]
+        name: Option<PathBuf>,
+
+        /// built-in workloads to run
+        #[arg(
+            long = "workload",
+            help = "built-in workload to run, all of them if not given",
+            value_parser = ["hello", "mandelbrot", "hanoi"],
+            conflicts_with = "name"
+        )]
+        workloads: Vec<String>,
+
+        /// runs of each configuration
+        #[arg(
+            short = 'n',
+            long = "rounds",
+            help = "how many times each configuration runs, must be greater than 0",
+            default_value_t = NonZeroU32::new(3).unwrap()
+        )]
+        rounds: NonZeroU32,
+
+        /// backends to compare
+        #[arg(
+            long = "backend",
+            help = "backend to time, all of them if not given",
+            value_enum
+        )]
+        backends: Vec<BftBackend>,
+
+        /// optimization levels to compare
+        #[arg(
+            short = 'O',
+            help = "optimization level to time, all of them if not given",
+            value_enum
+        )]
+        opt_levels: Vec<BftOptLevel>,
+
+        /// input of the program
+        #[arg(
+            long = "input",
+            help = "file the program reads as input, empty if not given",
+            requires = "name"
+        )]
+        input: Option<PathBuf>,
+    }
+
+    impl BftBenchArgs {
+        /// get application name, None for the built-in workloads
+        pub fn name(&self) -> Option<&Path> {
+            self.name.as_deref()
+        }
+
+        /// get chosen built-in workloads, empty for all of them
+        pub fn workloads(&self) -> Vec<&str> {
+            self.workloads.iter().map(String::as_str).collect()
+        }
+
+        /// get runs of each configuration
+        pub fn rounds(&self) -> u32 {
+            self.rounds.get()
+        }
+
+        /// get backends to time
+        pub fn backends(&self) -> Vec<BFBackend> {
+            let backends = match self.backends.as_slice() {
+                [] => BftBackend::value_variants(),
+                backends => backends,
+            };
+            backends.iter().map(|&backend| backend.into()).collect()
+        }
+
+        /// get optimization levels to time
+        pub fn opt_levels(&self) -> Vec<BFOptLevel> {
+            let levels = match self.opt_levels.as_slice() {
+                [] => BftOptLevel::value_variants(),
+                levels => levels,
+            };
+            levels.iter().map(|&level| level.into()).collect()
+        }
+
+        /// get input file, None for an empty input
+        pub fn input(&self) -> Option<&Path> {
+            self.input.as_deref()
+        }
+    }
+
+    /// Arguments of the `serve` subcommand
+    #[derive(Debug, Args)]
+    pub struct BftServeArgs {
+        /// address to listen on
+        #[arg(
+            long = "bind",
+            help = "address and port to listen on",
+            default_value = "127.0.0.1:8080"
+        )]
+        bind: String,
+
+        /// worker threads
+        #[arg(
+            short = 'j',
+            long = "threads",
+            help = "how many requests are served at the same time",
+            default_value_t = NonZeroUsize::new(4).unwrap(),
+        )]
+        threads: NonZeroUsize,
+
+        /// instruction budget of every run
+        #[arg(
+            long = "max-fuel",
+            help = "maximum number of instructions a program may execute",
+            default_value_t = 10_000_000
+        )]
+        max_fuel: u64,
+
+        /// tape size of every run
+        #[arg(
+            long = "max-cells",
+            help = "maximum number of cells of the tape, which never extends",
+            default_value_t = NonZeroUsize::new(30000).unwrap(),
+        )]
+        max_cells: NonZeroUsize,
+
+        /// output size of every run
+        #[arg(
+            long = "max-output",
+            help = "maximum number of bytes a program may write",
+            default_value_t = 65536
+        )]
+        max_output: usize,
+
+        /// request size
+        #[arg(
+            long = "max-request",
+            help = "maximum number of bytes of a request body",
+            default_value_t = 1 << 20
+        )]
+        max_request: usize,
+
+        /// concurrent WebSocket sessions
+        #[arg(
+            long = "max-sessions",
+            help = "maximum number of WebSocket sessions running at the same time",
+            default_value_t = 16
+        )]
+        max_sessions: usize,
+    }
+
+    impl BftServeArgs {
+        /// get address to listen on
+        pub fn bind(&self) -> &str {
+            &self.bind
+        }
+
+        /// get number of worker threads
+        pub fn threads(&self) -> usize {
+            self.threads.get()
+        }
+
+        /// get instruction budget of every run
+        pub fn max_fuel(&self) -> u64 {
+            self.max_fuel
+        }
+
+        /// get tape size of every run
+        pub fn max_cells(&self) -> usize {
+            self.max_cells.get()
+        }
+
+        /// get output size of every run
+        pub fn max_output(&self) -> usize {
+            self.max_output
+        }
+
+        /// get request size
+        pub fn max_request(&self) -> usize {
+            self.max_request
+        }
+
+        /// get number of concurrent WebSocket sessions
+        pub fn max_sessions(&self) -> usize {
+            self.max_sessions
+        }
+    }
+
+    /// Emit targets selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftTarget {
+        /// standalone Rust source file
+        Rust,
+
+        /// portable C source file
+        C,
+
+        /// WebAssembly text module
+        Wat,
+
+        /// WebAssembly binary module
+        Wasm,
+
+        /// textual LLVM IR module
+        #[cfg(feature = "llvm")]
+        Llvm,
+    }
+
+    impl From<BftTarget> for BFEmitTarget {
+        fn from(target: BftTarget) -> Self {
+            match target {
+                BftTarget::Rust => BFEmitTarget::Rust,
+                BftTarget::C => BFEmitTarget::C,
+                BftTarget::Wat | BftTarget::Wasm => BFEmitTarget::Wat,
+                #[cfg(feature = "llvm")]
+                BftTarget::Llvm => BFEmitTarget::Llvm,
+            }
+        }
+    }
+
+    /// Cell widths selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftCellWidth {
+        /// 8-bit cells
+        #[value(name = "8")]
+        W8,
+
+        /// 16-bit cells
+        #[value(name = "16")]
+        W16,
+
+        /// 32-bit cells
+        #[value(name = "32")]
+        W32,
+    }
+
+    impl From<BftCellWidth> for BFCellWidth {
+        fn from(width: BftCellWidth) -> Self {
+            match width {
+                BftCellWidth::W8 => BFCellWidth::U8,
+                BftCellWidth::W16 => BFCellWidth::U16,
+                BftCellWidth::W32 => BFCellWidth::U32,
+            }
+        }
+    }
+
+    /// EOF behaviors selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftEof {
+        /// store zero into the cell
+        Zero,
+
+        /// store minus one into the cell
+        MinusOne,
+
+        /// leave the cell unchanged
+        Unchanged,
+
+        /// abort with an error
+        Error,
+    }
+
+    impl From<BftEof> for BFEofBehavior {
+        fn from(eof: BftEof) -> Self {
+            match eof {
+                BftEof::Zero => BFEofBehavior::Zero,
+                BftEof::MinusOne => BFEofBehavior::MinusOne,
+                BftEof::Unchanged => BFEofBehavior::Unchanged,
+                BftEof::Error => BFEofBehavior::Error,
+            }
+        }
+    }
+
+    /// Line ending translations selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftNewline {
+        /// read the input as it comes
+        Unchanged,
+
+        /// read CRLF as LF
+        Lf,
+
+        /// read LF as CRLF
+        Crlf,
+    }
+
+    impl From<BftNewline> for BFNewline {
+        fn from(newline: BftNewline) -> Self {
+            match newline {
+                BftNewline::Unchanged => BFNewline::Unchanged,
+                BftNewline::Lf => BFNewline::Lf,
+                BftNewline::Crlf => BFNewline::CrLf,
+            }
+        }
+    }
+
+    /// Optimization levels selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftOptLevel {
+        /// raw instruction stream
+        #[value(name = "0")]
+        O0,
+
+        /// fold runs of instructions
+        #[value(name = "1")]
+        O1,
+
+        /// replace clear, copy and scan loops
+        #[value(name = "2")]
+        O2,
+
+        /// fuse offsets and fold constants
+        #[value(name = "3")]
+        O3,
+    }
+
+    impl From<BftOptLevel> for BFOptLevel {
+        fn from(level: BftOptLevel) -> Self {
+            match level {
+                BftOptLevel::O0 => BFOptLevel::O0,
+                BftOptLevel::O1 => BFOptLevel::O1,
+                BftOptLevel::O2 => BFOptLevel::O2,
+                BftOptLevel::O3 => BFOptLevel::O3,
+            }
+        }
+    }
+
+    /// Head modes selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftHeadMode {
+        /// abort with an error
+        Strict,
+
+        /// stop the head at the edge
+        Clamp,
+    }
+
+    impl From<BftHeadMode> for BFHeadMode {
+        fn from(mode: BftHeadMode) -> Self {
+            match mode {
+                BftHeadMode::Strict => BFHeadMode::Strict,
+                BftHeadMode::Clamp => BFHeadMode::Clamp,
+            }
+        }
+    }
+
+    /// Interpreter backends selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftBackend {
+        /// single dispatch loop matching every instruction
+        Loop,
+
+        /// pre-compiled array of instruction handlers
+        Threaded,
+
+        /// native code compiled with cranelift
+        #[cfg(feature = "jit")]
+        Jit,
+    }
+
+    impl From<BftBackend> for BFBackend {
+        fn from(backend: BftBackend) -> Self {
+            match backend {
+                BftBackend::Loop => BFBackend::Loop,
+                BftBackend::Threaded => BFBackend::Threaded,
+                #[cfg(feature = "jit")]
+                BftBackend::Jit => BFBackend::Jit,
+            }
+        }
+    }
+
+    /// Instruction kinds written to a trace selectable from command line
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+    pub enum BftTraceOps {
+        /// every instruction
+        All,
+
+        /// input, output and extension commands
+        Io,
+
+        /// loop boundaries
+        Loops,
+    }
+
+    impl From<BftTraceOps> for BFTraceOps {
+        fn from(ops: BftTraceOps) -> Self {
+            match ops {
+                BftTraceOps::All => BFTraceOps::All,
+                BftTraceOps::Io => BFTraceOps::Io,
+                BftTraceOps::Loops => BFTraceOps::Loops,
+            }
+        }
+    }
+
+    /// parse a non-negative number of seconds
+    fn parse_seconds(value: &str) -> Result<f64, String> {
+        match value.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+            _ => Err(format!("`{}` isn't a number of seconds", value)),
+        }
+    }
+
+    /// parse a `N..M` range of steps, N and M may be left out
+    fn parse_steps(range: &str) -> Result<(u64, Option<u64>), String> {
+        let (first, end) = range
+            .split_once("..")
+            .ok_or_else(|| format!("`{}` isn't a N..M range", range))?;
+        let bound = |bound: &str| bound.parse::<u64>().map_err(|e| e.to_string());
+        let first = if first.is_empty() { 0 } else { bound(first)? };
+        let end = if end.is_empty() {
+            None
+        } else {
+            Some(bound(end)?)
+        };
+        Ok((first, end))
+    }
+
+    impl Default for BftCli {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl BftCli {
+        /// create a new BftCli struct
+        pub fn new() -> Self {
+            BftCli::parse()
+        }
+
+        /// create a new BftCli struct, the options not given taking their
+        /// value out of `defaults`, pairs of an option id and its value
+        pub fn with_defaults(defaults: &[(&str, String)]) -> Self {
+            let matches = command_with_defaults(<BftCli as CommandFactory>::command(), defaults)
+                .get_matches();
+            BftCli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+        }
+
+        /// get subcommand, None to interpret `PROGRAM`
+        pub fn command(&self) -> Option<&BftCommand> {
+            self.command.as_ref()
+        }
+
+        /// get number of `-v` given
+        pub fn verbose(&self) -> u8 {
+            self.verbose
+        }
+
+        /// get whether `-q` is given
+        pub fn quiet(&self) -> bool {
+            self.quiet
+        }
+
+        /// get form of the errors and warnings
+        pub fn diagnostics_format(&self) -> BftDiagnosticsFormat {
+            self.diagnostics_format
+        }
+
+        /// get when the diagnostics and logs are colored
+        pub fn color(&self) -> BftColor {
+            self.color
+        }
+
+        /// get arguments of `run` given without the subcommand
+        pub fn run_args(&self) -> &BftRunArgs {
+            &self.run
+        }
+    }
+
+    impl BftRunArgs {
+        /// get application name, the first one if there are several,
+        /// `<eval>` for a program given with `--eval`
+        pub fn name(&self) -> &Path {
+            self.names().next().unwrap_or(Path::new(EVAL_NAME))
+        }
+
+        /// get application names in the order they run
+        pub fn names(&self) -> impl Iterator<Item = &Path> {
+            self.names.iter().map(PathBuf::as_path)
+        }
+
+        /// get program given on the command line
+        pub fn eval(&self) -> Option<&str> {
+            self.eval.as_deref()
+        }
+
+        /// get type size
+        pub fn cells_size(&self) -> usize {
+            // the strict profile overrides the defaults of a config file or
+            // of the environment
+            if self.strict {
+                30000
+            } else {
+                self.cells.get()
+            }
+        }
+
+        /// get extensible flag
+        pub fn cells_extensible(&self) -> bool {
+            self.allow_extend && !self.strict
+        }
+
+        /// get cell width
+        pub fn cell_width(&self) -> BFCellWidth {
+            if self.strict {
+                BFCellWidth::U8
+            } else {
+                self.cell_width.into()
+            }
+        }
+
+        /// whether the tape is sized from the program
+        pub fn auto_size(&self) -> bool {
+            self.auto_size
+        }
+
+        /// get interpreter backend
+        pub fn backend(&self) -> BFBackend {
+            self.backend.into()
+        }
+
+        /// get instruction budget
+        pub fn fuel(&self) -> Option<u64> {
+            self.fuel
+        }
+
+        /// get wall-clock budget, None for unlimited
+        pub fn timeout(&self) -> Option<Duration> {
+            self.timeout.map(Duration::from_secs_f64)
+        }
+
+        /// get optimization level
+        pub fn opt_level(&self) -> BFOptLevel {
+            self.opt_level.into()
+        }
+
+        /// get emit IR flag
+        pub fn emit_ir(&self) -> bool {
+            self.emit_ir
+        }
+
+        /// whether a newline is printed after the output if missing
+        pub fn final_newline(&self) -> bool {
+            !self.no_final_newline && !self.strict
+        }
+
+        /// whether a prompt is printed before reading a value
+        pub fn prompt(&self) -> bool {
+            !self.strict && self.input.is_none()
+        }
+
+        /// get input file, None to read stdin
+        pub fn input(&self) -> Option<&Path> {
+            self.input.as_deref()
+        }
+
+        /// get output file, None to write to stdout
+        pub fn output(&self) -> Option<&Path> {
+            self.output.as_deref()
+        }
+
+        /// get file copying the output
+        pub fn tee(&self) -> Option<&Path> {
+            self.tee.as_deref()
+        }
+
+        /// get what reading does once input is exhausted
+        pub fn eof(&self) -> BFEofBehavior {
+            match self.eof {
+                _ if self.strict => BFEofBehavior::Unchanged,
+                Some(eof) => eof.into(),
+                None => BFEofBehavior::Error,
+            }
+        }
+
+        /// get how the line endings of the input are translated
+        pub fn newline(&self) -> BFNewline {
+            self.newline.into()
+        }
+
+        /// get head mode
+        pub fn head_mode(&self) -> BFHeadMode {
+            if self.strict {
+                BFHeadMode::Strict
+            } else {
+                self.head_mode.into()
+            }
+        }
+
+        /// get the cell holding the exit status, None to exit successfully
+        pub fn exit_cell(&self) -> Option<usize> {
+            self.exit_cell
+        }
+
+        /// whether the `?` random byte command is enabled
+        pub fn random(&self) -> bool {
+            self.random
+        }
+
+        /// get seed of the random bytes, None to take one from the clock
+        pub fn seed(&self) -> Option<u64> {
+            self.seed
+        }
+
+        /// get trace file, `-` for stderr, None if not tracing
+        pub fn trace(&self) -> Option<&Path> {
+            self.trace.as_deref()
+        }
+
+        /// get whether the digest of the run is printed
+        pub fn digest(&self) -> bool {
+            self.digest
+        }
+
+        /// get whether a timing summary of the run is printed
+        pub fn time(&self) -> bool {
+            self.time
+        }
+
+        /// get whether the programs run again when their files change
+        pub fn watch(&self) -> bool {
+            self.watch
+        }
+
+        /// get which executed instructions are written to the trace
+        pub fn trace_filter(&self) -> BFTraceFilter {
+            let (first, end) = self.trace_steps.unwrap_or((0, None));
+            BFTraceFilter {
+                ops: self.trace_ops.into(),
+                first,
+                end,
+            }
         }
     }
 }
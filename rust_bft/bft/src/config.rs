@@ -0,0 +1,162 @@
+//! Defaults of the command line options
+//!
+//! `~/.config/bft/config.toml`, or the file given with `--config`, holds
+//! defaults for the options of every subcommand taking them:
+//!
+//! ```toml
+//! cells = 65536
+//! extensible = true
+//! cell-width = 16
+//! eof = "zero"
+//! opt-level = 3
+//!
+//! [dialects.blub]
+//! "+" = "Blub. Blub."
+//! "-" = "Blub! Blub!"
+//! "<" = "Blub? Blub."
+//! ">" = "Blub. Blub?"
+//! "," = "Blub. Blub!"
+//! "." = "Blub! Blub."
+//! "[" = "Blub! Blub?"
+//! "]" = "Blub? Blub!"
+//! separator = " "
+//! ```
+//!
+//! Every `[dialects.NAME]` table spells the eight commands for `bft
+//! translate --from NAME` or `--to NAME`, its tokens written separated by
+//! `separator`, a space if not given.
+//!
+//! An option given on the command line or by its `BFT_*` environment
+//! variable overrides its default, a missing default config file is an
+//! empty one.
+
+use bft_types::dialect::BFDialect;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+/// Defaults read from a config file, None for the built-in ones
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BftConfig {
+    cells: Option<usize>,
+    extensible: Option<bool>,
+    cell_width: Option<u32>,
+    eof: Option<String>,
+    opt_level: Option<u8>,
+    #[serde(default)]
+    dialects: BTreeMap<String, BftDialectConfig>,
+}
+
+/// Tokens of a dialect read from a `[dialects.NAME]` table
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BftDialectConfig {
+    #[serde(rename = "+")]
+    increment: String,
+    #[serde(rename = "-")]
+    decrement: String,
+    #[serde(rename = "<")]
+    left: String,
+    #[serde(rename = ">")]
+    right: String,
+    #[serde(rename = ",")]
+    read: String,
+    #[serde(rename = ".")]
+    write: String,
+    #[serde(rename = "[")]
+    open: String,
+    #[serde(rename = "]")]
+    close: String,
+    separator: Option<String>,
+}
+
+impl BftConfig {
+    /// Load the config file given with `--config` in `args`, or the
+    /// default one if it exists
+    pub fn load(args: &[OsString]) -> Result<Self, Box<dyn Error>> {
+        let (path, given) = match config_arg(args) {
+            Some(path) => (path, true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if !given && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+        };
+        toml::from_str(&source).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Defaults as the ids of the options with their value on the command
+    /// line
+    pub fn defaults(&self) -> Vec<(&'static str, String)> {
+        let mut defaults = Vec::new();
+        if let Some(cells) = self.cells {
+            defaults.push(("cells", cells.to_string()));
+        }
+        if let Some(extensible) = self.extensible {
+            defaults.push(("allow_extend", extensible.to_string()));
+        }
+        if let Some(width) = self.cell_width {
+            defaults.push(("cell_width", width.to_string()));
+        }
+        if let Some(eof) = &self.eof {
+            defaults.push(("eof", eof.clone()));
+        }
+        if let Some(level) = self.opt_level {
+            defaults.push(("opt_level", level.to_string()));
+        }
+        defaults
+    }
+
+    /// Dialect of the `[dialects.NAME]` table, None if there is none,
+    /// error if its tokens don't tell the commands apart
+    pub fn dialect(&self, name: &str) -> Option<Result<BFDialect, String>> {
+        let config = self.dialects.get(name)?;
+        let tokens = [
+            config.increment.as_str(),
+            &config.decrement,
+            &config.left,
+            &config.right,
+            &config.read,
+            &config.write,
+            &config.open,
+            &config.close,
+        ];
+        let separator = config.separator.as_deref().unwrap_or(" ");
+        Some(BFDialect::new(tokens, separator).map_err(|e| format!("dialect {}: {}", name, e)))
+    }
+}
+
+/// path given with `--config PATH` or `--config=PATH`
+fn config_arg(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(path.into());
+        }
+    }
+    None
+}
+
+/// `$XDG_CONFIG_HOME/bft/config.toml`, `~/.config/bft/config.toml` if
+/// it isn't set
+fn default_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("bft").join("config.toml"))
+}
@@ -0,0 +1,216 @@
+//! Interactive debugger
+//!
+//! `bft debug` runs the program one packed instruction at a time and reads
+//! its commands from stdin, one per line, an empty line repeating the last
+//! command:
+//!
+//! ```text
+//! step [N]          s   execute N instructions, 1 if not given
+//! continue          c   run up to the next breakpoint or the end
+//! break LINE:COL    b   stop before the instruction at LINE:COL runs
+//! delete LINE:COL   d   remove a breakpoint
+//! print [N]         p   print the cells within N of the head, 4 if not given
+//! where             w   print where the program stopped
+//! quit              q   leave the debugger
+//! ```
+//!
+//! The program reads the `--input` file and writes to stdout like the
+//! debugger messages. A breakpoint stops an optimized program before the
+//! packed instruction built out of its source instruction.
+
+use crate::cli::bft_cli_mod::BftDebugArgs;
+use bft_interp::BFVirtualMachine;
+use bft_types::{BFProgram, BFValidProgram};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+/// index of the source instruction at `LINE:COL`
+fn instruction_at(bf_info: &BFValidProgram, position: &str) -> Result<usize, String> {
+    let parsed = position.split_once(':').and_then(|(line, column)| {
+        Some((line.parse::<usize>().ok()?, column.parse::<usize>().ok()?))
+    });
+    let Some((line, column)) = parsed else {
+        return Err(format!("`{}` isn't a LINE:COL position", position));
+    };
+    bf_info
+        .instructions()
+        .iter()
+        .position(|info| info.line() == line && info.column() == column)
+        .ok_or_else(|| format!("no instruction at {}", position))
+}
+
+/// the cells within `radius` of `head` as `cells START..END: VALUES`, the
+/// head cell bracketed
+pub fn cells_around(cells: &[u8], head: usize, radius: usize) -> String {
+    let start = head.saturating_sub(radius);
+    let end = (head + radius + 1).min(cells.len());
+    let values: Vec<String> = (start..end)
+        .map(|index| {
+            if index == head {
+                format!("[{}]", cells[index])
+            } else {
+                cells[index].to_string()
+            }
+        })
+        .collect();
+    format!("cells {}..{}: {}", start, end, values.join(" "))
+}
+
+/// Debugging session of a program
+struct BftDebugger<'a> {
+    bf_info: &'a BFValidProgram,
+    bf_vm: BFVirtualMachine<'a, u8>,
+    input: &'a [u8],
+
+    /// source indices of the breakpoints
+    breakpoints: BTreeSet<usize>,
+
+    /// whether the program halted or failed
+    stopped: bool,
+}
+
+impl BftDebugger<'_> {
+    /// whether a breakpoint is set within the next packed instruction
+    fn at_breakpoint(&self) -> bool {
+        let span = self.bf_vm.code().source_span(self.bf_vm.program_counter());
+        self.breakpoints
+            .range(span.first()..=span.last())
+            .next()
+            .is_some()
+    }
+
+    /// execute the next instruction, false once the program stopped
+    fn step(&mut self) -> bool {
+        if self.stopped {
+            return false;
+        }
+        match self.bf_vm.step(&mut self.input, &mut io::stdout()) {
+            Ok(true) => true,
+            Ok(false) => {
+                println!("program halted after {} steps", self.bf_vm.stats().steps());
+                self.stopped = true;
+                false
+            }
+            Err(e) => {
+                println!("program failed: {}", e);
+                self.stopped = true;
+                false
+            }
+        }
+    }
+
+    /// print where the program stopped
+    fn print_where(&self) {
+        if self.stopped {
+            println!("program stopped");
+            return;
+        }
+        let pc = self.bf_vm.program_counter();
+        let op = self.bf_vm.code().ops()[pc];
+        let head = self.bf_vm.head();
+        println!(
+            "at {} {:?} {}, head {} cell {}",
+            self.bf_vm.code().source_span(pc).location(self.bf_info),
+            op.opcode(),
+            op.operand(),
+            head,
+            self.bf_vm.cells()[head]
+        );
+    }
+
+    /// print the cells within `radius` of the head, the head cell bracketed
+    fn print_cells(&self, radius: usize) {
+        println!(
+            "{}",
+            cells_around(self.bf_vm.cells(), self.bf_vm.head(), radius)
+        );
+    }
+
+    /// run `command`, false to leave the debugger
+    fn run(&mut self, command: &str) -> Result<bool, String> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        let count = |default: usize| match argument {
+            Some(count) => count
+                .parse::<usize>()
+                .map_err(|_| format!("`{}` isn't a count", count)),
+            None => Ok(default),
+        };
+        match name {
+            "step" | "s" => {
+                for _ in 0..count(1)? {
+                    if !self.step() {
+                        break;
+                    }
+                }
+                self.print_where();
+            }
+            "continue" | "c" => {
+                while self.step() {
+                    if self.at_breakpoint() {
+                        println!("breakpoint");
+                        self.print_where();
+                        break;
+                    }
+                }
+            }
+            "break" | "b" | "delete" | "d" => {
+                let position = argument.ok_or("a LINE:COL position is required")?;
+                let index = instruction_at(self.bf_info, position)?;
+                if name.starts_with('b') {
+                    self.breakpoints.insert(index);
+                    println!("breakpoint at {}", position);
+                } else if self.breakpoints.remove(&index) {
+                    println!("deleted breakpoint at {}", position);
+                } else {
+                    println!("no breakpoint at {}", position);
+                }
+            }
+            "print" | "p" => self.print_cells(count(4)?),
+            "where" | "w" => self.print_where(),
+            "quit" | "q" => return Ok(false),
+            _ => return Err(format!("unknown command `{}`", name)),
+        }
+        Ok(true)
+    }
+}
+
+/// debug a bft program with the commands read from stdin
+pub fn debug(args: &BftDebugArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let bf_info = BFProgram::new(args.name(), &crate::load_source(args.name())?).validate()?;
+    let input = match args.input() {
+        Some(path) => fs::read(path)?,
+        None => Vec::new(),
+    };
+    let mut bf_vm = BFVirtualMachine::new(args.cells_size(), args.cells_extensible(), &bf_info);
+    bf_vm.set_opt_level(args.opt_level());
+    bf_vm.set_prompt(false);
+    bf_vm.set_eof(args.eof());
+    let mut debugger = BftDebugger {
+        bf_info: &bf_info,
+        stopped: bf_vm.halted(),
+        bf_vm,
+        input: &input,
+        breakpoints: BTreeSet::new(),
+    };
+
+    debugger.print_where();
+    let mut last = String::new();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let command = match line.trim() {
+            "" => last.clone(),
+            command => command.to_string(),
+        };
+        match debugger.run(&command) {
+            Ok(true) => last = command,
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,368 @@
+//! Errors and warnings of bft as diagnostics
+//!
+//! Diagnostics are printed to stderr with their severity, code, position
+//! and the source line of their instruction:
+//!
+//! ```text
+//! error[head-out-of-bounds]: the head fell off the tape
+//!  --> hello.bf:2:1
+//!   |
+//! 2 | <+
+//!   | ^
+//! ```
+//!
+//! in colors with `--color always`, or with `--color auto` when stderr is
+//! a terminal and `NO_COLOR` isn't set. Or each diagnostic is printed as a
+//! single line JSON object with `--diagnostics-format json`:
+//!
+//! ```text
+//! {"code":"head-out-of-bounds","column":1,"file":"hello.bf","help":null,"line":2,
+//!  "message":"the head fell off the tape","severity":"error",
+//!  "span":{"column_end":2,"column_start":1,"line_end":2,"line_start":2}}
+//! ```
+//!
+//! `file`, `line`, `column` and `span` are null when the diagnostic isn't
+//! about a program or an instruction, the span covers the instruction.
+//! JSON diagnostics are never colored.
+//!
+//! The exit status of bft failing with an error tells its class, see
+//! `BftExitStatus`.
+
+use crate::cli::bft_cli_mod::{BftColor, BftDiagnosticsFormat};
+use bft_interp::lint::{self, BFSeverity};
+use bft_interp::{BFLimit, BFVmErr};
+use bft_types::{BFCharInfo, BFProgram};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::OnceLock;
+
+/// Form of the diagnostics chosen with `--diagnostics-format`
+static FORMAT: OnceLock<BftDiagnosticsFormat> = OnceLock::new();
+
+/// Whether diagnostics are colored
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+/// Print the diagnostics of the process in `format`, colored if `color`
+pub fn configure(format: BftDiagnosticsFormat, color: bool) {
+    let _ = FORMAT.set(format);
+    let _ = COLOR.set(color);
+}
+
+/// Whether `choice` colors what is printed to stderr
+pub fn use_color(choice: BftColor) -> bool {
+    match choice {
+        BftColor::Always => true,
+        BftColor::Never => false,
+        BftColor::Auto => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !no_color && io::stderr().is_terminal()
+        }
+    }
+}
+
+/// `text` in the ANSI `style` if diagnostics are colored
+fn paint(text: &str, style: &str) -> String {
+    if COLOR.get().copied().unwrap_or(false) {
+        format!("\x1b[{}m{}\x1b[0m", style, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// ANSI style of the severity of an error, bold red
+const ERROR_STYLE: &str = "1;31";
+
+/// ANSI style of the severity of a warning, bold yellow
+const WARNING_STYLE: &str = "1;33";
+
+/// ANSI style of the position and the margin, bold blue
+const GUTTER_STYLE: &str = "1;34";
+
+/// ANSI style of a suggestion, bold cyan
+const HELP_STYLE: &str = "1;36";
+
+/// ANSI style of the message, bold
+const MESSAGE_STYLE: &str = "1";
+
+/// Exit status of bft failing, stable for scripts to branch on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BftExitStatus {
+    /// any other failure
+    Failure = 1,
+
+    /// the command line is wrong
+    Usage = 2,
+
+    /// a program doesn't parse, or `check` found errors
+    Parse = 3,
+
+    /// the virtual machine failed running a program
+    Runtime = 4,
+
+    /// reading or writing a file, a stream or a URL failed
+    Io = 5,
+
+    /// the program ran past `--timeout`
+    Timeout = 124,
+}
+
+impl From<BftExitStatus> for ExitCode {
+    fn from(status: BftExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// Error or warning of bft, with the program and instruction it is about
+#[derive(Debug)]
+pub struct BftDiagnostic {
+    severity: BFSeverity,
+    code: &'static str,
+    message: String,
+    file: Option<PathBuf>,
+    cmd: Option<BFCharInfo>,
+    help: Option<&'static str>,
+    status: BftExitStatus,
+
+    /// source line of the instruction
+    snippet: Option<String>,
+
+    /// error the diagnostic was made of
+    source: Option<Box<dyn Error>>,
+}
+
+impl BftDiagnostic {
+    /// Create a diagnostic about no program in particular
+    pub fn new(severity: BFSeverity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            file: None,
+            cmd: None,
+            help: None,
+            status: BftExitStatus::Failure,
+            snippet: None,
+            source: None,
+        }
+    }
+
+    /// Create an error about the command line
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self {
+            status: BftExitStatus::Usage,
+            ..Self::new(BFSeverity::Error, "error", message)
+        }
+    }
+
+    /// Set the program the diagnostic is about
+    pub fn in_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Set the instruction the diagnostic is about
+    pub fn at(mut self, cmd: Option<BFCharInfo>) -> Self {
+        self.cmd = cmd;
+        self
+    }
+
+    /// Show the line of the instruction out of `source`, the source of the
+    /// program
+    pub fn with_source(mut self, source: &str) -> Self {
+        let line = self.cmd.and_then(|cmd| source.lines().nth(cmd.line() - 1));
+        self.snippet = line.map(str::to_string);
+        self
+    }
+
+    /// Suggest how to avoid the diagnostic
+    pub fn with_help(mut self, help: &'static str) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    /// Diagnostic of an error of the virtual machine running `file`
+    pub fn vm_err(e: BFVmErr, file: &Path) -> Self {
+        let message = match &e {
+            BFVmErr::HeadInvalidPositionErr(_) => "the head fell off the tape".to_string(),
+            BFVmErr::IOErr(e) => e.err().to_string(),
+            BFVmErr::FuelExhaustedErr(_) => "fuel exhausted".to_string(),
+            BFVmErr::NewlineErr(e) => format!("{} writing the final newline", e),
+            BFVmErr::LimitErr(limit, _) => limit.to_string(),
+        };
+        let status = match &e {
+            BFVmErr::IOErr(_) | BFVmErr::NewlineErr(_) => BftExitStatus::Io,
+            BFVmErr::LimitErr(BFLimit::Timeout, _) => BftExitStatus::Timeout,
+            _ => BftExitStatus::Runtime,
+        };
+        let diagnostic = Self::new(BFSeverity::Error, e.code_name(), message)
+            .in_file(file)
+            .at(e.cmd());
+        Self {
+            status,
+            source: Some(e.into()),
+            ..diagnostic
+        }
+    }
+
+    /// Diagnostic of the first unmatched bracket of `bf_info` failing to
+    /// validate with `e`, in the file the bracket came from
+    pub fn unmatched(e: Box<dyn Error>, bf_info: &BFProgram) -> Self {
+        let first = lint::check(bf_info)
+            .into_iter()
+            .find(|d| d.severity() == BFSeverity::Error);
+        let (code, message) = first.map_or(("error", e.to_string()), |d| {
+            (d.kind().code(), d.kind().message().to_string())
+        });
+        let file = first.map_or(bf_info.filename(), |d| bf_info.file_name(d.cmd().file()));
+        let diagnostic = Self::new(BFSeverity::Error, code, message)
+            .in_file(file)
+            .at(first.map(|d| d.cmd()));
+        Self {
+            status: BftExitStatus::Parse,
+            source: Some(e),
+            ..diagnostic
+        }
+    }
+
+    /// Diagnostic of any error of bft
+    pub fn from_error(e: Box<dyn Error>) -> Self {
+        match e.downcast::<Self>() {
+            Ok(diagnostic) => *diagnostic,
+            Err(e) => {
+                let (code, status) = if e.is::<io::Error>() {
+                    ("io", BftExitStatus::Io)
+                } else if is_fetch_error(&*e) {
+                    ("error", BftExitStatus::Io)
+                } else {
+                    ("error", BftExitStatus::Failure)
+                };
+                let diagnostic = Self::new(BFSeverity::Error, code, e.to_string());
+                Self {
+                    status,
+                    source: Some(e),
+                    ..diagnostic
+                }
+            }
+        }
+    }
+
+    /// Exit status of bft failing with the diagnostic
+    pub fn exit_status(&self) -> BftExitStatus {
+        self.status
+    }
+
+    /// Virtual machine error the diagnostic is made of, if any
+    pub fn vm_source(&self) -> Option<&BFVmErr> {
+        self.source.as_ref()?.downcast_ref()
+    }
+
+    /// The diagnostic as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        let span = self.cmd.map(|cmd| {
+            serde_json::json!({
+                "line_start": cmd.line(),
+                "column_start": cmd.column(),
+                "line_end": cmd.line(),
+                "column_end": cmd.column() + 1,
+            })
+        });
+        serde_json::json!({
+            "severity": self.severity.to_string(),
+            "code": self.code,
+            "message": self.message,
+            "file": self.file.as_ref().map(|file| file.display().to_string()),
+            "line": self.cmd.map(|cmd| cmd.line()),
+            "column": self.cmd.map(|cmd| cmd.column()),
+            "help": self.help,
+            "span": span,
+        })
+    }
+
+    /// The diagnostic for a human, with its position and source line
+    pub fn render(&self) -> String {
+        let style = match self.severity {
+            BFSeverity::Error => ERROR_STYLE,
+            BFSeverity::Warning => WARNING_STYLE,
+        };
+        // errors bft knows nothing more about only take their severity
+        let label = match self.code {
+            "error" => self.severity.to_string(),
+            code => format!("{}[{}]", self.severity, code),
+        };
+        let mut out = format!(
+            "{}{}\n",
+            paint(&label, style),
+            paint(&format!(": {}", self.message), MESSAGE_STYLE)
+        );
+
+        let line = self
+            .cmd
+            .map(|cmd| cmd.line().to_string())
+            .unwrap_or_default();
+        let margin = " ".repeat(line.len().max(1));
+        if let Some(file) = &self.file {
+            let position = match self.cmd {
+                Some(cmd) => format!("{}:{}:{}", file.display(), cmd.line(), cmd.column()),
+                None => file.display().to_string(),
+            };
+            out += &format!("{}{} {}\n", margin, paint("-->", GUTTER_STYLE), position);
+        }
+        if let (Some(cmd), Some(snippet)) = (self.cmd, &self.snippet) {
+            // the caret is aligned with the same tabs as the source line
+            let indent: String = snippet
+                .chars()
+                .take(cmd.column() - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let bar = paint("|", GUTTER_STYLE);
+            out += &format!("{} {}\n", margin, bar);
+            out += &format!("{} {} {}\n", paint(&line, GUTTER_STYLE), bar, snippet);
+            out += &format!("{} {} {}{}\n", margin, bar, indent, paint("^", style));
+        }
+        if let Some(help) = self.help {
+            out += &format!("{} {} {}\n", margin, paint("= help:", HELP_STYLE), help);
+        }
+        out
+    }
+
+    /// Print the diagnostic to stderr, a warning only unless `-q` silenced
+    /// warnings
+    pub fn emit(&self) {
+        if self.severity == BFSeverity::Warning && !tracing::enabled!(tracing::Level::WARN) {
+            return;
+        }
+        match FORMAT.get() {
+            Some(BftDiagnosticsFormat::Json) => eprintln!("{}", self.to_json()),
+            _ => eprint!("{}", self.render()),
+        }
+    }
+}
+
+/// whether `e` is an error fetching a program from a URL
+#[cfg(feature = "url")]
+fn is_fetch_error(e: &(dyn Error + 'static)) -> bool {
+    e.is::<bft_types::url::BFFetchError>()
+}
+
+/// whether `e` is an error fetching a program from a URL
+#[cfg(not(feature = "url"))]
+fn is_fetch_error(_e: &(dyn Error + 'static)) -> bool {
+    false
+}
+
+impl fmt::Display for BftDiagnostic {
+    /// print the message of the diagnostic
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for BftDiagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref()
+    }
+}
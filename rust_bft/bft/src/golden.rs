@@ -0,0 +1,127 @@
+//! Golden-file tests
+//!
+//! A directory of programs is a test suite: every `NAME.b` or `NAME.bf`
+//! next to a `NAME.expected` file is a test, run on `NAME.in` if it exists
+//! and passing when it writes exactly the expected file. Programs without
+//! an expected file are left alone, so a suite can hold helpers.
+//!
+//! Tests run concurrently with the batch runner under the limits of the
+//! `test` arguments, a failing test is reported with the first line its
+//! output differs on.
+
+use crate::cli::bft_cli_mod::BftTestArgs;
+use bft_interp::batch::{self, BFBatchJob, BFBatchOutcome, BFBatchResult, BFBatchSummary};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Instant;
+
+/// programs of `dir` and its subdirectories having an expected file
+fn discover(dir: &Path, tests: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            discover(&path, tests)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "b" || ext == "bf")
+            && path.with_extension("expected").is_file()
+        {
+            tests.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// describe the first line `output` differs from `expected` on
+fn first_difference(expected: &[u8], output: &[u8]) -> String {
+    let mut expected_lines = expected.split_inclusive(|&b| b == b'\n');
+    let mut output_lines = output.split_inclusive(|&b| b == b'\n');
+    let show = |line: Option<&[u8]>| match line {
+        Some(line) => format!("{:?}", String::from_utf8_lossy(line)),
+        None => "end of output".to_string(),
+    };
+    for number in 1.. {
+        let (want, got) = (expected_lines.next(), output_lines.next());
+        if want != got {
+            return format!(
+                "line {} differs\n      expected: {}\n      actual:   {}",
+                number,
+                show(want),
+                show(got)
+            );
+        }
+    }
+    unreachable!("bft: outputs of a failed test differ")
+}
+
+/// print the report line of a test
+fn report(result: &BFBatchResult, expected: &[u8]) {
+    let label = match result.outcome() {
+        BFBatchOutcome::Passed => "PASS",
+        BFBatchOutcome::Failed => "FAIL",
+        _ => "ERROR",
+    };
+    println!(
+        "{:<6}{} ({:.3}ms)",
+        label,
+        result.name(),
+        result.elapsed().as_secs_f64() * 1000.0
+    );
+    match result.outcome() {
+        BFBatchOutcome::Failed => println!("      {}", first_difference(expected, result.output())),
+        BFBatchOutcome::Errored(e) => println!("      {}", e),
+        _ => (),
+    }
+}
+
+/// run the golden tests found by the `test` arguments
+pub fn test(args: &BftTestArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let mut tests = Vec::new();
+    for path in args.paths() {
+        discover(path, &mut tests)?;
+    }
+    if tests.is_empty() {
+        return Err("no golden test found".into());
+    }
+
+    let mut jobs = Vec::new();
+    for path in &tests {
+        jobs.push(BFBatchJob {
+            name: path.display().to_string(),
+            source: fs::read_to_string(path)?,
+            input: fs::read(path.with_extension("in")).unwrap_or_default(),
+            expected: Some(fs::read(path.with_extension("expected"))?),
+        });
+    }
+    let threads = match args.jobs() {
+        Some(jobs) => jobs,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    let start = Instant::now();
+    let results = batch::run_batch(&jobs, &args.run_options(), threads);
+    let elapsed = start.elapsed();
+    for (result, job) in results.iter().zip(&jobs) {
+        report(result, job.expected.as_deref().unwrap_or_default());
+    }
+    let summary = BFBatchSummary::new(&results);
+    println!(
+        "{} tests, {} passed, {} failed, {} errors in {:.3}ms",
+        results.len(),
+        summary.passed,
+        summary.failed,
+        summary.errored,
+        elapsed.as_secs_f64() * 1000.0
+    );
+    Ok(if summary.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
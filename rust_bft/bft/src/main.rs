@@ -3,36 +3,1017 @@
 //! Parsing brainfuck instructions from files then running the program
 //! on the brainfuck interpreter with a virtual machine.
 
-use bft_interp::BFVirtualMachine;
-use bft_types::BFProgram;
+use bft_interp::analysis::{BFLoop, BFLoopTree, BFProgramMetrics, BFTapeUsage};
+use bft_interp::batch::{self, BFBatchJob, BFBatchOutcome, BFBatchResult, BFBatchSummary};
+use bft_interp::emit::{self, BFEmitOptions};
+use bft_interp::extension::{BFExtensions, BFRandom};
+use bft_interp::lint::{self, BFSeverity};
+use bft_interp::verify::{self, BFRunTrace, BFSemantics, BFSemanticsReport};
+use bft_interp::{digest, trace};
+use bft_interp::{
+    BFBackend, BFCellWidth, BFOptLevel, BFPackedProgram, BFRunOptions, BFTee, BFVirtualMachine,
+    BFVmErr, CellKind,
+};
+use bft_types::dialect::BFDialect;
+use bft_types::format::{self, format_source};
+#[cfg(feature = "url")]
+use bft_types::url;
+use bft_types::{BFProgram, BFValidProgram};
+use clap::CommandFactory;
+use diagnostic::{BftDiagnostic, BftExitStatus};
 use std::error::Error;
-use std::io;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::slice;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing_subscriber::filter::LevelFilter;
 
+mod archive;
+mod bench;
 mod cli;
-pub use crate::cli::bft_cli_mod::BftCli;
+mod config;
+mod debug;
+mod diagnostic;
+mod golden;
+mod repl;
+mod serve;
+mod stream;
+#[cfg(feature = "llvm")]
+mod validate;
+pub use crate::cli::bft_cli_mod::{
+    BftCheckArgs, BftCli, BftCommand, BftCompileArgs, BftFmtArgs, BftRunAllArgs, BftRunArgs,
+    BftServeArgs, BftStatsArgs, BftStatsFormat, BftTranslateArgs, BftVerifyArgs, BftVisualizeArgs,
+    BftVisualizeFormat,
+};
 
-/// run bft program with cli arguments
-fn bft_run(cli: &BftCli) -> Result<(), Box<dyn Error>> {
-    let mut bf_info = BFProgram::from_file(cli.name())?;
-    bf_info.match_square_bracket()?;
+/// instruction budget of each semantics unless `--fuel` is given
+const SEMANTICS_FUEL: u64 = 10_000_000;
 
-    let size = cli.cells_size();
-    let extend = cli.cells_extensible();
-    let mut bf_vm = BFVirtualMachine::<u8>::new(size, extend, &bf_info);
+/// source of the program `name`, fetched if it's a URL, read from stdin
+/// if it's `-`
+fn load_source(name: &Path) -> Result<String, Box<dyn Error>> {
+    if name.as_os_str() == "-" {
+        return Ok(io::read_to_string(io::stdin())?);
+    }
+    #[cfg(feature = "url")]
+    if let Some(url) = name.to_str().filter(|name| url::is_url(name)) {
+        return Ok(url::fetch_source(url, url::MAX_SOURCE_LEN)?);
+    }
+    Ok(fs::read_to_string(name)?)
+}
+
+/// Programs run by `bft run`
+struct BftSources {
+    /// name and source of every program, in the order they run
+    programs: Vec<(PathBuf, String)>,
+
+    /// input of the programs if stdin held a program and its input,
+    /// everything after the first `!` of stdin
+    input: Option<Vec<u8>>,
+}
+
+/// programs run by `args`, given with `--eval` or loaded, stdin only holds
+/// a program if the input is read from `--input`
+fn run_sources(args: &BftRunArgs) -> Result<BftSources, Box<dyn Error>> {
+    if let Some(source) = args.eval() {
+        return Ok(BftSources {
+            programs: vec![(args.name().into(), source.to_string())],
+            input: None,
+        });
+    }
+    let mut sources = BftSources {
+        programs: Vec::new(),
+        input: None,
+    };
+    for name in args.names() {
+        if name.as_os_str() != "-" || args.input().is_some() {
+            sources.programs.push((name.into(), load_source(name)?));
+            continue;
+        }
+        if sources.input.is_some() {
+            return Err(BftDiagnostic::usage("only one PROGRAM can be read from stdin").into());
+        }
+        let mut stdin = Vec::new();
+        io::Read::read_to_end(&mut io::stdin(), &mut stdin)?;
+        let (source, input) = match stdin.iter().position(|&byte| byte == b'!') {
+            Some(bang) => (&stdin[..bang], &stdin[bang + 1..]),
+            None => (&stdin[..], &[][..]),
+        };
+        let source = String::from_utf8(source.to_vec())?;
+        sources.programs.push((name.into(), source));
+        sources.input = Some(input.to_vec());
+    }
+    Ok(sources)
+}
+
+/// log to stderr at the level chosen with `-v` and `-q`, warnings if
+/// neither is given, colored if `color`
+fn init_logging(cli: &BftCli, color: bool) {
+    let level = match (cli.quiet(), cli.verbose()) {
+        (true, _) => LevelFilter::OFF,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(color)
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+/// extension commands of the programs run with `args`
+fn run_extensions<T: CellKind>(args: &BftRunArgs) -> BFExtensions<'static, T> {
+    let mut extensions = BFExtensions::new();
+    if args.random() {
+        let seed = args.seed().unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            now.map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+        extensions.register('?', BFRandom::new(seed));
+    }
+    extensions
+}
+
+/// Validated programs run one after another on the same tape
+struct BftPrograms {
+    /// the programs in their order
+    parts: Vec<Arc<BFValidProgram>>,
+
+    /// their concatenation named after the first, its instructions keeping
+    /// their own files, which is what the tape is sized and the statistics
+    /// are taken from
+    whole: Arc<BFValidProgram>,
+
+    /// how long parsing and validating the programs took
+    parse_time: Duration,
+}
+
+/// `--time` summary of a run taking `parse`, `pack` and `execute` to
+/// parse, optimize and execute `steps` instructions
+fn time_summary(parse: Duration, pack: Duration, execute: Duration, steps: u64) -> String {
+    let millis = |time: Duration| time.as_secs_f64() * 1000.0;
+    let rate = steps as f64 / execute.as_secs_f64().max(f64::MIN_POSITIVE);
+    format!(
+        "parse {:.3}ms, optimize {:.3}ms, execute {:.3}ms, {} instructions, {:.0} instructions/s",
+        millis(parse),
+        millis(pack),
+        millis(execute),
+        steps,
+        rate
+    )
+}
+
+/// parse and validate `programs` given with `args`, keeping the
+/// `extensions` commands
+fn parse_programs(
+    args: &BftRunArgs,
+    programs: &[(PathBuf, String)],
+    extensions: &[char],
+) -> Result<BftPrograms, Box<dyn Error>> {
+    let mut parts = Vec::new();
+    let mut parse_time = Duration::ZERO;
+    for (name, source) in programs {
+        let parsing = Instant::now();
+        let mut bf_info = BFProgram::with_extensions(name, source, extensions);
+        if let Err(e) = bf_info.match_square_bracket() {
+            let diagnostic = BftDiagnostic::unmatched(e, &bf_info).with_source(source);
+            return Err(diagnostic.into());
+        }
+        let bf_info = bf_info.validate()?;
+        parse_time += parsing.elapsed();
+        tracing::info!(
+            program = %name.display(),
+            instructions = bf_info.instructions().len(),
+            elapsed = ?parsing.elapsed(),
+            "parsed"
+        );
+        parts.push(Arc::new(bf_info));
+    }
+    let whole = match parts.as_slice() {
+        [bf_info] => bf_info.clone(),
+        _ => {
+            let sources: Vec<_> = programs
+                .iter()
+                .map(|(name, source)| (name, source.as_str()))
+                .collect();
+            let bf_info = BFProgram::compose(args.name(), &sources, extensions);
+            Arc::new(bf_info.validate()?)
+        }
+    };
+    Ok(BftPrograms {
+        parts,
+        whole,
+        parse_time,
+    })
+}
+
+/// how often `--watch` looks for changes of the watched files
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// modification time and length of the files of `paths`, None for a file
+/// that can't be read
+fn watched_state(paths: &[&Path]) -> Vec<Option<(SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+/// run bft programs like `bft_run_once` again every time a program or the
+/// `--input` file changes, until the process is stopped
+fn bft_watch(args: &BftRunArgs, stats: Option<BftStatsFormat>) -> Result<ExitCode, Box<dyn Error>> {
+    let mut paths: Vec<&Path> = args.names().collect();
+    if paths.iter().any(|path| path.as_os_str() == "-") {
+        return Err(
+            BftDiagnostic::usage("--watch can't run a PROGRAM read from stdin again").into(),
+        );
+    }
+    paths.extend(args.input());
+    if paths.is_empty() {
+        return Err(BftDiagnostic::usage("--watch needs a PROGRAM or an --input file").into());
+    }
 
-    bf_vm.interpret(&mut io::stdin(), &mut io::stdout())?;
+    let mut state = watched_state(&paths);
+    loop {
+        if let Err(e) = bft_run_once(args, stats) {
+            BftDiagnostic::from_error(e).emit();
+        }
+        io::stdout().flush()?;
+        let changed = loop {
+            thread::sleep(WATCH_INTERVAL);
+            let now = watched_state(&paths);
+            if let Some(index) = (0..paths.len()).find(|index| now[*index] != state[*index]) {
+                state = now;
+                break paths[index];
+            }
+        };
+        eprintln!(
+            "bft: ---- {} changed, running again ----",
+            changed.display()
+        );
+    }
+}
+
+/// run bft programs, `--watch` running them again on changes
+fn bft_run(args: &BftRunArgs, stats: Option<BftStatsFormat>) -> Result<ExitCode, Box<dyn Error>> {
+    if args.watch() {
+        return bft_watch(args, stats);
+    }
+    bft_run_once(args, stats)
+}
+
+/// run bft programs with cli arguments, one after another on the same
+/// tape, the exit status is taken from the exit cell if one is chosen,
+/// statistics of the programs and of the run are printed to stderr in the
+/// `stats` format if given
+fn bft_run_once(
+    args: &BftRunArgs,
+    stats: Option<BftStatsFormat>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let result = match args.cell_width() {
+        BFCellWidth::U8 => bft_run_cells::<u8>(args, stats),
+        BFCellWidth::U16 => bft_run_cells::<u16>(args, stats),
+        BFCellWidth::U32 => bft_run_cells::<u32>(args, stats),
+    };
+    result.map_err(|e| {
+        let diagnostic = BftDiagnostic::from_error(e);
+        match diagnostic.vm_source() {
+            Some(BFVmErr::IOErr(e)) if e.err().kind() == io::ErrorKind::UnexpectedEof => diagnostic
+                .with_help(
+                    "the input ran out, `--eof zero`, `minus-one` or `unchanged` keep running",
+                )
+                .into(),
+            _ => diagnostic.into(),
+        }
+    })
+}
+
+/// run bft programs like `bft_run` on cells of type `T`, the exit status
+/// is the low byte of the exit cell
+fn bft_run_cells<T>(
+    args: &BftRunArgs,
+    stats: Option<BftStatsFormat>,
+) -> Result<ExitCode, Box<dyn Error>>
+where
+    T: Default + CellKind + Copy + Into<u64> + fmt::Display,
+{
+    let extensions = run_extensions(args);
+    let BftSources { programs, input } = run_sources(args)?;
+    if args.digest() && programs.len() > 1 {
+        return Err(BftDiagnostic::usage("--digest takes a single PROGRAM").into());
+    }
+    // the programs sharing the tape run like their concatenation
+    let BftPrograms {
+        parts: bf_infos,
+        whole,
+        parse_time,
+    } = parse_programs(args, &programs, &extensions.commands())?;
+
+    let usage = BFTapeUsage::analyze(&whole);
+    let (size, extend) = match usage.cells() {
+        Some(cells) if args.auto_size() => (cells, false),
+        None if args.auto_size() => (args.cells_size(), true),
+        _ => (args.cells_size(), args.cells_extensible()),
+    };
+    if usage.left() > 0 {
+        let message = "the head may move left of the first cell";
+        BftDiagnostic::new(BFSeverity::Warning, "head-left", message)
+            .in_file(args.name())
+            .emit();
+    }
+    match usage.cells() {
+        Some(cells) if cells > size && !extend => {
+            let message = format!(
+                "the head may move up to cell {}, past the {} cells of the tape",
+                cells - 1,
+                size
+            );
+            BftDiagnostic::new(BFSeverity::Warning, "tape-overflow", message)
+                .in_file(args.name())
+                .emit();
+        }
+        _ => (),
+    }
+    tracing::info!(cells = size, extensible = extend, "sized the tape");
+    let start = Instant::now();
+    let mut bf_vm = BFVirtualMachine::<T>::new_shared(size, extend, bf_infos[0].clone());
+    bf_vm.set_fuel(args.fuel());
+    bf_vm.set_deadline(args.timeout().map(|timeout| start + timeout));
+    bf_vm.set_opt_level(args.opt_level());
+    bf_vm.set_head_mode(args.head_mode());
+    bf_vm.set_eof(args.eof());
+    bf_vm.set_prompt(args.prompt() && input.is_none());
+    bf_vm.set_newline(args.newline());
+    bf_vm.set_extensions(extensions);
+    let mut stdin: Box<dyn io::Read> = match (input, args.input()) {
+        (Some(input), _) => Box::new(io::Cursor::new(input)),
+        (None, Some(path)) => Box::new(io::BufReader::new(fs::File::open(path)?)),
+        (None, None) => Box::new(io::stdin()),
+    };
+    let mut stdout: Box<dyn Write> = match args.output() {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    if let Some(path) = args.tee() {
+        let transcript = io::BufWriter::new(fs::File::create(path)?);
+        stdout = Box::new(BFTee::new(stdout, transcript));
+    }
+
+    if args.emit_ir() {
+        for (index, bf_info) in bf_infos.iter().enumerate() {
+            if index > 0 {
+                bf_vm.load_shared(bf_info.clone());
+            }
+            let stats = bf_vm.stats();
+            writeln!(
+                stdout,
+                "; bft IR {}, {} ops",
+                stats.opt_level(),
+                stats.ops()
+            )?;
+            write!(stdout, "{}", bf_vm.code().listing(bf_info))?;
+        }
+        stdout.flush()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut trace: Option<Box<dyn Write>> = match args.trace() {
+        Some(path) if path.as_os_str() == "-" => Some(Box::new(io::stderr().lock())),
+        Some(path) => Some(Box::new(io::BufWriter::new(fs::File::create(path)?))),
+        None => None,
+    };
+    let mut pack_time = Duration::ZERO;
+    let mut execute_time = Duration::ZERO;
+    for (index, bf_info) in bf_infos.iter().enumerate() {
+        if index > 0 {
+            bf_vm.load_shared(bf_info.clone());
+        }
+        pack_time += bf_vm.pack_time();
+        let executing = Instant::now();
+        // a single final newline, once the last program halted
+        bf_vm.set_final_newline(args.final_newline() && index + 1 == bf_infos.len());
+        let ran = match &mut trace {
+            None if args.digest() => {
+                digest::interpret_digested(&mut bf_vm, &mut stdin, &mut stdout)
+                    .map(|digest| eprintln!("bft: digest {}", digest))
+            }
+            Some(trace) => {
+                let filter = args.trace_filter();
+                trace::interpret_traced(&mut bf_vm, &mut stdin, &mut stdout, &filter, trace)
+            }
+            None => bf_vm.interpret_with(args.backend(), &mut stdin, &mut stdout),
+        };
+        execute_time += executing.elapsed();
+        let (name, source) = &programs[index];
+        ran.map_err(|e| BftDiagnostic::vm_err(e, name).with_source(source))?;
+    }
+    if let Some(trace) = &mut trace {
+        trace.flush()?;
+    }
+    stdout.flush()?;
+    if let Some(format) = stats {
+        let elapsed = start.elapsed();
+        let stats = bf_vm.stats();
+        let metrics = BFProgramMetrics::measure(&whole);
+        let packed = (stats.ops(), stats.opt_level());
+        let run = Some((stats.steps(), elapsed));
+        print_stats(&mut io::stderr(), &metrics, packed, run, format)?;
+    }
+    if args.time() {
+        let steps = bf_vm.stats().steps();
+        let summary = time_summary(parse_time, pack_time, execute_time, steps);
+        eprintln!("bft: {}", summary);
+    }
+
+    match args.exit_cell() {
+        Some(cell) => match bf_vm.cells().get(cell) {
+            Some(&value) => Ok(ExitCode::from(value.into() as u8)),
+            None => Err(format!("exit cell {} is outside the tape", cell).into()),
+        },
+        None => Ok(ExitCode::SUCCESS),
+    }
+}
+
+/// print the metrics of a program packed into a number of ops at an
+/// optimization level, and the steps and time of its run if it ran, to
+/// `out` in `format`
+fn print_stats(
+    out: &mut dyn Write,
+    metrics: &BFProgramMetrics,
+    (ops, opt_level): (usize, BFOptLevel),
+    run: Option<(u64, Duration)>,
+    format: BftStatsFormat,
+) -> io::Result<()> {
+    let usage = metrics.tape();
+    if format == BftStatsFormat::Json {
+        let histogram: serde_json::Map<_, _> = metrics
+            .histogram()
+            .map(|(command, count)| (command.to_string(), count.into()))
+            .collect();
+        let report = serde_json::json!({
+            "instructions": metrics.instructions(),
+            "histogram": histogram,
+            "extensions": metrics.extensions(),
+            "loops": metrics.loops(),
+            "max_depth": metrics.max_depth(),
+            "drift": metrics.drift(),
+            "tape": {
+                "cells": usage.cells(),
+                "left": usage.left(),
+                "right": usage.right(),
+            },
+            "ops": ops,
+            "opt_level": opt_level.to_string(),
+            "steps": run.map(|(steps, _)| steps),
+            "seconds": run.map(|(_, elapsed)| elapsed.as_secs_f64()),
+        });
+        return writeln!(out, "{}", report);
+    }
+
+    let histogram: Vec<String> = metrics
+        .histogram()
+        .map(|(command, count)| format!("{}{}", command, count))
+        .collect();
+    let tape = match usage.cells() {
+        Some(cells) => format!("{} cells", cells),
+        None => "unbounded".to_string(),
+    };
+    writeln!(out, "instructions  {}", metrics.instructions())?;
+    writeln!(out, "histogram     {}", histogram.join(" "))?;
+    writeln!(
+        out,
+        "loops         {} (depth {})",
+        metrics.loops(),
+        metrics.max_depth()
+    )?;
+    writeln!(out, "drift         {}", metrics.drift())?;
+    writeln!(out, "tape          {}", tape)?;
+    writeln!(out, "ops           {} at {}", ops, opt_level)?;
+    if let Some((steps, elapsed)) = run {
+        writeln!(out, "steps         {}", steps)?;
+        writeln!(out, "time          {:.3?}", elapsed)?;
+    }
     Ok(())
 }
 
+/// print the metrics of the programs of `args` to stdout, or run them
+/// and print the statistics of the run too to stderr with `--execute`
+fn bft_stats(args: &BftStatsArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let run_args = args.run_args();
+    if args.execute() {
+        return bft_run(run_args, Some(args.format()));
+    }
+    let BftSources { programs, .. } = run_sources(run_args)?;
+    let extensions = run_extensions::<u8>(run_args).commands();
+    let BftPrograms { whole, .. } = parse_programs(run_args, &programs, &extensions)?;
+    let packed = BFPackedProgram::optimize(&whole, run_args.opt_level());
+    print_stats(
+        &mut io::stdout(),
+        &BFProgramMetrics::measure(&whole),
+        (packed.ops().len(), run_args.opt_level()),
+        None,
+        args.format(),
+    )?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// parse and lint a bft program without running it, failing if it has
+/// errors
+fn bft_check(args: &BftCheckArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let bf_info = BFProgram::new(args.name(), &load_source(args.name())?);
+    let diagnostics = lint::check(&bf_info);
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity() == BFSeverity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    let name = args.name().display();
+
+    if args.json() {
+        let diagnostics: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                serde_json::json!({
+                    "line": d.cmd().line(),
+                    "column": d.cmd().column(),
+                    "severity": d.severity().to_string(),
+                    "code": d.kind().code(),
+                    "message": d.kind().message(),
+                })
+            })
+            .collect();
+        let report = serde_json::json!({
+            "file": name.to_string(),
+            "instructions": bf_info.instructions().len(),
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": diagnostics,
+        });
+        println!("{}", report);
+    } else if diagnostics.is_empty() {
+        println!(
+            "{}: ok, {} instructions",
+            name,
+            bf_info.instructions().len()
+        );
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}:{}", name, diagnostic);
+        }
+        println!("{}: {} errors, {} warnings", name, errors, warnings);
+    }
+    Ok(if errors == 0 {
+        ExitCode::SUCCESS
+    } else {
+        BftExitStatus::Parse.into()
+    })
+}
+
+/// print a bft program in the canonical layout, failing with `--check` if
+/// it isn't already
+fn bft_fmt(args: &BftFmtArgs) -> Result<ExitCode, Box<dyn Error>> {
+    if args.write() && args.name().as_os_str() == "-" {
+        return Err(BftDiagnostic::usage("a program read from stdin can't be written back").into());
+    }
+    let source = load_source(args.name())?;
+    let formatted = format_source(&source);
+    if args.check() {
+        if formatted == source {
+            return Ok(ExitCode::SUCCESS);
+        }
+        eprintln!("bft: {} isn't formatted", args.name().display());
+        return Ok(ExitCode::FAILURE);
+    }
+    if args.write() {
+        fs::write(args.name(), formatted)?;
+    } else {
+        print!("{}", formatted);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// dialect called `name`, built in or defined by `config`
+fn dialect(name: &str, config: &config::BftConfig) -> Result<BFDialect, Box<dyn Error>> {
+    match name {
+        "brainfuck" | "bf" => Ok(BFDialect::brainfuck()),
+        "ook" => Ok(BFDialect::ook()),
+        _ => match config.dialect(name) {
+            Some(dialect) => Ok(dialect?),
+            None => {
+                let message = format!(
+                    "unknown dialect `{}`, brainfuck, ook or one of the config file",
+                    name
+                );
+                Err(BftDiagnostic::usage(message).into())
+            }
+        },
+    }
+}
+
+/// print a bft program written in one dialect in another
+fn bft_translate(
+    args: &BftTranslateArgs,
+    config: &config::BftConfig,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let from = dialect(args.from(), config)?;
+    let to = dialect(args.to(), config)?;
+    let commands = from.decode(&load_source(args.name())?);
+    let translated = match (args.pretty(), args.to()) {
+        (true, "brainfuck" | "bf") => format_source(&commands),
+        (true, _) => to.encode_wrapped(&commands, format::WIDTH),
+        (false, _) => to.encode(&commands) + "\n",
+    };
+    match args.output() {
+        Some(path) => fs::write(path, translated)?,
+        None => print!("{}", translated),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// write `loops` nested at `depth` as lines of an indented tree
+fn write_loop_tree(out: &mut String, loops: &[BFLoop], depth: usize) {
+    for bf_loop in loops {
+        let (start, end) = (bf_loop.start(), bf_loop.end());
+        *out += &format!(
+            "{}loop {}:{}..{}:{}, {} instructions\n",
+            "  ".repeat(depth),
+            start.line(),
+            start.column(),
+            end.line(),
+            end.column(),
+            bf_loop.instructions()
+        );
+        write_loop_tree(out, bf_loop.loops(), depth + 1);
+    }
+}
+
+/// write `loops` as nodes of a DOT graph, linked from the node `parent`
+fn write_loop_graph(out: &mut String, loops: &[BFLoop], parent: &str) {
+    for bf_loop in loops {
+        let (start, end) = (bf_loop.start(), bf_loop.end());
+        let node = format!("loop_{}_{}", start.line(), start.column());
+        *out += &format!(
+            "    {} [label=\"{}:{}..{}:{}\\n{} instructions\"];\n",
+            node,
+            start.line(),
+            start.column(),
+            end.line(),
+            end.column(),
+            bf_loop.instructions()
+        );
+        *out += &format!("    {} -> {};\n", parent, node);
+        write_loop_graph(out, bf_loop.loops(), &node);
+    }
+}
+
+/// print the loop structure of a bft program
+fn bft_visualize(args: &BftVisualizeArgs) -> Result<(), Box<dyn Error>> {
+    let source = load_source(args.name())?;
+    let mut bf_info = BFProgram::new(args.name(), &source);
+    if let Err(e) = bf_info.match_square_bracket() {
+        let diagnostic = BftDiagnostic::unmatched(e, &bf_info).with_source(&source);
+        return Err(diagnostic.into());
+    }
+    let tree = BFLoopTree::build(&bf_info.validate()?);
+    let name = args.name().display().to_string();
+    let mut out = String::new();
+    match args.format() {
+        BftVisualizeFormat::Tree => {
+            out += &format!("{}, {} instructions\n", name, tree.instructions());
+            write_loop_tree(&mut out, tree.loops(), 1);
+        }
+        BftVisualizeFormat::Dot => {
+            out += "digraph loops {\n    node [shape=box];\n";
+            out += &format!(
+                "    program [label=\"{}\\n{} instructions\"];\n",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                tree.instructions()
+            );
+            write_loop_graph(&mut out, tree.loops(), "program");
+            out += "}\n";
+        }
+    }
+    print!("{}", out);
+    Ok(())
+}
+
+/// transpile bft program into a standalone source file
+fn bft_compile(args: &BftCompileArgs) -> Result<(), Box<dyn Error>> {
+    let bf_info = BFProgram::new(args.name(), &load_source(args.name())?).validate()?;
+    let options = BFEmitOptions {
+        cells: args.cells_size(),
+        extensible: args.cells_extensible(),
+        cell_width: args.cell_width(),
+        eof: args.eof(),
+    };
+    let source = emit::emit(&bf_info, args.target(), &options);
+    #[cfg(feature = "llvm")]
+    if args.validate() {
+        if args.target() != emit::BFEmitTarget::Llvm {
+            return Err(BftDiagnostic::usage("--validate requires --target llvm").into());
+        }
+        let mut input = Vec::new();
+        io::Read::read_to_end(&mut io::stdin(), &mut input)?;
+        validate::validate_llvm(&bf_info, &source, &options, &input)?;
+    }
+    let output = if args.assemble() {
+        wat::parse_str(&source)?
+    } else {
+        source.into_bytes()
+    };
+
+    match args.output() {
+        Some(path) => fs::write(path, output)?,
+        None => io::stdout().write_all(&output)?,
+    }
+    Ok(())
+}
+
+/// compare two backends running the bft program on stdin
+fn bft_verify(args: &BftVerifyArgs) -> Result<(), Box<dyn Error>> {
+    let bf_info = BFProgram::new(args.name(), &load_source(args.name())?).validate()?;
+    let mut input = Vec::new();
+    io::Read::read_to_end(&mut io::stdin(), &mut input)?;
+    if args.semantics() {
+        return bft_verify_semantics(args, &bf_info, &input);
+    }
+
+    let trace = |backend: BFBackend, level: BFOptLevel| {
+        let mut bf_vm =
+            BFVirtualMachine::<u8>::new(args.cells_size(), args.cells_extensible(), &bf_info);
+        bf_vm.set_eof(args.eof());
+        bf_vm.set_fuel(args.fuel());
+        bf_vm.set_opt_level(level);
+        bf_vm.set_head_mode(args.head_mode());
+        BFRunTrace::record(&mut bf_vm, backend, &input)
+    };
+    let baseline = trace(args.baseline(), args.baseline_opt());
+    let candidate = trace(args.candidate(), args.candidate_opt());
+
+    if let Some(divergence) = verify::first_divergence(&baseline, &candidate) {
+        return Err(divergence.to_string().into());
+    }
+    println!(
+        "{:?} {} and {:?} {} agree on {} IO events",
+        args.baseline(),
+        args.baseline_opt(),
+        args.candidate(),
+        args.candidate_opt(),
+        baseline.events().len()
+    );
+    Ok(())
+}
+
+/// run the baseline under every semantics and report how the outputs group
+fn bft_verify_semantics(
+    args: &BftVerifyArgs,
+    bf_info: &BFValidProgram,
+    input: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let options = BFRunOptions {
+        cells: args.cells_size(),
+        extensible: args.cells_extensible(),
+        head_mode: args.head_mode(),
+        backend: args.baseline(),
+        opt_level: args.baseline_opt(),
+        // wide cells turn wrapping loops into very long ones
+        fuel: args.fuel().or(Some(SEMANTICS_FUEL)),
+        ..BFRunOptions::default()
+    };
+    let report = BFSemanticsReport::run(bf_info, input, &BFSemantics::all(), &options);
+    let groups = report.groups();
+    if let [group] = groups.as_slice() {
+        println!(
+            "{} semantics agree on {} output bytes",
+            group.len(),
+            group[0].output().len()
+        );
+        return Ok(());
+    }
+
+    for (index, group) in groups.iter().enumerate() {
+        let run = group[0];
+        let outcome = match run.outcome() {
+            Ok(()) => "finished".to_string(),
+            Err(e) => e.clone(),
+        };
+        println!(
+            "group {}: {} output bytes, {}",
+            index + 1,
+            run.output().len(),
+            outcome
+        );
+        if index > 0 {
+            match run.output_divergence(groups[0][0]) {
+                Some(offset) => println!("  output diverges from group 1 at byte {}", offset),
+                None => println!("  same output as group 1"),
+            }
+        }
+        for run in group {
+            println!("  {}", run.semantics());
+        }
+    }
+    Err(format!(
+        "outputs diverge across {} groups of semantics",
+        groups.len()
+    )
+    .into())
+}
+
+/// brainfuck programs in the directories or matching the glob patterns
+fn collect_programs(paths: &[String]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut programs = Vec::new();
+    for path in paths {
+        let dir = PathBuf::from(path);
+        if dir.is_dir() {
+            let mut found = Vec::new();
+            for entry in fs::read_dir(&dir)? {
+                let file = entry?.path();
+                if file.is_file() && file.extension().is_some_and(|ext| ext == "bf") {
+                    found.push(file);
+                }
+            }
+            found.sort();
+            programs.extend(found);
+        } else {
+            let found = glob::glob(path)?.collect::<Result<Vec<_>, _>>()?;
+            if found.is_empty() {
+                return Err(format!("no program matches {}", path).into());
+            }
+            programs.extend(found);
+        }
+    }
+    Ok(programs)
+}
+
+/// JSON report of a finished batch
+fn batch_report(results: &[BFBatchResult], summary: &BFBatchSummary) -> serde_json::Value {
+    let programs: Vec<_> = results
+        .iter()
+        .map(|result| {
+            let error = match result.outcome() {
+                BFBatchOutcome::Errored(e) => Some(e.as_str()),
+                _ => None,
+            };
+            serde_json::json!({
+                "name": result.name(),
+                "outcome": result.outcome().label(),
+                "error": error,
+                "elapsed_ms": result.elapsed().as_secs_f64() * 1000.0,
+                "steps": result.stats().map(|stats| stats.steps()),
+                "ops": result.stats().map(|stats| stats.ops()),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "passed": summary.passed,
+        "failed": summary.failed,
+        "finished": summary.finished,
+        "errored": summary.errored,
+        "programs": programs,
+    })
+}
+
+/// run many bft programs concurrently, each with its own input and
+/// expected output, failing if any program fails
+fn bft_run_all(args: &BftRunAllArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let mut jobs = Vec::new();
+    for path in args.paths() {
+        if archive::is_archive(Path::new(path)) {
+            jobs.extend(archive::archive_jobs(Path::new(path))?);
+            continue;
+        }
+        for path in collect_programs(slice::from_ref(path))? {
+            jobs.push(BFBatchJob {
+                name: path.display().to_string(),
+                source: fs::read_to_string(&path)?,
+                input: fs::read(path.with_extension("in")).unwrap_or_default(),
+                expected: fs::read(path.with_extension("out")).ok(),
+            });
+        }
+    }
+    let threads = match args.jobs() {
+        Some(jobs) => jobs,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    let start = Instant::now();
+    let results = batch::run_batch(&jobs, &args.run_options(), threads);
+    let elapsed = start.elapsed();
+    let summary = BFBatchSummary::new(&results);
+
+    let width = results
+        .iter()
+        .map(|r| r.name().len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<width$}  {:<6} {:>10} {:>12}",
+        "PROGRAM", "RESULT", "TIME(ms)", "STEPS"
+    );
+    for result in &results {
+        let steps = result
+            .stats()
+            .map_or_else(|| "-".to_string(), |stats| stats.steps().to_string());
+        println!(
+            "{:<width$}  {:<6} {:>10.3} {:>12}",
+            result.name(),
+            result.outcome().label(),
+            result.elapsed().as_secs_f64() * 1000.0,
+            steps
+        );
+        if let BFBatchOutcome::Errored(e) = result.outcome() {
+            println!("    {}", e);
+        }
+    }
+    println!(
+        "{} programs, {} passed, {} failed, {} finished, {} errors in {:.3}ms",
+        results.len(),
+        summary.passed,
+        summary.failed,
+        summary.finished,
+        summary.errored,
+        elapsed.as_secs_f64() * 1000.0
+    );
+
+    if let Some(path) = args.json() {
+        let report = batch_report(&results, &summary);
+        fs::write(path, serde_json::to_string_pretty(&report)? + "\n")?;
+    }
+    Ok(if summary.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// run bft programs posted over HTTP until the process is stopped
+fn bft_serve(args: &BftServeArgs) -> Result<(), Box<dyn Error>> {
+    let limits = serve::BftServeLimits {
+        max_fuel: args.max_fuel(),
+        max_cells: args.max_cells(),
+        max_output: args.max_output(),
+        max_request: args.max_request(),
+        max_sessions: args.max_sessions(),
+    };
+    serve::serve(args.bind(), args.threads(), limits)
+}
+
 /// Main entry for the brainfuck application
 fn main() -> ExitCode {
-    let cli = BftCli::new();
+    let args: Vec<_> = std::env::args_os().collect();
+    let config = match config::BftConfig::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("bft: {}", e);
+            return BftExitStatus::Usage.into();
+        }
+    };
+    let cli = BftCli::with_defaults(&config.defaults());
+    let color = diagnostic::use_color(cli.color());
+    init_logging(&cli, color);
+    diagnostic::configure(cli.diagnostics_format(), color);
+    let result = match cli.command() {
+        Some(BftCommand::Run(args)) => bft_run(args, None),
+        Some(BftCommand::Check(args)) => bft_check(args),
+        Some(BftCommand::Fmt(args)) => bft_fmt(args),
+        Some(BftCommand::Translate(args)) => bft_translate(args, &config),
+        Some(BftCommand::Visualize(args)) => bft_visualize(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::Compile(args)) => bft_compile(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::Debug(args)) => debug::debug(args),
+        Some(BftCommand::Repl(args)) => repl::repl(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::Stats(args)) => bft_stats(args),
+        Some(BftCommand::Verify(args)) => bft_verify(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::RunAll(args)) => bft_run_all(args),
+        Some(BftCommand::Test(args)) => golden::test(args),
+        Some(BftCommand::Serve(args)) => bft_serve(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::Bench(args)) => bench::bench(args).map(|()| ExitCode::SUCCESS),
+        Some(BftCommand::Completions(args)) => {
+            let mut command = <BftCli as CommandFactory>::command();
+            clap_complete::generate(args.shell(), &mut command, "bft", &mut io::stdout());
+            Ok(ExitCode::SUCCESS)
+        }
+        None => bft_run(cli.run_args(), None),
+    };
 
-    if let Some(e) = bft_run(&cli).err() {
-        println!("{:#?}", e.to_string());
-        return ExitCode::from(1);
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            let diagnostic = BftDiagnostic::from_error(e);
+            diagnostic.emit();
+            diagnostic.exit_status().into()
+        }
     }
-
-    ExitCode::SUCCESS
 }
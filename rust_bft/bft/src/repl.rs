@@ -0,0 +1,241 @@
+//! Read-eval-print loop
+//!
+//! Every line typed on stdin is a program running on the tape and head the
+//! previous lines left, then the head and its cell are printed. A line
+//! opening more loops than it closes waits for the next lines to close
+//! them. The text after a `!` is the input of the `,` commands of the line,
+//! which read zero once it's exhausted.
+//!
+//! A line starting with `:` outside of a loop is a command of the REPL:
+//!
+//! ```text
+//! :tape [N]             print the cells within N of the head, 4 if not given
+//! :reset                zero the tape and move the head back to the first cell
+//! :load FILE            run the program of FILE on the tape
+//! :set [OPTION VALUE]   print the options, or set `eof` to zero, minus-one,
+//!                       unchanged or error, or `opt` to 0, 1, 2 or 3
+//! :undo                 take back the last line, load or reset
+//! :help                 print these commands
+//! :quit                 leave the REPL
+//! ```
+
+use crate::cli::bft_cli_mod::{BftEof, BftOptLevel, BftReplArgs};
+use bft_interp::{BFOwnedVirtualMachine, BFSnapshot, BFVirtualMachine};
+use bft_types::BFProgram;
+use clap::ValueEnum;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// Most changes of the tape `:undo` can take back
+const MAX_UNDO: usize = 64;
+
+/// Help of the commands, printed by `:help`
+const HELP: &str = "\
+:tape [N]             print the cells within N of the head, 4 if not given
+:reset                zero the tape and move the head back to the first cell
+:load FILE            run the program of FILE on the tape
+:set [OPTION VALUE]   print the options, or set eof or opt
+:undo                 take back the last line, load or reset
+:help                 print these commands
+:quit                 leave the REPL";
+
+/// loops opened by `source` and not closed yet, negative if it closes more
+fn open_loops(source: &str) -> i64 {
+    source.chars().fold(0, |depth, c| match c {
+        '[' => depth + 1,
+        ']' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// name of the command line value of `value`
+fn value_name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// Session of the REPL
+struct BftRepl {
+    bf_vm: BFOwnedVirtualMachine<u8>,
+
+    /// length and extensible flag of a reset tape
+    cells: usize,
+    extensible: bool,
+
+    eof: BftEof,
+    opt_level: BftOptLevel,
+
+    /// tapes and heads before the last changes, the last change last
+    history: VecDeque<BFSnapshot<u8>>,
+}
+
+impl BftRepl {
+    /// session with a zeroed tape of the size given in `args`
+    fn new(args: &BftReplArgs) -> Result<Self, Box<dyn Error>> {
+        let empty = Arc::new(BFProgram::new("<repl>", "").validate()?);
+        let mut repl = Self {
+            bf_vm: BFVirtualMachine::new_shared(args.cells_size(), args.cells_extensible(), empty),
+            cells: args.cells_size(),
+            extensible: args.cells_extensible(),
+            eof: BftEof::Zero,
+            opt_level: BftOptLevel::O1,
+            history: VecDeque::new(),
+        };
+        repl.reset()?;
+        Ok(repl)
+    }
+
+    /// zero the tape and move the head back to the first cell, keeping
+    /// the options
+    fn reset(&mut self) -> Result<(), Box<dyn Error>> {
+        let empty = Arc::new(BFProgram::new("<repl>", "").validate()?);
+        self.bf_vm = BFVirtualMachine::new_shared(self.cells, self.extensible, empty);
+        self.bf_vm.set_eof(self.eof.into());
+        self.bf_vm.set_opt_level(self.opt_level.into());
+        self.bf_vm.set_prompt(false);
+        self.bf_vm.set_final_newline(false);
+        Ok(())
+    }
+
+    /// remember the tape and head for `:undo`
+    fn save(&mut self) {
+        if self.history.len() == MAX_UNDO {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.bf_vm.snapshot());
+    }
+
+    /// run `source` named `name` on the tape with `input`, then print its
+    /// output and the head, or its error
+    fn run(&mut self, name: &str, source: &str, input: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut output = Vec::new();
+        let result = BFProgram::new(name, source).validate().and_then(|bf_info| {
+            self.save();
+            self.bf_vm.load_shared(Arc::new(bf_info));
+            self.bf_vm.interpret(&mut &input[..], &mut output)?;
+            Ok(())
+        });
+        // the output of the line ends with a newline before the head is printed
+        if !output.is_empty() && !output.ends_with(b"\n") {
+            output.push(b'\n');
+        }
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&output)?;
+        match result {
+            Ok(()) => self.print_head(&mut stdout)?,
+            Err(e) => writeln!(stdout, "error: {}", e)?,
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// print the head and its cell
+    fn print_head(&self, out: &mut impl Write) -> io::Result<()> {
+        let head = self.bf_vm.head();
+        writeln!(out, "head {} cell {}", head, self.bf_vm.cells()[head])
+    }
+
+    /// set `option` to `value`
+    fn set(&mut self, option: &str, value: &str) -> Result<(), String> {
+        match option {
+            "eof" => {
+                self.eof = BftEof::from_str(value, true)?;
+                self.bf_vm.set_eof(self.eof.into());
+            }
+            "opt" => {
+                self.opt_level = BftOptLevel::from_str(value, true)?;
+                self.bf_vm.set_opt_level(self.opt_level.into());
+            }
+            _ => return Err(format!("unknown option `{}`, eof or opt", option)),
+        }
+        Ok(())
+    }
+
+    /// run the command `command` without its `:`, false to leave the REPL
+    fn command(&mut self, command: &str) -> Result<bool, Box<dyn Error>> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let arguments: Vec<&str> = words.collect();
+        match (name, arguments.as_slice()) {
+            ("tape", []) => println!("{}", self.tape(4)),
+            ("tape", [radius]) => {
+                let radius = radius
+                    .parse()
+                    .map_err(|_| format!("`{}` isn't a count", radius))?;
+                println!("{}", self.tape(radius));
+            }
+            ("reset", []) => {
+                self.save();
+                self.reset()?;
+                self.print_head(&mut io::stdout())?;
+            }
+            ("load", [path]) => {
+                let source = crate::load_source(path.as_ref())?;
+                self.run(path, &source, &[])?;
+            }
+            ("set", []) => println!(
+                "eof {}, opt {}",
+                value_name(self.eof),
+                value_name(self.opt_level)
+            ),
+            ("set", [option, value]) => self.set(option, value)?,
+            ("undo", []) => match self.history.pop_back() {
+                Some(snapshot) => {
+                    self.bf_vm.restore(snapshot);
+                    self.print_head(&mut io::stdout())?;
+                }
+                None => return Err("nothing to undo".into()),
+            },
+            ("help", []) => println!("{}", HELP),
+            ("quit", []) => return Ok(false),
+            ("tape" | "reset" | "load" | "set" | "undo" | "help" | "quit", _) => {
+                return Err(format!("wrong arguments of `:{}`, see `:help`", name).into())
+            }
+            _ => return Err(format!("unknown command `:{}`, see `:help`", name).into()),
+        }
+        Ok(true)
+    }
+
+    /// the cells within `radius` of the head
+    fn tape(&self, radius: usize) -> String {
+        crate::debug::cells_around(self.bf_vm.cells(), self.bf_vm.head(), radius)
+    }
+}
+
+/// run the lines typed on stdin until its end or `:quit`
+pub fn repl(args: &BftReplArgs) -> Result<(), Box<dyn Error>> {
+    let mut repl = BftRepl::new(args)?;
+
+    let mut source = String::new();
+    let mut input = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if let Some(command) = line
+            .trim_start()
+            .strip_prefix(':')
+            .filter(|_| source.is_empty())
+        {
+            match repl.command(command) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(e) => println!("error: {}", e),
+            }
+            continue;
+        }
+        let (code, line_input) = line.split_once('!').unwrap_or((&line, ""));
+        source.push_str(code);
+        source.push('\n');
+        input.extend_from_slice(line_input.as_bytes());
+        if open_loops(&source) > 0 {
+            continue;
+        }
+
+        repl.run("<repl>", &source, &input)?;
+        source.clear();
+        input.clear();
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+//! HTTP execution service
+//!
+//! `POST /run` takes a JSON object with the program `source` and its
+//! `input`, runs it under the limits of the server and answers with the
+//! output, statistics and diagnostics as JSON. A request may ask for less
+//! fuel or fewer cells than the server allows but never for more.
+//!
+//! `GET /stream` runs a program interactively over a WebSocket, see `stream`.
+//!
+//! `GET /metrics` exposes counters and a duration histogram of all runs in
+//! the Prometheus text format.
+
+use crate::cli::bft_cli_mod::BftEof;
+use crate::stream;
+use bft_interp::metrics::{BFMetricsSink, BFPrometheusMetrics, BFRunReport};
+use bft_interp::{BFEofBehavior, BFFnWriter, BFRunStats, BFVirtualMachine, BFVmErr};
+use bft_types::{BFProgram, BFValidProgram};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Limits every run of the server is held to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BftServeLimits {
+    /// instructions a program may execute
+    pub max_fuel: u64,
+
+    /// cells of the tape, which never extends
+    pub max_cells: usize,
+
+    /// bytes a program may write
+    pub max_output: usize,
+
+    /// bytes of a request body, or of a WebSocket message
+    pub max_request: usize,
+
+    /// WebSocket sessions running at the same time
+    pub max_sessions: usize,
+}
+
+/// Body of a `POST /run` request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BftRunRequest {
+    /// brainfuck source of the program
+    source: String,
+
+    /// text the program reads
+    #[serde(default)]
+    input: String,
+
+    /// instruction budget, the server limit if not given
+    fuel: Option<u64>,
+
+    /// number of cells, the server limit if not given
+    cells: Option<usize>,
+
+    /// what reading does once input is exhausted, as on the command line
+    eof: Option<String>,
+
+    /// print a newline after the output if it doesn't end with one
+    #[serde(default = "default_final_newline")]
+    final_newline: bool,
+}
+
+/// a final newline is printed unless turned off, as on the command line
+fn default_final_newline() -> bool {
+    true
+}
+
+/// Diagnostic of a run which didn't halt normally
+pub fn diagnostic(kind: &str, message: impl ToString) -> Value {
+    json!({ "kind": kind, "message": message.to_string() })
+}
+
+/// Answer of a request which didn't run to the end
+pub fn failure(status: u16, kind: &str, message: impl ToString) -> (u16, Value) {
+    (
+        status,
+        json!({ "ok": false, "error": diagnostic(kind, message) }),
+    )
+}
+
+/// A validated request, ready to run
+pub struct BftRunSetup {
+    bf_info: BFValidProgram,
+    input: Vec<u8>,
+    cells: usize,
+    fuel: u64,
+    eof: BFEofBehavior,
+    final_newline: bool,
+}
+
+impl BftRunSetup {
+    /// Parse a run request and its program, holding it to `limits`,
+    /// or the answer telling why it can't run
+    pub fn new(body: &[u8], limits: &BftServeLimits) -> Result<Self, (u16, Value)> {
+        let request: BftRunRequest =
+            serde_json::from_slice(body).map_err(|e| failure(400, "request", e))?;
+        let eof = match request.eof.as_deref() {
+            None => BFEofBehavior::default(),
+            Some(eof) => BftEof::from_str(eof, false)
+                .map_err(|e| failure(400, "request", e))?
+                .into(),
+        };
+        let bf_info = BFProgram::new("request", &request.source)
+            .validate()
+            .map_err(|e| failure(200, "syntax", e))?;
+        Ok(Self {
+            bf_info,
+            input: request.input.into_bytes(),
+            cells: request
+                .cells
+                .unwrap_or(limits.max_cells)
+                .clamp(1, limits.max_cells),
+            fuel: request.fuel.unwrap_or(limits.max_fuel).min(limits.max_fuel),
+            eof,
+            final_newline: request.final_newline,
+        })
+    }
+
+    /// input given with the request
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// virtual machine set up to run the program under the limits, reads
+    /// print no prompt to the server's stdout
+    pub fn vm(&self) -> BFVirtualMachine<'_, u8> {
+        let mut bf_vm = BFVirtualMachine::<u8>::new(self.cells, false, &self.bf_info);
+        bf_vm.set_eof(self.eof);
+        bf_vm.set_fuel(Some(self.fuel));
+        bf_vm.set_final_newline(self.final_newline);
+        bf_vm.set_prompt(false);
+        bf_vm
+    }
+}
+
+/// Diagnostic of a virtual machine error with its stable code, `kind`
+/// replaces the kind of IO errors caused on purpose, eg. by the output limit
+pub fn vm_diagnostic(err: BFVmErr, kind: Option<(&str, &str)>) -> Value {
+    let mut value = match (&err, kind) {
+        (BFVmErr::IOErr(_), Some((kind, message))) => diagnostic(kind, message),
+        (e, _) => diagnostic(e.kind(), e),
+    };
+    value["code"] = json!(err.code_name());
+    value
+}
+
+/// Statistics of a run in JSON
+pub fn stats_json(stats: BFRunStats) -> Value {
+    json!({
+        "steps": stats.steps(),
+        "ops": stats.ops(),
+        "opt_level": stats.opt_level().to_string(),
+    })
+}
+
+/// Report a run which started at `start` and ended with `answer` to
+/// `metrics`
+pub fn record(metrics: &dyn BFMetricsSink, start: Instant, steps: u64, answer: &Value) {
+    metrics.record(&BFRunReport {
+        steps,
+        elapsed: start.elapsed(),
+        error: answer["error"]["kind"].as_str(),
+    });
+}
+
+/// Run the program of a request body, returning the HTTP status and the
+/// JSON answer
+pub fn run_request(
+    body: &[u8],
+    limits: &BftServeLimits,
+    metrics: &dyn BFMetricsSink,
+) -> (u16, Value) {
+    let start = Instant::now();
+    let setup = match BftRunSetup::new(body, limits) {
+        Ok(setup) => setup,
+        Err(answer) => {
+            record(metrics, start, 0, &answer.1);
+            return answer;
+        }
+    };
+    let mut bf_vm = setup.vm();
+
+    let mut output = Vec::new();
+    let mut truncated = false;
+    let mut writer = BFFnWriter::new(|byte| {
+        if output.len() >= limits.max_output {
+            truncated = true;
+            return Err(io::Error::other("output limit exceeded"));
+        }
+        output.push(byte);
+        Ok(())
+    });
+    let result = bf_vm.interpret(&mut setup.input(), &mut writer);
+
+    let limit = truncated.then_some(("output_limit", "Output limit exceeded"));
+    let error = match result {
+        Ok(()) => Value::Null,
+        Err(e) => vm_diagnostic(e, limit),
+    };
+    let answer = json!({
+        "ok": error.is_null(),
+        "output": String::from_utf8_lossy(&output),
+        "stats": stats_json(bf_vm.stats()),
+        "error": error,
+    });
+    record(metrics, start, bf_vm.stats().steps(), &answer);
+    (200, answer)
+}
+
+/// Answer a single HTTP request
+fn handle(
+    mut request: Request,
+    limits: &BftServeLimits,
+    metrics: &Arc<BFPrometheusMetrics>,
+) -> io::Result<()> {
+    let (status, answer) = match (request.method(), request.url()) {
+        (Method::Post, "/run") => {
+            let mut body = Vec::new();
+            request
+                .as_reader()
+                .take(limits.max_request as u64 + 1)
+                .read_to_end(&mut body)?;
+            if body.len() > limits.max_request {
+                failure(413, "request", "Request too large")
+            } else {
+                run_request(&body, limits, metrics.as_ref())
+            }
+        }
+        (_, "/run") => failure(405, "request", "Use POST"),
+        (Method::Get, "/stream") => match stream::handshake_key(&request) {
+            Some(key) => match stream::BftSessionSlot::take(limits.max_sessions) {
+                Some(slot) => {
+                    return stream::accept(request, &key, slot, *limits, Arc::clone(metrics))
+                }
+                None => failure(503, "busy", "Too many stream sessions"),
+            },
+            None => failure(400, "request", "Expected a WebSocket handshake"),
+        },
+        (Method::Get, "/metrics") => {
+            let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+                .expect("bft: static header is valid");
+            return request
+                .respond(Response::from_string(metrics.render()).with_header(content_type));
+        }
+        _ => failure(404, "request", "Not found"),
+    };
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .expect("bft: static header is valid");
+    request.respond(
+        Response::from_string(answer.to_string())
+            .with_status_code(status)
+            .with_header(content_type),
+    )
+}
+
+/// Serve requests on `bind` with `threads` workers until the process ends
+pub fn serve(bind: &str, threads: usize, limits: BftServeLimits) -> Result<(), Box<dyn Error>> {
+    let server = Arc::new(Server::http(bind).map_err(|e| e.to_string())?);
+    let metrics = Arc::new(BFPrometheusMetrics::new());
+    println!("bft: listening on http://{}", server.server_addr());
+    io::stdout().flush()?;
+
+    let workers: Vec<_> = (0..threads.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let metrics = Arc::clone(&metrics);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Err(e) = handle(request, &limits, &metrics) {
+                        eprintln!("bft: failed to answer a request: {}", e);
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Interactive runs over a WebSocket
+//!
+//! After the handshake on `GET /stream` the client sends a run request as
+//! the first text message, shaped like the body of `POST /run`. The
+//! program then runs step by step under the limits of the server:
+//!
+//! - output goes out as `{"type": "output", "data": "..."}` messages,
+//!   bytes of an incomplete UTF-8 character are held back,
+//! - `{"type": "state", "steps": n, "head": n}` is sent every
+//!   `STATE_INTERVAL` instructions,
+//! - `{"type": "waiting"}` is sent when the program reads and no input is
+//!   left, the client answers with `{"type": "input", "data": "..."}` or a
+//!   binary message, `{"type": "eof"}` to end the input or
+//!   `{"type": "stop"}` to stop the program,
+//! - `{"type": "halted", "ok": ..., "error": ..., "stats": ...}` ends the
+//!   run, then the server closes the socket.
+//!
+//! Client messages are only read while the program waits for input, a
+//! program which never reads runs until it halts or its fuel runs out.
+//! Messages are limited to `--max-request` bytes and at most
+//! `--max-sessions` sessions run at the same time, later handshakes are
+//! answered with 503.
+
+use crate::serve::{diagnostic, record, stats_json, vm_diagnostic, BftRunSetup, BftServeLimits};
+use bft_interp::metrics::{BFMetricsSink, BFPrometheusMetrics};
+use bft_interp::{BFFnReader, BFFnWriter};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use tiny_http::{Header, ReadWrite, Request, Response};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::{Role, WebSocketConfig};
+use tungstenite::{Message, WebSocket};
+
+/// number of output bytes sent together at most
+const OUTPUT_CHUNK: usize = 256;
+
+/// number of instructions between two state messages
+const STATE_INTERVAL: u64 = 100_000;
+
+/// Socket of a session
+type BftSocket = WebSocket<Box<dyn ReadWrite + Send>>;
+
+/// number of sessions running
+static SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Place of a running session, given back once the session ends
+pub struct BftSessionSlot(());
+
+impl BftSessionSlot {
+    /// take a place if fewer than `max` sessions are running
+    pub fn take(max: usize) -> Option<Self> {
+        SESSIONS
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(()))
+    }
+}
+
+impl Drop for BftSessionSlot {
+    fn drop(&mut self) {
+        SESSIONS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Message of the client after the run request
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum BftClientMessage {
+    /// more text for the program to read
+    Input { data: String },
+
+    /// the program reads nothing more
+    Eof,
+
+    /// stop the program
+    Stop,
+}
+
+/// State of a running session, shared by the input and output handlers
+struct BftSession {
+    socket: BftSocket,
+    input: VecDeque<u8>,
+    eof: bool,
+    stopped: bool,
+    pending: Vec<u8>,
+    written: usize,
+    max_output: usize,
+    truncated: bool,
+}
+
+impl BftSession {
+    /// send a JSON message
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        self.socket
+            .send(Message::text(message.to_string()))
+            .map_err(io::Error::other)
+    }
+
+    /// send the pending output, all of it or up to the last complete
+    /// UTF-8 character
+    fn send_output(&mut self, all: bool) -> io::Result<()> {
+        let len = match std::str::from_utf8(&self.pending) {
+            _ if all => self.pending.len(),
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_some() => self.pending.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        if len == 0 {
+            return Ok(());
+        }
+        let data = String::from_utf8_lossy(&self.pending[..len]).into_owned();
+        self.pending.drain(..len);
+        self.send(json!({ "type": "output", "data": data }))
+    }
+
+    /// take an output byte of the program
+    fn write(&mut self, byte: u8) -> io::Result<()> {
+        if self.written >= self.max_output {
+            self.truncated = true;
+            return Err(io::Error::other("output limit exceeded"));
+        }
+        self.written += 1;
+        self.pending.push(byte);
+        if self.pending.len() >= OUTPUT_CHUNK {
+            self.send_output(false)?;
+        }
+        Ok(())
+    }
+
+    /// give the next input byte to the program, asking the client for more
+    /// once none is left
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            if let Some(byte) = self.input.pop_front() {
+                return Ok(Some(byte));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            if self.stopped {
+                return Err(io::Error::other("stopped by the client"));
+            }
+            self.send_output(false)?;
+            self.send(json!({ "type": "waiting" }))?;
+            self.receive()?;
+        }
+    }
+
+    /// wait for a message of the client and apply it
+    fn receive(&mut self) -> io::Result<()> {
+        match self.socket.read().map_err(io::Error::other)? {
+            Message::Text(text) => match serde_json::from_str(&text) {
+                Ok(BftClientMessage::Input { data }) => self.input.extend(data.bytes()),
+                Ok(BftClientMessage::Eof) => self.eof = true,
+                Ok(BftClientMessage::Stop) => self.stopped = true,
+                Err(e) => {
+                    let error = diagnostic("request", e);
+                    self.send(json!({ "type": "error", "error": error }))?;
+                }
+            },
+            Message::Binary(data) => self.input.extend(data.iter()),
+            Message::Close(_) => self.stopped = true,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Key of a WebSocket handshake request, None if it isn't one
+pub fn handshake_key(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| header.value.as_str().to_string())
+}
+
+/// Complete the handshake of `request` and run the session on a thread
+/// of its own, so a long session doesn't hold up a worker, `slot` is held
+/// until the session ends
+pub fn accept(
+    request: Request,
+    key: &str,
+    slot: BftSessionSlot,
+    limits: BftServeLimits,
+    metrics: Arc<BFPrometheusMetrics>,
+) -> io::Result<()> {
+    let accept = Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes()))
+        .expect("bft: accept key is a valid header");
+    let stream = request.upgrade("websocket", Response::empty(101).with_header(accept));
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(limits.max_request))
+        .max_frame_size(Some(limits.max_request));
+    let socket = WebSocket::from_raw_socket(stream, Role::Server, Some(config));
+    thread::spawn(move || {
+        if let Err(e) = session(socket, &limits, metrics.as_ref()) {
+            eprintln!("bft: stream session failed: {}", e);
+        }
+        drop(slot);
+    });
+    Ok(())
+}
+
+/// Run the program of the first message of the client
+fn session(
+    mut socket: BftSocket,
+    limits: &BftServeLimits,
+    metrics: &dyn BFMetricsSink,
+) -> io::Result<()> {
+    let body = loop {
+        match socket.read().map_err(io::Error::other)? {
+            Message::Text(text) => break text.as_bytes().to_vec(),
+            Message::Binary(data) => break data.to_vec(),
+            Message::Close(_) => return Ok(()),
+            _ => {}
+        }
+    };
+    let start = Instant::now();
+    let session = RefCell::new(BftSession {
+        socket,
+        input: VecDeque::new(),
+        eof: false,
+        stopped: false,
+        pending: Vec::new(),
+        written: 0,
+        max_output: limits.max_output,
+        truncated: false,
+    });
+
+    let halted = match BftRunSetup::new(&body, limits) {
+        Err((_, answer)) => json!({
+            "type": "halted",
+            "ok": false,
+            "error": answer["error"],
+            "stats": Value::Null,
+        }),
+        Ok(setup) => {
+            session.borrow_mut().input.extend(setup.input());
+            let mut bf_vm = setup.vm();
+            let mut reader = BFFnReader::new(|| session.borrow_mut().read());
+            let mut writer = BFFnWriter::new(|byte| session.borrow_mut().write(byte));
+            let result = loop {
+                match bf_vm.step(&mut reader, &mut writer) {
+                    Ok(true) => {
+                        let steps = bf_vm.stats().steps();
+                        if steps % STATE_INTERVAL == 0 {
+                            let state =
+                                json!({ "type": "state", "steps": steps, "head": bf_vm.head() });
+                            session.borrow_mut().send(state)?;
+                        }
+                    }
+                    Ok(false) => break Ok(()),
+                    Err(e) => break Err(e),
+                }
+            };
+
+            let session = session.borrow();
+            let cause = if session.truncated {
+                Some(("output_limit", "Output limit exceeded"))
+            } else if session.stopped {
+                Some(("stopped", "Stopped by the client"))
+            } else {
+                None
+            };
+            let error = match result {
+                Ok(()) => Value::Null,
+                Err(e) => vm_diagnostic(e, cause),
+            };
+            json!({
+                "type": "halted",
+                "ok": error.is_null(),
+                "error": error,
+                "stats": stats_json(bf_vm.stats()),
+            })
+        }
+    };
+
+    let steps = halted["stats"]["steps"].as_u64().unwrap_or(0);
+    record(metrics, start, steps, &halted);
+
+    let mut session = session.into_inner();
+    session.send_output(true)?;
+    session.send(halted)?;
+    session.socket.close(None).map_err(io::Error::other)?;
+    // wait for the client to acknowledge the close
+    while session.socket.read().is_ok() {}
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Validate emitted LLVM IR against the interpreter
+//!
+//! The IR is executed with `lli`, or the binary named by the `LLI`
+//! environment variable, on the same input as the virtual machine and
+//! both outputs must be identical, as must the errors printed to stderr.
+
+use bft_interp::emit::BFEmitOptions;
+use bft_interp::{BFCellWidth, BFVirtualMachine, CellKind};
+use bft_types::BFValidProgram;
+use std::error::Error;
+use std::io::{Cursor, Write};
+use std::process::{self, Command, Stdio};
+
+/// stdout and stderr of the interpreter as the emitted program prints them
+fn interpreter_output<T: Default + CellKind>(
+    bf_info: &BFValidProgram,
+    options: &BFEmitOptions,
+    input: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    let mut bf_vm = BFVirtualMachine::<T>::new(options.cells, options.extensible, bf_info);
+    bf_vm.set_eof(options.eof);
+    bf_vm.set_prompt(false);
+
+    let mut w_buf = Cursor::new(Vec::<u8>::new());
+    let errors = match bf_vm.interpret(&mut Cursor::new(input), &mut w_buf) {
+        Ok(()) => Vec::new(),
+        Err(e) => format!("{}\n", e).into_bytes(),
+    };
+    (w_buf.into_inner(), errors)
+}
+
+/// `lli` command, LLVM before 15 needs opaque pointers turned on
+fn lli_command() -> Result<Command, Box<dyn Error>> {
+    let lli = std::env::var("LLI").unwrap_or_else(|_| "lli".to_string());
+    let version = Command::new(&lli)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", lli, e))?;
+    let major = String::from_utf8_lossy(&version.stdout)
+        .split("LLVM version ")
+        .nth(1)
+        .and_then(|v| v.split('.').next())
+        .and_then(|v| v.parse::<u32>().ok());
+
+    let mut command = Command::new(&lli);
+    if major.is_some_and(|v| v < 15) {
+        command.arg("-opaque-pointers");
+    }
+    Ok(command)
+}
+
+/// stdout and stderr of the IR module executed by `lli`
+fn lli_output(ir: &str, input: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+    let ir_path = std::env::temp_dir().join(format!("bft-validate-{}.ll", process::id()));
+    std::fs::write(&ir_path, ir)?;
+
+    let child = lli_command()?
+        .arg(&ir_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let result = child.and_then(|mut child| {
+        child.stdin.take().unwrap().write_all(input)?;
+        child.wait_with_output()
+    });
+    let _ = std::fs::remove_file(&ir_path);
+    let output = result?;
+    Ok((output.stdout, output.stderr))
+}
+
+/// Run `ir` with `lli` and the program on the interpreter, both on
+/// `input`, reporting the first byte where their outputs differ or the
+/// differing errors
+pub fn validate_llvm(
+    bf_info: &BFValidProgram,
+    ir: &str,
+    options: &BFEmitOptions,
+    input: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let (expected, expected_err) = match options.cell_width {
+        BFCellWidth::U8 => interpreter_output::<u8>(bf_info, options, input),
+        BFCellWidth::U16 => interpreter_output::<u16>(bf_info, options, input),
+        BFCellWidth::U32 => interpreter_output::<u32>(bf_info, options, input),
+    };
+    let (actual, actual_err) = lli_output(ir, input)?;
+
+    if actual == expected {
+        if actual_err == expected_err {
+            return Ok(());
+        }
+        return Err(format!(
+            "LLVM IR error differs from the interpreter: expected {:?}, got {:?}",
+            String::from_utf8_lossy(&expected_err),
+            String::from_utf8_lossy(&actual_err)
+        )
+        .into());
+    }
+    let offset = actual
+        .iter()
+        .zip(&expected)
+        .position(|(a, e)| a != e)
+        .unwrap_or(actual.len().min(expected.len()));
+    Err(format!(
+        "LLVM IR output differs from the interpreter at byte {}: expected {:?}, got {:?}",
+        offset,
+        expected.get(offset),
+        actual.get(offset)
+    )
+    .into())
+}
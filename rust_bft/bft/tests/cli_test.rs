@@ -0,0 +1,1100 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// run the `bft` binary on `program` with `args`, returning the exit status
+fn bft_status(program: &str, name: &str, args: &[&str]) -> Option<i32> {
+    let path = std::env::temp_dir().join(format!("bft-cli-{}-{}.bf", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap()
+        .status;
+    std::fs::remove_file(&path).unwrap();
+    status.code()
+}
+
+/// run the `bft` binary on `program` with `args` and `input` as stdin,
+/// returning what it wrote to stdout
+fn bft_output(program: &str, name: &str, args: &[&str], input: &[u8]) -> Vec<u8> {
+    let path = std::env::temp_dir().join(format!("bft-cli-{}-{}.bf", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    output.stdout
+}
+
+/// dbfi, the brainfuck self-interpreter of Daniel B. Cristofani, reading
+/// a program then `!` then the input of that program
+const DBFI: &str = "\
+>>>+[[-]>>[-]++>+>+++++++[<++++>>++<-]++>>+>+>+++++[>++>++++++<<-]+>>>,<++[[>[
+->>]<[>>]<<-]<[<]<+>>[>]>[<+>-[[<+>-]>]<[[[-]<]++<-[<+++++++++>[<->-]>>]>>]]<<
+]<]<[[<]>[[>]>>[>>]+[<<]<[<]<+>>-]>[>]+[->>]<<<<[[<<]<[<]+<<[+>+<<-[>-->+<<-[>
++<[>>+<<-]]]>[<+>-]<]++>>-->[>]>>[>>]]<<[>>+<[[<]<]>[[<<]<[<]+[-<+>>-[<<+>++>-
+[<->[<<+>>-]]]<[>+<-]>]>[>]>]>[>>]>>]<<[>>+>>+>>]<<[->>>>>>>>]<<[>.>>>>>>>]<<[
+>->>>>>]<<[>,>>>]<<[>+>]<<[+<<]<]
+";
+
+#[test]
+fn test_strict_self_interpreter() {
+    let hello = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+    let input = format!("{}!", hello);
+    let output = bft_output(DBFI, "dbfi-hello", &["--strict"], input.as_bytes());
+    assert_eq!(output, b"Hello World!\n");
+
+    // cat stopping at a zero byte, then the inner program reads past the
+    // end of input which leaves the cell unchanged
+    let output = bft_output(DBFI, "dbfi-cat", &["--strict"], b",[.,]!ok\0");
+    assert_eq!(output, b"ok");
+    let output = bft_output(DBFI, "dbfi-eof", &["--strict"], b",.,.,.!ab");
+    assert_eq!(output, b"abb");
+}
+
+#[test]
+fn test_random_seed() {
+    let program = "?.>?.>?.";
+    let args = ["--random", "--seed", "12", "--no-final-newline"];
+    let first = bft_output(program, "random-first", &args, b"");
+    let second = bft_output(program, "random-second", &args, b"");
+    assert_eq!(first.len(), 3);
+    assert_eq!(first, second);
+    // without `--random` the `?` is a comment
+    assert_eq!(bft_output(program, "random-off", &[], b""), b"\0\0\0\n");
+}
+
+#[test]
+fn test_newline() {
+    let program = ",.,.,.,.";
+    let output = bft_output(
+        program,
+        "newline-lf",
+        &["--strict", "--newline", "lf"],
+        b"a\r\nb\r\n",
+    );
+    assert_eq!(output, b"a\nb\n");
+    let output = bft_output(
+        program,
+        "newline-crlf",
+        &["--strict", "--newline=crlf"],
+        b"a\nb",
+    );
+    assert_eq!(output, b"a\r\nb");
+}
+
+#[test]
+fn test_bench() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args([
+            "bench",
+            "--workload",
+            "hello",
+            "-n",
+            "2",
+            "--backend",
+            "loop",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.starts_with("hello (906 instructions)\n"));
+    assert_eq!(report.matches("Minstr/s").count(), 4);
+
+    // a given program is checked against its first configuration
+    let output = bft_output("+++.", "bench", &["bench", "-O", "0", "-O", "3"], b"");
+    assert!(String::from_utf8(output)
+        .unwrap()
+        .contains("(4 instructions)"));
+}
+
+#[test]
+fn test_auto_size() {
+    let program = ">>>>>+++.";
+    let path = std::env::temp_dir().join(format!("bft-cli-auto-size-{}.bf", std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["-c", "3"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("may move up to cell 5, past the 3 cells"));
+
+    let output = bft_output(program, "auto-size", &["--auto-size"], b"");
+    assert_eq!(output, b"\x03\n");
+}
+
+#[test]
+fn test_exit_cell() {
+    let program = "+++>+++++";
+    assert_eq!(bft_status(program, "exit-none", &[]), Some(0));
+    assert_eq!(bft_status(program, "exit-zero", &["--exit-cell"]), Some(3));
+    assert_eq!(bft_status(program, "exit-one", &["--exit-cell=1"]), Some(5));
+    assert_eq!(
+        bft_status(program, "exit-outside", &["-c", "4", "--exit-cell=4"]),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_subcommands() {
+    let program = "++[->+<]>.";
+    assert_eq!(bft_output(program, "run-alias", &[], b""), b"\x02\n");
+    assert_eq!(bft_output(program, "run", &["run"], b""), b"\x02\n");
+    assert_eq!(bft_status(program, "check", &["check"]), Some(0));
+    assert_eq!(bft_status("+[", "check-unbalanced", &["check"]), Some(3));
+    let report = bft_output("+-\n>[]", "check-warnings", &["check"], b"");
+    let report = String::from_utf8(report).unwrap();
+    let lines: Vec<_> = report.lines().collect();
+    assert!(lines[0].ends_with(".bf:1:1: warning: these two instructions cancel each other"));
+    assert!(lines[1].ends_with(".bf:2:2: warning: empty loop never ends once entered"));
+    assert!(lines[2].ends_with(".bf: 0 errors, 2 warnings"));
+    let report = bft_status("]", "check-json", &["check", "--format", "json"]);
+    assert_eq!(report, Some(3));
+    assert_eq!(
+        bft_status(program, "fmt-check", &["fmt", "--check"]),
+        Some(1)
+    );
+    let formatted = bft_output("x y\n+[>[-]+<-]", "fmt", &["fmt"], b"");
+    assert_eq!(formatted, b"x y\n+\n[\n  >[-]+<-\n]\n");
+    let formatted = String::from_utf8(formatted).unwrap();
+    assert_eq!(
+        bft_status(&formatted, "fmt-formatted", &["fmt", "--check"]),
+        Some(0)
+    );
+
+    let tree = bft_output("+[>[-]\n<[-]]>[.]", "visualize", &["visualize"], b"");
+    let tree = String::from_utf8(tree).unwrap();
+    assert!(tree.ends_with(
+        ".bf, 15 instructions\n  loop 1:2..2:5, 8 instructions\n    loop 1:4..1:6, 1 instructions\n    \
+         loop 2:2..2:4, 1 instructions\n  loop 2:7..2:9, 1 instructions\n"
+    ));
+    let dot = bft_output(
+        "[[]]",
+        "visualize-dot",
+        &["visualize", "--format", "dot"],
+        b"",
+    );
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph loops {\n"));
+    assert!(dot.contains("    loop_1_1 -> loop_1_2;\n"));
+    assert_eq!(
+        bft_status("[", "visualize-unmatched", &["visualize"]),
+        Some(3)
+    );
+
+    let commands = b"break 1:9\ncontinue\nprint 2\nstep 100\n";
+    let session = String::from_utf8(bft_output(program, "debug", &["debug"], commands)).unwrap();
+    assert!(session.contains("at 1:9 PointerIncrement 1, head 0 cell 0\n"));
+    assert!(session.contains("cells 0..3: [0] 2 0\n"));
+    assert!(session.contains("program halted after 15 steps\n"));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let lines = b"+++>++\n<[->+<\n]>.\n,.!A\n";
+    child.stdin.take().unwrap().write_all(lines).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(
+        output.stdout,
+        b"head 1 cell 2\n\x05\nhead 1 cell 5\nA\nhead 1 cell 65\n"
+    );
+
+    let path = std::env::temp_dir().join(format!("bft-cli-stats-{}.bf", std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let stats = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_bft"))
+            .arg("stats")
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    // the program only runs with `--execute`
+    let output = stats(&[]);
+    let metrics = String::from_utf8(output.stdout).unwrap();
+    assert!(metrics.contains("histogram     +3 -1 <1 >2 ,0 .1 [1 ]1\n"));
+    assert!(metrics.contains("drift         1\n"));
+    assert!(!metrics.contains("steps"));
+    let output = stats(&["--execute"]);
+    assert_eq!(output.stdout, b"\x02\n");
+    let run = String::from_utf8(output.stderr).unwrap();
+    assert!(run.contains("loops         1 (depth 1)\n"));
+    assert!(run.contains("steps         14\n"));
+    let output = stats(&["--format", "json", "-O", "3"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["histogram"]["+"], 3);
+    assert_eq!(
+        (&report["ops"], &report["opt_level"]),
+        (&3.into(), &"-O3".into())
+    );
+    assert_eq!(report["tape"]["cells"], 2);
+    assert!(report["steps"].is_null());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_repl_commands() {
+    let path = std::env::temp_dir().join(format!("bft-cli-repl-{}.bf", std::process::id()));
+    std::fs::write(&path, "++>+").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["repl", "-c", "4"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let lines = format!(
+        "+++\n>++\n:undo\n:tape 1\n:load {}\n:reset\n:undo\n\
+        :set eof minus-one\n:set\n,!\n:set opt 7\n:set opt 3\n[-]\n:frob\n:quit\n+\n",
+        path.display()
+    );
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let session = String::from_utf8(output.stdout).unwrap();
+    let expected = [
+        "head 0 cell 3",
+        "head 1 cell 2",
+        // undo takes back the last line, then loading and resetting
+        "head 0 cell 3",
+        "cells 0..2: [3] 0",
+        "head 1 cell 1",
+        "head 0 cell 0",
+        "head 1 cell 1",
+        "eof minus-one, opt 1",
+        "head 1 cell 255",
+        "error: invalid variant: 7",
+        "head 1 cell 0",
+        "error: unknown command `:frob`, see `:help`",
+    ];
+    // nothing runs after `:quit`
+    assert_eq!(session.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_eval() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["--strict", "--eval", "++++++++[>++++++++<-]>+."])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["run", "-E", "+["])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--> <eval>:1:2"));
+    // an inline program takes the place of `PROGRAM`
+    assert_eq!(bft_status("+", "eval-name", &["--eval", "+"]), Some(2));
+}
+
+#[test]
+fn test_stdin_program() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["--strict", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let program = b"read the input after the bang\n++++++++[>++++++++<-]>+.,.,.!Bc";
+    child.stdin.take().unwrap().write_all(program).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    // the input is what follows the bang, without any prompt
+    assert_eq!(output.stdout, b"ABc");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["check", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"+[").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.starts_with(b"-:1:2: error: "));
+}
+
+#[test]
+fn test_input_file() {
+    let input = std::env::temp_dir().join(format!("bft-cli-input-{}.in", std::process::id()));
+    std::fs::write(&input, "ab").unwrap();
+    let path = input.to_str().unwrap();
+    // stdin is left alone and nothing is prompted
+    let output = bft_output(",.,.", "input-file", &["--input", path], b"zz");
+    assert_eq!(output, b"ab\n");
+    let output = bft_output(
+        ",.,.,.",
+        "input-eof",
+        &["--input", path, "--eof", "zero"],
+        b"",
+    );
+    assert_eq!(output, b"ab\0\n");
+    let output = bft_output(
+        ",.,.,.",
+        "input-strict",
+        &["--strict", "--input", path],
+        b"",
+    );
+    assert_eq!(output, b"abb");
+    let output = bft_output(
+        ",.,.,.",
+        "input-minus",
+        &["--input", path, "--eof", "minus-one"],
+        b"",
+    );
+    assert_eq!(output, b"ab\xff\n");
+
+    // running out of input points at `--eof`
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["--input", path, "--eval", ",,,"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("= help: the input ran out, `--eof zero`")
+    );
+    std::fs::remove_file(&input).unwrap();
+}
+
+#[test]
+fn test_output_file() {
+    let output = std::env::temp_dir().join(format!("bft-cli-output-{}.out", std::process::id()));
+    let path = output.to_str().unwrap();
+    std::fs::write(&output, "truncated").unwrap();
+    // the file gets the output, the prompt stays on stdout
+    let stdout = bft_output(",+.", "output-file", &["-o", path], b"a");
+    assert_eq!(stdout, b"Input a value: \n");
+    assert_eq!(std::fs::read(&output).unwrap(), b"b\n");
+
+    let stdout = bft_output("+++", "output-ir", &["--emit-ir", "--output", path], b"");
+    assert!(stdout.is_empty());
+    assert!(std::fs::read_to_string(&output)
+        .unwrap()
+        .starts_with("; bft IR -O1, 1 ops\n"));
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_tee() {
+    let tee = std::env::temp_dir().join(format!("bft-cli-tee-{}.out", std::process::id()));
+    let path = tee.to_str().unwrap();
+    let stdout = bft_output(",[.,]", "tee", &["--strict", "--tee", path], b"ab\r\n\0");
+    assert_eq!(stdout, b"ab\r\n");
+    assert_eq!(std::fs::read(&tee).unwrap(), stdout);
+    std::fs::remove_file(&tee).unwrap();
+}
+
+#[test]
+fn test_shared_tape() {
+    let dir = std::env::temp_dir();
+    let mut paths = Vec::new();
+    for (index, source) in ["++++++++[>++++++++<-]>", "+.>", "<+."].iter().enumerate() {
+        let path = dir.join(format!("bft-cli-shared-{}-{}.b", index, std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        paths.push(path.to_str().unwrap().to_string());
+    }
+    // the head and the cells carry over, a single newline ends the output
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["run"])
+        .args(&paths)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"AB\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["run", "-O3"])
+        .args(&paths)
+        .output()
+        .unwrap();
+    assert_eq!(output.stdout, b"AB\n");
+
+    // each program keeps its own brackets and positions
+    std::fs::write(&paths[2], "<+.[").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(&paths)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let message = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(message.contains("error[unmatched-open]: no close bracket matches"));
+    assert!(message.contains(&format!("--> {}:1:4", paths[2])));
+    for path in paths {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_cell_width() {
+    // the second cell only wraps to zero with 8-bit cells
+    let program = "++++++++++++++++[>++++++++++++++++<-]>[>+<[-]]";
+    let status = |width| {
+        bft_status(
+            program,
+            "cell-width",
+            &["--cell-width", width, "--exit-cell=2"],
+        )
+    };
+    assert_eq!(status("8"), Some(0));
+    assert_eq!(status("16"), Some(1));
+    assert_eq!(status("32"), Some(1));
+    // the output is the low byte of the cell
+    let output = bft_output("-.", "cell-width-output", &["--cell-width", "16"], b"");
+    assert_eq!(output, b"\xff\n");
+    assert_eq!(
+        bft_status(
+            "+",
+            "cell-width-strict",
+            &["--strict", "--cell-width", "16"]
+        ),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_config_file() {
+    let dir = std::env::temp_dir().join(format!("bft-cli-config-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("bft")).unwrap();
+    let config = dir.join("bft").join("config.toml");
+    std::fs::write(&config, "cells = 2\neof = \"zero\"\ncell-width = 16\n").unwrap();
+    let path = config.to_str().unwrap();
+
+    let output = bft_output(",.>", "config", &["--config", path], b"");
+    assert_eq!(output, b"Input a value: \n\0\n");
+    // the command line overrides the file, `--strict` ignores it
+    let status = bft_status(">>", "config-cells", &["run", "--config", path, "-c", "3"]);
+    assert_eq!(status, Some(0));
+    let output = bft_output("-.", "config-strict", &["--config", path, "--strict"], b"");
+    assert_eq!(output, b"\xff");
+    let output = bft_output("-.", "config-width", &["--config", path], b"");
+    assert_eq!(output, b"\xff\n");
+
+    // the default file is found in the config directory
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .env("XDG_CONFIG_HOME", &dir)
+        .args(["--eval", ">>"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    std::fs::write(&config, "cels = 2\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["--config", path, "--eval", "+"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown field `cels`"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_env_vars() {
+    let bft = |envs: &[(&str, &str)], args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .envs(envs.iter().copied())
+            .args(args)
+            .output()
+            .unwrap();
+        (output.status.code(), output.stdout)
+    };
+    let cells = [("BFT_CELLS", "2")];
+    assert_eq!(bft(&cells, &["--eval", ">>"]).0, Some(4));
+    // the command line overrides the environment, `--strict` ignores it
+    assert_eq!(bft(&cells, &["--eval", ">>", "-c", "3"]).0, Some(0));
+    assert_eq!(bft(&cells, &["--strict", "--eval", ">>"]).0, Some(0));
+    let (status, stdout) = bft(&[("BFT_OPT", "3")], &["--emit-ir", "--eval", "+"]);
+    assert_eq!(status, Some(0));
+    assert!(stdout.starts_with(b"; bft IR -O3"));
+    let envs = [("BFT_EOF", "zero"), ("BFT_EXTENSIBLE", "true")];
+    let (status, stdout) = bft(
+        &envs,
+        &["--strict", "--eval", ",.>>>>", "--input", "/dev/null"],
+    );
+    assert_eq!((status, stdout), (Some(0), b"\0".to_vec()));
+    assert_eq!(bft(&[("BFT_OPT", "7")], &["--eval", "+"]).0, Some(2));
+}
+
+#[test]
+fn test_verbosity() {
+    let stderr = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(args)
+            .args(["--head-mode", "clamp", "-e", "-c", "1", "--eof", "zero"])
+            .args(["--input", "/dev/null", "--eval", "<,.>>"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let default = stderr(&[]);
+    assert_eq!(
+        default,
+        "warning[head-left]: the head may move left of the first cell\n --> <eval>\n"
+    );
+    assert_eq!(stderr(&["-q"]), "");
+    let info = stderr(&["-v"]);
+    assert!(info.contains("parsed") && !info.contains("extended the tape"));
+    let debug = stderr(&["-vv"]);
+    assert!(debug.contains("packed the program") && debug.contains("extended the tape cells=3"));
+    let trace = stderr(&["-vvv"]);
+    assert!(trace.contains("kind=Input value=None") && trace.contains("kind=Output"));
+}
+
+#[test]
+fn test_diagnostics_format() {
+    let diagnostics = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(["--diagnostics-format", "json", "--input", "/dev/null"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        stderr
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect::<Vec<serde_json::Value>>()
+    };
+    let head = diagnostics(&["--eval", "+\n<"]);
+    assert_eq!(head.len(), 2);
+    assert_eq!(head[0]["severity"], "warning");
+    assert_eq!(head[0]["code"], "head-left");
+    let error = &head[1];
+    assert_eq!(error["severity"], "error");
+    assert_eq!(error["code"], "head-out-of-bounds");
+    assert_eq!(error["file"], "<eval>");
+    assert_eq!((&error["line"], &error["column"]), (&2.into(), &1.into()));
+    assert_eq!(error["span"]["column_end"], 2);
+
+    let error = &diagnostics(&["--eval", "[+"])[0];
+    assert_eq!(error["code"], "unmatched-open");
+    assert_eq!(
+        error["message"],
+        "no close bracket matches this open bracket"
+    );
+    let error = &diagnostics(&["--eval", ","])[0];
+    assert_eq!(error["code"], "io");
+    assert!(error["help"].as_str().unwrap().contains("--eof zero"));
+    let error = &diagnostics(&["missing.bf"])[0];
+    assert!(error["file"].is_null() && error["span"].is_null());
+}
+
+#[test]
+fn test_color() {
+    let stderr = |envs: &[(&str, &str)], args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .env_remove("NO_COLOR")
+            .envs(envs.iter().copied())
+            .args(args)
+            .args(["--eval", "+\n\t+<"])
+            .output()
+            .unwrap();
+        assert!(output.stdout.is_empty());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    // the source line is shown with a caret under the instruction
+    let plain = "warning[head-left]: the head may move left of the first cell\n \
+        --> <eval>\n\
+        error[head-out-of-bounds]: the head fell off the tape\n \
+        --> <eval>:2:3\n  |\n2 | \t+<\n  | \t ^\n";
+    assert_eq!(stderr(&[], &[]), plain);
+    assert_eq!(stderr(&[], &["--color", "never"]), plain);
+    let colored = stderr(&[], &["--color", "always"]);
+    assert!(colored.starts_with("\x1b[1;33mwarning[head-left]\x1b[0m"));
+    assert!(colored.contains("\x1b[1;31merror[head-out-of-bounds]\x1b[0m"));
+    // `NO_COLOR` only turns off the automatic colors
+    assert_eq!(stderr(&[("NO_COLOR", "1")], &["--color", "auto"]), plain);
+    assert_eq!(
+        stderr(&[("NO_COLOR", "1")], &["--color", "always"]),
+        colored
+    );
+}
+
+#[test]
+fn test_completions() {
+    for (shell, marker) in [
+        ("bash", "_bft()"),
+        ("zsh", "#compdef bft"),
+        ("fish", "complete -c bft"),
+        ("powershell", "Register-ArgumentCompleter"),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(["completions", shell])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(script.contains(marker), "{}", shell);
+        // subcommands and their options are completed
+        assert!(script.contains("run-all") && script.contains("diagnostics-format"));
+    }
+    assert_eq!(
+        bft_status("", "completions", &["completions", "tcsh"]),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_exit_codes() {
+    let bft = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(args)
+            .output()
+            .unwrap();
+        output.status.code()
+    };
+    assert_eq!(bft(&["--cells", "0", "--eval", "+"]), Some(2));
+    assert_eq!(bft(&["--digest", "--eval", "+", "-"]), Some(2));
+    assert_eq!(bft(&["--eval", "+["]), Some(3));
+    assert_eq!(bft(&["--eval", "<"]), Some(4));
+    assert_eq!(bft(&["--fuel", "10", "--eval", "+[]"]), Some(4));
+    assert_eq!(bft(&["/nonexistent/program.bf"]), Some(5));
+    assert_eq!(bft(&["--timeout", "0.1", "--eval", "+[]"]), Some(124));
+}
+
+#[test]
+fn test_time() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["--time", "-O0", "--eval", "+++[-]."])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\0\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let summary = stderr.strip_prefix("bft: parse ").unwrap();
+    assert!(summary.contains("ms, optimize "), "{}", summary);
+    assert!(summary.contains("ms, 11 instructions, "), "{}", summary);
+    assert!(summary.ends_with(" instructions/s\n"), "{}", summary);
+}
+
+#[test]
+fn test_watch() {
+    use std::io::BufRead;
+
+    let path = std::env::temp_dir().join(format!("bft-cli-watch-{}.bf", std::process::id()));
+    std::fs::write(&path, "++++++++[>++++++++<-]>+.").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["run", "--watch", "--input", "/dev/null"])
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "A\n");
+
+    // the program runs again once its file changes
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::fs::write(&path, "++++++++[>++++++++<-]>++.").unwrap();
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "B\n");
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("changed, running again"));
+    std::fs::remove_file(&path).unwrap();
+
+    let status = bft_status("+", "watch-stdin", &["run", "--watch", "-"]);
+    assert_eq!(status, Some(2));
+}
+
+#[test]
+fn test_translate() {
+    let ook = bft_output(
+        "+[->+<]. done",
+        "translate-ook",
+        &["translate", "--to", "ook"],
+        b"",
+    );
+    let ook = String::from_utf8(ook).unwrap();
+    assert!(ook.starts_with("Ook. Ook. Ook! Ook? Ook! Ook! Ook. Ook?"));
+    let args = ["translate", "--from", "ook", "--to", "bf"];
+    assert_eq!(bft_output(&ook, "translate-bf", &args, b""), b"+[->+<].\n");
+    let args = [
+        "translate",
+        "--from",
+        "ook",
+        "--to",
+        "brainfuck",
+        "--pretty",
+    ];
+    let formatted = bft_output(&ook, "translate-pretty", &args, b"");
+    assert_eq!(formatted, b"+[->+<].\n");
+
+    // the config file defines dialects, their programs running the same
+    let dir = std::env::temp_dir().join(format!("bft-cli-dialect-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.toml");
+    let dialect = r#"[dialects.words]
+"+" = "inc"
+"-" = "dec"
+"<" = "left"
+">" = "right"
+"," = "get"
+"." = "put"
+"[" = "do"
+"]" = "od"
+"#;
+    std::fs::write(&config, dialect).unwrap();
+    let path = config.to_str().unwrap();
+    let args = ["translate", "--config", path, "--to", "words"];
+    let words = bft_output("+++[>++++++++++++<-]>.", "translate-words", &args, b"");
+    let words = String::from_utf8(words).unwrap();
+    assert!(words.starts_with("inc inc inc do right inc"));
+    let args = [
+        "translate",
+        "--config",
+        path,
+        "--from",
+        "words",
+        "--to",
+        "bf",
+    ];
+    let bf = String::from_utf8(bft_output(&words, "translate-back", &args, b"")).unwrap();
+    let output = bft_output(&bf, "translate-run", &["--no-final-newline"], b"");
+    assert_eq!(output, b"$");
+
+    assert_eq!(
+        bft_status("+", "translate-unknown", &["translate", "--to", "blub"]),
+        Some(2)
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_digest() {
+    let path = std::env::temp_dir().join(format!("bft-cli-digest-{}.bf", std::process::id()));
+    std::fs::write(&path, ",[>+++<-]>[-<+>]<.+.").unwrap();
+    let digest = |level: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(["--digest", level])
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"\x05").unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.ends_with(b"\x0f\x10\n"));
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let digest_o0 = digest("-O0");
+    assert!(digest_o0.starts_with("bft: digest "));
+    assert_eq!(digest_o0, digest("-O3"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_run_all() {
+    let dir = std::env::temp_dir().join(format!("bft-cli-run-all-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("echo.bf"), ",[.,]").unwrap();
+    std::fs::write(dir.join("echo.in"), "hi").unwrap();
+    std::fs::write(dir.join("echo.out"), "hi\n").unwrap();
+    std::fs::write(dir.join("plain.bf"), "+.").unwrap();
+
+    let run_all = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(["run-all", "--eof", "zero", "-j", "2", "--json"])
+            .arg(dir.join("report.json"))
+            .arg(&dir)
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+    let output = run_all(&[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Input a value"));
+    let report = std::fs::read_to_string(dir.join("report.json")).unwrap();
+    assert!(report.contains("\"passed\": 1"));
+    assert!(report.contains("\"finished\": 1"));
+
+    std::fs::write(dir.join("echo.out"), "ho\n").unwrap();
+    assert_eq!(run_all(&[]).status.code(), Some(1));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_run_all_archives() {
+    let data = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+    let run_all = |archive: &str| {
+        Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args(["run-all", "--eof", "zero"])
+            .arg(format!("{}/{}", data, archive))
+            .output()
+            .unwrap()
+    };
+    // stored and deflated entries, with programs in a subdirectory
+    let output = run_all("corpus.zip");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("corpus.zip:programs/hanoi.b"));
+    assert!(stdout.contains("3 programs, 3 passed"));
+
+    // gzip compressed tar with a pax long name
+    let output = run_all("corpus.tar.gz");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("/echo.b"));
+    assert!(stdout.contains("2 programs, 1 passed, 1 failed"));
+}
+
+#[test]
+fn test_golden_files() {
+    let dir = std::env::temp_dir().join(format!("bft-cli-golden-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    std::fs::write(dir.join("echo.b"), ",[.,]").unwrap();
+    std::fs::write(dir.join("echo.in"), "one\ntwo").unwrap();
+    std::fs::write(dir.join("echo.expected"), "one\ntwo").unwrap();
+    std::fs::write(dir.join("nested/loop.bf"), "+[]").unwrap();
+    std::fs::write(dir.join("nested/loop.expected"), "").unwrap();
+    std::fs::write(dir.join("helper.b"), "+[]").unwrap();
+
+    let test = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_bft"))
+            .args([
+                "test",
+                "--eof",
+                "zero",
+                "--no-final-newline",
+                "--fuel",
+                "1000",
+            ])
+            .args(extra)
+            .arg(&dir)
+            .output()
+            .unwrap()
+    };
+    let output = test(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("PASS  "));
+    assert!(stdout.contains("ERROR "));
+    assert!(stdout.contains("Fuel exhausted"));
+    assert!(stdout.contains("2 tests, 1 passed, 0 failed, 1 errors"));
+
+    std::fs::remove_file(dir.join("nested/loop.bf")).unwrap();
+    std::fs::write(dir.join("echo.expected"), "one\nTwo").unwrap();
+    let output = test(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("line 2 differs"));
+    assert!(stdout.contains("expected: \"Two\""));
+
+    std::fs::write(dir.join("echo.expected"), "one\ntwo").unwrap();
+    assert!(test(&[]).status.success());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// send `request` to the server at `addr`, returning the whole response
+fn http(addr: &str, request: &str) -> String {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// POST `body` to `/run`
+fn post_run(addr: &str, body: &str) -> String {
+    http(
+        addr,
+        &format!(
+            "POST /run HTTP/1.1\r\nHost: bft\r\nConnection: close\r\n\
+            Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+    )
+}
+
+/// run an interactive program over `/stream`, feeding it input in two parts
+fn stream_session(addr: &str) {
+    use tungstenite::Message;
+    let stream = std::net::TcpStream::connect(addr).unwrap();
+    let (mut socket, _) = tungstenite::client(format!("ws://{}/stream", addr), stream).unwrap();
+    let request = r#"{"source": ",[.,]", "input": "hi", "eof": "zero", "final_newline": false}"#;
+    socket.send(Message::text(request)).unwrap();
+
+    let mut replies = vec![r#"{"type": "eof"}"#, r#"{"type": "input", "data": "yo"}"#];
+    let mut events = Vec::new();
+    let halted = loop {
+        let message = match socket.read().unwrap() {
+            Message::Text(text) => serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            _ => continue,
+        };
+        match message["type"].as_str().unwrap() {
+            "output" => events.push(message["data"].as_str().unwrap().to_string()),
+            "waiting" => {
+                events.push("waiting".to_string());
+                socket.send(Message::text(replies.pop().unwrap())).unwrap();
+            }
+            "halted" => break message,
+            kind => panic!("unexpected {} message", kind),
+        }
+    };
+    assert_eq!(events, ["hi", "waiting", "yo", "waiting"]);
+    assert_eq!(halted["ok"], true);
+    assert!(halted["stats"]["steps"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_serve() {
+    use std::io::{BufRead, Read};
+    use std::process::Stdio;
+    let mut server = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["serve", "--bind", "127.0.0.1:0", "--max-fuel", "1000"])
+        .args(["--max-output", "4"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // kept open until the end, the server prints to stdout while running
+    let mut stdout = std::io::BufReader::new(server.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let addr = line.trim().rsplit("http://").next().unwrap().to_string();
+
+    let response = post_run(
+        &addr,
+        r#"{"source": ",[.,]", "input": "hi", "eof": "zero"}"#,
+    );
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains(r#""ok":true"#));
+    assert!(response.contains(r#""output":"hi\n""#));
+
+    let response = post_run(&addr, r#"{"source": "+[]"}"#);
+    assert!(response.contains(r#""kind":"fuel""#));
+    assert!(response.contains(r#""code":"fuel-exhausted""#));
+    let response = post_run(&addr, r#"{"source": "+[.]"}"#);
+    assert!(response.contains(r#""kind":"output_limit""#));
+    let response = post_run(&addr, r#"{"source": "["}"#);
+    assert!(response.contains(r#""kind":"syntax""#));
+    let response = post_run(&addr, r#"{"program": "+"}"#);
+    assert!(response.starts_with("HTTP/1.1 400"));
+    let response = http(&addr, "GET /run HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405"));
+    let response = http(&addr, "GET /stream HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 400"));
+
+    stream_session(&addr);
+
+    let response = http(&addr, "GET /metrics HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains("bft_programs_run_total 6\n"));
+    assert!(response.contains("bft_fuel_exhaustions_total 1\n"));
+    assert!(response.contains("bft_errors_total{kind=\"syntax\"} 1\n"));
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+    let mut log = String::new();
+    stdout.read_to_string(&mut log).unwrap();
+    assert!(!log.contains("Input a value"));
+}
+
+#[test]
+fn test_serve_stream_limits() {
+    use std::io::BufRead;
+    use std::process::Stdio;
+    use tungstenite::Message;
+    let mut server = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["serve", "--bind", "127.0.0.1:0", "--max-request", "64"])
+        .args(["--max-sessions", "1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdout = std::io::BufReader::new(server.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let addr = line.trim().rsplit("http://").next().unwrap().to_string();
+    // the socket, or the status the handshake was refused with
+    let connect = || {
+        let stream = std::net::TcpStream::connect(&addr).unwrap();
+        match tungstenite::client(format!("ws://{}/stream", addr), stream) {
+            Ok((socket, _)) => Ok(socket),
+            Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(response))) => {
+                Err(response.status().as_u16())
+            }
+            Err(e) => panic!("handshake failed: {}", e),
+        }
+    };
+
+    // no second session while the first one runs
+    let idle = connect().unwrap();
+    assert_eq!(connect().err(), Some(503));
+    drop(idle);
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let mut socket = connect().unwrap();
+    socket.send(Message::text("x".repeat(100))).unwrap();
+    assert!(socket.read().is_err());
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+}
+
+#[test]
+fn test_url_program() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            std::io::Read::read_exact(&mut stream, &mut byte).unwrap();
+            request.push(byte[0]);
+        }
+        let program = "++++++++[>++++++++<-]>+.";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            program.len(),
+            program
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args([
+            "--no-final-newline",
+            &format!("http://127.0.0.1:{}/a.b", port),
+        ])
+        .output()
+        .unwrap();
+    server.join().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .arg("ftp://127.0.0.1/a.b")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unsupported URL scheme"));
+}
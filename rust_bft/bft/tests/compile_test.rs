@@ -0,0 +1,315 @@
+use bft_interp::emit::{self, BFEmitOptions};
+use bft_interp::{BFCellWidth, BFEofBehavior, BFVirtualMachine, CellKind};
+use bft_types::{BFProgram, BFValidProgram};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// build emitted rust source with rustc and run it on `input`
+fn run_emitted(source: &str, name: &str, input: &[u8]) -> Vec<u8> {
+    run_binary(&build_emitted(source, name), input)
+}
+
+/// build emitted rust source with rustc, returning the binary path
+fn build_emitted(source: &str, name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let src_path = dir.join(format!("{}.rs", name));
+    let bin_path = dir.join(name);
+    std::fs::write(&src_path, source).unwrap();
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let status = Command::new(rustc)
+        .arg("-O")
+        .arg("-o")
+        .arg(&bin_path)
+        .arg(&src_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    bin_path
+}
+
+/// build emitted C source with cc and run it on `input`
+fn run_emitted_c(source: &str, name: &str, input: &[u8]) -> Vec<u8> {
+    run_binary(&build_emitted_c(source, name), input)
+}
+
+/// build emitted C source with cc, returning the binary path
+fn build_emitted_c(source: &str, name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let src_path = dir.join(format!("{}.c", name));
+    let bin_path = dir.join(format!("{}_c", name));
+    std::fs::write(&src_path, source).unwrap();
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .arg("-std=c99")
+        .arg("-O2")
+        .arg("-o")
+        .arg(&bin_path)
+        .arg(&src_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    bin_path
+}
+
+/// run a compiled program feeding `input` to its stdin
+fn run_binary(bin_path: &Path, input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new(bin_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap().stdout
+}
+
+/// run the same program on the brainfuck virtual machine
+fn run_vm<T: Default + CellKind>(
+    bf_info: &BFValidProgram,
+    eof: BFEofBehavior,
+    input: &[u8],
+) -> Vec<u8> {
+    let mut bf_vm = BFVirtualMachine::<T>::new(100, false, bf_info);
+    bf_vm.set_eof(eof);
+    let mut w_buf = Cursor::new(Vec::<u8>::new());
+    bf_vm
+        .interpret(&mut Cursor::new(input.to_vec()), &mut w_buf)
+        .unwrap();
+    w_buf.into_inner()
+}
+
+#[test]
+fn test_compile_rust_echo() {
+    let bf_info = BFProgram::new("", ",[.,]").validate().unwrap();
+    let options = BFEmitOptions {
+        cells: 100,
+        eof: BFEofBehavior::Zero,
+        ..BFEmitOptions::default()
+    };
+    let source = emit::emit_rust(&bf_info, &options);
+    let output = run_emitted(&source, "echo", b"abc");
+
+    assert_eq!(output, b"abc\n");
+    assert_eq!(output, run_vm::<u8>(&bf_info, BFEofBehavior::Zero, b"abc"));
+}
+
+#[test]
+fn test_compile_rust_cell_width() {
+    let bf_info = BFProgram::new("", "-[>+<-----]>.").validate().unwrap();
+    let options = BFEmitOptions {
+        cells: 100,
+        cell_width: BFCellWidth::U16,
+        ..BFEmitOptions::default()
+    };
+    let source = emit::emit_rust(&bf_info, &options);
+    let output = run_emitted(&source, "cell_width", b"");
+
+    assert_eq!(output, vec![0x33, 10]);
+    assert_eq!(output, run_vm::<u16>(&bf_info, BFEofBehavior::Error, b""));
+}
+
+#[test]
+fn test_compile_rust_head_error() {
+    let bf_info = BFProgram::new("", "+.<").validate().unwrap();
+    let source = emit::emit_rust(&bf_info, &BFEmitOptions::default());
+    let output = Command::new(build_emitted(&source, "head_error"))
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"\x01");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Head falling off edge by     1:3    > Decrement current pointer\n"
+    );
+}
+
+#[test]
+fn test_compile_c_semantics() {
+    let bf_info = BFProgram::new("", ",[.,]>-[>+<-----]>.")
+        .validate()
+        .unwrap();
+    for (width, eof) in [
+        (BFCellWidth::U8, BFEofBehavior::Zero),
+        (BFCellWidth::U16, BFEofBehavior::Zero),
+    ] {
+        let options = BFEmitOptions {
+            cells: 100,
+            cell_width: width,
+            eof,
+            ..BFEmitOptions::default()
+        };
+        let source = emit::emit_c(&bf_info, &options);
+        let output = run_emitted_c(&source, &format!("c_{}", width.bits()), b"hi");
+        let expected = match width {
+            BFCellWidth::U8 => run_vm::<u8>(&bf_info, eof, b"hi"),
+            _ => run_vm::<u16>(&bf_info, eof, b"hi"),
+        };
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn test_compile_c_head_error() {
+    let bf_info = BFProgram::new("", "+.<").validate().unwrap();
+    let source = emit::emit_c(&bf_info, &BFEmitOptions::default());
+    let output = Command::new(build_emitted_c(&source, "c_head_error"))
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"\x01");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Head falling off edge by     1:3    > Decrement current pointer\n"
+    );
+}
+
+/// host state of an emitted WebAssembly module
+struct WasmHost {
+    input: std::collections::VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+/// assemble emitted WAT and run it in wasmi on `input`,
+/// returning the status of `run`, the fault line and the output
+fn run_emitted_wat(source: &str, input: &[u8]) -> (i32, i32, Vec<u8>) {
+    use wasmi::{Caller, Engine, Linker, Module, Store};
+
+    let engine = Engine::default();
+    let module = Module::new(&engine, wat::parse_str(source).unwrap()).unwrap();
+    let host = WasmHost {
+        input: input.iter().copied().collect(),
+        output: Vec::new(),
+    };
+    let mut store = Store::new(&engine, host);
+    let mut linker = Linker::<WasmHost>::new(&engine);
+    linker
+        .func_wrap("env", "read_byte", |mut caller: Caller<'_, WasmHost>| {
+            caller.data_mut().input.pop_front().map_or(-1, i32::from)
+        })
+        .unwrap();
+    linker
+        .func_wrap(
+            "env",
+            "write_byte",
+            |mut caller: Caller<'_, WasmHost>, value: i32| {
+                caller.data_mut().output.push(value as u8);
+            },
+        )
+        .unwrap();
+
+    let instance = linker.instantiate_and_start(&mut store, &module).unwrap();
+    let run = instance.get_typed_func::<(), i32>(&store, "run").unwrap();
+    let status = run.call(&mut store, ()).unwrap();
+    let line = instance
+        .get_global(&store, "fault_line")
+        .unwrap()
+        .get(&store)
+        .i32()
+        .unwrap();
+    (status, line, store.into_data().output)
+}
+
+#[test]
+fn test_compile_wat_semantics() {
+    let bf_info = BFProgram::new("", ",[.,]>-[>+<-----]>.")
+        .validate()
+        .unwrap();
+    for width in [BFCellWidth::U8, BFCellWidth::U16] {
+        let options = BFEmitOptions {
+            cells: 100,
+            cell_width: width,
+            eof: BFEofBehavior::Zero,
+            ..BFEmitOptions::default()
+        };
+        let (status, _, mut output) = run_emitted_wat(&emit::emit_wat(&bf_info, &options), b"hi");
+        let expected = match width {
+            BFCellWidth::U8 => run_vm::<u8>(&bf_info, BFEofBehavior::Zero, b"hi"),
+            _ => run_vm::<u16>(&bf_info, BFEofBehavior::Zero, b"hi"),
+        };
+        // the interpreter output carries the final newline
+        output.push(b'\n');
+        assert_eq!(status, 0);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn test_compile_wat_faults() {
+    let bf_info = BFProgram::new("", "+.\n>>").validate().unwrap();
+    let options = BFEmitOptions {
+        cells: 2,
+        ..BFEmitOptions::default()
+    };
+    let source = emit::emit_wat(&bf_info, &options);
+    assert_eq!(run_emitted_wat(&source, b""), (1, 2, vec![1]));
+
+    let options = BFEmitOptions {
+        cells: 2,
+        extensible: true,
+        ..BFEmitOptions::default()
+    };
+    let source = emit::emit_wat(&bf_info, &options);
+    assert_eq!(run_emitted_wat(&source, b""), (0, 0, vec![1]));
+
+    let bf_info = BFProgram::new("", ",").validate().unwrap();
+    let source = emit::emit_wat(&bf_info, &BFEmitOptions::default());
+    assert_eq!(run_emitted_wat(&source, b"").0, 2);
+}
+
+/// validate emitted LLVM IR with `bft compile --validate` on `input`,
+/// returning whether it passed and the emitted IR
+#[cfg(feature = "llvm")]
+fn validate_emitted_llvm(program: &str, args: &[&str], input: &[u8]) -> (bool, String) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let bf_path = dir.join("validate_llvm.bf");
+    let ir_path = dir.join("validate_llvm.ll");
+    std::fs::write(&bf_path, program).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["compile", "--target", "llvm", "--validate", "-o"])
+        .arg(&ir_path)
+        .args(args)
+        .arg(&bf_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let status = child.wait_with_output().unwrap().status;
+    (status.success(), std::fs::read_to_string(ir_path).unwrap())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_compile_llvm_validate() {
+    let program = ",.,.,.,.-.>++[<+>-]<.";
+    for width in ["8", "16", "32"] {
+        for eof in ["zero", "minus-one", "unchanged", "error"] {
+            let args = ["--cells", "3", "--cell-width", width, "--eof", eof];
+            let (valid, ir) = validate_emitted_llvm(program, &args, b"abc");
+            assert!(valid, "{} {}", width, eof);
+            assert!(ir.starts_with("; Generated by bft"));
+        }
+    }
+
+    assert!(validate_emitted_llvm("+[>+]", &["--cells", "3"], b"").0);
+    assert!(validate_emitted_llvm(">>>", &["--cells", "3", "--extensible"], b"").0);
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let bf_path = dir.join("validate_llvm_stdout.bf");
+    std::fs::write(&bf_path, ",.").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_bft"))
+        .args(["compile", "--target", "llvm", "--validate"])
+        .arg(&bf_path)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"; Generated by bft"));
+}
@@ -6,7 +6,9 @@ fn test_bft_run() {
         "",
         "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..
         +++.>>.<-.<.+++.------.--------.>>+.>++.",
-    );
+    )
+    .validate()
+    .unwrap();
     let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(1000, false, &bf_info);
     let mut r_buf = Cursor::new(Vec::<u8>::new());
     let mut w_buf = Cursor::new(Vec::<u8>::new());
@@ -19,7 +21,9 @@ fn test_bft_run() {
 
 #[test]
 fn test_input_output() {
-    let bf_info = bft_types::BFProgram::new("", ",>,>,>,>,>,.<.<.<.<.<.");
+    let bf_info = bft_types::BFProgram::new("", ",>,>,>,>,>,.<.<.<.<.<.")
+        .validate()
+        .unwrap();
     let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(1000, false, &bf_info);
     let mut r_buf = Cursor::new(vec![1, 2, 3, 4, 5, 6]);
     let mut w_buf = Cursor::new(Vec::<u8>::new());
@@ -31,7 +35,9 @@ fn test_input_output() {
 
 #[test]
 fn test_increment() {
-    let bf_info = bft_types::BFProgram::new("", ",+>,+>,+>,+>,+>,+.<.<.<.<.<.");
+    let bf_info = bft_types::BFProgram::new("", ",+>,+>,+>,+>,+>,+.<.<.<.<.<.")
+        .validate()
+        .unwrap();
     let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(10, false, &bf_info);
     let mut r_buf = Cursor::new(vec![1, 2, 3, 4, 5, 6]);
     let mut w_buf = Cursor::new(Vec::<u8>::new());
@@ -43,7 +49,9 @@ fn test_increment() {
 
 #[test]
 fn test_decrement() {
-    let bf_info = bft_types::BFProgram::new("", ",->,->,->,->,->,-.<.<.<.<.<.");
+    let bf_info = bft_types::BFProgram::new("", ",->,->,->,->,->,-.<.<.<.<.<.")
+        .validate()
+        .unwrap();
     let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(10, false, &bf_info);
     let mut r_buf = Cursor::new(vec![1, 2, 3, 4, 5, 6]);
     let mut w_buf = Cursor::new(Vec::<u8>::new());
@@ -55,7 +63,7 @@ fn test_decrement() {
 
 #[test]
 fn test_loop() {
-    let bf_info = bft_types::BFProgram::new("", ",[-.]");
+    let bf_info = bft_types::BFProgram::new("", ",[-.]").validate().unwrap();
     let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(10, false, &bf_info);
     let mut r_buf = Cursor::new(vec![6]);
     let mut w_buf = Cursor::new(Vec::<u8>::new());
@@ -64,3 +72,30 @@ fn test_loop() {
     assert!(result.is_ok());
     assert_eq!(w_buf.get_ref(), &vec![5, 4, 3, 2, 1, 0, 10]);
 }
+
+#[test]
+fn test_opt_levels() {
+    let bf_info = bft_types::BFProgram::new(
+        "",
+        "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..
+        +++.>>.<-.<.+++.------.--------.>>+.>++.",
+    )
+    .validate()
+    .unwrap();
+    for level in [
+        bft_interp::BFOptLevel::O0,
+        bft_interp::BFOptLevel::O1,
+        bft_interp::BFOptLevel::O2,
+        bft_interp::BFOptLevel::O3,
+    ] {
+        let mut bf_vm = bft_interp::BFVirtualMachine::<u8>::new(1000, false, &bf_info);
+        bf_vm.set_opt_level(level);
+        let mut r_buf = Cursor::new(Vec::<u8>::new());
+        let mut w_buf = Cursor::new(Vec::<u8>::new());
+        let result = bf_vm.interpret(&mut r_buf, &mut w_buf);
+
+        assert!(result.is_ok());
+        assert_eq!(w_buf.get_ref(), &Vec::<u8>::from("Hello World!\n"));
+        assert_eq!(bf_vm.stats().opt_level(), level);
+    }
+}
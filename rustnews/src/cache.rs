@@ -0,0 +1,173 @@
+//! Responses of the APIs kept on disk for a while
+//!
+//! A response is a JSON file of `$XDG_CACHE_HOME/rustnews/`, or of
+//! `~/.cache/rustnews/`, named after the URL it was fetched from, which
+//! tells the endpoint and the date. It is fresh for the `ttl` of the
+//! `[cache]` table of the config, then removed the next time a response is
+//! cached. Offline every cached response is served, however old.
+
+use crate::config::Config;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Seconds a response is fresh when the config doesn't tell
+const DEFAULT_TTL: u64 = 6 * 60 * 60;
+
+/// Longest part of a file name taken from a URL as is
+const MAX_NAME: usize = 100;
+
+/// Offset basis and prime of the 64 bit FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Directory of the responses, as configured
+#[derive(Debug, Clone)]
+pub struct Cache {
+    /// None if disabled or without a home
+    dir: Option<PathBuf>,
+    ttl: Duration,
+    offline: bool,
+    verbose: bool,
+}
+
+impl Cache {
+    pub fn new(config: &Config, verbose: bool) -> Self {
+        let settings = config.cache();
+        Self {
+            dir: default_dir().filter(|_| settings.enabled.unwrap_or(true)),
+            ttl: Duration::from_secs(settings.ttl.unwrap_or(DEFAULT_TTL)),
+            offline: settings.offline.unwrap_or(false),
+            verbose,
+        }
+    }
+
+    /// Whether nothing is fetched, the responses being the cached ones
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Response of `url` if it is cached and fresh, or cached at all
+    /// offline
+    pub fn get(&self, url: &str) -> Option<Value> {
+        let path = self.dir.as_ref()?.join(file_name(url));
+        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+        if !self.offline && !self.fresh(modified) {
+            return None;
+        }
+        if self.verbose {
+            eprintln!("rustnews: reading cached {}", path.display());
+        }
+        serde_json::from_reader(File::open(&path).ok()?).ok()
+    }
+
+    /// Cache `response` of `url` and remove the stale responses
+    ///
+    /// A response that can't be cached is only logged, it will be fetched
+    /// again next time.
+    pub fn put(&self, url: &str, response: &Value) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if let Err(err) = self.write(dir, url, response) {
+            if self.verbose {
+                eprintln!("rustnews: not caching {}: {}", url, err);
+            }
+        }
+    }
+
+    fn write(&self, dir: &Path, url: &str, response: &Value) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let modified = fs::metadata(&path)?.modified()?;
+            if !self.fresh(modified) {
+                fs::remove_file(path)?;
+            }
+        }
+        serde_json::to_writer(File::create(dir.join(file_name(url)))?, response)?;
+        Ok(())
+    }
+
+    /// Whether a response written at `modified` is still fresh
+    fn fresh(&self, modified: SystemTime) -> bool {
+        // a file of the future was just written by another clock
+        SystemTime::now()
+            .duration_since(modified)
+            .map_or(true, |age| age < self.ttl)
+    }
+}
+
+/// Name of the file of `url`, its characters other than letters and digits
+/// replaced and cut if it's too long, then the hash of the whole URL, which
+/// tells apart the URLs that read the same once replaced
+fn file_name(url: &str) -> String {
+    let readable = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let mut name: String = readable
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    name.truncate(MAX_NAME);
+    format!("{}_{:016x}.json", name, fnv1a(url))
+}
+
+/// FNV-1a hash of `text`, the same on every build unlike `DefaultHasher`,
+/// so a rebuilt rustnews still finds the responses it cached
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// `$XDG_CACHE_HOME/rustnews`, `~/.cache/rustnews` if it isn't set
+fn default_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+    Some(dir.join("rustnews"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let name = file_name("https://en.wikipedia.org/api/rest_v1/feed/onthisday/all/10/14");
+        assert!(name.starts_with("en_wikipedia_org_api_rest_v1_feed_onthisday_all_10_14_"));
+        assert_eq!(
+            name.len(),
+            "en_wikipedia_org_api_rest_v1_feed_onthisday_all_10_14".len() + 1 + 16 + ".json".len()
+        );
+        assert_eq!(
+            file_name("a/b?c=d"),
+            format!("a_b_c_d_{:016x}.json", fnv1a("a/b?c=d"))
+        );
+
+        // URLs that read the same are told apart by their hash
+        assert_ne!(
+            file_name("https://newsapi.org/v2/everything?q=U.S."),
+            file_name("https://newsapi.org/v2/everything?q=U+S")
+        );
+
+        // long URLs are cut
+        let long = format!("https://newsapi.org/v2/everything?q={}", "a".repeat(200));
+        let name = file_name(&long);
+        assert_eq!(name.len(), MAX_NAME + 1 + 16 + ".json".len());
+        assert!(name.starts_with("newsapi_org_v2_everything_q_aaa"));
+        assert_eq!(name, file_name(&long));
+        assert_ne!(name, file_name(&format!("{}b", long)));
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        // the reference values of the FNV-1a hash
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a("foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,344 @@
+//! Command line of rustnews using clap
+use crate::config::Defaults;
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+/// Print what happened on a day in history, with a news headline about it
+///
+/// Events come from the Wikipedia on-this-day feed, headlines from NewsAPI
+/// searching the keywords of each event. The options not given default to
+/// their `RUSTNEWS_*` environment variable, then to the config file.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Day of the events as MM-DD, today if not given
+    #[arg(long, value_name = "MM-DD", value_parser = parse_date, conflicts_with = "daemon")]
+    date: Option<MonthDay>,
+
+    /// How many events to print [default: 1]
+    #[arg(long, value_name = "N", env = "RUSTNEWS_COUNT")]
+    count: Option<NonZeroUsize>,
+
+    /// Language of the Wikipedia feed and of the headlines [default: en]
+    #[arg(long, value_name = "LANG", env = "RUSTNEWS_LANG")]
+    lang: Option<String>,
+
+    /// Only print the events, without looking up headlines
+    #[arg(long, env = "RUSTNEWS_NO_NEWS")]
+    no_news: bool,
+
+    /// Print the first N sentences of the article of each headline [default: 3]
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "3",
+        env = "RUSTNEWS_SUMMARY"
+    )]
+    summary: Option<NonZeroUsize>,
+
+    /// What happened on the day to print
+    #[arg(long = "type", value_enum, default_value_t = OnThisDay::Events)]
+    kind: OnThisDay,
+
+    /// How the events are printed [default: text, template with --template]
+    #[arg(long, value_enum, env = "RUSTNEWS_FORMAT")]
+    format: Option<OutputFormat>,
+
+    /// Template file rendering the digest, `--format template` is the
+    /// default with one
+    #[arg(long, value_name = "FILE", env = "RUSTNEWS_TEMPLATE")]
+    template: Option<PathBuf>,
+
+    /// RSS feed file the digest is also added to, as the item of the day
+    #[arg(long, value_name = "FILE", env = "RUSTNEWS_FEED")]
+    feed: Option<PathBuf>,
+
+    /// Pick events and headlines shown in the last year like the others
+    #[arg(long, env = "RUSTNEWS_ALLOW_REPEATS")]
+    allow_repeats: bool,
+
+    /// Also raise a desktop notification per event with its headline
+    #[arg(long, env = "RUSTNEWS_NOTIFY")]
+    notify: bool,
+
+    /// Also send the digest to TARGET configured in `[deliver.TARGET]`,
+    /// even if not enabled
+    #[arg(long, value_enum, value_name = "TARGET")]
+    send: Vec<Target>,
+
+    /// Make the digest of the cached responses only, without the other
+    /// sources nor the targets needing the network
+    #[arg(long, env = "RUSTNEWS_OFFLINE")]
+    offline: bool,
+
+    /// Keep running, making the digest of the day at the times of `--at`
+    #[arg(long)]
+    daemon: bool,
+
+    /// Local time of the digests of `--daemon` as HH:MM, repeated for
+    /// several a day
+    #[arg(long, value_name = "HH:MM", value_parser = parse_time)]
+    at: Vec<NaiveTime>,
+
+    /// Log the requests and the cache to stderr
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Config file, `~/.config/rustnews/config.toml` if not given
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Day of the events, today if `--date` isn't given
+    pub fn date(&self) -> MonthDay {
+        self.date.unwrap_or_else(|| {
+            let today = Local::now().date_naive();
+            MonthDay {
+                month: today.month(),
+                day: today.day(),
+            }
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.map_or(1, NonZeroUsize::get)
+    }
+
+    pub fn lang(&self) -> &str {
+        self.lang.as_deref().unwrap_or("en")
+    }
+
+    pub fn kind(&self) -> OnThisDay {
+        self.kind
+    }
+
+    pub fn no_news(&self) -> bool {
+        self.no_news
+    }
+
+    /// Sentences of the summaries of the articles, None without `--summary`
+    pub fn summary(&self) -> Option<usize> {
+        self.summary.map(NonZeroUsize::get)
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        let default = match self.template {
+            Some(_) => OutputFormat::Template,
+            None => OutputFormat::Text,
+        };
+        self.format.unwrap_or(default)
+    }
+
+    pub fn template(&self) -> Option<&Path> {
+        self.template.as_deref()
+    }
+
+    pub fn feed(&self) -> Option<&Path> {
+        self.feed.as_deref()
+    }
+
+    pub fn allow_repeats(&self) -> bool {
+        self.allow_repeats
+    }
+
+    pub fn notify(&self) -> bool {
+        self.notify
+    }
+
+    pub fn send(&self) -> &[Target] {
+        &self.send
+    }
+
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn daemon(&self) -> bool {
+        self.daemon
+    }
+
+    pub fn at(&self) -> &[NaiveTime] {
+        &self.at
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+
+    /// Take the options not given from `defaults`
+    pub fn apply_defaults(&mut self, defaults: &Defaults) -> Result<(), String> {
+        if self.count.is_none() {
+            if let Some(count) = defaults.count {
+                let count = NonZeroUsize::new(count).ok_or("the default count can't be 0")?;
+                self.count = Some(count);
+            }
+        }
+        if self.lang.is_none() {
+            self.lang = defaults.lang.clone();
+        }
+        if self.format.is_none() {
+            self.format = defaults.format;
+        }
+        if self.template.is_none() {
+            self.template = defaults.template.clone();
+        }
+        if self.feed.is_none() {
+            self.feed = defaults.feed.clone();
+        }
+        if self.at.is_empty() {
+            for time in defaults.at.iter().flatten() {
+                self.at.push(parse_time(time)?);
+            }
+        }
+        if self.summary.is_none() {
+            if let Some(summary) = defaults.summary {
+                let summary = NonZeroUsize::new(summary).ok_or("the default summary can't be 0")?;
+                self.summary = Some(summary);
+            }
+        }
+        self.no_news |= defaults.no_news.unwrap_or(false);
+        self.notify |= defaults.notify.unwrap_or(false);
+        Ok(())
+    }
+}
+
+/// Events of the Wikipedia on-this-day feed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OnThisDay {
+    /// Events in history
+    Events,
+
+    /// People born on the day
+    Births,
+
+    /// People who died on the day
+    Deaths,
+
+    /// Holidays and observances of the day
+    Holidays,
+
+    /// Events picked by the editors of Wikipedia
+    Selected,
+
+    /// All of them
+    All,
+}
+
+/// Layout of the printed events
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The article extract of every event and its headline, in paragraphs
+    Text,
+
+    /// A line of year and summary per event, its headline indented below
+    Oneline,
+
+    /// The events, their headlines and the other sources as a JSON object
+    Json,
+
+    /// A section per event with its extract quoted and its headline linked
+    Markdown,
+
+    /// A page with inline styles, to paste in an email or a site
+    Html,
+
+    /// The digest rendered by the `--template` file
+    Template,
+
+    /// An iCalendar file of all-day events, to import in a calendar
+    Ics,
+}
+
+/// Target the digest can be sent to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Target {
+    Email,
+    Telegram,
+    Slack,
+    Discord,
+    Mastodon,
+}
+
+/// Day of the year, without a year
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MonthDay {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl fmt::Display for MonthDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}-{:02}", self.month, self.day)
+    }
+}
+
+/// Serialized as `MM-DD`
+impl Serialize for MonthDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse `MM-DD`, any day of a leap year is accepted
+fn parse_date(s: &str) -> Result<MonthDay, String> {
+    let invalid = || format!("`{}` isn't a day as MM-DD", s);
+    let (month, day) = s.split_once('-').ok_or_else(invalid)?;
+    let month = month.parse().map_err(|_| invalid())?;
+    let day = day.parse().map_err(|_| invalid())?;
+    NaiveDate::from_ymd_opt(2000, month, day).ok_or_else(invalid)?;
+    Ok(MonthDay { month, day })
+}
+
+/// Parse `HH:MM`
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("`{}` isn't a time as HH:MM", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("10-14"), Ok(MonthDay { month: 10, day: 14 }));
+        assert_eq!(parse_date("2-9"), Ok(MonthDay { month: 2, day: 9 }));
+        // whatever the year, February 29 is a day
+        assert_eq!(parse_date("02-29"), Ok(MonthDay { month: 2, day: 29 }));
+        for date in [
+            "02-30",
+            "13-01",
+            "00-10",
+            "1014",
+            "10-",
+            "ab-cd",
+            "2024-10-14",
+        ] {
+            assert!(parse_date(date).is_err(), "{}", date);
+        }
+        assert_eq!(
+            parse_date("02-30").unwrap_err(),
+            "`02-30` isn't a day as MM-DD"
+        );
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("07:30"),
+            Ok(NaiveTime::from_hms_opt(7, 30, 0).unwrap())
+        );
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("7h30").is_err());
+    }
+}
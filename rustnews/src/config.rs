@@ -0,0 +1,584 @@
+//! API keys, endpoints and defaults of rustnews
+//!
+//! `~/.config/rustnews/config.toml`, or the file given with `--config`,
+//! holds:
+//!
+//! ```toml
+//! news-api-key = "0123456789abcdef0123456789abcdef"
+//!
+//! [endpoints]
+//! wikipedia = "https://api.wikimedia.org/feed/v1/wikipedia"
+//! news-api = "https://newsapi.org/v2/top-headlines"
+//! shortener = "https://is.gd/create.php"
+//!
+//! [defaults]
+//! count = 3
+//! lang = "en"
+//! format = "oneline"
+//! no-news = false
+//! summary = 3
+//! template = "/home/me/.config/rustnews/digest.md.j2"
+//! feed = "/home/me/public/rustnews.xml"
+//! notify = true
+//! at = ["08:00", "18:30"]
+//!
+//! [sources.news-api]
+//! enabled = false
+//!
+//! [sources.hacker-news]
+//! enabled = true
+//! list = "best"
+//! keywords = ["rust"]
+//! limit = 5
+//!
+//! [sources.reddit]
+//! enabled = true
+//! subreddits = ["rust", "programming"]
+//! period = "day"
+//! limit = 3
+//!
+//! [sources.lobsters]
+//! enabled = true
+//! tags = ["rust"]
+//! limit = 5
+//!
+//! [sources.crates-io]
+//! enabled = true
+//! keywords = ["async", "cli"]
+//! sort = "new"
+//! limit = 5
+//!
+//! [sources.github-trending]
+//! enabled = true
+//! language = "rust"
+//! since = "daily"
+//! limit = 5
+//!
+//! [sources.this-week-in-rust]
+//! enabled = true
+//! limit = 10
+//!
+//! [[sources.feeds]]
+//! name = "Inside Rust"
+//! url = "https://blog.rust-lang.org/inside-rust/feed.xml"
+//! limit = 3
+//!
+//! [cache]
+//! enabled = true
+//! ttl = 21600
+//!
+//! [retry]
+//! attempts = 3
+//! delay = 500
+//!
+//! [deliver.email]
+//! server = "smtp.example.org"
+//! tls = "starttls"
+//! username = "me"
+//! password = "secret"
+//! from = "rustnews <me@example.org>"
+//! to = ["me@example.org"]
+//! subject = "On this day, {{ date }}"
+//!
+//! [deliver.telegram]
+//! token = "123456:ABC-DEF1234ghIkl-zyx57W2v1u123ew11"
+//! chat-id = "@rustnews"
+//!
+//! [deliver.slack]
+//! webhook = "https://hooks.slack.com/services/T000/B000/XXXX"
+//!
+//! [deliver.discord]
+//! webhook = "https://discord.com/api/webhooks/0000/XXXX"
+//!
+//! [deliver.mastodon]
+//! instance = "https://mastodon.social"
+//! token = "0123456789abcdef"
+//! hashtags = ["OnThisDay", "history"]
+//! visibility = "unlisted"
+//! max-chars = 500
+//! ```
+//!
+//! Every key is optional, a missing default config file is an empty one.
+//! A `[sources.NAME]` table enables or disables the source NAME, Wikipedia
+//! and NewsAPI are enabled unless disabled, the others only if enabled.
+//! Hacker News lists the `limit` first `top` or `best` stories, those with
+//! one of `keywords` in their title if given. Reddit lists the `limit` top
+//! posts of the `period` of every subreddit of `subreddits`, Lobsters the
+//! `limit` first stories of its front page, or of `tags` if given. Every
+//! `[[sources.feeds]]` table is a source of its own, the `limit` latest
+//! entries of the RSS or Atom feed at `url`, enabled unless disabled. This
+//! Week in Rust lists the `limit` first links of the community updates of
+//! its latest issue. crates.io lists the `limit` crates last published, or
+//! `sort = "updated"` last updated, matching any of `keywords` if given.
+//! GitHub trending lists the `limit` repositories trending `since` the last
+//! day, week or month, only those in `language` if given.
+//! `template` is the default of `--template`, with the fields of
+//! `render::Digest` described in `render::template`, `feed` that of
+//! `--feed`, `notify` that of `--notify` and `at` that of `--at`.
+//! `RUSTNEWS_NEWS_API_KEY`, `RUSTNEWS_WIKIPEDIA_URL`, `RUSTNEWS_NEWS_API_URL`
+//! and `RUSTNEWS_SHORTENER_URL` override the key and the endpoints, the
+//! command line options and their `RUSTNEWS_*` variables the defaults.
+//!
+//! `[cache]` keeps the responses of Wikipedia, NewsAPI and the shortener
+//! for `ttl` seconds, unless `enabled = false`. With `offline = true`, or
+//! `--offline`, the digest is only made of the cached responses.
+//! `[retry]` sends a request again, up to `attempts` times in all, when it
+//! fails for a while, first after about `delay` milliseconds.
+//!
+//! A `[deliver.NAME]` table sends the digest to the target NAME, unless
+//! `enabled = false`. `email` sends it as text with an HTML alternative
+//! through the SMTP `server`, on `port` if not the default of its `tls`,
+//! `starttls`, `tls` or `none`. `subject` is a template like those of
+//! `--template`. `RUSTNEWS_SMTP_PASSWORD` overrides `password`. `telegram`
+//! posts it to the chat `chat-id`, a `@channel` or the id of a chat, with
+//! the bot of `token`, which `RUSTNEWS_TELEGRAM_TOKEN` overrides. `slack`
+//! and `discord` post it to the channel of their `webhook`, which
+//! `RUSTNEWS_SLACK_WEBHOOK` and `RUSTNEWS_DISCORD_WEBHOOK` override.
+//! `mastodon` posts a status of every event with a headline, followed by
+//! `hashtags`, to the account of `token` on `instance`, in at most
+//! `max-chars` characters. `RUSTNEWS_MASTODON_TOKEN` overrides `token`.
+//! `--send NAME` sends the digest to NAME even if it isn't enabled.
+
+use crate::cli::OutputFormat;
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings read from the config file and the environment
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    news_api_key: Option<String>,
+    #[serde(default)]
+    endpoints: Endpoints,
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default)]
+    sources: Sources,
+    #[serde(default)]
+    cache: CacheConfig,
+    #[serde(default)]
+    retry: RetryConfig,
+    #[serde(default)]
+    deliver: Deliver,
+}
+
+/// URLs of the services rustnews talks to
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Endpoints {
+    /// Wikipedia feeds, followed by `/LANG/onthisday/...`
+    pub wikipedia: String,
+    pub news_api: String,
+    pub shortener: String,
+
+    /// Hacker News API, followed by `/topstories.json` or `/item/ID.json`
+    pub hacker_news: String,
+
+    /// Reddit, followed by `/r/NAME/top.json`
+    pub reddit: String,
+
+    /// Lobsters, followed by `/hottest.json` or `/t/TAGS.json`
+    pub lobsters: String,
+
+    /// Feed of the issues of This Week in Rust
+    pub this_week_in_rust: String,
+
+    /// crates.io API, followed by `/crates`
+    pub crates_io: String,
+
+    /// GitHub trending page, followed by `/LANGUAGE`
+    pub github_trending: String,
+
+    /// Telegram Bot API, followed by `/botTOKEN/METHOD`
+    pub telegram: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            wikipedia: "https://api.wikimedia.org/feed/v1/wikipedia".into(),
+            news_api: "https://newsapi.org/v2/top-headlines".into(),
+            shortener: "https://is.gd/create.php".into(),
+            hacker_news: "https://hacker-news.firebaseio.com/v0".into(),
+            reddit: "https://www.reddit.com".into(),
+            lobsters: "https://lobste.rs".into(),
+            this_week_in_rust: "https://this-week-in-rust.org/rss.xml".into(),
+            crates_io: "https://crates.io/api/v1".into(),
+            github_trending: "https://github.com/trending".into(),
+            telegram: "https://api.telegram.org".into(),
+        }
+    }
+}
+
+/// Defaults of the command line options, None for the built-in ones
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Defaults {
+    pub count: Option<usize>,
+    pub lang: Option<String>,
+    pub format: Option<OutputFormat>,
+    pub no_news: Option<bool>,
+    pub summary: Option<usize>,
+    pub template: Option<PathBuf>,
+    pub feed: Option<PathBuf>,
+    pub notify: Option<bool>,
+    pub at: Option<Vec<String>>,
+}
+
+/// Settings of every source, by name
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Sources {
+    pub wikipedia: SourceConfig,
+    pub news_api: SourceConfig,
+    pub hacker_news: HackerNewsConfig,
+    pub reddit: RedditConfig,
+    pub lobsters: LobstersConfig,
+    pub this_week_in_rust: ThisWeekInRustConfig,
+    pub crates_io: CratesIoConfig,
+    pub github_trending: GithubTrendingConfig,
+    pub feeds: Vec<FeedConfig>,
+}
+
+/// Settings of a source without any of its own
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceConfig {
+    pub enabled: Option<bool>,
+}
+
+impl SourceConfig {
+    /// Whether the source is enabled, `default` if the config doesn't tell
+    pub fn enabled_or(&self, default: bool) -> bool {
+        self.enabled.unwrap_or(default)
+    }
+}
+
+/// Settings of the Hacker News source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HackerNewsConfig {
+    pub enabled: Option<bool>,
+    pub list: HackerNewsList,
+    pub keywords: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/// Stories listed by Hacker News
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HackerNewsList {
+    #[default]
+    Top,
+    Best,
+}
+
+/// Settings of the Reddit source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedditConfig {
+    pub enabled: Option<bool>,
+    pub subreddits: Vec<String>,
+    pub period: RedditPeriod,
+    pub limit: Option<usize>,
+}
+
+/// Time the top posts of a subreddit are taken from
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedditPeriod {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+/// Settings of the Lobsters source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LobstersConfig {
+    pub enabled: Option<bool>,
+    pub tags: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/// Settings of the This Week in Rust source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThisWeekInRustConfig {
+    pub enabled: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+/// Settings of the crates.io source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CratesIoConfig {
+    pub enabled: Option<bool>,
+    pub keywords: Vec<String>,
+    pub sort: CratesIoSort,
+    pub limit: Option<usize>,
+}
+
+/// Order of the crates listed by crates.io
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CratesIoSort {
+    /// Last published first
+    #[default]
+    New,
+
+    /// Last updated first
+    Updated,
+}
+
+/// Settings of the GitHub trending source
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubTrendingConfig {
+    pub enabled: Option<bool>,
+    pub language: Option<String>,
+    pub since: GithubTrendingSince,
+    pub limit: Option<usize>,
+}
+
+/// Time the stars of trending repositories are counted over
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GithubTrendingSince {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Settings of the cache of the responses
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: Option<bool>,
+
+    /// Seconds a response is fresh
+    pub ttl: Option<u64>,
+    pub offline: Option<bool>,
+}
+
+/// Settings of the retries of the requests
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Attempts of a request, the first included
+    pub attempts: Option<u32>,
+
+    /// Milliseconds before the first retry
+    pub delay: Option<u64>,
+}
+
+/// Settings of every delivery target, by name, None if not configured
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Deliver {
+    pub email: Option<EmailConfig>,
+    pub telegram: Option<TelegramConfig>,
+    pub slack: Option<WebhookConfig>,
+    pub discord: Option<WebhookConfig>,
+    pub mastodon: Option<MastodonConfig>,
+}
+
+/// Settings of the email delivery
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub enabled: Option<bool>,
+    pub server: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: EmailTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: Option<String>,
+}
+
+/// Settings of the Telegram delivery
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TelegramConfig {
+    pub enabled: Option<bool>,
+    pub token: String,
+    pub chat_id: String,
+}
+
+/// Settings of a delivery posting to a webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub enabled: Option<bool>,
+    pub webhook: String,
+}
+
+/// Settings of the Mastodon delivery
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct MastodonConfig {
+    pub enabled: Option<bool>,
+    pub instance: String,
+    pub token: String,
+    #[serde(default)]
+    pub hashtags: Vec<String>,
+
+    /// `public`, `unlisted`, `private` or `direct`, the default of the
+    /// account if not given
+    pub visibility: Option<String>,
+    pub max_chars: Option<usize>,
+}
+
+/// How the connection to the SMTP server is encrypted
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTls {
+    /// Upgraded from plain text, on port 587 by default
+    #[default]
+    Starttls,
+
+    /// Encrypted from the start, on port 465 by default
+    Tls,
+
+    /// Not encrypted, on port 25 by default
+    None,
+}
+
+/// Settings of an RSS or Atom feed source
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedConfig {
+    pub name: String,
+    pub url: String,
+    pub enabled: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+impl Config {
+    /// Load the config file `path`, or the default one if it exists, and
+    /// override it with the environment
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let mut config = match path.map(PathBuf::from).or_else(default_path) {
+            Some(file) => match fs::read_to_string(&file) {
+                Ok(source) => {
+                    toml::from_str(&source).map_err(|e| format!("{}: {}", file.display(), e))?
+                }
+                Err(e) if path.is_none() && e.kind() == std::io::ErrorKind::NotFound => {
+                    Self::default()
+                }
+                Err(e) => return Err(format!("{}: {}", file.display(), e).into()),
+            },
+            None => Self::default(),
+        };
+
+        let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+        if let Some(key) = var("RUSTNEWS_NEWS_API_KEY") {
+            config.news_api_key = Some(key);
+        }
+        if let Some(url) = var("RUSTNEWS_WIKIPEDIA_URL") {
+            config.endpoints.wikipedia = url;
+        }
+        if let Some(url) = var("RUSTNEWS_NEWS_API_URL") {
+            config.endpoints.news_api = url;
+        }
+        if let Some(url) = var("RUSTNEWS_SHORTENER_URL") {
+            config.endpoints.shortener = url;
+        }
+        if let (Some(email), Some(password)) =
+            (&mut config.deliver.email, var("RUSTNEWS_SMTP_PASSWORD"))
+        {
+            email.password = Some(password);
+        }
+        if let (Some(telegram), Some(token)) =
+            (&mut config.deliver.telegram, var("RUSTNEWS_TELEGRAM_TOKEN"))
+        {
+            telegram.token = token;
+        }
+        if let (Some(slack), Some(webhook)) =
+            (&mut config.deliver.slack, var("RUSTNEWS_SLACK_WEBHOOK"))
+        {
+            slack.webhook = webhook;
+        }
+        if let (Some(discord), Some(webhook)) =
+            (&mut config.deliver.discord, var("RUSTNEWS_DISCORD_WEBHOOK"))
+        {
+            discord.webhook = webhook;
+        }
+        if let (Some(mastodon), Some(token)) =
+            (&mut config.deliver.mastodon, var("RUSTNEWS_MASTODON_TOKEN"))
+        {
+            mastodon.token = token;
+        }
+        Ok(config)
+    }
+
+    /// NewsAPI key, error telling where to configure it if there is none
+    pub fn news_api_key(&self) -> Result<&str, Box<dyn Error>> {
+        match self
+            .news_api_key
+            .as_deref()
+            .filter(|key| !key.trim().is_empty())
+        {
+            Some(key) => Ok(key),
+            None => Err("no NewsAPI key configured, set `news-api-key` in \
+                ~/.config/rustnews/config.toml or RUSTNEWS_NEWS_API_KEY, \
+                or pass --no-news"
+                .into()),
+        }
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    pub fn defaults(&self) -> &Defaults {
+        &self.defaults
+    }
+
+    pub fn sources(&self) -> &Sources {
+        &self.sources
+    }
+
+    pub fn cache(&self) -> &CacheConfig {
+        &self.cache
+    }
+
+    /// Make the digests of the cached responses only, as with `--offline`
+    pub fn set_offline(&mut self) {
+        self.cache.offline = Some(true);
+    }
+
+    /// Whether nothing is fetched
+    pub fn offline(&self) -> bool {
+        self.cache.offline.unwrap_or(false)
+    }
+
+    pub fn retry(&self) -> &RetryConfig {
+        &self.retry
+    }
+
+    pub fn deliver(&self) -> &Deliver {
+        &self.deliver
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rustnews/config.toml`, `~/.config/rustnews/config.toml`
+/// if it isn't set
+fn default_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("rustnews").join("config.toml"))
+}
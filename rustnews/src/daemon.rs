@@ -0,0 +1,157 @@
+//! Digests made every day at the times of `--at`
+//!
+//! A digest is fetched and printed once. Fetching the events after a
+//! network error, and sending to a target that failed, are tried again
+//! after a delay doubling at every attempt, plus some random jitter so
+//! that many clients don't retry together, then given up on until the
+//! next time.
+
+use crate::retry;
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use rand::Rng;
+use std::error::Error;
+use std::future::Future;
+
+/// Attempts of a step of the digest after the failed one
+const RETRIES: u32 = 5;
+
+/// Delay before the first retry, in seconds
+const FIRST_DELAY: u64 = 30;
+
+/// Most random seconds added to a delay
+const JITTER: u64 = 30;
+
+/// How often a failed step of the digest is tried again
+#[derive(Debug, Copy, Clone)]
+pub struct Backoff {
+    retries: u32,
+}
+
+impl Backoff {
+    /// Every step tried once, as for a single digest
+    pub fn once() -> Self {
+        Self { retries: 0 }
+    }
+
+    /// Steps tried again `RETRIES` times, as in the daemon
+    pub fn daemon() -> Self {
+        Self { retries: RETRIES }
+    }
+
+    /// Result of `attempt`, made again after a delay while it fails with an
+    /// error `retried` accepts, `what` naming it in the logs
+    pub async fn retry<T, F, Fut>(
+        &self,
+        what: &str,
+        retried: fn(&(dyn Error + 'static)) -> bool,
+        mut attempt: F,
+    ) -> Result<T, Box<dyn Error>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn Error>>>,
+    {
+        let mut retry = 0;
+        loop {
+            let err = match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(err) if retry == self.retries || !retried(err.as_ref()) => return Err(err),
+                Err(err) => err,
+            };
+            let delay = FIRST_DELAY * 2u64.pow(retry) + rand::thread_rng().gen_range(0..=JITTER);
+            eprintln!("rustnews: {}: {}, retrying in {}s", what, err, delay);
+            tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+            retry += 1;
+        }
+    }
+}
+
+/// Whether `err` came from the network, so that the same request may
+/// work later
+pub fn network_error(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<reqwest::Error>().is_some_and(|err| {
+        err.is_connect()
+            || err.is_timeout()
+            || err.is_request()
+            || err.status().is_some_and(retry::transient_status)
+    })
+}
+
+/// Whether any error is worth trying again, eg. of a delivery
+pub fn any_error(_: &(dyn Error + 'static)) -> bool {
+    true
+}
+
+/// Run `digest` every day at every local time of `times`, never returning
+/// unless `times` is empty, a digest failing after its retries given up
+/// on until the next time
+pub async fn run_at<F, Fut>(
+    times: &[NaiveTime],
+    verbose: bool,
+    mut digest: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    if times.is_empty() {
+        return Err("--daemon needs the times of the digests with --at".into());
+    }
+    loop {
+        let now = Local::now();
+        let Some(next) = next_run(now, times) else {
+            return Err("no time of --at exists in the next days".into());
+        };
+        if verbose {
+            eprintln!("rustnews: next digest at {}", next.format("%Y-%m-%d %H:%M"));
+        }
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        if let Err(err) = digest().await {
+            eprintln!("rustnews: {}, giving up until the next digest", err);
+        }
+    }
+}
+
+/// First time of `times` after `now`, today or tomorrow, None if none of
+/// them exists, skipped by a change of DST
+fn next_run(now: DateTime<Local>, times: &[NaiveTime]) -> Option<DateTime<Local>> {
+    (0..=2)
+        .flat_map(|days| {
+            let day = now.date_naive() + Duration::days(days);
+            times
+                .iter()
+                .filter_map(move |time| day.and_time(*time).and_local_timezone(Local).earliest())
+        })
+        .filter(|time| *time > now)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_run() {
+        let now = at(10, 12, 0);
+        let times = [time(8, 0), time(18, 30)];
+        assert_eq!(next_run(now, &times), Some(at(10, 18, 30)));
+        // a time of today that passed is tomorrow's
+        assert_eq!(next_run(now, &[time(8, 0)]), Some(at(11, 8, 0)));
+        assert_eq!(next_run(now, &[time(12, 0)]), Some(at(11, 12, 0)));
+        assert_eq!(next_run(at(10, 23, 59), &times), Some(at(11, 8, 0)));
+        assert_eq!(next_run(now, &[]), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Digest posted to a Discord channel by a webhook
+//!
+//! The digest is a message of embeds, one per event and per source, cut to
+//! the 10 embeds of a message and the lengths Discord takes.
+
+use super::{truncate, Delivery};
+use crate::config::{Config, WebhookConfig};
+use crate::render::{self, Digest, Headline};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::error::Error;
+
+/// Most embeds of a message
+const MAX_EMBEDS: usize = 10;
+
+/// Longest title of an embed
+const MAX_TITLE: usize = 256;
+
+/// Longest description of an embed
+const MAX_DESCRIPTION: usize = 4096;
+
+/// Message of the digest to the channel of the webhook of the config
+pub struct Discord {
+    client: Client,
+    retry: Retry,
+    webhook: String,
+    summaries: bool,
+}
+
+impl Discord {
+    pub fn new(
+        client: &Client,
+        config: &Config,
+        settings: &WebhookConfig,
+        summaries: bool,
+        verbose: bool,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            webhook: settings.webhook.clone(),
+            summaries,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Delivery for Discord {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        let mut embeds = Vec::new();
+        for event in &digest.events {
+            let mut description = event
+                .event
+                .summary
+                .clone()
+                .unwrap_or_else(|| event.event.title.clone());
+            if let Some(Headline { article, short_url }) = &event.headline {
+                description = format!(
+                    "{}\n\n{}, {}",
+                    description,
+                    link(&article.title, article.url.as_deref()),
+                    short_url
+                );
+                if let Some(summary) = article.summary.as_ref().filter(|_| self.summaries) {
+                    description = format!("{}\n{}", description, summary);
+                }
+            }
+            embeds.push(embed(
+                &render::event_line(event.event),
+                event.event.url.as_deref(),
+                &description,
+            ));
+        }
+        for section in &digest.sections {
+            if section.items.is_empty() {
+                continue;
+            }
+            let lines: Vec<String> = section
+                .items
+                .iter()
+                .map(|item| {
+                    let line = format!("• {}", link(&item.title, item.url.as_deref()));
+                    if item.details.is_empty() {
+                        line
+                    } else {
+                        format!("{} ({})", line, item.details.join(", "))
+                    }
+                })
+                .collect();
+            embeds.push(embed(section.name, None, &lines.join("\n")));
+        }
+        embeds.truncate(MAX_EMBEDS);
+        let message = json!({
+            "content": format!("On this day, {}", digest.date),
+            "embeds": embeds,
+        });
+        self.retry
+            .send_not_idempotent(self.client.post(&self.webhook).json(&message))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Embed of `title`, linking to `url` if any, over `description`
+fn embed(title: &str, url: Option<&str>, description: &str) -> Value {
+    let mut embed = json!({
+        "title": truncate(title, MAX_TITLE),
+        "description": truncate(description, MAX_DESCRIPTION),
+    });
+    if let Some(url) = url {
+        embed["url"] = url.into();
+    }
+    embed
+}
+
+/// `title` linking to `url`, if any
+fn link(title: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("[{}]({})", title.replace(['[', ']'], ""), url),
+        None => title.to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+//! Digest sent by email through an SMTP server
+
+use super::Delivery;
+use crate::config::{EmailConfig, EmailTls};
+use crate::render::{self, Digest};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::error::Error;
+
+/// Subject of the emails when the config doesn't tell
+const DEFAULT_SUBJECT: &str = "On this day, {{ date }}";
+
+/// Email of the digest as text and HTML to the recipients of the config
+pub struct Email {
+    settings: EmailConfig,
+    summaries: bool,
+}
+
+impl Email {
+    pub fn new(settings: &EmailConfig, summaries: bool) -> Self {
+        Self {
+            settings: settings.clone(),
+            summaries,
+        }
+    }
+
+    /// Connection to the server of the config, not opened yet
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, Box<dyn Error>> {
+        let server = &self.settings.server;
+        let mut builder = match self.settings.tls {
+            EmailTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(server)?,
+            EmailTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(server)?,
+            EmailTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(server),
+        };
+        if let Some(port) = self.settings.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &self.settings.username {
+            let password = self.settings.password.clone().unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+        Ok(builder.build())
+    }
+}
+
+#[async_trait(?Send)]
+impl Delivery for Email {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        let subject = self.settings.subject.as_deref().unwrap_or(DEFAULT_SUBJECT);
+        let subject = render::render_template(digest, "subject", subject)?;
+        let mut message = Message::builder()
+            .from(self.settings.from.parse::<Mailbox>()?)
+            .subject(subject.trim());
+        for to in &self.settings.to {
+            message = message.to(to.parse::<Mailbox>()?);
+        }
+        let text = render::text(digest, self.summaries)
+            .trim_start()
+            .to_string();
+        let html = render::html(digest, self.summaries);
+        let message = message.multipart(MultiPart::alternative_plain_html(text, html))?;
+        self.transport()?.send(message).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Events posted as Mastodon statuses
+//!
+//! A status is posted per event with a headline, its line of `--format
+//! oneline`, the title and short URL of the headline and the hashtags of
+//! the config, the line then the title cut to fit the character limit of
+//! the instance.
+
+use super::{truncate, Delivery};
+use crate::config::{Config, MastodonConfig};
+use crate::render::{self, Digest, Headline};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+
+/// Characters of a status when the config doesn't tell, those of
+/// mastodon.social
+const DEFAULT_MAX_CHARS: usize = 500;
+
+/// Characters Mastodon counts for any URL
+const URL_LENGTH: usize = 23;
+
+/// Statuses of the events to the account of the token of the config
+pub struct Mastodon {
+    client: Client,
+    retry: Retry,
+    settings: MastodonConfig,
+    verbose: bool,
+}
+
+impl Mastodon {
+    pub fn new(client: &Client, config: &Config, settings: &MastodonConfig, verbose: bool) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            settings: settings.clone(),
+            verbose,
+        }
+    }
+
+    /// Status of the event `line` and of its `headline`
+    fn status(&self, line: &str, headline: &Headline) -> String {
+        let hashtags: Vec<String> = self
+            .settings
+            .hashtags
+            .iter()
+            .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+            .collect();
+        let max = self.settings.max_chars.unwrap_or(DEFAULT_MAX_CHARS);
+        status(line, headline, &hashtags, max)
+    }
+}
+
+/// Status of the event `line`, the title and short URL of `headline` and
+/// `hashtags`, of at most `max` characters as Mastodon counts them
+///
+/// The line is cut first, the title keeps at least half of the room left
+/// by the URL and the hashtags, dropped if they leave none.
+fn status(line: &str, headline: &Headline, hashtags: &[String], max: usize) -> String {
+    let mut tags = String::new();
+    if !hashtags.is_empty() {
+        tags = format!("\n\n{}", hashtags.join(" "));
+    }
+    // the newlines and the space around the title
+    let fixed = |tags: &str| 3 + URL_LENGTH + tags.chars().count();
+    if fixed(&tags) + 2 > max {
+        tags.clear();
+    }
+    let room = max.saturating_sub(fixed(&tags));
+    let title = &headline.article.title;
+    let line_length = line.chars().count();
+    let title_length = title.chars().count();
+    let title_room = room
+        .saturating_sub(line_length)
+        .max(title_length.min(room / 2))
+        .min(title_length);
+    format!(
+        "{}\n\n{} {}{}",
+        truncate(line, room - title_room),
+        truncate(title, title_room),
+        headline.short_url,
+        tags
+    )
+}
+
+#[async_trait(?Send)]
+impl Delivery for Mastodon {
+    fn name(&self) -> &str {
+        "mastodon"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        let url = format!(
+            "{}/api/v1/statuses",
+            self.settings.instance.trim_end_matches('/')
+        );
+        for (index, event) in digest.events.iter().enumerate() {
+            let Some(headline) = &event.headline else {
+                continue;
+            };
+            if self.verbose {
+                eprintln!("rustnews: posting status {} to {}", index + 1, url);
+            }
+            let status = self.status(&render::event_line(event.event), headline);
+            let mut body = json!({"status": status});
+            if let Some(visibility) = &self.settings.visibility {
+                body["visibility"] = visibility.as_str().into();
+            }
+            // the instance keeps the key for about an hour, a retry of the
+            // request isn't posted twice in the meantime while another
+            // status, eg. a corrected one, gets a key of its own
+            let mut hasher = DefaultHasher::new();
+            body.to_string().hash(&mut hasher);
+            let key = format!("rustnews-{:016x}", hasher.finish());
+            self.retry
+                .send(
+                    self.client
+                        .post(&url)
+                        .bearer_auth(&self.settings.token)
+                        .header("Idempotency-Key", key)
+                        .json(&body),
+                )
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::Item;
+
+    fn headline(title: &str) -> Headline {
+        Headline {
+            article: Item {
+                title: title.to_string(),
+                ..Item::default()
+            },
+            short_url: "https://is.gd/abcdef".to_string(),
+        }
+    }
+
+    /// characters of `status` as Mastodon counts them
+    fn length(status: &str) -> usize {
+        status.chars().count() - "https://is.gd/abcdef".len() + URL_LENGTH
+    }
+
+    #[test]
+    fn test_status() {
+        let tags = ["#history".to_string()];
+        assert_eq!(
+            status("1969: Apollo 11", &headline("Moon landing"), &tags, 500),
+            "1969: Apollo 11\n\nMoon landing https://is.gd/abcdef\n\n#history"
+        );
+
+        // the line is cut before the title
+        let line = "x".repeat(400);
+        let out = status(&line, &headline(&"y".repeat(100)), &tags, 500);
+        assert_eq!(length(&out), 500);
+        assert!(out.contains(&format!("\n\n{} https://", "y".repeat(100))));
+
+        // then the title too, down to half of the room
+        let out = status(&line, &headline(&"y".repeat(400)), &tags, 500);
+        assert_eq!(length(&out), 500);
+        assert!(out.ends_with(" https://is.gd/abcdef\n\n#history"));
+
+        // hashtags leaving no room are dropped
+        let out = status("line", &headline("title"), &["#a".repeat(30)], 40);
+        assert_eq!(out, "line\n\ntitle https://is.gd/abcdef");
+    }
+}
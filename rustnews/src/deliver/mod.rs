@@ -0,0 +1,128 @@
+//! Where the digest is sent besides stdout
+//!
+//! Every target implements `Delivery` and gets the whole digest once it is
+//! printed. A target is enabled by its command line option or by its
+//! `[deliver.NAME]` table of the config file, `--send NAME` enables one
+//! configured but disabled. Offline only the desktop notifications are
+//! raised.
+
+use crate::cli::{Cli, Target};
+use crate::config::Config;
+use crate::render::Digest;
+use async_trait::async_trait;
+use reqwest::Client;
+use std::error::Error;
+
+mod discord;
+mod email;
+mod mastodon;
+mod notify;
+mod slack;
+mod telegram;
+
+pub use discord::Discord;
+pub use email::Email;
+pub use mastodon::Mastodon;
+pub use notify::Notify;
+pub use slack::Slack;
+pub use telegram::Telegram;
+
+/// Target the digest is sent to
+#[async_trait(?Send)]
+pub trait Delivery {
+    /// Name of the target in the logs
+    fn name(&self) -> &str;
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>>;
+}
+
+/// Enabled targets, error if one of `--send` isn't configured
+pub fn targets(
+    client: &Client,
+    cli: &Cli,
+    config: &Config,
+) -> Result<Vec<Box<dyn Delivery>>, Box<dyn Error>> {
+    let summaries = cli.summary().is_some();
+    let verbose = cli.verbose();
+    let deliver = config.deliver();
+    let wanted =
+        |target, enabled: Option<bool>| cli.send().contains(&target) || enabled.unwrap_or(true);
+    let missing = |name: &str| -> Box<dyn Error> {
+        format!("--send {}: no [deliver.{}] in the config", name, name).into()
+    };
+
+    let mut targets: Vec<Box<dyn Delivery>> = Vec::new();
+    if cli.notify() {
+        targets.push(Box::new(Notify::new(summaries)));
+    }
+    if config.offline() {
+        if verbose {
+            eprintln!("rustnews: offline, not sending the digest");
+        }
+        return Ok(targets);
+    }
+    match &deliver.email {
+        Some(email) if wanted(Target::Email, email.enabled) => {
+            targets.push(Box::new(Email::new(email, summaries)));
+        }
+        None if cli.send().contains(&Target::Email) => return Err(missing("email")),
+        _ => (),
+    }
+    match &deliver.telegram {
+        Some(telegram) if wanted(Target::Telegram, telegram.enabled) => {
+            let telegram = Telegram::new(client, config, telegram, summaries, verbose);
+            targets.push(Box::new(telegram));
+        }
+        None if cli.send().contains(&Target::Telegram) => return Err(missing("telegram")),
+        _ => (),
+    }
+    match &deliver.slack {
+        Some(slack) if wanted(Target::Slack, slack.enabled) => {
+            targets.push(Box::new(Slack::new(
+                client, config, slack, summaries, verbose,
+            )));
+        }
+        None if cli.send().contains(&Target::Slack) => return Err(missing("slack")),
+        _ => (),
+    }
+    match &deliver.discord {
+        Some(discord) if wanted(Target::Discord, discord.enabled) => {
+            targets.push(Box::new(Discord::new(
+                client, config, discord, summaries, verbose,
+            )));
+        }
+        None if cli.send().contains(&Target::Discord) => return Err(missing("discord")),
+        _ => (),
+    }
+    match &deliver.mastodon {
+        Some(mastodon) if wanted(Target::Mastodon, mastodon.enabled) => {
+            targets.push(Box::new(Mastodon::new(client, config, mastodon, verbose)));
+        }
+        None if cli.send().contains(&Target::Mastodon) => return Err(missing("mastodon")),
+        _ => (),
+    }
+    Ok(targets)
+}
+
+/// At most `max` characters of `text`, ending with `…` if it's cut
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 5), "short");
+        assert_eq!(truncate("a longer text", 8), "a longe…");
+        assert_eq!(truncate("éèêë", 3), "éè…");
+        assert_eq!(truncate("text", 0), "…");
+    }
+}
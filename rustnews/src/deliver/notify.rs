@@ -0,0 +1,51 @@
+//! Desktop notifications of the events
+//!
+//! A notification is raised per event, with its line of `--format oneline`
+//! as summary and its headline as body.
+
+use super::Delivery;
+use crate::render::{self, Digest, Headline};
+use async_trait::async_trait;
+use notify_rust::Notification;
+use std::error::Error;
+
+/// Notifications of the events and their headlines
+pub struct Notify {
+    summaries: bool,
+}
+
+impl Notify {
+    /// With the summaries of the articles in the bodies if `summaries`
+    pub fn new(summaries: bool) -> Self {
+        Self { summaries }
+    }
+}
+
+#[async_trait(?Send)]
+impl Delivery for Notify {
+    fn name(&self) -> &str {
+        "notify"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        for event in &digest.events {
+            // the extract tells the event without a headline
+            let body = match &event.headline {
+                Some(Headline { article, short_url }) => {
+                    let mut body = format!("{}\n{}", article.title, short_url);
+                    if let Some(summary) = article.summary.as_ref().filter(|_| self.summaries) {
+                        body = format!("{}\n\n{}", body, summary);
+                    }
+                    body
+                }
+                None => event.event.summary.clone().unwrap_or_default(),
+            };
+            Notification::new()
+                .appname("rustnews")
+                .summary(&render::event_line(event.event))
+                .body(&body)
+                .show()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! Digest posted to a Slack channel by an incoming webhook
+//!
+//! The digest is a message of blocks, a section per event and per source,
+//! cut to the 50 blocks and 3000 characters of section Slack takes.
+
+use super::{truncate, Delivery};
+use crate::config::{Config, WebhookConfig};
+use crate::render::{self, Digest, Headline};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::error::Error;
+
+/// Most blocks of a message
+const MAX_BLOCKS: usize = 50;
+
+/// Longest text of a section block
+const MAX_SECTION: usize = 3000;
+
+/// Message of the digest to the channel of the webhook of the config
+pub struct Slack {
+    client: Client,
+    retry: Retry,
+    webhook: String,
+    summaries: bool,
+}
+
+impl Slack {
+    pub fn new(
+        client: &Client,
+        config: &Config,
+        settings: &WebhookConfig,
+        summaries: bool,
+        verbose: bool,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            webhook: settings.webhook.clone(),
+            summaries,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Delivery for Slack {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        let title = format!("On this day, {}", digest.date);
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": {"type": "plain_text", "text": title},
+        })];
+        for event in &digest.events {
+            let extract = event.event.summary.as_ref().unwrap_or(&event.event.title);
+            let mut text = format!(
+                "*{}*\n{}",
+                escape(&render::event_line(event.event)),
+                escape(extract)
+            );
+            if let Some(Headline { article, short_url }) = &event.headline {
+                text = format!(
+                    "{}\n\n{}, {}",
+                    text,
+                    link(&article.title, article.url.as_deref()),
+                    short_url
+                );
+                if let Some(summary) = article.summary.as_ref().filter(|_| self.summaries) {
+                    text = format!("{}\n{}", text, escape(summary));
+                }
+            }
+            blocks.push(section(&text));
+        }
+        for digest_section in &digest.sections {
+            if digest_section.items.is_empty() {
+                continue;
+            }
+            blocks.push(json!({"type": "divider"}));
+            let mut text = format!("*{}*", escape(digest_section.name));
+            for item in &digest_section.items {
+                text = format!("{}\n• {}", text, link(&item.title, item.url.as_deref()));
+                if !item.details.is_empty() {
+                    text = format!("{} ({})", text, escape(&item.details.join(", ")));
+                }
+            }
+            blocks.push(section(&text));
+        }
+        blocks.truncate(MAX_BLOCKS);
+        let message = json!({"text": title, "blocks": blocks});
+        self.retry
+            .send_not_idempotent(self.client.post(&self.webhook).json(&message))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Section block of the mrkdwn `text`
+fn section(text: &str) -> Value {
+    json!({
+        "type": "section",
+        "text": {"type": "mrkdwn", "text": truncate(text, MAX_SECTION)},
+    })
+}
+
+/// `text` with the characters of Slack markup escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `title` linking to `url`, if any
+fn link(title: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("<{}|{}>", url, escape(title)),
+        None => escape(title),
+    }
+}
@@ -0,0 +1,128 @@
+//! Digest posted to a Telegram chat by a bot
+//!
+//! Messages are at most 4096 characters, the digest is split between lines
+//! in as few as needed.
+
+use super::Delivery;
+use crate::config::{Config, TelegramConfig};
+use crate::render::{self, Digest};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::error::Error;
+
+/// Longest message of the Bot API
+const MAX_MESSAGE: usize = 4096;
+
+/// Messages of the digest as text to the chat of the config
+pub struct Telegram {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    settings: TelegramConfig,
+    summaries: bool,
+    verbose: bool,
+}
+
+impl Telegram {
+    pub fn new(
+        client: &Client,
+        config: &Config,
+        settings: &TelegramConfig,
+        summaries: bool,
+        verbose: bool,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().telegram.clone(),
+            settings: settings.clone(),
+            summaries,
+            verbose,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Delivery for Telegram {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn send(&self, digest: &Digest<'_>) -> Result<(), Box<dyn Error>> {
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.endpoint.trim_end_matches('/'),
+            self.settings.token
+        );
+        let messages = split_message(&render::text(digest, self.summaries), MAX_MESSAGE);
+        // one at a time, so they arrive in order
+        for (index, text) in messages.iter().enumerate() {
+            if self.verbose {
+                eprintln!("rustnews: posting message {} to telegram", index + 1);
+            }
+            let body = json!({
+                "chat_id": self.settings.chat_id,
+                "text": text,
+                "disable_web_page_preview": true,
+            });
+            let response = self
+                .retry
+                .send_not_idempotent(self.client.post(&url).json(&body))
+                .await?
+                .json::<Value>()
+                .await?;
+            if response.get("ok").and_then(Value::as_bool) != Some(true) {
+                let description = response
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or("no description");
+                return Err(format!("telegram: {}", description).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `text` split between lines in messages of at most `max` characters,
+/// lines longer than that are split too
+fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut message = String::new();
+    let mut length = 0;
+    for line in text.trim().split_inclusive('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        for piece in chars.chunks(max) {
+            if length + piece.len() > max {
+                messages.push(message.trim_end().to_string());
+                message.clear();
+                length = 0;
+            }
+            message.extend(piece);
+            length += piece.len();
+        }
+    }
+    messages.push(message.trim_end().to_string());
+    messages.retain(|message| !message.trim().is_empty());
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("one\ntwo\nthree", 100), ["one\ntwo\nthree"]);
+        // lines stay whole as long as they fit
+        assert_eq!(split_message("one\ntwo\nthree", 8), ["one\ntwo", "three"]);
+        assert_eq!(
+            split_message("abcdefghij\nxy", 4),
+            ["abcd", "efgh", "ij", "xy"]
+        );
+        // characters are counted, not bytes
+        assert_eq!(split_message("ééé", 2), ["éé", "é"]);
+        assert!(split_message("\n\n", 10).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! RSS feed of the digests, one item a day
+//!
+//! The file given with `--feed` is read if it exists, the item of the day
+//! replaces the one of an earlier run of the same day and the feed is
+//! written back with its latest items only.
+
+use crate::render::{self, Digest};
+use chrono::Local;
+use rss::{Channel, ChannelBuilder, GuidBuilder, ItemBuilder};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+
+/// Digests kept in the feed, the oldest are dropped
+const MAX_ITEMS: usize = 60;
+
+/// Add `digest` to the feed `path` as its item of today, creating the file
+/// if needed
+pub fn append(path: &Path, digest: &Digest, summaries: bool) -> Result<(), Box<dyn Error>> {
+    let at = |e: &dyn Error| format!("{}: {}", path.display(), e);
+    let mut channel = match File::open(path) {
+        Ok(file) => Channel::read_from(BufReader::new(file)).map_err(|e| at(&e))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => ChannelBuilder::default()
+            .title("rustnews")
+            .link("https://en.wikipedia.org/wiki/Wikipedia:On_this_day/Today")
+            .description("What happened on a day in history, with news headlines about it")
+            .build(),
+        Err(e) => return Err(at(&e).into()),
+    };
+
+    let now = Local::now();
+    let guid = format!("rustnews-{}", now.format("%Y-%m-%d"));
+    let item = ItemBuilder::default()
+        .title(Some(format!("On this day, {}", digest.date)))
+        .link(
+            digest
+                .events
+                .first()
+                .and_then(|event| event.event.url.clone()),
+        )
+        .description(Some(render::html(digest, summaries)))
+        .pub_date(Some(now.to_rfc2822()))
+        .guid(Some(
+            GuidBuilder::default()
+                .value(guid.clone())
+                .permalink(false)
+                .build(),
+        ))
+        .build();
+    let mut items: Vec<rss::Item> = channel
+        .items()
+        .iter()
+        .filter(|item| item.guid().map(|guid| guid.value()) != Some(guid.as_str()))
+        .cloned()
+        .collect();
+    items.insert(0, item);
+    items.truncate(MAX_ITEMS);
+    channel.set_items(items);
+
+    // written aside then renamed, a failed write won't lose the feed
+    let temporary = path.with_extension("tmp");
+    let file = File::create(&temporary).map_err(|e| at(&e))?;
+    let mut writer = channel.write_to(BufWriter::new(file)).map_err(|e| at(&e))?;
+    writer.flush().map_err(|e| at(&e))?;
+    fs::rename(&temporary, path).map_err(|e| at(&e))?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Events and articles already shown, so the random ones are new
+//!
+//! The history is a JSON file, `$XDG_DATA_HOME/rustnews/history.json` or
+//! `~/.local/share/rustnews/history.json`, of the day every event and
+//! article was last shown on. Those older than a year are forgotten.
+
+use crate::render::Digest;
+use crate::sources::Item;
+use chrono::{Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Days an event or an article is remembered
+const KEEP_DAYS: i64 = 365;
+
+/// Days events and articles were last shown on, by key
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    events: HashMap<String, NaiveDate>,
+    articles: HashMap<String, NaiveDate>,
+}
+
+impl History {
+    /// Load the history file, an empty history if there is none
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(path) = default_path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                Ok(serde_json::from_str(&source)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Whether `event` was shown in the last year
+    pub fn seen_event(&self, event: &Item) -> bool {
+        self.events.contains_key(&event_key(event))
+    }
+
+    /// Whether `article` was shown in the last year
+    pub fn seen_article(&self, article: &Item) -> bool {
+        self.articles.contains_key(&article_key(article))
+    }
+
+    /// Remember the events and the articles of `digest` as shown today
+    pub fn record(&mut self, digest: &Digest) {
+        let today = Local::now().date_naive();
+        for event in &digest.events {
+            self.events.insert(event_key(event.event), today);
+            if let Some(headline) = &event.headline {
+                self.articles.insert(article_key(&headline.article), today);
+            }
+        }
+        let oldest = today - Duration::days(KEEP_DAYS);
+        self.events.retain(|_, day| *day > oldest);
+        self.articles.retain(|_, day| *day > oldest);
+    }
+
+    /// Write the history file, creating its directory if needed
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = default_path() else {
+            return Ok(());
+        };
+        let at = |e: &dyn Error| format!("{}: {}", path.display(), e);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| at(&e))?;
+        }
+        // written aside then renamed, a failed write won't lose the history
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string(self)?).map_err(|e| at(&e))?;
+        fs::rename(&temporary, &path).map_err(|e| at(&e))?;
+        Ok(())
+    }
+}
+
+/// Key of `event`, the same event of another language is another one
+fn event_key(event: &Item) -> String {
+    format!("{}: {}", event.year.unwrap_or_default(), event.title)
+}
+
+/// Key of `article`, its URL if it has one
+fn article_key(article: &Item) -> String {
+    article.url.clone().unwrap_or_else(|| article.title.clone())
+}
+
+/// `$XDG_DATA_HOME/rustnews/history.json`, in `~/.local/share` if it isn't
+/// set
+fn default_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("share"),
+    };
+    Some(dir.join("rustnews").join("history.json"))
+}
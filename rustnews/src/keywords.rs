@@ -0,0 +1,142 @@
+//! Terms of an event to search its headlines with
+//!
+//! The words of the text are taken apart from stopwords, numbers and words
+//! of less than three letters. Runs of capitalized words are proper nouns,
+//! eg. `Soviet Union`, and come before the other words, longer terms first
+//! between terms of the same kind.
+
+/// Words never searched for
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "among", "and", "any", "are",
+    "around", "became", "become", "been", "before", "being", "below", "between", "both", "but",
+    "by", "can", "could", "did", "does", "during", "each", "first", "for", "from", "further",
+    "had", "has", "have", "her", "his", "how", "into", "its", "last", "later", "more", "most",
+    "new", "not", "now", "off", "once", "one", "only", "other", "our", "out", "over", "own",
+    "same", "she", "should", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "three", "through", "two", "under", "until", "upon",
+    "was", "were", "what", "when", "where", "which", "while", "who", "whom", "why", "will", "with",
+    "within", "would", "you",
+];
+
+/// Term of a text with what it's ranked by
+struct Term {
+    text: String,
+    proper: bool,
+    first: usize,
+}
+
+/// At most `max` terms of `text`, best first
+///
+/// A phrase of several words is quoted, so searching for it finds the
+/// whole phrase.
+pub fn keywords(text: &str, max: usize) -> Vec<String> {
+    let mut terms: Vec<Term> = Vec::new();
+    // words of the proper noun being read
+    let mut phrase: Vec<&str> = Vec::new();
+    for (index, raw) in text.split_whitespace().enumerate() {
+        if raw.starts_with('(') {
+            push_phrase(&mut terms, &mut phrase, index);
+        }
+        let word = raw.trim_matches(|c: char| !c.is_alphanumeric());
+        let wanted = word.chars().count() >= 3
+            && !word.chars().all(|c| c.is_ascii_digit())
+            && !STOPWORDS.contains(&&*word.to_lowercase());
+        if wanted && word.chars().next().is_some_and(char::is_uppercase) {
+            phrase.push(word);
+        } else {
+            push_phrase(&mut terms, &mut phrase, index);
+            if wanted {
+                push_term(&mut terms, word.to_lowercase(), false, index);
+            }
+        }
+        // punctuation ends a proper noun, `Paris, France` is two of them
+        if !raw.ends_with(|c: char| c.is_alphanumeric()) {
+            push_phrase(&mut terms, &mut phrase, index + 1);
+        }
+    }
+    let end = text.split_whitespace().count();
+    push_phrase(&mut terms, &mut phrase, end);
+
+    terms.sort_by(|a, b| {
+        let length = |term: &Term| term.text.chars().count();
+        b.proper
+            .cmp(&a.proper)
+            .then(length(b).cmp(&length(a)))
+            .then(a.first.cmp(&b.first))
+    });
+    terms
+        .into_iter()
+        .take(max)
+        .map(|term| {
+            if term.text.contains(' ') {
+                format!("\"{}\"", term.text)
+            } else {
+                term.text
+            }
+        })
+        .collect()
+}
+
+/// Push the proper noun of the words of `phrase`, if any, and empty it
+fn push_phrase(terms: &mut Vec<Term>, phrase: &mut Vec<&str>, index: usize) {
+    if phrase.is_empty() {
+        return;
+    }
+    let first = index.saturating_sub(phrase.len());
+    push_term(terms, phrase.join(" "), true, first);
+    phrase.clear();
+}
+
+/// Push `text` unless it's already a term
+fn push_term(terms: &mut Vec<Term>, text: String, proper: bool, first: usize) {
+    let taken = terms
+        .iter()
+        .any(|term| term.text.eq_ignore_ascii_case(&text));
+    if !taken {
+        terms.push(Term {
+            text,
+            proper,
+            first,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keywords() {
+        let text =
+            "The Soviet Union launches Sputnik 1, the first artificial satellite, into orbit.";
+        assert_eq!(
+            keywords(text, 4),
+            ["\"Soviet Union\"", "Sputnik", "artificial", "satellite"]
+        );
+        assert_eq!(
+            keywords(text, 10),
+            [
+                "\"Soviet Union\"",
+                "Sputnik",
+                "artificial",
+                "satellite",
+                "launches",
+                "orbit"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords_phrases() {
+        // parentheses and commas end a proper noun
+        let text = "Apollo 11 lands on the Moon in the Sea of Tranquility (Mare Tranquillitatis).";
+        assert_eq!(
+            keywords(text, 4),
+            ["\"Mare Tranquillitatis\"", "Tranquility", "Apollo", "Moon"]
+        );
+        assert_eq!(keywords("Paris, France", 2), ["France", "Paris"]);
+        // a term is only taken once, whatever its case
+        assert_eq!(keywords("Rust rust RUST", 3), ["Rust"]);
+        assert!(keywords("in 1066 of the", 3).is_empty());
+    }
+}
@@ -1,12 +1,32 @@
-use chrono::Local;
-use glob::glob;
-use rand::Rng;
-use reqwest::header::{self, HeaderMap, HeaderValue};
+use clap::Parser;
+use futures::future::join_all;
+use rand::seq::SliceRandom;
+use reqwest::Client;
 use serde_json::{self, Value};
 use std::error::Error;
 use std::fmt;
-use std::fs::{remove_file, File};
-use std::path::Path;
+
+mod cache;
+mod cli;
+mod config;
+mod daemon;
+mod deliver;
+mod digest_feed;
+mod history;
+mod keywords;
+mod render;
+mod retry;
+mod sources;
+mod summary;
+use cache::Cache;
+use cli::{Cli, MonthDay, OutputFormat};
+use config::Config;
+use daemon::Backoff;
+use deliver::Delivery;
+use history::History;
+use render::{Digest, Event, Headline, Section};
+use retry::Retry;
+use sources::{Item, NewsApi, NewsSource, Wikipedia};
 
 #[derive(Debug)]
 pub enum JsonDataParseError {
@@ -29,114 +49,293 @@ impl fmt::Display for JsonDataParseError {
 
 impl Error for JsonDataParseError {}
 
-fn shorten_url(long_url: &str) -> Result<String, Box<dyn Error>> {
+/// Short URL of `long_url`, `long_url` itself offline if it was never
+/// shortened
+async fn shorten_url(
+    client: &Client,
+    long_url: &str,
+    config: &Config,
+    cache: &Cache,
+    retry: &Retry,
+) -> Result<String, Box<dyn Error>> {
     let params = [("format", "json"), ("url", long_url)];
-    let shorten_url = reqwest::Url::parse_with_params("https://is.gd/create.php", &params)?;
-    let response = reqwest::blocking::Client::new()
-        .post(shorten_url)
-        .send()?
-        .json::<Value>()?;
+    let shorten_url = reqwest::Url::parse_with_params(&config.endpoints().shortener, &params)?;
+    let response = match cache.get(shorten_url.as_str()) {
+        Some(response) => response,
+        None if cache.offline() => return Ok(long_url.into()),
+        None => {
+            let response = retry
+                .send(client.post(shorten_url.clone()))
+                .await?
+                .json::<Value>()
+                .await?;
+            if response.get("shorturl").is_some() {
+                cache.put(shorten_url.as_str(), &response);
+            }
+            response
+        }
+    };
     let map = response
         .as_object()
         .ok_or(JsonDataParseError::DataNotHashmapError)?;
     match map.get("shorturl") {
-        Some(short_url) => Ok(short_url.to_string()),
+        Some(short_url) => {
+            let short_url = short_url
+                .as_str()
+                .ok_or(JsonDataParseError::InvalidTypeError)?;
+            Ok(short_url.into())
+        }
         None => Err("Shorturl not found".into()),
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let date = Local::now().date_naive().format("%m_%d").to_string();
-    let tmp_json_filename: &str = &format!("/tmp/rustnews_out{}.json", date);
-    let response: Value;
-    if !Path::new(tmp_json_filename).exists() {
-        let mut url: String =
-            "https://api.wikimedia.org/feed/v1/wikipedia/en/onthisday/events/".to_string();
-        url.push_str(&date.replace('_', "/"));
-        // response = reqwest::blocking::get(url)?.json::<Value>()?;
-        response = reqwest::blocking::Client::new()
-            .get(url)
-            .send()?
-            .json::<Value>()?;
+/// A random headline about `event` with its shortened URL, None if there
+/// is none
+///
+/// The headlines of `history` are only picked if there is no other.
+///
+/// With `--summary` the summary of the headline is that of its article,
+/// or its description if the article can't be read.
+async fn event_headline(
+    client: &Client,
+    event: &Item,
+    cli: &Cli,
+    config: &Config,
+    history: Option<&History>,
+    date: MonthDay,
+) -> Result<Option<Headline>, Box<dyn Error>> {
+    let Some(news_api) = NewsApi::about(client, config, cli.lang(), event, cli.verbose())? else {
+        return Ok(None);
+    };
+    let articles = news_api.fetch(date).await?;
+    let fresh: Vec<&Item> = articles
+        .iter()
+        .filter(|article| !history.is_some_and(|history| history.seen_article(article)))
+        .collect();
+    let pool: Vec<&Item> = if fresh.is_empty() {
+        articles.iter().collect()
+    } else {
+        fresh
+    };
+    let Some(mut article) = pool
+        .choose(&mut rand::thread_rng())
+        .map(|&article| article.clone())
+    else {
+        return Ok(None);
+    };
+    let long_url = article.url.as_deref().unwrap_or_default();
+    let cache = Cache::new(config, cli.verbose());
+    let retry = Retry::new(config, cli.verbose());
+    let short_url = shorten_url(client, long_url, config, &cache, &retry).await?;
+    if let Some(sentences) = cli.summary().filter(|_| !cache.offline()) {
+        if cli.verbose() {
+            eprintln!("rustnews: fetching {}", long_url);
+        }
+        // paywalls and bot checks are common, keep the headline anyway
+        match summary::summarize(client, &retry, long_url, sentences).await {
+            Ok(Some(text)) => article.summary = Some(text),
+            Ok(None) => (),
+            Err(err) => {
+                if cli.verbose() {
+                    eprintln!("rustnews: no summary of {}: {}", long_url, err);
+                }
+            }
+        }
+    }
+    Ok(Some(Headline { article, short_url }))
+}
 
-        for json_path in glob("/tmp/rustnews_out*.json")? {
-            remove_file(json_path?)?;
+/// Fetch the digest of `date`, print it and send it to the targets
+///
+/// `backoff` retries fetching the events after network errors and sending to
+/// each target that failed; the history is saved once any target got it.
+async fn run(
+    client: &Client,
+    cli: &Cli,
+    config: &Config,
+    targets: &[Box<dyn Delivery>],
+    date: MonthDay,
+    backoff: Backoff,
+) -> Result<(), Box<dyn Error>> {
+    let news = !cli.no_news() && config.sources().news_api.enabled_or(true);
+    let events = if config.sources().wikipedia.enabled_or(true) {
+        let wikipedia = Wikipedia::new(client, config, cli.lang(), cli.kind(), cli.verbose());
+        backoff
+            .retry("fetching the events", daemon::network_error, || {
+                wikipedia.fetch(date)
+            })
+            .await?
+    } else {
+        Vec::new()
+    };
+    let mut history = History::load()?;
+    let avoided = (!cli.allow_repeats()).then_some(&history);
+    let fresh: Vec<&Item> = events
+        .iter()
+        .filter(|event| !avoided.is_some_and(|history| history.seen_event(event)))
+        .collect();
+    // too few events not shown yet, some are shown again
+    let pool: Vec<&Item> = if fresh.len() < cli.count() {
+        events.iter().collect()
+    } else {
+        fresh
+    };
+    let chosen: Vec<&Item> = pool
+        .choose_multiple(&mut rand::thread_rng(), cli.count())
+        .copied()
+        .collect();
+    // the headlines of all the events and the other sources are fetched at
+    // once, those failing are left out of the digest rather than failing it
+    let headlines = async {
+        if !news {
+            return vec![None; chosen.len()];
         }
-        serde_json::to_writer_pretty(File::create(tmp_json_filename)?, &response)?;
+        let headlines = join_all(
+            chosen
+                .iter()
+                .map(|cur_event| event_headline(client, cur_event, cli, config, avoided, date)),
+        )
+        .await;
+        chosen
+            .iter()
+            .zip(headlines)
+            .map(|(cur_event, headline)| {
+                headline.unwrap_or_else(|err| {
+                    eprintln!("rustnews: no headline for {}: {}", cur_event.title, err);
+                    None
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    // the other sources aren't cached
+    let others = if config.offline() {
+        Vec::new()
     } else {
-        response = serde_json::from_reader(File::open(tmp_json_filename)?)?;
-    }
+        sources::others(client, config, cli.verbose())
+    };
+    let sections = async {
+        let sections = join_all(others.iter().map(|source| source.fetch(date))).await;
+        others
+            .iter()
+            .zip(sections)
+            .map(|(source, items)| {
+                items.unwrap_or_else(|err| {
+                    eprintln!("rustnews: no {} items: {}", source.name(), err);
+                    Vec::new()
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let (headlines, sections) = futures::join!(headlines, sections);
 
-    let events = response
-        .get("events")
-        .ok_or(JsonDataParseError::KeyNotFoundError)?
-        .as_array()
-        .ok_or(JsonDataParseError::DataNotArrayError)?;
-    let rand_num = rand::thread_rng().gen_range(0..events.len());
-    let cur_event = &events[rand_num];
-    // println!("{}: {}", cur_event["year"], cur_event["text"]);
-    let article_map = &cur_event
-        .get("pages")
-        .ok_or(JsonDataParseError::KeyNotFoundError)?
-        .as_array()
-        .ok_or(JsonDataParseError::DataNotArrayError)?[0]
-        .as_object()
-        .ok_or(JsonDataParseError::DataNotHashmapError)?;
-    let extract = article_map
-        .get("extract")
-        .ok_or(JsonDataParseError::KeyNotFoundError)?
-        .as_str()
-        .ok_or(JsonDataParseError::InvalidTypeError)?;
-    println!("\n\n{}", extract);
+    let digest = Digest {
+        date,
+        events: chosen
+            .into_iter()
+            .zip(headlines)
+            .map(|(event, headline)| Event { event, headline })
+            .collect(),
+        sections: others
+            .iter()
+            .zip(sections)
+            .map(|(source, items)| Section {
+                name: source.name(),
+                items,
+            })
+            .collect(),
+    };
+    let summaries = cli.summary().is_some();
+    match cli.format() {
+        OutputFormat::Text => print!("{}", render::text(&digest, summaries)),
+        OutputFormat::Oneline => print!("{}", render::oneline(&digest, summaries)),
+        OutputFormat::Json => print!("{}", render::json(&digest)?),
+        OutputFormat::Markdown => print!("{}", render::markdown(&digest, summaries)),
+        OutputFormat::Html => print!("{}", render::html(&digest, summaries)),
+        OutputFormat::Template => {
+            let path = cli
+                .template()
+                .ok_or("--format template needs a --template file")?;
+            print!("{}", render::template(&digest, path)?)
+        }
+        OutputFormat::Ics => print!("{}", render::ics(&digest)),
+    }
 
-    // TOP_HEADLINES_URL = "https://newsapi.org/v2/top-headlines"
-    // EVERYTHING_URL = "https://newsapi.org/v2/everything"
-    // SOURCES_URL = "https://newsapi.org/v2/sources"
-    // "Content-Type": "Application/JSON", "Authorization": a5645b9190f54f92bd7ab596d165343b
-    // https://github.com/mattlisiv/newsapi-python/blob/master/newsapi/const.py
-    if let Some(keyword) = &cur_event
-        .get("text")
-        .ok_or(JsonDataParseError::KeyNotFoundError)?
-        .to_string()
-        .replace(&['(', ')', ',', '\"', '.', ';', ':', '\''][..], "")
-        .rsplit_once(' ')
-    {
-        let params = [("language", "en"), ("q", keyword.1)];
-        let news_api_url =
-            reqwest::Url::parse_with_params("https://newsapi.org/v2/top-headlines", &params)?;
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            HeaderValue::from_static("application/json"),
-        );
-        headers.insert(header::USER_AGENT, HeaderValue::from_static("Rust Reqwest"));
-        headers.insert(
-            header::AUTHORIZATION,
-            HeaderValue::from_static("a5645b9190f54f92bd7ab596d165343b"),
-        );
-        let news = reqwest::blocking::Client::new()
-            .get(news_api_url)
-            .headers(headers)
-            .send()?
-            .json::<Value>()?;
-        let news_article = news
-            .get("articles")
-            .ok_or(JsonDataParseError::KeyNotFoundError)?
-            .as_array()
-            .ok_or(JsonDataParseError::DataNotArrayError)?;
-        if !news_article.is_empty() {
-            let rand_article_index = rand::thread_rng().gen_range(0..news_article.len());
-            let rand_article = &news_article[rand_article_index];
-            if let (
-                Some(Value::String(rand_article_title)),
-                Some(Value::String(rand_article_url)),
-            ) = (rand_article.get("title"), rand_article.get("url"))
-            {
-                let short_url = shorten_url(rand_article_url)?;
-                println!("\n\n{}, from {}", rand_article_title, short_url);
-            }
+    if let Some(path) = cli.feed() {
+        if cli.verbose() {
+            eprintln!("rustnews: adding the digest to {}", path.display());
+        }
+        digest_feed::append(path, &digest, summaries)?;
+    }
+    if cli.verbose() {
+        for target in targets {
+            eprintln!("rustnews: sending the digest to {}", target.name());
+        }
+    }
+    let digest = &digest;
+    let sent = join_all(targets.iter().map(|target| {
+        let what = format!("sending the digest to {}", target.name());
+        async move {
+            backoff
+                .retry(&what, daemon::any_error, || target.send(digest))
+                .await
         }
+    }))
+    .await;
+    let failed: Vec<String> = targets
+        .iter()
+        .zip(sent)
+        .filter_map(|(target, sent)| sent.err().map(|err| format!("{}: {}", target.name(), err)))
+        .collect();
+    if targets.is_empty() || failed.len() < targets.len() {
+        history.record(digest);
+        history.save()?;
     }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("failed to send the digest to {}", failed.join(", ")).into())
+    }
+}
 
-    Ok(())
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut cli = Cli::parse();
+    let mut config = Config::load(cli.config())?;
+    cli.apply_defaults(config.defaults())?;
+    if cli.offline() {
+        config.set_offline();
+    }
+    if !cli.no_news() && config.sources().news_api.enabled_or(true) && !config.offline() {
+        // fail before fetching anything rather than after the events, the
+        // cached headlines are read without a key
+        config.news_api_key()?;
+    }
+    if cli.template().is_none() && cli.format() == OutputFormat::Template {
+        return Err("--format template needs a --template file".into());
+    }
+    let client = Client::new();
+    let targets = deliver::targets(&client, &cli, &config)?;
+
+    if cli.daemon() {
+        return daemon::run_at(cli.at(), cli.verbose(), || {
+            run(
+                &client,
+                &cli,
+                &config,
+                &targets,
+                cli.date(),
+                Backoff::daemon(),
+            )
+        })
+        .await;
+    }
+    run(
+        &client,
+        &cli,
+        &config,
+        &targets,
+        cli.date(),
+        Backoff::once(),
+    )
+    .await
 }
@@ -0,0 +1,480 @@
+//! Layouts of the digest printed by rustnews
+//!
+//! The digest is rendered whole by the function of its `--format`, which
+//! returns the text to print.
+
+use crate::cli::MonthDay;
+use crate::sources::{EventKind, Item};
+use chrono::{Datelike, Duration, Local, NaiveDate, Utc};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Everything printed for a day, the events then the other sources
+#[derive(Debug, Serialize)]
+pub struct Digest<'a> {
+    pub date: MonthDay,
+    pub events: Vec<Event<'a>>,
+    pub sections: Vec<Section<'a>>,
+}
+
+/// Event of the day with its headline, if any
+#[derive(Debug, Serialize)]
+pub struct Event<'a> {
+    #[serde(flatten)]
+    pub event: &'a Item,
+    pub headline: Option<Headline>,
+}
+
+/// Article about an event with its shortened URL
+#[derive(Debug, Clone, Serialize)]
+pub struct Headline {
+    #[serde(flatten)]
+    pub article: Item,
+    pub short_url: String,
+}
+
+/// Items of a source other than the events, under its name
+#[derive(Debug, Serialize)]
+pub struct Section<'a> {
+    pub name: &'a str,
+    pub items: Vec<Item>,
+}
+
+/// Line telling what a birth, a death or a holiday is, None for the
+/// events whose extract tells it
+fn event_heading(event: &Item) -> Option<String> {
+    let year = event.year.unwrap_or_default();
+    match event.kind? {
+        EventKind::Birth => Some(format!("Born in {}: {}", year, event.title)),
+        EventKind::Death => Some(format!("Died in {}: {}", year, event.title)),
+        EventKind::Holiday => Some(format!("Holiday: {}", event.title)),
+        EventKind::Event | EventKind::Selected => None,
+    }
+}
+
+/// Heading of `event` in the formats with one per event, its year if its
+/// extract tells what happened
+fn event_title(event: &Item) -> String {
+    event_heading(event).unwrap_or_else(|| match event.year {
+        Some(year) => year.to_string(),
+        None => event.title.clone(),
+    })
+}
+
+/// Line of `event` in `--format oneline`, its year and title
+pub fn event_line(event: &Item) -> String {
+    let year = event.year.unwrap_or_default();
+    match event.kind {
+        Some(EventKind::Birth) => format!("{}: born {}", year, event.title),
+        Some(EventKind::Death) => format!("{}: died {}", year, event.title),
+        Some(EventKind::Holiday) => format!("holiday: {}", event.title),
+        _ => format!("{}: {}", year, event.title),
+    }
+}
+
+/// Title of `item` followed by its details
+fn item_line(item: &Item) -> String {
+    if item.details.is_empty() {
+        item.title.clone()
+    } else {
+        format!("{} ({})", item.title, item.details.join(", "))
+    }
+}
+
+/// Items of the sections, indented, each section under its name if
+/// `named`
+fn sections(out: &mut String, digest: &Digest, named: bool) {
+    for section in &digest.sections {
+        if section.items.is_empty() {
+            continue;
+        }
+        if named {
+            writeln!(out, "\n\n{}:", section.name).unwrap();
+        }
+        for item in &section.items {
+            match &item.url {
+                Some(url) => writeln!(out, "    {}, {}", item_line(item), url).unwrap(),
+                None => writeln!(out, "    {}", item_line(item)).unwrap(),
+            }
+        }
+    }
+}
+
+/// The extract of every event and its headline in paragraphs, then the
+/// sections under their names
+///
+/// The summary of a headline is only printed with `--summary`, NewsAPI
+/// descriptions often just repeat the title.
+pub fn text(digest: &Digest, summaries: bool) -> String {
+    let mut out = String::new();
+    for Event { event, headline } in &digest.events {
+        // the extract is about the person or the holiday, tell why it's
+        // about the day
+        if let Some(heading) = event_heading(event) {
+            writeln!(out, "\n\n{}", heading).unwrap();
+        }
+        let extract = event.summary.as_ref().unwrap_or(&event.title);
+        writeln!(out, "\n\n{}", extract).unwrap();
+        if let Some(Headline { article, short_url }) = headline {
+            writeln!(out, "\n\n{}, from {}", article.title, short_url).unwrap();
+            if let Some(summary) = article.summary.as_ref().filter(|_| summaries) {
+                writeln!(out, "\n{}", summary).unwrap();
+            }
+        }
+    }
+    sections(&mut out, digest, true);
+    out
+}
+
+/// A line per event, its headline indented below, then the items of the
+/// sections
+pub fn oneline(digest: &Digest, summaries: bool) -> String {
+    let mut out = String::new();
+    for Event { event, headline } in &digest.events {
+        writeln!(out, "{}", event_line(event)).unwrap();
+        if let Some(Headline { article, short_url }) = headline {
+            writeln!(out, "    {}, from {}", article.title, short_url).unwrap();
+            if let Some(summary) = article.summary.as_ref().filter(|_| summaries) {
+                writeln!(out, "        {}", summary).unwrap();
+            }
+        }
+    }
+    sections(&mut out, digest, false);
+    out
+}
+
+/// `text` with the characters Markdown would take as formatting escaped
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `title` linking to `url`, if any
+fn markdown_link(title: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("[{}](<{}>)", markdown_escape(title), url),
+        None => markdown_escape(title),
+    }
+}
+
+/// A heading per event followed by its extract quoted and its headline in
+/// a list, then a heading per section over the list of its items
+pub fn markdown(digest: &Digest, summaries: bool) -> String {
+    let mut out = format!("# On this day, {}\n", digest.date);
+    for Event { event, headline } in &digest.events {
+        let heading = event_title(event);
+        writeln!(out, "\n## {}\n", markdown_escape(&heading)).unwrap();
+        let extract = event.summary.as_ref().unwrap_or(&event.title);
+        for line in extract.lines() {
+            writeln!(out, "> {}", markdown_escape(line)).unwrap();
+        }
+        if let Some(url) = &event.url {
+            writeln!(out, ">\n> [Wikipedia](<{}>)", url).unwrap();
+        }
+        if let Some(Headline { article, short_url }) = headline {
+            let title = markdown_link(&article.title, article.url.as_deref());
+            writeln!(out, "\n- {}, [{}](<{}>)", title, short_url, short_url).unwrap();
+            if let Some(summary) = article.summary.as_ref().filter(|_| summaries) {
+                writeln!(out, "\n  {}", markdown_escape(summary)).unwrap();
+            }
+        }
+    }
+    for section in &digest.sections {
+        if section.items.is_empty() {
+            continue;
+        }
+        writeln!(out, "\n## {}\n", markdown_escape(section.name)).unwrap();
+        for item in &section.items {
+            let mut line = markdown_link(&item.title, item.url.as_deref());
+            if !item.details.is_empty() {
+                line = format!("{} ({})", line, markdown_escape(&item.details.join(", ")));
+            }
+            writeln!(out, "- {}", line).unwrap();
+        }
+    }
+    out
+}
+
+/// Inline styles of the HTML page, mail clients drop style sheets
+const HTML_BODY: &str = "font-family: Georgia, serif; max-width: 40em; margin: auto; color: #222";
+const HTML_HEADING: &str = "font-size: 1.2em; margin: 1.5em 0 0.5em";
+const HTML_QUOTE: &str = "margin: 0; padding: 0 1em; border-left: 3px solid #ccc; color: #555";
+const HTML_LIST: &str = "padding-left: 1.2em";
+const HTML_DETAILS: &str = "color: #888; font-size: 0.9em";
+const HTML_LINK: &str = "color: #0645ad";
+
+/// `text` with the characters HTML would take as markup escaped
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `title` linking to `url`, if any
+fn html_link(title: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!(
+            "<a href=\"{}\" style=\"{}\">{}</a>",
+            html_escape(url),
+            HTML_LINK,
+            html_escape(title)
+        ),
+        None => html_escape(title),
+    }
+}
+
+/// A page of the events, each under a heading with its extract quoted and
+/// its headline linked to the article and to its short URL, then the
+/// sections
+pub fn html(digest: &Digest, summaries: bool) -> String {
+    let title = format!("On this day, {}", digest.date);
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n");
+    out.push_str("<meta charset=\"utf-8\">\n");
+    writeln!(out, "<title>{}</title>\n</head>", html_escape(&title)).unwrap();
+    writeln!(out, "<body style=\"{}\">", HTML_BODY).unwrap();
+    writeln!(out, "<h1>{}</h1>", html_escape(&title)).unwrap();
+    for Event { event, headline } in &digest.events {
+        let heading = event_title(event);
+        let heading = html_link(&heading, event.url.as_deref());
+        writeln!(out, "<h2 style=\"{}\">{}</h2>", HTML_HEADING, heading).unwrap();
+        let extract = event.summary.as_ref().unwrap_or(&event.title);
+        let extract = html_escape(extract);
+        writeln!(
+            out,
+            "<blockquote style=\"{}\">{}</blockquote>",
+            HTML_QUOTE, extract
+        )
+        .unwrap();
+        if let Some(Headline { article, short_url }) = headline {
+            writeln!(out, "<ul style=\"{}\">", HTML_LIST).unwrap();
+            let title = html_link(&article.title, article.url.as_deref());
+            let short = html_link(short_url, Some(short_url));
+            write!(out, "<li>{}, {}", title, short).unwrap();
+            if let Some(summary) = article.summary.as_ref().filter(|_| summaries) {
+                write!(out, "<p>{}</p>", html_escape(summary)).unwrap();
+            }
+            writeln!(out, "</li>\n</ul>").unwrap();
+        }
+    }
+    for section in &digest.sections {
+        if section.items.is_empty() {
+            continue;
+        }
+        let name = html_escape(section.name);
+        writeln!(out, "<h2 style=\"{}\">{}</h2>", HTML_HEADING, name).unwrap();
+        writeln!(out, "<ul style=\"{}\">", HTML_LIST).unwrap();
+        for item in &section.items {
+            write!(out, "<li>{}", html_link(&item.title, item.url.as_deref())).unwrap();
+            if !item.details.is_empty() {
+                let details = html_escape(&item.details.join(", "));
+                write!(out, " <span style=\"{}\">{}</span>", HTML_DETAILS, details).unwrap();
+            }
+            writeln!(out, "</li>").unwrap();
+        }
+        writeln!(out, "</ul>").unwrap();
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Longest line of an iCalendar file in octets, longer ones are folded
+const ICS_LINE: usize = 75;
+
+/// `text` with the characters iCalendar takes as separators escaped
+fn ics_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Add the content line `line` to `out`, folded at `ICS_LINE` octets and
+/// ended by a CRLF
+fn ics_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > ICS_LINE {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// A calendar of the events as all-day events on the day of this year,
+/// their extract and Wikipedia link in their description
+///
+/// The headlines and the sections have no date of their own, they are left
+/// out. February 29 falls on the next leap year.
+pub fn ics(digest: &Digest) -> String {
+    let this_year = Local::now().year();
+    let day = (this_year..)
+        .find_map(|year| NaiveDate::from_ymd_opt(year, digest.date.month, digest.date.day))
+        .expect("a leap year ahead");
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut out = String::new();
+    ics_line(&mut out, "BEGIN:VCALENDAR");
+    ics_line(&mut out, "VERSION:2.0");
+    ics_line(&mut out, "PRODID:-//rustnews//On this day//EN");
+    for (index, Event { event, .. }) in digest.events.iter().enumerate() {
+        ics_line(&mut out, "BEGIN:VEVENT");
+        ics_line(
+            &mut out,
+            &format!("UID:{}-{}@rustnews", day.format("%Y%m%d"), index),
+        );
+        ics_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        ics_line(
+            &mut out,
+            &format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+        );
+        ics_line(
+            &mut out,
+            &format!(
+                "DTEND;VALUE=DATE:{}",
+                (day + Duration::days(1)).format("%Y%m%d")
+            ),
+        );
+        ics_line(
+            &mut out,
+            &format!("SUMMARY:{}", ics_escape(&event_line(event))),
+        );
+        let mut description = event.summary.clone().unwrap_or_else(|| event.title.clone());
+        if let Some(url) = &event.url {
+            description = format!("{}\n\n{}", description, url);
+        }
+        ics_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", ics_escape(&description)),
+        );
+        if let Some(url) = &event.url {
+            ics_line(&mut out, &format!("URL:{}", url));
+        }
+        ics_line(&mut out, "END:VEVENT");
+    }
+    ics_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// The whole digest as a JSON object
+pub fn json(digest: &Digest) -> Result<String, serde_json::Error> {
+    let mut out = serde_json::to_string_pretty(digest)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// The digest rendered by the minijinja template file `path`
+///
+/// The template gets the fields of `Digest`: `date` as `MM-DD`, `events`
+/// with the fields of their `Item` and their `headline`, if any, with those
+/// of its article and `short_url`, and `sections` with their `name` and
+/// `items`. The values are HTML escaped if `path` ends with `.html`.
+pub fn template(digest: &Digest, path: &Path) -> Result<String, Box<dyn Error>> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    render_template(digest, &path.display().to_string(), &source)
+}
+
+/// The digest rendered by the minijinja template `source`, named `name` in
+/// its errors
+pub fn render_template(
+    digest: &Digest,
+    name: &str,
+    source: &str,
+) -> Result<String, Box<dyn Error>> {
+    let out = minijinja::Environment::new().render_named_str(name, source, digest)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_escape() {
+        assert_eq!(markdown_escape("plain text."), "plain text.");
+        assert_eq!(
+            markdown_escape("*C#* [1]_<b>`"),
+            "\\*C\\#\\* \\[1\\]\\_\\<b\\>\\`"
+        );
+        assert_eq!(markdown_escape("a\\b"), "a\\\\b");
+        assert_eq!(
+            markdown_link("[x]", Some("https://a.b/c_d")),
+            "[\\[x\\]](<https://a.b/c_d>)"
+        );
+        assert_eq!(markdown_link("x_y", None), "x\\_y");
+    }
+
+    #[test]
+    fn test_html_escape() {
+        assert_eq!(html_escape("plain text"), "plain text");
+        assert_eq!(
+            html_escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(
+            html_link("a < b", Some("https://a.b/?x=1&y=2")),
+            format!(
+                "<a href=\"https://a.b/?x=1&amp;y=2\" style=\"{}\">a &lt; b</a>",
+                HTML_LINK
+            )
+        );
+        assert_eq!(html_link("a & b", None), "a &amp; b");
+    }
+
+    #[test]
+    fn test_ics_escape() {
+        assert_eq!(ics_escape("a, b; c\\d"), "a\\, b\\; c\\\\d");
+        assert_eq!(ics_escape("one\r\ntwo\nthree"), "one\\ntwo\\nthree");
+    }
+
+    #[test]
+    fn test_ics_line() {
+        let mut out = String::new();
+        ics_line(&mut out, "SUMMARY:short");
+        assert_eq!(out, "SUMMARY:short\r\n");
+
+        // folded lines start with a space, counted in their 75 octets
+        let mut out = String::new();
+        ics_line(&mut out, &format!("DESCRIPTION:{}", "x".repeat(200)));
+        let lines: Vec<&str> = out.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= ICS_LINE));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        let unfolded: String = lines.concat().replace(" x", "x");
+        assert_eq!(unfolded, format!("DESCRIPTION:{}", "x".repeat(200)));
+
+        // characters aren't split between lines
+        let mut out = String::new();
+        ics_line(&mut out, &"é".repeat(40));
+        assert!(out.split("\r\n").all(|line| line.len() <= ICS_LINE));
+        assert_eq!(out.replace("\r\n ", "").trim_end(), "é".repeat(40));
+    }
+}
@@ -0,0 +1,164 @@
+//! HTTP requests sent again when they fail for a while
+//!
+//! A request is sent again after a timeout, a connection error, a `429 Too
+//! Many Requests` or a `5xx` status, up to the `attempts` of the `[retry]`
+//! table of the config. The delay doubles at every attempt from `delay`
+//! milliseconds, a random half of it being taken off so that clients don't
+//! retry together, unless the server tells it with `Retry-After`. A request
+//! that shouldn't be made twice, eg. posting a message, is only sent again
+//! when it surely wasn't delivered, on a connection error, or when the
+//! server turned it down with one of these statuses.
+
+use crate::config::Config;
+use rand::Rng;
+use reqwest::header;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+/// Attempts of a request when the config doesn't tell
+const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// Milliseconds before the first retry when the config doesn't tell
+const DEFAULT_DELAY: u64 = 500;
+
+/// Longest delay before a retry
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How failed requests are sent again
+#[derive(Debug, Copy, Clone)]
+pub struct Retry {
+    attempts: u32,
+    delay: Duration,
+    verbose: bool,
+}
+
+impl Retry {
+    pub fn new(config: &Config, verbose: bool) -> Self {
+        let settings = config.retry();
+        Self {
+            attempts: settings.attempts.unwrap_or(DEFAULT_ATTEMPTS).max(1),
+            delay: Duration::from_millis(settings.delay.unwrap_or(DEFAULT_DELAY)),
+            verbose,
+        }
+    }
+
+    /// Response of `request`, sent again while it fails for a while
+    ///
+    /// The response of the last attempt is returned whatever its status, a
+    /// request that can't be cloned, eg. of a streamed body, is sent once.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        self.send_with(request, true).await
+    }
+
+    /// Response of `request`, which mustn't be made twice, sent again while
+    /// it's turned down for a while
+    ///
+    /// A timeout may come after the server got the request, it isn't sent
+    /// again.
+    pub async fn send_not_idempotent(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        self.send_with(request, false).await
+    }
+
+    /// Response of `request`, sent again after transport errors too if
+    /// `idempotent`
+    async fn send_with(
+        &self,
+        request: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<Response, reqwest::Error> {
+        let mut attempt = 1;
+        loop {
+            let again = match request.try_clone() {
+                Some(again) if attempt < self.attempts => again,
+                _ => return request.send().await,
+            };
+            let (url, wait) = match again.send().await {
+                Ok(response) if !transient_status(response.status()) => return Ok(response),
+                Ok(response) => {
+                    let wait = retry_after(&response).unwrap_or_else(|| self.backoff(attempt));
+                    (Some(response.url().clone()), wait)
+                }
+                // nothing was sent when connecting failed
+                Err(err)
+                    if err.is_connect()
+                        || (idempotent && (err.is_timeout() || err.is_request())) =>
+                {
+                    (err.url().cloned(), self.backoff(attempt))
+                }
+                Err(err) => return Err(err),
+            };
+            if self.verbose {
+                let url = url.map_or_else(|| "a request".into(), |url| url.to_string());
+                eprintln!("rustnews: retrying {} in {}ms", url, wait.as_millis());
+            }
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// Delay after the failed `attempt`, from 1
+    fn backoff(&self, attempt: u32) -> Duration {
+        let full = self
+            .delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(MAX_DELAY);
+        let half = full / 2;
+        half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Whether a response of `status` may be better later
+pub fn transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay told by the `Retry-After` seconds of `response`, if any
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let retry = Retry {
+            attempts: 10,
+            delay: Duration::from_millis(500),
+            verbose: false,
+        };
+        // between half and all of the delay doubled at every attempt
+        for (attempt, full) in [(1, 500), (2, 1000), (4, 4000)] {
+            for _ in 0..20 {
+                let delay = retry.backoff(attempt);
+                assert!(delay >= Duration::from_millis(full / 2), "{:?}", delay);
+                assert!(delay <= Duration::from_millis(full), "{:?}", delay);
+            }
+        }
+        // and never more than the longest one
+        for attempt in [8, 20, 40] {
+            let delay = retry.backoff(attempt);
+            assert!(delay >= MAX_DELAY / 2 && delay <= MAX_DELAY, "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_transient_status() {
+        assert!(transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!transient_status(StatusCode::NOT_FOUND));
+        assert!(!transient_status(StatusCode::OK));
+    }
+}
@@ -0,0 +1,134 @@
+//! Crates just published or updated on crates.io
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, CratesIoSort};
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use reqwest::header::{self, HeaderValue};
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Crates listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 10;
+
+/// Latest crates, those matching any keyword if there are some
+pub struct CratesIo {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    keywords: Vec<String>,
+    sort: CratesIoSort,
+    limit: usize,
+    verbose: bool,
+}
+
+impl CratesIo {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let settings = &config.sources().crates_io;
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().crates_io.clone(),
+            keywords: settings.keywords.clone(),
+            sort: settings.sort,
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+
+    /// Latest crates matching `keyword`, with the time they were published
+    /// or updated to sort them
+    async fn search(&self, keyword: Option<&str>) -> Result<Vec<(String, Item)>, Box<dyn Error>> {
+        let (sort, time) = match self.sort {
+            CratesIoSort::New => ("new", "created_at"),
+            CratesIoSort::Updated => ("recent-updates", "updated_at"),
+        };
+        let per_page = self.limit.to_string();
+        let mut params = vec![("sort", sort), ("per_page", &*per_page)];
+        if let Some(keyword) = keyword {
+            params.push(("q", keyword));
+        }
+        let url = format!("{}/crates", self.endpoint.trim_end_matches('/'));
+        let url = reqwest::Url::parse_with_params(&url, &params)?;
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        // crates.io refuses requests without a user agent
+        let response = self
+            .retry
+            .send(self.client.get(url).header(
+                header::USER_AGENT,
+                HeaderValue::from_static(concat!("rustnews/", env!("CARGO_PKG_VERSION"))),
+            ))
+            .await?
+            .json::<Value>()
+            .await?;
+        let crates = response
+            .get("crates")
+            .ok_or(JsonDataParseError::KeyNotFoundError)?
+            .as_array()
+            .ok_or(JsonDataParseError::DataNotArrayError)?;
+        let crates = crates.iter().filter_map(|krate| {
+            let name = krate.get("name")?.as_str()?;
+            let version = krate
+                .get("newest_version")
+                .or_else(|| krate.get("max_version"))?
+                .as_str()?;
+            let description = krate
+                .get("description")
+                .and_then(Value::as_str)
+                .map(|description| description.split_whitespace().collect::<Vec<_>>().join(" "));
+            let time = krate.get(time).and_then(Value::as_str).unwrap_or_default();
+            let title = match &description {
+                Some(description) => format!("{} {}: {}", name, version, description),
+                None => format!("{} {}", name, version),
+            };
+            let item = Item {
+                title,
+                url: Some(format!("https://crates.io/crates/{}", name)),
+                summary: description,
+                year: None,
+                kind: None,
+                details: time.get(..10).map(String::from).into_iter().collect(),
+            };
+            Some((time.to_string(), item))
+        });
+        Ok(crates.collect())
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for CratesIo {
+    fn name(&self) -> &str {
+        "crates-io"
+    }
+
+    /// The latest crates whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let searches = if self.keywords.is_empty() {
+            try_join_all([self.search(None)]).await?
+        } else {
+            try_join_all(
+                self.keywords
+                    .iter()
+                    .map(|keyword| self.search(Some(keyword.as_str()))),
+            )
+            .await?
+        };
+        // a crate matching several keywords is listed once
+        let mut crates: Vec<(String, Item)> = searches.into_iter().flatten().collect();
+        crates.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut listed: Vec<Item> = Vec::new();
+        for (_, item) in crates {
+            if !listed.iter().any(|other| other.url == item.url) {
+                listed.push(item);
+            }
+        }
+        listed.truncate(self.limit);
+        Ok(listed)
+    }
+}
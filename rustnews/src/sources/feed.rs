@@ -0,0 +1,91 @@
+//! Entries of any RSS or Atom feed
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, FeedConfig};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use feed_rs::model::Entry;
+use reqwest::Client;
+use std::error::Error;
+
+/// Entries listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 5;
+
+/// Latest entries of a feed, newest first
+pub struct Feed {
+    client: Client,
+    retry: Retry,
+    name: String,
+    url: String,
+    limit: usize,
+    verbose: bool,
+}
+
+impl Feed {
+    pub fn new(client: &Client, config: &Config, settings: &FeedConfig, verbose: bool) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            name: settings.name.clone(),
+            url: settings.url.clone(),
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+
+    /// Every entry of the feed, newest first
+    pub async fn entries(&self) -> Result<Vec<Entry>, Box<dyn Error>> {
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", self.url);
+        }
+        let body = self
+            .retry
+            .send(self.client.get(&self.url))
+            .await?
+            .bytes()
+            .await?;
+        let feed =
+            feed_rs::parser::parse(&body[..]).map_err(|e| format!("feed {}: {}", self.name, e))?;
+        let mut entries = feed.entries;
+        // entries without a date keep their place after the dated ones
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.published.or(entry.updated)));
+        Ok(entries)
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for Feed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The latest entries whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let entries = self.entries().await?;
+        Ok(entries.iter().map(entry_item).take(self.limit).collect())
+    }
+}
+
+/// Item of `entry`, with the day it was published
+pub fn entry_item(entry: &Entry) -> Item {
+    let title = match &entry.title {
+        Some(title) => title.content.trim().to_string(),
+        None => "(untitled)".into(),
+    };
+    let published = entry.published.or(entry.updated);
+    Item {
+        title,
+        url: entry.links.first().map(|link| link.href.clone()),
+        summary: entry
+            .summary
+            .as_ref()
+            .map(|summary| summary.content.trim().to_string()),
+        year: None,
+        kind: None,
+        details: published
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .into_iter()
+            .collect(),
+    }
+}
@@ -0,0 +1,129 @@
+//! Trending repositories of GitHub
+//!
+//! GitHub has no API for them, they are read from its trending page.
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, GithubTrendingSince};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::header::{self, HeaderValue};
+use reqwest::Client;
+use scraper::{ElementRef, Html, Selector};
+use std::error::Error;
+
+/// Repositories listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 10;
+
+/// Repositories starred the most lately, in a language if given
+pub struct GithubTrending {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    language: Option<String>,
+    since: GithubTrendingSince,
+    limit: usize,
+    verbose: bool,
+}
+
+impl GithubTrending {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let settings = &config.sources().github_trending;
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().github_trending.clone(),
+            language: settings.language.clone(),
+            since: settings.since,
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for GithubTrending {
+    fn name(&self) -> &str {
+        "github-trending"
+    }
+
+    /// The repositories trending now whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let mut url = self.endpoint.trim_end_matches('/').to_string();
+        if let Some(language) = &self.language {
+            url = format!("{}/{}", url, language.to_lowercase());
+        }
+        let since = match self.since {
+            GithubTrendingSince::Daily => "daily",
+            GithubTrendingSince::Weekly => "weekly",
+            GithubTrendingSince::Monthly => "monthly",
+        };
+        let url = reqwest::Url::parse_with_params(&url, [("since", since)])?;
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        let page = self
+            .retry
+            .send(self.client.get(url).header(
+                header::USER_AGENT,
+                HeaderValue::from_static(concat!("rustnews/", env!("CARGO_PKG_VERSION"))),
+            ))
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(repositories(&page).into_iter().take(self.limit).collect())
+    }
+}
+
+/// Repositories of the trending `page`, with their stars of the period
+fn repositories(page: &str) -> Vec<Item> {
+    let document = Html::parse_document(page);
+    let selector = |selector: &str| Selector::parse(selector).expect("valid selector");
+    let (rows, name, description) = (
+        selector("article.Box-row"),
+        selector("h2 a[href]"),
+        selector("p"),
+    );
+    let (language, stars, stars_since) = (
+        selector("[itemprop=\"programmingLanguage\"]"),
+        selector("a[href$=\"/stargazers\"]"),
+        selector("span.float-sm-right"),
+    );
+    let text = |element: ElementRef| element.text().collect::<Vec<_>>().join(" ");
+    let words = |text: String| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let first = |row: ElementRef, selector: &Selector| {
+        row.select(selector)
+            .next()
+            .map(|element| words(text(element)))
+    };
+
+    let mut items = Vec::new();
+    for row in document.select(&rows) {
+        let Some(link) = row.select(&name).next() else {
+            continue;
+        };
+        let Some(path) = link.value().attr("href") else {
+            continue;
+        };
+        let summary = first(row, &description);
+        let title = match &summary {
+            Some(summary) => format!("{}: {}", path.trim_matches('/'), summary),
+            None => path.trim_matches('/').to_string(),
+        };
+        let mut details: Vec<String> = first(row, &stars_since).into_iter().collect();
+        if let Some(total) = first(row, &stars) {
+            details.push(format!("{} stars", total));
+        }
+        details.extend(first(row, &language));
+        items.push(Item {
+            title,
+            url: Some(format!("https://github.com{}", path)),
+            summary,
+            year: None,
+            kind: None,
+            details,
+        });
+    }
+    items
+}
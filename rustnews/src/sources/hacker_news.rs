@@ -0,0 +1,125 @@
+//! Top or best stories of Hacker News
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, HackerNewsList};
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Stories listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 10;
+
+/// Stories looked at to find those matching the keywords
+const SCANNED: usize = 100;
+
+/// First stories of a list, those with a keyword in their title if any
+pub struct HackerNews {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    list: HackerNewsList,
+    keywords: Vec<String>,
+    limit: usize,
+    verbose: bool,
+}
+
+impl HackerNews {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let settings = &config.sources().hacker_news;
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().hacker_news.clone(),
+            list: settings.list,
+            keywords: settings.keywords.iter().map(|k| k.to_lowercase()).collect(),
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+
+    /// Whether `title` has one of the keywords as a word, any title if
+    /// there are none
+    fn wanted(&self, title: &str) -> bool {
+        self.keywords.is_empty()
+            || title
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| self.keywords.iter().any(|keyword| keyword == word))
+    }
+
+    /// Story `id`, None if it was deleted
+    async fn story(&self, id: u64) -> Result<Option<Item>, Box<dyn Error>> {
+        let url = format!("{}/item/{}.json", self.endpoint.trim_end_matches('/'), id);
+        let story = self
+            .retry
+            .send(self.client.get(url))
+            .await?
+            .json::<Value>()
+            .await?;
+        let Some(title) = story.get("title").and_then(Value::as_str) else {
+            return Ok(None);
+        };
+        let count = |key: &str| story.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let url = match story.get("url").and_then(Value::as_str) {
+            Some(url) => url.to_string(),
+            // Ask HN and the like only have their discussion
+            None => format!("https://news.ycombinator.com/item?id={}", id),
+        };
+        Ok(Some(Item {
+            title: title.into(),
+            url: Some(url),
+            summary: None,
+            year: None,
+            kind: None,
+            details: vec![
+                format!("{} points", count("score")),
+                format!("{} comments", count("descendants")),
+            ],
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for HackerNews {
+    fn name(&self) -> &str {
+        "hacker-news"
+    }
+
+    /// The stories of the moment whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let list = match self.list {
+            HackerNewsList::Top => "topstories",
+            HackerNewsList::Best => "beststories",
+        };
+        let url = format!("{}/{}.json", self.endpoint.trim_end_matches('/'), list);
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        let ids = self
+            .retry
+            .send(self.client.get(url))
+            .await?
+            .json::<Value>()
+            .await?;
+        let ids = ids
+            .as_array()
+            .ok_or(JsonDataParseError::DataNotArrayError)?;
+        let scanned = if self.keywords.is_empty() {
+            self.limit
+        } else {
+            SCANNED.max(self.limit)
+        };
+        let stories = ids.iter().filter_map(Value::as_u64).take(scanned);
+        let stories = try_join_all(stories.map(|id| self.story(id))).await?;
+        let wanted = stories.into_iter().flatten();
+        Ok(wanted
+            .filter(|story| self.wanted(&story.title))
+            .take(self.limit)
+            .collect())
+    }
+}
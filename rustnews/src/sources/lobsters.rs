@@ -0,0 +1,97 @@
+//! Stories of the Lobsters front page or of some of its tags
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::Config;
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Stories listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 10;
+
+/// First stories of the front page, or of those tagged with any tag
+pub struct Lobsters {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    tags: Vec<String>,
+    limit: usize,
+    verbose: bool,
+}
+
+impl Lobsters {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let settings = &config.sources().lobsters;
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().lobsters.clone(),
+            tags: settings.tags.clone(),
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for Lobsters {
+    fn name(&self) -> &str {
+        "lobsters"
+    }
+
+    /// The stories of the moment whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        let url = if self.tags.is_empty() {
+            format!("{}/hottest.json", endpoint)
+        } else {
+            format!("{}/t/{}.json", endpoint, self.tags.join(","))
+        };
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        let stories = self
+            .retry
+            .send(self.client.get(url))
+            .await?
+            .json::<Value>()
+            .await?;
+        let stories = stories
+            .as_array()
+            .ok_or(JsonDataParseError::DataNotArrayError)?;
+        let stories = stories.iter().filter_map(|story| {
+            let count = |key: &str| story.get(key).and_then(Value::as_i64).unwrap_or(0);
+            let tags: Vec<&str> = story
+                .get("tags")
+                .and_then(Value::as_array)
+                .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            // text posts have an empty URL, their discussion is the story
+            let url = story
+                .get("url")
+                .and_then(Value::as_str)
+                .filter(|url| !url.is_empty())
+                .or_else(|| story.get("comments_url")?.as_str())?;
+            let mut details = vec![
+                format!("{} points", count("score")),
+                format!("{} comments", count("comment_count")),
+            ];
+            if !tags.is_empty() {
+                details.push(tags.join(" "));
+            }
+            Some(Item {
+                title: story.get("title")?.as_str()?.into(),
+                url: Some(url.into()),
+                summary: None,
+                year: None,
+                kind: None,
+                details,
+            })
+        });
+        Ok(stories.take(self.limit).collect())
+    }
+}
@@ -0,0 +1,107 @@
+//! Where the items of the digest come from
+//!
+//! Every source implements `NewsSource`. Wikipedia gives the events of the
+//! day and NewsAPI the headlines about them, the sources returned by
+//! `others` are listed after the events, each under its name. A source is
+//! enabled or disabled by its `[sources.NAME]` table of the config file.
+
+use crate::cli::MonthDay;
+use crate::config::Config;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use std::error::Error;
+
+mod crates_io;
+mod feed;
+mod github_trending;
+mod hacker_news;
+mod lobsters;
+mod news_api;
+mod reddit;
+mod this_week_in_rust;
+mod wikipedia;
+
+pub use crates_io::CratesIo;
+pub use feed::Feed;
+pub use github_trending::GithubTrending;
+pub use hacker_news::HackerNews;
+pub use lobsters::Lobsters;
+pub use news_api::NewsApi;
+pub use reddit::Reddit;
+pub use this_week_in_rust::ThisWeekInRust;
+pub use wikipedia::Wikipedia;
+
+/// Entry of a source, an event, a headline or a story
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Item {
+    pub title: String,
+    pub url: Option<String>,
+
+    /// Longer text about the item, eg. the extract of an article
+    pub summary: Option<String>,
+
+    /// Year the item happened, for events in history
+    pub year: Option<i64>,
+
+    /// What happened, for events in history
+    pub kind: Option<EventKind>,
+
+    /// Short facts shown next to the title, eg. `120 points`
+    pub details: Vec<String>,
+}
+
+/// What an event in history is about
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Event,
+
+    /// An event picked by the editors of Wikipedia
+    Selected,
+    Birth,
+    Death,
+
+    /// A holiday of the day, without a year
+    Holiday,
+}
+
+/// Source of items for a day
+#[async_trait(?Send)]
+pub trait NewsSource {
+    /// Name of the source in the config file
+    fn name(&self) -> &str;
+
+    /// Items of the source for `date`, those of the moment for sources
+    /// without a history
+    async fn fetch(&self, date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>>;
+}
+
+/// Enabled sources listed after the events
+pub fn others(client: &Client, config: &Config, verbose: bool) -> Vec<Box<dyn NewsSource>> {
+    let mut sources: Vec<Box<dyn NewsSource>> = Vec::new();
+    if config.sources().hacker_news.enabled.unwrap_or(false) {
+        sources.push(Box::new(HackerNews::new(client, config, verbose)));
+    }
+    if config.sources().reddit.enabled.unwrap_or(false) {
+        sources.push(Box::new(Reddit::new(client, config, verbose)));
+    }
+    if config.sources().lobsters.enabled.unwrap_or(false) {
+        sources.push(Box::new(Lobsters::new(client, config, verbose)));
+    }
+    if config.sources().this_week_in_rust.enabled.unwrap_or(false) {
+        sources.push(Box::new(ThisWeekInRust::new(client, config, verbose)));
+    }
+    if config.sources().crates_io.enabled.unwrap_or(false) {
+        sources.push(Box::new(CratesIo::new(client, config, verbose)));
+    }
+    if config.sources().github_trending.enabled.unwrap_or(false) {
+        sources.push(Box::new(GithubTrending::new(client, config, verbose)));
+    }
+    for feed in &config.sources().feeds {
+        if feed.enabled.unwrap_or(true) {
+            sources.push(Box::new(Feed::new(client, config, feed, verbose)));
+        }
+    }
+    sources
+}
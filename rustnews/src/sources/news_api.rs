@@ -0,0 +1,138 @@
+//! Top headlines of NewsAPI about the keywords of an event
+
+use super::{Item, NewsSource};
+use crate::cache::Cache;
+use crate::cli::MonthDay;
+use crate::config::Config;
+use crate::keywords::keywords;
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Terms of an event searched for
+const KEYWORDS: usize = 3;
+
+/// Headlines in a language about any of a few keywords
+pub struct NewsApi {
+    client: Client,
+    retry: Retry,
+    cache: Cache,
+    endpoint: String,
+    key: String,
+    lang: String,
+
+    /// the keywords joined by `OR`
+    query: String,
+    verbose: bool,
+}
+
+impl NewsApi {
+    /// Headlines about the keywords of `event`, None if it has none, error
+    /// if no key is configured unless offline
+    pub fn about(
+        client: &Client,
+        config: &Config,
+        lang: &str,
+        event: &Item,
+        verbose: bool,
+    ) -> Result<Option<Self>, Box<dyn Error>> {
+        let keywords = keywords(&event.title, KEYWORDS);
+        if keywords.is_empty() {
+            return Ok(None);
+        }
+        let key = match config.news_api_key() {
+            Ok(key) => key.to_string(),
+            // offline the headlines are only read from the cache
+            Err(_) if config.offline() => String::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            cache: Cache::new(config, verbose),
+            endpoint: config.endpoints().news_api.clone(),
+            key,
+            lang: lang.into(),
+            query: keywords.join(" OR "),
+            verbose,
+        }))
+    }
+
+    /// Headlines of `url`, cached unless NewsAPI tells an error
+    async fn search(&self, url: reqwest::Url) -> Result<Value, Box<dyn Error>> {
+        if self.verbose {
+            eprintln!("rustnews: searching headlines about {}", self.query);
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("Rust Reqwest"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&self.key)?);
+        let news = self
+            .retry
+            .send(self.client.get(url.clone()).headers(headers))
+            .await?
+            .json::<Value>()
+            .await?;
+        if news.get("status").and_then(Value::as_str) == Some("ok") {
+            self.cache.put(url.as_str(), &news);
+        }
+        Ok(news)
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for NewsApi {
+    fn name(&self) -> &str {
+        "news-api"
+    }
+
+    /// The headlines of the moment whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        // TOP_HEADLINES_URL = "https://newsapi.org/v2/top-headlines"
+        // EVERYTHING_URL = "https://newsapi.org/v2/everything"
+        // SOURCES_URL = "https://newsapi.org/v2/sources"
+        // https://github.com/mattlisiv/newsapi-python/blob/master/newsapi/const.py
+        let params = [("language", &*self.lang), ("q", &*self.query)];
+        let news_api_url = reqwest::Url::parse_with_params(&self.endpoint, &params)?;
+        let news = match self.cache.get(news_api_url.as_str()) {
+            Some(news) => news,
+            None if self.cache.offline() => {
+                if self.verbose {
+                    eprintln!("rustnews: offline, no cached headline about {}", self.query);
+                }
+                return Ok(Vec::new());
+            }
+            None => self.search(news_api_url).await?,
+        };
+
+        let news_article = news
+            .get("articles")
+            .ok_or(JsonDataParseError::KeyNotFoundError)?
+            .as_array()
+            .ok_or(JsonDataParseError::DataNotArrayError)?;
+        if news_article.is_empty() && self.verbose {
+            eprintln!("rustnews: no headline about {}", self.query);
+        }
+        let articles = news_article.iter().filter_map(|article| {
+            Some(Item {
+                title: article.get("title")?.as_str()?.into(),
+                url: Some(article.get("url")?.as_str()?.into()),
+                summary: article
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(String::from),
+                year: None,
+                kind: None,
+                details: Vec::new(),
+            })
+        });
+        Ok(articles.collect())
+    }
+}
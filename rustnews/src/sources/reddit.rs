@@ -0,0 +1,121 @@
+//! Top posts of subreddits
+
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, RedditPeriod};
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use reqwest::header::{self, HeaderValue};
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Posts listed per subreddit when the config doesn't tell
+const DEFAULT_LIMIT: usize = 5;
+
+/// Subreddits listed when the config doesn't tell
+const DEFAULT_SUBREDDITS: [&str; 1] = ["rust"];
+
+/// Top posts of a period of every subreddit, one after another
+pub struct Reddit {
+    client: Client,
+    retry: Retry,
+    endpoint: String,
+    subreddits: Vec<String>,
+    period: RedditPeriod,
+    limit: usize,
+    verbose: bool,
+}
+
+impl Reddit {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let settings = &config.sources().reddit;
+        let subreddits = if settings.subreddits.is_empty() {
+            DEFAULT_SUBREDDITS.map(String::from).to_vec()
+        } else {
+            settings.subreddits.clone()
+        };
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            endpoint: config.endpoints().reddit.clone(),
+            subreddits,
+            period: settings.period,
+            limit: settings.limit.unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+
+    /// Top posts of `subreddit`, given with or without its `r/`
+    async fn subreddit(&self, subreddit: &str) -> Result<Vec<Item>, Box<dyn Error>> {
+        let subreddit = subreddit.trim_start_matches("r/");
+        let period = match self.period {
+            RedditPeriod::Hour => "hour",
+            RedditPeriod::Day => "day",
+            RedditPeriod::Week => "week",
+            RedditPeriod::Month => "month",
+            RedditPeriod::Year => "year",
+            RedditPeriod::All => "all",
+        };
+        let limit = self.limit.to_string();
+        let url = format!(
+            "{}/r/{}/top.json",
+            self.endpoint.trim_end_matches('/'),
+            subreddit
+        );
+        let params = [("t", period), ("limit", &*limit)];
+        let url = reqwest::Url::parse_with_params(&url, &params)?;
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        // Reddit throttles requests without a user agent of their own
+        let listing = self
+            .retry
+            .send(self.client.get(url).header(
+                header::USER_AGENT,
+                HeaderValue::from_static(concat!("rustnews/", env!("CARGO_PKG_VERSION"))),
+            ))
+            .await?
+            .json::<Value>()
+            .await?;
+        let posts = listing
+            .pointer("/data/children")
+            .ok_or(JsonDataParseError::KeyNotFoundError)?
+            .as_array()
+            .ok_or(JsonDataParseError::DataNotArrayError)?;
+        let endpoint = self.endpoint.trim_end_matches('/');
+        let posts = posts.iter().filter_map(|post| {
+            let post = post.get("data")?;
+            let count = |key: &str| post.get(key).and_then(Value::as_i64).unwrap_or(0);
+            Some(Item {
+                title: post.get("title")?.as_str()?.into(),
+                url: Some(format!("{}{}", endpoint, post.get("permalink")?.as_str()?)),
+                summary: None,
+                year: None,
+                kind: None,
+                details: vec![
+                    format!("r/{}", subreddit),
+                    format!("{} points", count("score")),
+                    format!("{} comments", count("num_comments")),
+                ],
+            })
+        });
+        Ok(posts.take(self.limit).collect())
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for Reddit {
+    fn name(&self) -> &str {
+        "reddit"
+    }
+
+    /// The posts of the moment whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let subreddits = self.subreddits.iter().map(|name| self.subreddit(name));
+        let posts = try_join_all(subreddits).await?;
+        Ok(posts.into_iter().flatten().collect())
+    }
+}
@@ -0,0 +1,107 @@
+//! Links of the latest issue of This Week in Rust
+
+use super::feed::{entry_item, Feed};
+use super::{Item, NewsSource};
+use crate::cli::MonthDay;
+use crate::config::{Config, FeedConfig};
+use crate::retry::Retry;
+use async_trait::async_trait;
+use reqwest::Client;
+use scraper::{ElementRef, Html, Selector};
+use std::error::Error;
+
+/// Links listed when the config doesn't tell
+const DEFAULT_LIMIT: usize = 10;
+
+/// Heading of the section of an issue the links are taken from
+const UPDATES: &str = "Updates from Rust Community";
+
+/// The latest issue followed by the first links of its community updates
+pub struct ThisWeekInRust {
+    client: Client,
+    retry: Retry,
+    feed: Feed,
+    limit: usize,
+    verbose: bool,
+}
+
+impl ThisWeekInRust {
+    pub fn new(client: &Client, config: &Config, verbose: bool) -> Self {
+        let feed = FeedConfig {
+            name: "this-week-in-rust".into(),
+            url: config.endpoints().this_week_in_rust.clone(),
+            enabled: None,
+            limit: None,
+        };
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            feed: Feed::new(client, config, &feed, verbose),
+            limit: config
+                .sources()
+                .this_week_in_rust
+                .limit
+                .unwrap_or(DEFAULT_LIMIT),
+            verbose,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for ThisWeekInRust {
+    fn name(&self) -> &str {
+        "this-week-in-rust"
+    }
+
+    /// The latest issue whatever `date`
+    async fn fetch(&self, _date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let entries = self.feed.entries().await?;
+        let Some(issue) = entries.first().map(entry_item) else {
+            return Ok(Vec::new());
+        };
+        let Some(url) = &issue.url else {
+            return Ok(vec![issue]);
+        };
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        let page = self.retry.send(self.client.get(url)).await?.text().await?;
+        let mut items = vec![issue.clone()];
+        items.extend(updates(&page).into_iter().take(self.limit));
+        Ok(items)
+    }
+}
+
+/// Links of the community updates of the issue `page`, with the
+/// subsection they are listed in
+fn updates(page: &str) -> Vec<Item> {
+    let document = Html::parse_document(page);
+    let blocks = Selector::parse("h2, h3, li").expect("valid selector");
+    let link = Selector::parse("a[href]").expect("valid selector");
+    let text = |element: ElementRef| element.text().collect::<String>().trim().to_string();
+
+    let mut in_updates = false;
+    let mut section = String::new();
+    let mut items = Vec::new();
+    for element in document.select(&blocks) {
+        match element.value().name() {
+            "h2" => in_updates = text(element).contains(UPDATES),
+            "h3" => section = text(element),
+            _ if in_updates => {
+                let Some(anchor) = element.select(&link).next() else {
+                    continue;
+                };
+                items.push(Item {
+                    title: text(anchor),
+                    url: anchor.value().attr("href").map(String::from),
+                    summary: None,
+                    year: None,
+                    kind: None,
+                    details: vec![section.clone()],
+                });
+            }
+            _ => (),
+        }
+    }
+    items
+}
@@ -0,0 +1,144 @@
+//! Events of the Wikipedia on-this-day feed
+
+use super::{EventKind, Item, NewsSource};
+use crate::cache::Cache;
+use crate::cli::{MonthDay, OnThisDay};
+use crate::config::Config;
+use crate::retry::Retry;
+use crate::JsonDataParseError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use std::error::Error;
+
+/// Events of a day in a language, the feed of every day being cached
+pub struct Wikipedia {
+    client: Client,
+    retry: Retry,
+    cache: Cache,
+    endpoint: String,
+    lang: String,
+    kind: OnThisDay,
+    verbose: bool,
+}
+
+impl Wikipedia {
+    pub fn new(
+        client: &Client,
+        config: &Config,
+        lang: &str,
+        kind: OnThisDay,
+        verbose: bool,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            retry: Retry::new(config, verbose),
+            cache: Cache::new(config, verbose),
+            endpoint: config.endpoints().wikipedia.clone(),
+            lang: lang.into(),
+            kind,
+            verbose,
+        }
+    }
+
+    /// Feed of `date`, from the cache if it was fetched lately
+    async fn feed(&self, date: MonthDay) -> Result<Value, Box<dyn Error>> {
+        let kind = match self.kind {
+            OnThisDay::Events => "events",
+            OnThisDay::Births => "births",
+            OnThisDay::Deaths => "deaths",
+            OnThisDay::Holidays => "holidays",
+            OnThisDay::Selected => "selected",
+            OnThisDay::All => "all",
+        };
+        let url = format!(
+            "{}/{}/onthisday/{}/{:02}/{:02}",
+            self.endpoint.trim_end_matches('/'),
+            self.lang,
+            kind,
+            date.month,
+            date.day
+        );
+        if let Some(feed) = self.cache.get(&url) {
+            return Ok(feed);
+        }
+        if self.cache.offline() {
+            return Err(format!(
+                "offline, and the {} of {} in `{}` were never fetched, \
+                run rustnews online for that day first",
+                kind, date, self.lang
+            )
+            .into());
+        }
+        if self.verbose {
+            eprintln!("rustnews: fetching {}", url);
+        }
+        let response = self
+            .retry
+            .send(self.client.get(&url))
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        self.cache.put(&url, &response);
+        Ok(response)
+    }
+}
+
+#[async_trait(?Send)]
+impl NewsSource for Wikipedia {
+    fn name(&self) -> &str {
+        "wikipedia"
+    }
+
+    async fn fetch(&self, date: MonthDay) -> Result<Vec<Item>, Box<dyn Error>> {
+        let response = self.feed(date).await?;
+        let lists: &[_] = match self.kind {
+            OnThisDay::Events => &[("events", EventKind::Event)],
+            OnThisDay::Births => &[("births", EventKind::Birth)],
+            OnThisDay::Deaths => &[("deaths", EventKind::Death)],
+            OnThisDay::Holidays => &[("holidays", EventKind::Holiday)],
+            OnThisDay::Selected => &[("selected", EventKind::Selected)],
+            OnThisDay::All => &[
+                ("selected", EventKind::Selected),
+                ("events", EventKind::Event),
+                ("births", EventKind::Birth),
+                ("deaths", EventKind::Death),
+                ("holidays", EventKind::Holiday),
+            ],
+        };
+        let mut items = Vec::new();
+        for (key, kind) in lists {
+            let events = response
+                .get(key)
+                .ok_or(JsonDataParseError::KeyNotFoundError)?
+                .as_array()
+                .ok_or(JsonDataParseError::DataNotArrayError)?;
+            items.extend(events.iter().filter_map(|event| event_item(event, *kind)));
+        }
+        Ok(items)
+    }
+}
+
+/// Item of `event` of the type `kind`, with the extract and the page of
+/// its first article, None if it has no text
+fn event_item(event: &Value, kind: EventKind) -> Option<Item> {
+    let article = event
+        .get("pages")
+        .and_then(Value::as_array)
+        .and_then(|pages| pages.first());
+    Some(Item {
+        title: event.get("text")?.as_str()?.into(),
+        url: article
+            .and_then(|article| article.pointer("/content_urls/desktop/page"))
+            .and_then(Value::as_str)
+            .map(String::from),
+        summary: article
+            .and_then(|article| article.get("extract"))
+            .and_then(Value::as_str)
+            .map(String::from),
+        year: event.get("year").and_then(Value::as_i64),
+        kind: Some(kind),
+        details: Vec::new(),
+    })
+}
@@ -0,0 +1,80 @@
+//! Short summaries of the articles of headlines
+//!
+//! The page of an article is stripped to the paragraphs of its text, those
+//! of its `<article>` or `<main>` element if it has one, without the short
+//! ones of bylines, captions or ads. The summary is its first sentences.
+
+use crate::retry::Retry;
+use reqwest::Client;
+use scraper::{Html, Selector};
+use std::error::Error;
+
+/// Paragraphs shorter than this are taken as boilerplate
+const MIN_PARAGRAPH: usize = 80;
+
+/// First `sentences` sentences of the article at `url`, None if its page
+/// has no text
+pub async fn summarize(
+    client: &Client,
+    retry: &Retry,
+    url: &str,
+    sentences: usize,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let page = retry
+        .send(client.get(url))
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let text = article_text(&page);
+    let summary: Vec<&str> = split_sentences(&text).take(sentences).collect();
+    if summary.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(summary.join(" ")))
+}
+
+/// Text of the paragraphs of the article of `page`, in a single line
+fn article_text(page: &str) -> String {
+    let document = Html::parse_document(page);
+    for scope in ["article p", "main p", "p"] {
+        let selector = Selector::parse(scope).expect("valid selector");
+        let paragraphs: Vec<String> = document
+            .select(&selector)
+            .map(|p| {
+                let text = p.text().collect::<Vec<_>>().join(" ");
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .filter(|p| p.chars().count() >= MIN_PARAGRAPH)
+            .collect();
+        if !paragraphs.is_empty() {
+            return paragraphs.join(" ");
+        }
+    }
+    String::new()
+}
+
+/// Sentences of `text`, each ending at a `.`, `!` or `?` followed by a
+/// space and a capital
+fn split_sentences(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text.trim();
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = rest.len();
+        for (index, c) in rest.char_indices() {
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+            let mut after = rest[index + c.len_utf8()..].chars();
+            if after.next() == Some(' ') && after.next().is_some_and(char::is_uppercase) {
+                end = index + c.len_utf8();
+                break;
+            }
+        }
+        let sentence = &rest[..end];
+        rest = rest[end..].trim_start();
+        Some(sentence)
+    })
+}